@@ -0,0 +1,471 @@
+//! `when` conditions of jobs.
+//!
+//! A condition is a small boolean expression deciding whether a job runs:
+//!
+//! ```text
+//! params.use_gpu
+//! params.mode == "full" && !params.dry_run
+//! exists("02_filter/hits.csv") || params.n_hits > 0
+//! ```
+//!
+//! Operands are `params.<name>` (the job's merged workflow and job parameters),
+//! `exists("<dependency>/<glob>")` (whether a dependency produced a matching file
+//! in its `outputs/`; the glob defaults to any file), and string, number and
+//! `true`/`false` literals. They combine with `==`, `!=`,
+//! `<`, `<=`, `>`, `>=`, `!`, `&&`, `||` and parentheses. A bare operand is true
+//! unless it is `false`, `0`, an empty string or `"false"`.
+//!
+//! There is no check on exit codes: a job only runs once all of its dependencies
+//! have succeeded.
+
+use serde_json::Value;
+
+/// Values a condition is evaluated against.
+pub trait ConditionContext {
+    /// Value of parameter `name`, or `None` if the job has no such parameter.
+    fn param(&self, name: &str) -> Option<Value>;
+
+    /// Whether dependency `job` has an output file matching `pattern`.
+    fn output_exists(&self, job: &str, pattern: &str) -> Result<bool, String>;
+}
+
+/// A parsed `when` condition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, CompareOp, Operand),
+    Operand(Operand),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Param(String),
+    Exists { job: String, pattern: String },
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    Not,
+    And,
+    Or,
+    Compare(CompareOp),
+    Str(String),
+    Word(String),
+}
+
+impl Condition {
+    /// Parses a condition, e.g. `params.use_gpu && exists("02_filter/hits.csv")`.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(format!("unexpected {} in '{source}'", describe(token)));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Jobs referenced through `exists(...)`.
+    pub fn jobs(&self) -> Vec<&str> {
+        let mut jobs = Vec::new();
+        self.expr.visit_operands(&mut |operand| {
+            if let Operand::Exists { job, .. } = operand {
+                jobs.push(job.as_str());
+            }
+        });
+        jobs.dedup();
+        jobs
+    }
+
+    /// Evaluates the condition against `context`.
+    pub fn evaluate(&self, context: &impl ConditionContext) -> Result<bool, String> {
+        self.expr.evaluate(context)
+    }
+}
+
+impl Expr {
+    fn visit_operands<'a>(&'a self, visit: &mut impl FnMut(&'a Operand)) {
+        match self {
+            Expr::Or(a, b) | Expr::And(a, b) => {
+                a.visit_operands(visit);
+                b.visit_operands(visit);
+            }
+            Expr::Not(a) => a.visit_operands(visit),
+            Expr::Compare(a, _, b) => {
+                visit(a);
+                visit(b);
+            }
+            Expr::Operand(a) => visit(a),
+        }
+    }
+
+    fn evaluate(&self, context: &impl ConditionContext) -> Result<bool, String> {
+        Ok(match self {
+            Expr::Or(a, b) => a.evaluate(context)? || b.evaluate(context)?,
+            Expr::And(a, b) => a.evaluate(context)? && b.evaluate(context)?,
+            Expr::Not(a) => !a.evaluate(context)?,
+            Expr::Compare(a, op, b) => compare(&a.value(context)?, *op, &b.value(context)?)?,
+            Expr::Operand(a) => is_truthy(&a.value(context)?),
+        })
+    }
+}
+
+impl Operand {
+    fn value(&self, context: &impl ConditionContext) -> Result<Value, String> {
+        match self {
+            Operand::Param(name) => context
+                .param(name)
+                .ok_or_else(|| format!("unknown parameter '{name}'")),
+            Operand::Exists { job, pattern } => {
+                context.output_exists(job, pattern).map(Value::Bool)
+            }
+            Operand::Literal(value) => Ok(value.clone()),
+        }
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty() && s != "false" && s != "0",
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+/// Numeric value of a number, or of a string holding one.
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn as_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn compare(a: &Value, op: CompareOp, b: &Value) -> Result<bool, String> {
+    if let (Some(x), Some(y)) = (as_number(a), as_number(b)) {
+        return Ok(match op {
+            CompareOp::Eq => x == y,
+            CompareOp::Ne => x != y,
+            CompareOp::Lt => x < y,
+            CompareOp::Le => x <= y,
+            CompareOp::Gt => x > y,
+            CompareOp::Ge => x >= y,
+        });
+    }
+    match op {
+        CompareOp::Eq => Ok(as_text(a) == as_text(b)),
+        CompareOp::Ne => Ok(as_text(a) != as_text(b)),
+        _ => Err(format!("cannot order {a} and {b}: both must be numbers")),
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::LParen => "'('".to_string(),
+        Token::RParen => "')'".to_string(),
+        Token::Not => "'!'".to_string(),
+        Token::And => "'&&'".to_string(),
+        Token::Or => "'||'".to_string(),
+        Token::Compare(_) => "comparison".to_string(),
+        Token::Str(s) => format!("string \"{s}\""),
+        Token::Word(w) => format!("'{w}'"),
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' {
+                    Token::LParen
+                } else {
+                    Token::RParen
+                });
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(ch) if ch == c => break,
+                        Some(ch) => text.push(ch),
+                        None => return Err(format!("unterminated string in '{source}'")),
+                    }
+                }
+                tokens.push(Token::Str(text));
+            }
+            '&' | '|' => {
+                chars.next();
+                if chars.next() != Some(c) {
+                    return Err(format!("expected '{c}{c}' in '{source}'"));
+                }
+                tokens.push(if c == '&' { Token::And } else { Token::Or });
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let followed_by_eq = chars.next_if_eq(&'=').is_some();
+                tokens.push(match (c, followed_by_eq) {
+                    ('=', true) => Token::Compare(CompareOp::Eq),
+                    ('!', true) => Token::Compare(CompareOp::Ne),
+                    ('<', true) => Token::Compare(CompareOp::Le),
+                    ('>', true) => Token::Compare(CompareOp::Ge),
+                    ('<', false) => Token::Compare(CompareOp::Lt),
+                    ('>', false) => Token::Compare(CompareOp::Gt),
+                    ('!', false) => Token::Not,
+                    _ => return Err(format!("expected '==' in '{source}'")),
+                });
+            }
+            c if c.is_alphanumeric() || matches!(c, '_' | '.' | '-') => {
+                let mut word = String::new();
+                while let Some(ch) =
+                    chars.next_if(|ch| ch.is_alphanumeric() || matches!(ch, '_' | '.' | '-'))
+                {
+                    word.push(ch);
+                }
+                tokens.push(Token::Word(word));
+            }
+            other => return Err(format!("unexpected '{other}' in '{source}'")),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.tokens.get(self.pos) == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat(&Token::LParen) {
+            let expr = self.or()?;
+            if !self.eat(&Token::RParen) {
+                return Err("missing ')'".to_string());
+            }
+            return Ok(expr);
+        }
+        let left = self.operand()?;
+        if let Some(Token::Compare(op)) = self.tokens.get(self.pos) {
+            let op = *op;
+            self.pos += 1;
+            return Ok(Expr::Compare(left, op, self.operand()?));
+        }
+        Ok(Expr::Operand(left))
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        match self.next() {
+            Some(Token::Str(text)) => Ok(Operand::Literal(Value::String(text))),
+            Some(Token::Word(word)) => {
+                if self.eat(&Token::LParen) {
+                    return self.call(&word);
+                }
+                if let Some(name) = word.strip_prefix("params.") {
+                    return Ok(Operand::Param(name.to_string()));
+                }
+                match word.as_str() {
+                    "true" => Ok(Operand::Literal(Value::Bool(true))),
+                    "false" => Ok(Operand::Literal(Value::Bool(false))),
+                    _ => word
+                        .parse::<f64>()
+                        .ok()
+                        .and_then(serde_json::Number::from_f64)
+                        .map(|n| Operand::Literal(Value::Number(n)))
+                        .ok_or_else(|| {
+                            format!("unknown name '{word}'; parameters are written params.{word}")
+                        }),
+                }
+            }
+            Some(token) => Err(format!("expected a value, found {}", describe(&token))),
+            None => Err("expected a value, found the end of the condition".to_string()),
+        }
+    }
+
+    /// Parses the argument of function `name`, whose `(` was already read.
+    fn call(&mut self, name: &str) -> Result<Operand, String> {
+        let Some(Token::Str(argument)) = self.next() else {
+            return Err(format!("{name}() takes a quoted job name"));
+        };
+        if !self.eat(&Token::RParen) {
+            return Err(format!("missing ')' after {name}(\"{argument}\""));
+        }
+        match name {
+            "exists" => {
+                let (job, pattern) = argument.split_once('/').unwrap_or((&argument, "**"));
+                if job.is_empty() || pattern.is_empty() {
+                    return Err(format!(
+                        "exists(\"{argument}\") must name a job and optionally a pattern, e.g. exists(\"02_filter/*.csv\")"
+                    ));
+                }
+                Ok(Operand::Exists {
+                    job: job.to_string(),
+                    pattern: pattern.to_string(),
+                })
+            }
+            _ => Err(format!("unknown function '{name}'; use exists(...)")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct Context {
+        params: HashMap<String, Value>,
+        outputs: Vec<(&'static str, &'static str)>,
+    }
+
+    impl ConditionContext for Context {
+        fn param(&self, name: &str) -> Option<Value> {
+            self.params.get(name).cloned()
+        }
+
+        fn output_exists(&self, job: &str, pattern: &str) -> Result<bool, String> {
+            Ok(self
+                .outputs
+                .iter()
+                .any(|(j, file)| *j == job && (pattern == "**" || *file == pattern)))
+        }
+    }
+
+    fn context() -> Context {
+        Context {
+            params: HashMap::from([
+                ("use_gpu".to_string(), Value::Bool(true)),
+                ("mode".to_string(), Value::from("full")),
+                ("n_hits".to_string(), Value::from(3)),
+                ("threshold".to_string(), Value::from("0.5")),
+            ]),
+            outputs: vec![("02_filter", "hits.csv")],
+        }
+    }
+
+    fn eval(source: &str) -> Result<bool, String> {
+        Condition::parse(source)?.evaluate(&context())
+    }
+
+    #[test]
+    fn test_condition_params() {
+        assert_eq!(eval("params.use_gpu"), Ok(true));
+        assert_eq!(eval("!params.use_gpu"), Ok(false));
+        assert_eq!(eval("params.mode == \"full\""), Ok(true));
+        assert_eq!(eval("params.mode != 'full'"), Ok(false));
+        assert_eq!(eval("params.n_hits > 2 && params.n_hits <= 3"), Ok(true));
+        // Numeric strings compare as numbers
+        assert_eq!(eval("params.threshold >= 0.25"), Ok(true));
+        assert_eq!(
+            eval("params.mode == \"fast\" || (params.use_gpu && params.n_hits != 0)"),
+            Ok(true)
+        );
+        assert!(eval("params.missing").is_err());
+        assert!(eval("params.mode < 3").is_err());
+    }
+
+    #[test]
+    fn test_condition_dependencies() {
+        assert_eq!(eval("exists(\"02_filter/hits.csv\")"), Ok(true));
+        assert_eq!(eval("exists(\"02_filter/empty.csv\")"), Ok(false));
+        assert_eq!(eval("exists(\"02_filter\")"), Ok(true));
+
+        let condition =
+            Condition::parse("exists(\"02_filter/*.csv\") && !exists(\"01_prepare/skip\")")
+                .unwrap();
+        assert_eq!(condition.jobs(), vec!["02_filter", "01_prepare"]);
+    }
+
+    #[test]
+    fn test_condition_syntax_errors() {
+        for source in [
+            "",
+            "use_gpu",
+            "params.a ==",
+            "params.a = 1",
+            "params.a & params.b",
+            "(params.a",
+            "params.a params.b",
+            "exists(02_filter)",
+            "exists(\"/hits.csv\")",
+            "glob(\"x\")",
+            "exit_code(\"01_prepare\") == 0",
+            "\"unterminated",
+        ] {
+            assert!(
+                Condition::parse(source).is_err(),
+                "{source} should not parse"
+            );
+        }
+    }
+}
@@ -0,0 +1,388 @@
+//! `${...}` references in parameter values.
+//!
+//! String parameter values, and the strings in array values, may refer to other
+//! values:
+//!
+//! ```text
+//! "${global.project}_${run.id}"    workflow parameter `project` and the run ID
+//! "${job.prefix}/results"          another parameter of the same job
+//! "${env.USER}"                    a host environment variable
+//! ```
+//!
+//! In workflow parameters (global_params.json), `${global.x}` refers to another
+//! workflow parameter and `${job.x}` is not available. In job parameters,
+//! `${global.x}` refers to the workflow parameter and `${job.x}` to the job's
+//! merged parameters, its own values over the workflow's. The built-ins are
+//! `${run.id}` and `${run.date}`.
+//!
+//! A value that is a single reference takes the referenced value with its type,
+//! so `"${global.epochs}"` can become the number `10`; otherwise each reference is
+//! replaced by the text of its value. `$${` writes a literal `${`. References
+//! between parameters must not form a cycle.
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::params::{JobParams, WorkflowParams};
+
+/// Built-in `${run.*}` values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunInfo {
+    /// `${run.id}`: identifies the run
+    pub id: String,
+    /// `${run.date}`: day the run started, as `YYYY-MM-DD`
+    pub date: String,
+}
+
+/// Which parameters `Interpolator::resolve_each` resolves.
+#[derive(Debug, Clone, Copy)]
+pub enum Scope<'a> {
+    /// Workflow parameters; `${global.x}` refers to the values being resolved
+    Workflow,
+    /// Job parameters, over the already resolved workflow parameters `global`
+    Job { global: &'a WorkflowParams },
+}
+
+/// Resolves `${...}` references in parameter values.
+pub struct Interpolator<'a> {
+    run: RunInfo,
+    env: EnvLookup<'a>,
+}
+
+/// Looks up `${env.X}` values.
+type EnvLookup<'a> = Box<dyn Fn(&str) -> Option<String> + 'a>;
+
+impl<'a> Interpolator<'a> {
+    /// Creates an interpolator reading `${env.X}` from the process environment.
+    pub fn new(run: RunInfo) -> Self {
+        Self {
+            run,
+            env: Box::new(|name| std::env::var(name).ok()),
+        }
+    }
+
+    /// Reads `${env.X}` through `env` instead of the process environment.
+    pub fn with_env(mut self, env: impl Fn(&str) -> Option<String> + 'a) -> Self {
+        self.env = Box::new(env);
+        self
+    }
+
+    /// Resolves the workflow parameters `global`.
+    ///
+    /// Returns `Err` listing every parameter that cannot be resolved.
+    pub fn resolve_workflow(&self, global: &WorkflowParams) -> Result<WorkflowParams, String> {
+        collect(self.resolve_each(global, Scope::Workflow))
+    }
+
+    /// Resolves the parameters of a job, where `global` are the resolved workflow
+    /// parameters. Only the job's own parameters are returned.
+    ///
+    /// Returns `Err` listing every parameter that cannot be resolved.
+    pub fn resolve_job(
+        &self,
+        global: &WorkflowParams,
+        job: &JobParams,
+    ) -> Result<JobParams, String> {
+        collect(self.resolve_each(job, Scope::Job { global }))
+    }
+
+    /// Resolves every value of `values` on its own, so a broken reference in one
+    /// value does not hide the others.
+    pub fn resolve_each(
+        &self,
+        values: &JobParams,
+        scope: Scope,
+    ) -> HashMap<String, Result<Value, String>> {
+        let mut resolver = Resolver {
+            interpolator: self,
+            values,
+            scope,
+            resolved: HashMap::new(),
+            stack: Vec::new(),
+        };
+        // In name order, so a cycle is always reported from the same parameter
+        let mut names: Vec<&String> = values.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| (name.clone(), resolver.param(name)))
+            .collect()
+    }
+}
+
+/// Whether `value` contains a `${...}` reference, or a malformed one.
+pub fn has_references(value: &Value) -> bool {
+    match value {
+        Value::String(text) => parse(text).map_or(true, |parts| {
+            parts
+                .iter()
+                .any(|part| matches!(part, Part::Reference { .. }))
+        }),
+        Value::Array(items) => items.iter().any(has_references),
+        _ => false,
+    }
+}
+
+/// Text of a value inside a longer string.
+pub fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn collect(results: HashMap<String, Result<Value, String>>) -> Result<JobParams, String> {
+    let mut errors: Vec<(String, String)> = results
+        .iter()
+        .filter_map(|(name, result)| result.as_ref().err().map(|e| (name.clone(), e.clone())))
+        .collect();
+    if errors.is_empty() {
+        return Ok(results
+            .into_iter()
+            .filter_map(|(name, result)| result.ok().map(|value| (name, value)))
+            .collect());
+    }
+    errors.sort();
+    Err(errors
+        .iter()
+        .map(|(name, e)| format!("Parameter '{name}': {e}"))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+#[derive(Debug, PartialEq)]
+enum Part<'t> {
+    Literal(String),
+    Reference { namespace: &'t str, name: &'t str },
+}
+
+fn parse(text: &str) -> Result<Vec<Part<'_>>, String> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut rest = text;
+    while let Some(pos) = rest.find('$') {
+        literal.push_str(&rest[..pos]);
+        let from_dollar = &rest[pos..];
+        if let Some(after) = from_dollar.strip_prefix("$${") {
+            literal.push_str("${");
+            rest = after;
+        } else if let Some(after) = from_dollar.strip_prefix("${") {
+            let end = after
+                .find('}')
+                .ok_or_else(|| format!("unterminated '${{' in '{text}'"))?;
+            let reference = after[..end].trim();
+            let (namespace, name) = reference
+                .split_once('.')
+                .filter(|(namespace, name)| {
+                    matches!(*namespace, "global" | "job" | "env" | "run") && !name.is_empty()
+                })
+                .ok_or_else(|| {
+                    format!(
+                        "invalid reference '${{{reference}}}': expected global.<name>, job.<name>, env.<NAME> or run.<name>"
+                    )
+                })?;
+            if !literal.is_empty() {
+                parts.push(Part::Literal(std::mem::take(&mut literal)));
+            }
+            parts.push(Part::Reference { namespace, name });
+            rest = &after[end + 1..];
+        } else {
+            literal.push('$');
+            rest = &from_dollar[1..];
+        }
+    }
+    literal.push_str(rest);
+    if !literal.is_empty() {
+        parts.push(Part::Literal(literal));
+    }
+    Ok(parts)
+}
+
+/// Resolves the values of one scope, remembering finished values and the chain
+/// of parameters being resolved to detect cycles.
+struct Resolver<'r, 'a> {
+    interpolator: &'r Interpolator<'a>,
+    values: &'r JobParams,
+    scope: Scope<'r>,
+    resolved: HashMap<String, Result<Value, String>>,
+    stack: Vec<String>,
+}
+
+impl Resolver<'_, '_> {
+    /// Namespace the values being resolved are referred to by.
+    fn namespace(&self) -> &'static str {
+        match self.scope {
+            Scope::Workflow => "global",
+            Scope::Job { .. } => "job",
+        }
+    }
+
+    fn param(&mut self, name: &str) -> Result<Value, String> {
+        if let Some(result) = self.resolved.get(name) {
+            return result.clone();
+        }
+        if let Some(start) = self.stack.iter().position(|n| n == name) {
+            let namespace = self.namespace();
+            let cycle: Vec<String> = self.stack[start..]
+                .iter()
+                .chain(std::iter::once(&name.to_string()))
+                .map(|n| format!("{namespace}.{n}"))
+                .collect();
+            return Err(format!("reference cycle {}", cycle.join(" -> ")));
+        }
+        let Some(raw) = self.values.get(name) else {
+            return Err(format!("unknown parameter '{}.{name}'", self.namespace()));
+        };
+
+        self.stack.push(name.to_string());
+        let result = self.value(raw);
+        self.stack.pop();
+        self.resolved.insert(name.to_string(), result.clone());
+        result
+    }
+
+    fn value(&mut self, raw: &Value) -> Result<Value, String> {
+        match raw {
+            Value::String(text) => self.text(text),
+            Value::Array(items) => items.iter().map(|item| self.value(item)).collect(),
+            other => Ok(other.clone()),
+        }
+    }
+
+    fn text(&mut self, text: &str) -> Result<Value, String> {
+        let parts = parse(text)?;
+        if let [Part::Reference { namespace, name }] = parts.as_slice() {
+            return self.reference(namespace, name);
+        }
+        let mut resolved = String::new();
+        for part in &parts {
+            match part {
+                Part::Literal(literal) => resolved.push_str(literal),
+                Part::Reference { namespace, name } => {
+                    resolved.push_str(&value_to_text(&self.reference(namespace, name)?))
+                }
+            }
+        }
+        Ok(Value::String(resolved))
+    }
+
+    fn reference(&mut self, namespace: &str, name: &str) -> Result<Value, String> {
+        match (namespace, self.scope) {
+            ("global", Scope::Workflow) | ("job", Scope::Job { .. })
+                if self.values.contains_key(name) =>
+            {
+                self.param(name)
+            }
+            ("global", Scope::Job { global }) | ("job", Scope::Job { global }) => global
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("unknown parameter '{namespace}.{name}'")),
+            ("global", Scope::Workflow) => Err(format!("unknown parameter 'global.{name}'")),
+            ("job", Scope::Workflow) => Err(format!(
+                "'${{job.{name}}}' is only available in job parameters"
+            )),
+            ("env", _) => (self.interpolator.env)(name)
+                .map(Value::String)
+                .ok_or_else(|| format!("environment variable '{name}' is not set")),
+            ("run", _) => match name {
+                "id" => Ok(Value::String(self.interpolator.run.id.clone())),
+                "date" => Ok(Value::String(self.interpolator.run.date.clone())),
+                _ => Err(format!(
+                    "unknown built-in 'run.{name}' (available: run.id, run.date)"
+                )),
+            },
+            _ => unreachable!("references are parsed into known namespaces"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn interpolator() -> Interpolator<'static> {
+        let run = RunInfo {
+            id: "silva-run".to_string(),
+            date: "2025-01-01".to_string(),
+        };
+        Interpolator::new(run).with_env(|name| (name == "USER").then(|| "ada".to_string()))
+    }
+
+    fn params(value: Value) -> JobParams {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_references_are_resolved() {
+        let interpolator = interpolator();
+        let global = interpolator
+            .resolve_workflow(&params(json!({
+                "project": "dock",
+                "prefix": "${global.project}_${run.id}",
+                "epochs": 10,
+                "literal": "$${global.project} costs $5",
+            })))
+            .unwrap();
+        assert_eq!(global["prefix"], json!("dock_silva-run"));
+        assert_eq!(global["literal"], json!("${global.project} costs $5"));
+
+        let job = interpolator
+            .resolve_job(
+                &global,
+                &params(json!({
+                    "out": "${job.dir}/${job.project}",
+                    "dir": "/data/${env.USER}/${run.date}",
+                    "project": "${global.project}-v2",
+                    "epochs": "${global.epochs}",
+                    "tags": ["${global.prefix}", "x${job.epochs}"],
+                })),
+            )
+            .unwrap();
+        assert_eq!(job["out"], json!("/data/ada/2025-01-01/dock-v2"));
+        // A single reference keeps the type of the referenced value
+        assert_eq!(job["epochs"], json!(10));
+        assert_eq!(job["tags"], json!(["dock_silva-run", "x10"]));
+        assert!(!job.contains_key("prefix"));
+    }
+
+    #[test]
+    fn test_broken_references_are_reported() {
+        let interpolator = interpolator();
+        let err = interpolator
+            .resolve_workflow(&params(json!({
+                "a": "${global.b}",
+                "b": "x${global.a}",
+                "home": "${env.HOME_DIR}",
+                "job": "${job.name}",
+                "open": "${global.a",
+                "other": "${params.a}",
+                "run": "${run.time}",
+                "missing": "${global.nothing}",
+                "fine": "ok",
+            })))
+            .unwrap_err();
+
+        for expected in [
+            "Parameter 'a': reference cycle global.a -> global.b -> global.a",
+            "Parameter 'b': reference cycle global.a -> global.b -> global.a",
+            "Parameter 'home': environment variable 'HOME_DIR' is not set",
+            "Parameter 'job': '${job.name}' is only available in job parameters",
+            "Parameter 'open': unterminated '${' in '${global.a'",
+            "Parameter 'other': invalid reference '${params.a}'",
+            "Parameter 'run': unknown built-in 'run.time'",
+            "Parameter 'missing': unknown parameter 'global.nothing'",
+        ] {
+            assert!(err.contains(expected), "{expected} not in {err}");
+        }
+        assert!(!err.contains("'fine'"));
+
+        let err = interpolator
+            .resolve_job(&WorkflowParams::new(), &params(json!({ "a": "${job.a}" })))
+            .unwrap_err();
+        assert_eq!(err, "Parameter 'a': reference cycle job.a -> job.a");
+
+        assert!(has_references(&json!(["a", "${job.a}"])));
+        assert!(!has_references(&json!("$${job.a} $5")));
+    }
+}
@@ -1,8 +1,10 @@
+use crate::condition::Condition;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
 
 /// Represents the type of a parameter.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -34,18 +36,59 @@ impl fmt::Display for ParamType {
 }
 
 /// Represents a parameter definition in job.toml.
+///
+/// Besides the type, a definition can constrain the values it accepts:
+///
+/// ```toml
+/// [params.epochs]
+/// type = "integer"
+/// default = 10
+/// hint = "Training epochs"
+/// min = 1
+/// max = 1000
+///
+/// [params.pdb_id]
+/// type = "string"
+/// required = true                   # no default; a value must be set
+/// hint = "PDB entry to download"
+/// pattern = "[0-9][A-Za-z0-9]{3}"
+/// ```
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ParamDefinition {
     #[serde(rename = "type")]
     pub param_type: ParamType,
-    pub default: toml::Value,
+    /// Value used when none is set. Parameters without one are required.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<toml::Value>,
     pub hint: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub enum_values: Option<Vec<String>>,
+    /// Smallest allowed `integer` or `float` value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Largest allowed `integer` or `float` value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// Regular expression a `string` value must match as a whole.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Fewest characters a `string` value may have.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    /// Whether a value must be set instead of falling back to `default`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub required: bool,
+    /// Type of the elements of an `array`. The other constraints then apply to
+    /// every element.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub items: Option<ParamType>,
+    /// Whether the path of a `file` or `directory` must exist.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub must_exist: bool,
 }
 
 impl ParamDefinition {
-    /// Creates a new parameter definition.
+    /// Creates a new parameter definition without constraints.
     pub fn new(
         param_type: ParamType,
         default: toml::Value,
@@ -54,29 +97,66 @@ impl ParamDefinition {
     ) -> Self {
         Self {
             param_type,
-            default,
+            default: Some(default),
             hint,
             enum_values,
+            min: None,
+            max: None,
+            pattern: None,
+            min_length: None,
+            required: false,
+            items: None,
+            must_exist: false,
+        }
+    }
+
+    /// Returns `true` if a value must be set: the parameter is `required` or has
+    /// no default.
+    pub fn is_required(&self) -> bool {
+        self.required || self.default.is_none()
+    }
+
+    /// The default value, unless the parameter is required.
+    pub fn default_value(&self) -> Option<&toml::Value> {
+        if self.required {
+            None
+        } else {
+            self.default.as_ref()
         }
     }
 
     /// Validates a value against this parameter definition.
+    ///
+    /// Relative `must_exist` paths are resolved against the current directory;
+    /// see `validate_in`.
     pub fn validate(&self, value: &toml::Value) -> Result<(), String> {
+        self.validate_in(value, Path::new(""))
+    }
+
+    /// Validates a value against this parameter definition, resolving relative
+    /// `must_exist` paths against `dir`.
+    pub fn validate_in(&self, value: &toml::Value, dir: &Path) -> Result<(), String> {
         match self.param_type {
             ParamType::String => {
-                if !value.is_str() {
+                let Some(text) = value.as_str() else {
                     return Err(format!("Expected string, got {value}"));
-                }
+                };
+                self.check_text(text)?;
             }
             ParamType::Integer => {
-                if !value.is_integer() {
+                let Some(number) = value.as_integer() else {
                     return Err(format!("Expected integer, got {value}"));
-                }
+                };
+                self.check_range(number as f64)?;
             }
             ParamType::Float => {
-                if !value.is_float() && !value.is_integer() {
+                let Some(number) = value
+                    .as_float()
+                    .or_else(|| value.as_integer().map(|i| i as f64))
+                else {
                     return Err(format!("Expected float, got {value}"));
-                }
+                };
+                self.check_range(number)?;
             }
             ParamType::Boolean => {
                 if !value.is_bool() {
@@ -84,8 +164,17 @@ impl ParamDefinition {
                 }
             }
             ParamType::File | ParamType::Directory => {
-                if !value.is_str() {
+                let Some(path) = value.as_str() else {
                     return Err(format!("Expected path string, got {value}"));
+                };
+                if self.must_exist {
+                    let resolved = dir.join(path);
+                    if self.param_type == ParamType::File && !resolved.is_file() {
+                        return Err(format!("File '{path}' does not exist"));
+                    }
+                    if self.param_type == ParamType::Directory && !resolved.is_dir() {
+                        return Err(format!("Directory '{path}' does not exist"));
+                    }
                 }
             }
             ParamType::Enum => {
@@ -104,13 +193,115 @@ impl ParamDefinition {
                 }
             }
             ParamType::Array => {
-                if !value.is_array() {
+                let Some(elements) = value.as_array() else {
                     return Err(format!("Expected array, got {value}"));
+                };
+                if let Some(items) = &self.items {
+                    let element = ParamDefinition {
+                        param_type: items.clone(),
+                        items: None,
+                        ..self.clone()
+                    };
+                    for (i, item) in elements.iter().enumerate() {
+                        element
+                            .validate_in(item, dir)
+                            .map_err(|e| format!("Element {}: {e}", i + 1))?;
+                    }
                 }
             }
         }
         Ok(())
     }
+
+    /// Parses a value written as text, e.g. in the parameter editor or on the
+    /// command line, into the JSON value stored in params files.
+    ///
+    /// Arrays are written as `[a, b]` or `a, b`; their elements are parsed as the
+    /// `items` type, or kept as strings without one. The value is not validated.
+    pub fn parse_value(&self, text: &str) -> Result<serde_json::Value, String> {
+        if self.param_type != ParamType::Array {
+            return parse_scalar(text, &self.param_type);
+        }
+
+        let trimmed = text.trim();
+        let inner = trimmed
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+            .unwrap_or(trimmed)
+            .trim();
+        if inner.is_empty() {
+            return Ok(serde_json::Value::Array(Vec::new()));
+        }
+        let items = inner
+            .split(',')
+            .enumerate()
+            .map(|(i, item)| match &self.items {
+                Some(item_type) => {
+                    parse_scalar(item, item_type).map_err(|e| format!("Element {}: {e}", i + 1))
+                }
+                None => Ok(serde_json::Value::String(item.trim().to_string())),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(serde_json::Value::Array(items))
+    }
+
+    fn check_range(&self, number: f64) -> Result<(), String> {
+        if let Some(min) = self.min
+            && number < min
+        {
+            return Err(format!("Value {number} is below the minimum of {min}"));
+        }
+        if let Some(max) = self.max
+            && number > max
+        {
+            return Err(format!("Value {number} is above the maximum of {max}"));
+        }
+        Ok(())
+    }
+
+    fn check_text(&self, text: &str) -> Result<(), String> {
+        if let Some(min_length) = self.min_length
+            && text.chars().count() < min_length
+        {
+            return Err(format!(
+                "Value '{text}' is shorter than {min_length} character(s)"
+            ));
+        }
+        if let Some(pattern) = &self.pattern {
+            let regex = regex_automata::meta::Regex::new(&format!("^(?:{pattern})$"))
+                .map_err(|e| format!("Invalid pattern '{pattern}': {e}"))?;
+            if !regex.is_match(text) {
+                return Err(format!("Value '{text}' does not match pattern '{pattern}'"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses text into a JSON value of a non-array parameter type.
+fn parse_scalar(text: &str, param_type: &ParamType) -> Result<serde_json::Value, String> {
+    let trimmed = text.trim();
+
+    match param_type {
+        ParamType::String | ParamType::File | ParamType::Directory | ParamType::Enum => {
+            Ok(serde_json::Value::String(trimmed.to_string()))
+        }
+        ParamType::Integer => trimmed
+            .parse::<i64>()
+            .map(|n| serde_json::json!(n))
+            .map_err(|_| format!("Invalid integer: {trimmed}")),
+        ParamType::Float => trimmed
+            .parse::<f64>()
+            .map(|f| serde_json::json!(f))
+            .map_err(|_| format!("Invalid float: {trimmed}")),
+        ParamType::Boolean => match trimmed.to_lowercase().as_str() {
+            "true" | "yes" | "1" => Ok(serde_json::Value::Bool(true)),
+            "false" | "no" | "0" => Ok(serde_json::Value::Bool(false)),
+            _ => Err(format!("Invalid boolean: {trimmed} (use true/false)")),
+        },
+        // Arrays of arrays cannot be written as text
+        ParamType::Array => Err(format!("Invalid array element: {trimmed}")),
+    }
 }
 
 /// Detects whether the host has an NVIDIA GPU available by checking `nvidia-smi`.
@@ -135,19 +326,34 @@ pub enum ImageSource {
     TarFile(String),
     /// Load image from a Singularity/Apptainer SIF file (e.g., "./image.sif").
     SifFile(String),
+    /// Build image from a Dockerfile (e.g., "Dockerfile"), set via `dockerfile` in `[container]`.
+    Dockerfile(String),
 }
 
 /// Represents the container configuration for a job.
 /// The `image` field can be a Docker registry URL or a local file path (.tar or .sif).
+/// Alternatively, `dockerfile` builds the image from a Dockerfile in the job folder.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Container {
     /// Docker image source: either a registry URL (e.g., "ubuntu:22.04")
     /// or a local file path (.tar for Docker, .sif for Singularity/Apptainer).
+    /// With `dockerfile`, optionally names the repository the built image is tagged into.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub image: String,
     /// Optional registry hint. Set to `"local"` to bypass default_registry resolution
     /// for images that are built locally and do not exist in any remote registry.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub registry: Option<String>,
+    /// Dockerfile to build the image from, relative to the job folder.
+    /// Its directory is used as the build context.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dockerfile: Option<String>,
+    /// Build arguments passed to the Dockerfile build (`ARG` values).
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub build_args: HashMap<String, String>,
+    /// Build stage to stop at in a multi-stage Dockerfile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
 }
 
 impl Container {
@@ -156,16 +362,22 @@ impl Container {
         Self {
             image,
             registry: None,
+            dockerfile: None,
+            build_args: HashMap::new(),
+            target: None,
         }
     }
 
     /// Returns the image source type based on the image string and registry hint.
+    /// - `dockerfile` set → Dockerfile (takes precedence over `image`)
     /// - `registry = "local"` → LocalImage (skip registry prefix resolution)
     /// - Paths ending with ".tar" → TarFile
     /// - Paths ending with ".sif" → SifFile
     /// - Everything else → Registry
     pub fn get_image_source(&self) -> ImageSource {
-        if self.registry.as_deref() == Some("local") {
+        if let Some(dockerfile) = &self.dockerfile {
+            ImageSource::Dockerfile(dockerfile.clone())
+        } else if self.registry.as_deref() == Some("local") {
             ImageSource::LocalImage(self.image.clone())
         } else if self.image.ends_with(".tar") {
             ImageSource::TarFile(self.image.clone())
@@ -187,6 +399,47 @@ pub struct Scripts {
     pub run: String,
     #[serde(default = "default_post_script")]
     pub post: String,
+    /// Program the scripts are run with.
+    #[serde(default, skip_serializing_if = "Interpreter::is_default")]
+    pub interpreter: Interpreter,
+    /// Program and arguments run instead of the `run` script, e.g.
+    /// `["python", "main.py", "--in", "inputs/"]`. Needs no shell in the image.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command: Vec<String>,
+}
+
+/// Program a job's scripts are run with, set as `interpreter` in `[scripts]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Interpreter {
+    /// `sh <script>`, for images without bash (e.g. alpine).
+    Sh,
+    /// `bash <script>`.
+    #[default]
+    Bash,
+    /// `python3 <script>`.
+    Python3,
+    /// The script itself, run through its `#!` line; needs no shell in the image.
+    Shebang,
+}
+
+impl Interpreter {
+    /// Returns `true` for the default, `bash`.
+    pub fn is_default(&self) -> bool {
+        *self == Interpreter::default()
+    }
+
+    /// Program and arguments running `script`, a path relative to the job folder.
+    pub fn argv(&self, script: &str) -> Vec<String> {
+        let program = match self {
+            Interpreter::Sh => "sh",
+            Interpreter::Bash => "bash",
+            Interpreter::Python3 => "python3",
+            Interpreter::Shebang if script.contains('/') => return vec![script.to_string()],
+            Interpreter::Shebang => return vec![format!("./{script}")],
+        };
+        vec![program.to_string(), script.to_string()]
+    }
 }
 
 fn default_pre_script() -> String {
@@ -207,6 +460,440 @@ impl Default for Scripts {
             pre: default_pre_script(),
             run: default_run_script(),
             post: default_post_script(),
+            interpreter: Interpreter::default(),
+            command: Vec::new(),
+        }
+    }
+}
+
+/// A duration written as a number followed by a unit, e.g. `"90s"`, `"30m"`, `"2h"`
+/// or `"1d"`. Parts can be combined (`"1h30m"`); a bare number means seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HumanDuration(pub Duration);
+
+impl HumanDuration {
+    /// Parses a duration such as `"2h"` or `"1h30m"`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err("Empty duration".to_string());
+        }
+        if let Ok(secs) = value.parse::<u64>() {
+            return Ok(Self(Duration::from_secs(secs)));
+        }
+
+        let mut total = 0u64;
+        let mut number = String::new();
+        for c in value.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            let unit = match c {
+                's' => 1,
+                'm' => 60,
+                'h' => 60 * 60,
+                'd' => 24 * 60 * 60,
+                _ => return Err(format!("Invalid duration '{value}': unknown unit '{c}'")),
+            };
+            let amount: u64 = number.parse().map_err(|_| {
+                format!("Invalid duration '{value}': expected a number before '{c}'")
+            })?;
+            total += amount * unit;
+            number.clear();
+        }
+        if !number.is_empty() {
+            return Err(format!(
+                "Invalid duration '{value}': missing unit after '{number}'"
+            ));
+        }
+        Ok(Self(Duration::from_secs(total)))
+    }
+}
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut secs = self.0.as_secs();
+        if secs == 0 {
+            return write!(f, "0s");
+        }
+        for (unit, unit_secs) in [("d", 86400), ("h", 3600), ("m", 60), ("s", 1)] {
+            if secs >= unit_secs {
+                write!(f, "{}{unit}", secs / unit_secs)?;
+                secs %= unit_secs;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for HumanDuration {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<HumanDuration> for String {
+    fn from(duration: HumanDuration) -> Self {
+        duration.to_string()
+    }
+}
+
+/// A size in bytes written as a number with an optional unit, e.g. `"512m"`,
+/// `"8g"` or `"8GiB"`. Units are binary (`k` = 1024 bytes); a bare number means bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    const UNITS: [(&'static str, u64); 5] = [
+        ("t", 1 << 40),
+        ("g", 1 << 30),
+        ("m", 1 << 20),
+        ("k", 1 << 10),
+        ("b", 1),
+    ];
+
+    /// Parses a size such as `"8g"` or `"1.5GB"`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let trimmed = value.trim();
+        let lower = trimmed.to_ascii_lowercase();
+        let split = lower
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(lower.len());
+        let (number, unit) = lower.split_at(split);
+        let number: f64 = number
+            .parse()
+            .map_err(|_| format!("Invalid size '{trimmed}': expected a number"))?;
+        let unit = unit.trim();
+        let unit = unit
+            .strip_suffix("ib")
+            .or_else(|| unit.strip_suffix('b').filter(|u| !u.is_empty()))
+            .unwrap_or(unit);
+        let multiplier = if unit.is_empty() {
+            1
+        } else {
+            Self::UNITS
+                .iter()
+                .find(|(name, _)| *name == unit)
+                .map(|(_, multiplier)| *multiplier)
+                .ok_or_else(|| format!("Invalid size '{trimmed}': unknown unit '{unit}'"))?
+        };
+        Ok(Self((number * multiplier as f64) as u64))
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (unit, multiplier) in Self::UNITS {
+            if self.0 >= multiplier && self.0 % multiplier == 0 {
+                return write!(f, "{}{unit}", self.0 / multiplier);
+            }
+        }
+        write!(f, "{}b", self.0)
+    }
+}
+
+impl TryFrom<String> for ByteSize {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<ByteSize> for String {
+    fn from(size: ByteSize) -> Self {
+        size.to_string()
+    }
+}
+
+/// GPUs given to a job's container.
+///
+/// Written as a device count (`gpus = 2`), `"all"`, or a comma-separated list of
+/// device IDs (`gpus = "0,1"`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "GpusValue", into = "GpusValue")]
+pub enum Gpus {
+    /// Every GPU on the host.
+    All,
+    /// Any `n` GPUs; `0` disables GPU passthrough.
+    Count(u32),
+    /// The GPUs with these device IDs.
+    Devices(Vec<String>),
+}
+
+/// Raw TOML form of [`Gpus`].
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum GpusValue {
+    Count(u32),
+    Text(String),
+}
+
+impl TryFrom<GpusValue> for Gpus {
+    type Error = String;
+
+    fn try_from(value: GpusValue) -> Result<Self, Self::Error> {
+        match value {
+            GpusValue::Count(count) => Ok(Gpus::Count(count)),
+            GpusValue::Text(text) if text.trim() == "all" => Ok(Gpus::All),
+            GpusValue::Text(text) => {
+                let ids: Vec<String> = text.split(',').map(|id| id.trim().to_string()).collect();
+                if ids.iter().any(|id| id.is_empty()) {
+                    return Err(format!(
+                        "Invalid gpus '{text}': expected a count, \"all\" or device IDs like \"0,1\""
+                    ));
+                }
+                Ok(Gpus::Devices(ids))
+            }
+        }
+    }
+}
+
+impl From<Gpus> for GpusValue {
+    fn from(gpus: Gpus) -> Self {
+        match gpus {
+            Gpus::All => GpusValue::Text("all".to_string()),
+            Gpus::Count(count) => GpusValue::Count(count),
+            Gpus::Devices(ids) => GpusValue::Text(ids.join(",")),
+        }
+    }
+}
+
+/// Whether a job runs with GPUs, set as `gpu` in the `[resources]` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GpuPolicy {
+    /// The job needs a GPU; the run fails before any job starts on a host without one.
+    Required,
+    /// The job uses a GPU when the host has one and runs on CPU otherwise.
+    Optional,
+    /// The job never gets a GPU.
+    None,
+}
+
+/// A host path mounted into job containers, set in a `[[mounts]]` entry.
+///
+/// ```toml
+/// [[mounts]]
+/// host = "/data/references"
+/// container = "/references"
+/// read_only = true
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Mount {
+    /// Path on the host. Relative paths resolve against the workflow folder for
+    /// workflow.toml mounts and against the job folder for job.toml mounts.
+    pub host: String,
+    /// Absolute path inside the container.
+    pub container: String,
+    /// Mounts the path read-only.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+}
+
+/// Values of one axis of a job's `[matrix]`.
+///
+/// ```toml
+/// [matrix]
+/// ligand = { inputs = "*.sdf" }
+/// temperature = [300, 310]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MatrixAxis {
+    /// Explicit values, e.g. `["a.sdf", "b.sdf"]`.
+    Values(Vec<toml::Value>),
+    /// Paths of the files in the job's `inputs/` matching a glob, relative to `inputs/`.
+    Inputs { inputs: String },
+}
+
+/// Axes of a parameter sweep by parameter name. The job runs once for every
+/// combination of the axes' values.
+pub type Matrix = BTreeMap<String, MatrixAxis>;
+
+/// Files a job takes from its dependencies' outputs, staged into its `inputs/`.
+///
+/// ```toml
+/// inputs = ["*.csv"]                 # patterns over every dependency's outputs
+///
+/// [inputs]                           # or named inputs bound to dependency outputs
+/// model = "02_train.model"
+/// metrics = { from = "03_eval.metrics", optional = true, path = "scores" }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Inputs {
+    /// Glob patterns matched against the file names in each dependency's
+    /// `outputs/`; matches are copied flat into `inputs/`. Empty copies everything.
+    Patterns(Vec<String>),
+    /// Bindings by input name, each staged into its own folder of `inputs/`.
+    Named(BTreeMap<String, InputBinding>),
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Inputs::Patterns(Vec::new())
+    }
+}
+
+impl Inputs {
+    /// Returns the input patterns; empty for named inputs.
+    pub fn patterns(&self) -> &[String] {
+        match self {
+            Inputs::Patterns(patterns) => patterns,
+            Inputs::Named(_) => &[],
+        }
+    }
+}
+
+/// A named input: the dependency output it takes, as `"<job>.<output>"`, or all
+/// outputs of a dependency, as `"<job>"`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum InputBinding {
+    /// A required input staged into `inputs/<job>/`, e.g. `"02_train.model"`.
+    Source(String),
+    /// An input with options, e.g. `{ from = "02_train.model", optional = true }`.
+    Detailed {
+        /// The dependency output, as in `Source`.
+        from: String,
+        /// Lets the job start when the output has no files.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        optional: bool,
+        /// Folder of `inputs/` the files are staged into instead of `<job>`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+    },
+}
+
+impl InputBinding {
+    /// Returns the bound dependency output, e.g. `"02_train.model"`.
+    pub fn source(&self) -> &str {
+        match self {
+            InputBinding::Source(from) | InputBinding::Detailed { from, .. } => from,
+        }
+    }
+
+    /// Returns `true` if the job may start without files for this input.
+    pub fn optional(&self) -> bool {
+        matches!(self, InputBinding::Detailed { optional: true, .. })
+    }
+
+    /// Returns the folder of `inputs/` chosen for the input, if any.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            InputBinding::Detailed { path, .. } => path.as_deref(),
+            InputBinding::Source(_) => None,
+        }
+    }
+
+    /// Splits the source into a dependency job and an output name, given the
+    /// names of the job's dependencies: `"02_train.model"` becomes
+    /// `("02_train", Some("model"))` and `"02_train"` becomes `("02_train", None)`.
+    ///
+    /// Returns `None` if the source names no dependency.
+    pub fn split_source<S: AsRef<str>>(&self, dependencies: &[S]) -> Option<(&str, Option<&str>)> {
+        let source = self.source();
+        let is_dependency = |name: &str| dependencies.iter().any(|d| d.as_ref() == name);
+        if is_dependency(source) {
+            return Some((source, None));
+        }
+        match source.rsplit_once('.') {
+            Some((job, output)) if is_dependency(job) && !output.is_empty() => {
+                Some((job, Some(output)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Files a job collects into its `outputs/` after its scripts succeed.
+///
+/// ```toml
+/// outputs = ["results/*.json"]       # patterns, collected flat into outputs/
+///
+/// [outputs]                          # or named outputs, each in outputs/<name>/
+/// model = "model.pkl"
+/// metrics = "metrics/*.json"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Outputs {
+    /// Glob patterns relative to the job folder.
+    Patterns(Vec<String>),
+    /// A glob pattern by output name; other jobs bind inputs to these names.
+    Named(BTreeMap<String, String>),
+}
+
+impl Default for Outputs {
+    fn default() -> Self {
+        Outputs::Patterns(Vec::new())
+    }
+}
+
+impl Outputs {
+    /// Returns `true` if the job collects no outputs.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Outputs::Patterns(patterns) => patterns.is_empty(),
+            Outputs::Named(named) => named.is_empty(),
+        }
+    }
+
+    /// Returns the names of the job's named outputs; empty for plain patterns.
+    pub fn names(&self) -> Vec<&str> {
+        match self {
+            Outputs::Patterns(_) => Vec::new(),
+            Outputs::Named(named) => named.keys().map(String::as_str).collect(),
+        }
+    }
+}
+
+/// Resource limits of a job's container, set in the `[resources]` table.
+/// Every field is optional; unset fields leave the runtime's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Resources {
+    /// CPU quota in cores, e.g. `2` or `0.5`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<f64>,
+    /// Memory limit, e.g. `"8g"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<ByteSize>,
+    /// Size of `/dev/shm`, e.g. `"1g"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shm_size: Option<ByteSize>,
+    /// Maximum number of processes in the container.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids_limit: Option<i64>,
+    /// Whether the job runs with GPUs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu: Option<GpuPolicy>,
+    /// GPUs passed through to the container.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpus: Option<Gpus>,
+}
+
+impl Resources {
+    /// Returns `true` if no limit is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns the job's GPU policy: `gpu` when set, otherwise `optional` when
+    /// `gpus` asks for devices and `none` for `gpus = 0`.
+    ///
+    /// `None` means the job did not say, and the backend decides from the image.
+    pub fn gpu_policy(&self) -> Option<GpuPolicy> {
+        match (self.gpu, &self.gpus) {
+            (Some(policy), _) => Some(policy),
+            (None, Some(Gpus::Count(0))) => Some(GpuPolicy::None),
+            (None, Some(_)) => Some(GpuPolicy::Optional),
+            (None, None) => None,
         }
     }
 }
@@ -268,15 +955,58 @@ pub struct JobMeta {
     /// Scripts to execute.
     #[serde(default)]
     pub scripts: Scripts,
-    /// List of input file patterns to copy from dependent jobs.
+    /// Files to copy from dependent jobs: patterns, or named inputs bound to
+    /// named outputs of dependencies.
     #[serde(default)]
-    pub inputs: Vec<String>,
-    /// List of output file patterns to collect from the job.
+    pub inputs: Inputs,
+    /// Files to collect from the job: patterns, or patterns by output name.
     #[serde(default)]
-    pub outputs: Vec<String>,
+    pub outputs: Outputs,
     /// Parameter definitions for this job.
     #[serde(default)]
     pub params: HashMap<String, ParamDefinition>,
+    /// Whether the job's outputs may be restored from the result cache.
+    /// Only takes effect when caching is enabled for the workflow; set to
+    /// `false` for jobs with side effects or non-deterministic results.
+    #[serde(default = "default_cache", skip_serializing_if = "is_default_cache")]
+    pub cache: bool,
+    /// Maximum run time of each script (e.g. `"2h"`); a script that runs longer
+    /// is killed and counts as failed. No limit when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<HumanDuration>,
+    /// How many times a failed or timed-out script is run again.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub retries: u32,
+    /// Wait before the first retry (e.g. `"30s"`), doubled for every further retry.
+    /// Retries start immediately when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_backoff: Option<HumanDuration>,
+    /// CPU, memory and GPU limits of the job's container.
+    #[serde(default, skip_serializing_if = "Resources::is_empty")]
+    pub resources: Resources,
+    /// Host paths mounted into the job's container, after those of workflow.toml.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mounts: Vec<Mount>,
+    /// Condition deciding whether the job runs (e.g. `"params.use_gpu"`); the job
+    /// is skipped when it is false. See [`crate::condition`] for the syntax.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+    /// Parameter sweep: the job runs once per combination of these values, each
+    /// run getting the values as parameters and its own `outputs/`.
+    #[serde(default, skip_serializing_if = "Matrix::is_empty")]
+    pub matrix: Matrix,
+}
+
+fn default_cache() -> bool {
+    true
+}
+
+fn is_default_cache(cache: &bool) -> bool {
+    *cache
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
 }
 
 impl JobMeta {
@@ -287,12 +1017,30 @@ impl JobMeta {
             description,
             container,
             scripts: Scripts::default(),
-            inputs: Vec::new(),
-            outputs: Vec::new(),
+            inputs: Inputs::default(),
+            outputs: Outputs::default(),
             params: HashMap::new(),
+            cache: default_cache(),
+            timeout: None,
+            retries: 0,
+            retry_backoff: None,
+            resources: Resources::default(),
+            mounts: Vec::new(),
+            when: None,
+            matrix: Matrix::new(),
         }
     }
 
+    /// Parses the job's `when` condition, if it has one.
+    pub fn condition(&self) -> Result<Option<Condition>, String> {
+        self.when
+            .as_deref()
+            .map(|when| {
+                Condition::parse(when).map_err(|e| format!("Invalid 'when' condition: {e}"))
+            })
+            .transpose()
+    }
+
     /// Adds a parameter definition to this job.
     pub fn add_param(&mut self, name: String, definition: ParamDefinition) {
         self.params.insert(name, definition);
@@ -315,17 +1063,12 @@ impl JobMeta {
 
     /// Validates a params HashMap against this job's parameter definitions.
     /// Accepts JSON-based JobParams and converts values for validation against TOML definitions.
+    /// Every required parameter must be set; see `params::validate_values`.
     pub fn validate_params(&self, params: &crate::params::JobParams) -> Result<(), String> {
-        for (param_name, param_value) in params {
-            if let Some(param_def) = self.params.get(param_name) {
-                // Convert JSON value to TOML for validation
-                let toml_value = crate::params::json_to_toml(param_value);
-                param_def.validate(&toml_value)?;
-            } else {
-                return Err(format!("Unknown parameter: {param_name}"));
-            }
+        if let Some(param_name) = params.keys().find(|name| !self.params.contains_key(*name)) {
+            return Err(format!("Unknown parameter: {param_name}"));
         }
-        Ok(())
+        crate::params::validate_values(&self.params, params, Path::new(""))
     }
 
     /// Generates default parameters based on the parameter definitions.
@@ -333,7 +1076,10 @@ impl JobMeta {
     pub fn generate_default_params(&self) -> crate::params::JobParams {
         self.params
             .iter()
-            .map(|(name, def)| (name.clone(), crate::params::toml_to_json(&def.default)))
+            .filter_map(|(name, def)| {
+                let default = def.default_value()?;
+                Some((name.clone(), crate::params::toml_to_json(default)))
+            })
             .collect()
     }
 }
@@ -369,6 +1115,254 @@ mod tests {
         assert_eq!(meta.container.image, "ubuntu:22.04");
     }
 
+    #[test]
+    fn test_parse_job_meta_cache_opt_out() {
+        let toml_str = r#"
+            name = "Test Job"
+            description = "A test job"
+
+            [container]
+            image = "ubuntu:22.04"
+        "#;
+        let meta: JobMeta = toml::from_str(toml_str).unwrap();
+        assert!(meta.cache);
+        assert!(!toml::to_string(&meta).unwrap().contains("cache"));
+
+        let toml_str = r#"
+            name = "Test Job"
+            description = "A test job"
+            cache = false
+
+            [container]
+            image = "ubuntu:22.04"
+        "#;
+        let meta: JobMeta = toml::from_str(toml_str).unwrap();
+        assert!(!meta.cache);
+        let reparsed: JobMeta = toml::from_str(&toml::to_string(&meta).unwrap()).unwrap();
+        assert!(!reparsed.cache);
+    }
+
+    #[test]
+    fn test_parse_job_meta_timeout_and_retries() {
+        let toml_str = r#"
+            name = "Test Job"
+            description = "A test job"
+            timeout = "2h"
+            retries = 2
+            retry_backoff = "30s"
+
+            [container]
+            image = "ubuntu:22.04"
+        "#;
+        let meta: JobMeta = toml::from_str(toml_str).unwrap();
+        assert_eq!(meta.timeout, Some(HumanDuration(Duration::from_secs(7200))));
+        assert_eq!(meta.retries, 2);
+        assert_eq!(
+            meta.retry_backoff,
+            Some(HumanDuration(Duration::from_secs(30)))
+        );
+
+        let serialized = toml::to_string(&meta).unwrap();
+        assert!(serialized.contains("timeout = \"2h\""));
+        assert_eq!(toml::from_str::<JobMeta>(&serialized).unwrap(), meta);
+
+        let invalid = toml_str.replace("\"2h\"", "\"2 hours\"");
+        assert!(toml::from_str::<JobMeta>(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_human_duration_parse_and_display() {
+        let parse = |s| HumanDuration::parse(s).map(|d| d.0.as_secs());
+        assert_eq!(parse("90"), Ok(90));
+        assert_eq!(parse("90s"), Ok(90));
+        assert_eq!(parse("1h30m"), Ok(5400));
+        assert_eq!(parse("1d"), Ok(86400));
+        assert!(parse("").is_err());
+        assert!(parse("5x").is_err());
+        assert!(parse("1h30").is_err());
+        assert!(parse("m").is_err());
+
+        assert_eq!(
+            HumanDuration(Duration::from_secs(5400)).to_string(),
+            "1h30m"
+        );
+        assert_eq!(HumanDuration(Duration::from_secs(0)).to_string(), "0s");
+    }
+
+    #[test]
+    fn test_parse_job_meta_mounts() {
+        let toml_str = r#"
+            name = "Test Job"
+            description = "A test job"
+
+            [container]
+            image = "ubuntu:22.04"
+
+            [[mounts]]
+            host = "/data/references"
+            container = "/references"
+            read_only = true
+
+            [[mounts]]
+            host = "scratch"
+            container = "/scratch"
+        "#;
+        let meta: JobMeta = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            meta.mounts,
+            vec![
+                Mount {
+                    host: "/data/references".to_string(),
+                    container: "/references".to_string(),
+                    read_only: true,
+                },
+                Mount {
+                    host: "scratch".to_string(),
+                    container: "/scratch".to_string(),
+                    read_only: false,
+                },
+            ]
+        );
+        let serialized = toml::to_string(&meta).unwrap();
+        assert_eq!(toml::from_str::<JobMeta>(&serialized).unwrap(), meta);
+    }
+
+    #[test]
+    fn test_parse_job_meta_when() {
+        let toml_str = r#"
+            name = "Test Job"
+            description = "A test job"
+            when = "params.use_gpu && exists('01_prepare/*.pdb')"
+
+            [container]
+            image = "ubuntu:22.04"
+        "#;
+        let meta: JobMeta = toml::from_str(toml_str).unwrap();
+        let condition = meta.condition().unwrap().unwrap();
+        assert_eq!(condition.jobs(), vec!["01_prepare"]);
+
+        let serialized = toml::to_string(&meta).unwrap();
+        assert_eq!(toml::from_str::<JobMeta>(&serialized).unwrap(), meta);
+
+        let mut meta = JobMeta::new(
+            "Test Job".to_string(),
+            String::new(),
+            Container::new("ubuntu:22.04".to_string()),
+        );
+        assert_eq!(meta.condition(), Ok(None));
+        meta.when = Some("params.a ==".to_string());
+        assert!(meta.condition().is_err());
+    }
+
+    #[test]
+    fn test_parse_job_meta_matrix() {
+        let toml_str = r#"
+            name = "Docking"
+            description = "Docks every ligand"
+
+            [container]
+            image = "ubuntu:22.04"
+
+            [matrix]
+            ligand = { inputs = "ligands/*.sdf" }
+            temperature = [300, 310]
+        "#;
+        let meta: JobMeta = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            meta.matrix["ligand"],
+            MatrixAxis::Inputs {
+                inputs: "ligands/*.sdf".to_string()
+            }
+        );
+        assert_eq!(
+            meta.matrix["temperature"],
+            MatrixAxis::Values(vec![toml::Value::Integer(300), toml::Value::Integer(310)])
+        );
+        let serialized = toml::to_string(&meta).unwrap();
+        assert_eq!(toml::from_str::<JobMeta>(&serialized).unwrap(), meta);
+    }
+
+    #[test]
+    fn test_parse_job_meta_resources() {
+        let toml_str = r#"
+            name = "Test Job"
+            description = "A test job"
+
+            [container]
+            image = "ubuntu:22.04"
+
+            [resources]
+            cpus = 2.5
+            memory = "8g"
+            shm_size = "512MB"
+            pids_limit = 256
+            gpus = "0,1"
+        "#;
+        let meta: JobMeta = toml::from_str(toml_str).unwrap();
+        assert_eq!(meta.resources.cpus, Some(2.5));
+        assert_eq!(meta.resources.memory, Some(ByteSize(8 << 30)));
+        assert_eq!(meta.resources.shm_size, Some(ByteSize(512 << 20)));
+        assert_eq!(meta.resources.pids_limit, Some(256));
+        assert_eq!(
+            meta.resources.gpus,
+            Some(Gpus::Devices(vec!["0".to_string(), "1".to_string()]))
+        );
+        let reparsed: JobMeta = toml::from_str(&toml::to_string(&meta).unwrap()).unwrap();
+        assert_eq!(reparsed, meta);
+
+        let count = toml_str.replace("\"0,1\"", "1");
+        let meta: JobMeta = toml::from_str(&count).unwrap();
+        assert_eq!(meta.resources.gpus, Some(Gpus::Count(1)));
+        let all = toml_str.replace("\"0,1\"", "\"all\"");
+        let meta: JobMeta = toml::from_str(&all).unwrap();
+        assert_eq!(meta.resources.gpus, Some(Gpus::All));
+        assert!(toml::from_str::<JobMeta>(&toml_str.replace("\"0,1\"", "\"0,\"")).is_err());
+
+        // No [resources] table is written when no limit is set
+        let meta = JobMeta::new(
+            "job".to_string(),
+            String::new(),
+            Container::new("ubuntu:22.04".to_string()),
+        );
+        assert!(!toml::to_string(&meta).unwrap().contains("resources"));
+    }
+
+    #[test]
+    fn test_resources_gpu_policy() {
+        let parse = |table: &str| -> Resources { toml::from_str(table).unwrap() };
+        assert_eq!(
+            parse("gpu = \"required\"").gpu_policy(),
+            Some(GpuPolicy::Required)
+        );
+        assert_eq!(
+            parse("gpu = \"none\"\ngpus = 2").gpu_policy(),
+            Some(GpuPolicy::None)
+        );
+        assert_eq!(
+            parse("gpus = \"1\"").gpu_policy(),
+            Some(GpuPolicy::Optional)
+        );
+        assert_eq!(parse("gpus = 0").gpu_policy(), Some(GpuPolicy::None));
+        assert_eq!(parse("").gpu_policy(), None);
+        assert!(toml::from_str::<Resources>("gpu = \"always\"").is_err());
+    }
+
+    #[test]
+    fn test_byte_size_parse_and_display() {
+        let parse = |s| ByteSize::parse(s).map(|size| size.0);
+        assert_eq!(parse("1024"), Ok(1024));
+        assert_eq!(parse("8g"), Ok(8 << 30));
+        assert_eq!(parse("8GiB"), Ok(8 << 30));
+        assert_eq!(parse("1.5k"), Ok(1536));
+        assert_eq!(parse("64b"), Ok(64));
+        assert!(parse("").is_err());
+        assert!(parse("8 gigs").is_err());
+        assert!(parse("g").is_err());
+
+        assert_eq!(ByteSize(8 << 30).to_string(), "8g");
+        assert_eq!(ByteSize(1536).to_string(), "1536b");
+    }
+
     #[test]
     fn test_parse_job_meta_with_scripts() {
         let toml_str = r#"
@@ -388,6 +1382,30 @@ mod tests {
         assert_eq!(meta.scripts.pre, "setup.sh");
         assert_eq!(meta.scripts.run, "compute.sh");
         assert_eq!(meta.scripts.post, "cleanup.sh");
+        assert_eq!(meta.scripts.interpreter, Interpreter::Bash);
+    }
+
+    #[test]
+    fn test_parse_scripts_interpreter_and_command() {
+        let scripts: Scripts = toml::from_str(
+            r#"
+            interpreter = "shebang"
+            command = ["python", "main.py", "--in", "inputs/"]
+        "#,
+        )
+        .unwrap();
+        assert_eq!(scripts.interpreter, Interpreter::Shebang);
+        assert_eq!(
+            scripts.command,
+            vec!["python", "main.py", "--in", "inputs/"]
+        );
+        assert_eq!(scripts.run, "./run.sh");
+
+        assert_eq!(Interpreter::Shebang.argv("run.sh"), vec!["./run.sh"]);
+        assert_eq!(Interpreter::Shebang.argv("./run.sh"), vec!["./run.sh"]);
+        assert_eq!(Interpreter::Sh.argv("./run.sh"), vec!["sh", "./run.sh"]);
+        assert_eq!(Interpreter::Python3.argv("a.py"), vec!["python3", "a.py"]);
+        assert!(toml::from_str::<Scripts>("interpreter = \"perl\"").is_err());
     }
 
     #[test]
@@ -415,11 +1433,19 @@ mod tests {
 
         let pdb_id = meta.params.get("pdb_id").unwrap();
         assert_eq!(pdb_id.param_type, ParamType::String);
-        assert_eq!(pdb_id.default.as_str().unwrap(), "4OHU");
+        assert_eq!(pdb_id.default.as_ref().unwrap().as_str().unwrap(), "4OHU");
 
         let num_iterations = meta.params.get("num_iterations").unwrap();
         assert_eq!(num_iterations.param_type, ParamType::Integer);
-        assert_eq!(num_iterations.default.as_integer().unwrap(), 100);
+        assert_eq!(
+            num_iterations
+                .default
+                .as_ref()
+                .unwrap()
+                .as_integer()
+                .unwrap(),
+            100
+        );
     }
 
     #[test]
@@ -464,8 +1490,55 @@ mod tests {
         "#;
 
         let meta: JobMeta = toml::from_str(toml_str).unwrap();
-        assert_eq!(meta.inputs, vec!["*.csv"]);
-        assert_eq!(meta.outputs, vec!["results/*.json"]);
+        assert_eq!(meta.inputs, Inputs::Patterns(vec!["*.csv".to_string()]));
+        assert_eq!(
+            meta.outputs,
+            Outputs::Patterns(vec!["results/*.json".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_job_meta_with_named_io() {
+        let toml_str = r#"
+            name = "Test Job"
+            description = "A test job"
+
+            [container]
+            image = "ubuntu:22.04"
+
+            [inputs]
+            model = "02.train.model"
+            scores = { from = "03_eval.metrics", optional = true, path = "eval" }
+            raw = "01_extract"
+
+            [outputs]
+            report = "report.html"
+            figures = "figures/*.png"
+        "#;
+
+        let meta: JobMeta = toml::from_str(toml_str).unwrap();
+        let Inputs::Named(inputs) = &meta.inputs else {
+            panic!("expected named inputs");
+        };
+        let dependencies = ["01_extract", "02.train", "03_eval"];
+        assert_eq!(
+            inputs["model"].split_source(&dependencies),
+            Some(("02.train", Some("model")))
+        );
+        assert_eq!(
+            inputs["raw"].split_source(&dependencies),
+            Some(("01_extract", None))
+        );
+        assert_eq!(inputs["raw"].split_source(&["02.train"]), None);
+        assert!(inputs["scores"].optional());
+        assert_eq!(inputs["scores"].path(), Some("eval"));
+        assert!(!inputs["model"].optional());
+        assert!(meta.inputs.patterns().is_empty());
+        assert_eq!(meta.outputs.names(), vec!["figures", "report"]);
+
+        // Named inputs and outputs survive a round trip
+        let reparsed: JobMeta = toml::from_str(&toml::to_string_pretty(&meta).unwrap()).unwrap();
+        assert_eq!(reparsed, meta);
     }
 
     #[test]
@@ -494,6 +1567,108 @@ mod tests {
         assert!(meta.validate_params(&params).is_err());
     }
 
+    #[test]
+    fn test_param_constraints() {
+        let toml_str = r#"
+            name = "Test Job"
+            description = "A test job"
+
+            [container]
+            image = "ubuntu:22.04"
+
+            [params.epochs]
+            type = "integer"
+            default = 10
+            hint = "Training epochs"
+            min = 1
+            max = 1000
+
+            [params.pdb_id]
+            type = "string"
+            required = true
+            hint = "PDB entry"
+            pattern = "[0-9][A-Za-z0-9]{3}"
+
+            [params.label]
+            type = "string"
+            default = "run"
+            hint = "Label"
+            min_length = 2
+
+            [params.weights]
+            type = "array"
+            default = [0.5, 1]
+            hint = "Weights"
+            items = "float"
+            min = 0
+
+            [params.structure]
+            type = "file"
+            default = "Cargo.toml"
+            hint = "Structure file"
+            must_exist = true
+        "#;
+
+        let meta: JobMeta = toml::from_str(toml_str).unwrap();
+        let pdb_id = &meta.params["pdb_id"];
+        assert!(pdb_id.is_required());
+        assert_eq!(pdb_id.default, None);
+        assert!(!meta.generate_default_params().contains_key("pdb_id"));
+
+        let epochs = &meta.params["epochs"];
+        assert!(epochs.validate(&toml::Value::Integer(1000)).is_ok());
+        assert_eq!(
+            epochs.validate(&toml::Value::Integer(0)),
+            Err("Value 0 is below the minimum of 1".to_string())
+        );
+        assert!(pdb_id.validate(&toml::Value::from("4OHU")).is_ok());
+        assert_eq!(
+            pdb_id.validate(&toml::Value::from("4OHU.pdb")),
+            Err("Value '4OHU.pdb' does not match pattern '[0-9][A-Za-z0-9]{3}'".to_string())
+        );
+        assert!(
+            meta.params["label"]
+                .validate(&toml::Value::from("a"))
+                .is_err()
+        );
+
+        let weights = &meta.params["weights"];
+        assert!(
+            weights
+                .validate(&toml::Value::try_from([0.5, 2.0]).unwrap())
+                .is_ok()
+        );
+        assert_eq!(
+            weights.validate(&toml::Value::try_from(["0.5"]).unwrap()),
+            Err("Element 1: Expected float, got \"0.5\"".to_string())
+        );
+        assert!(
+            weights
+                .validate(&toml::Value::try_from([-1.0]).unwrap())
+                .is_err()
+        );
+
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let structure = &meta.params["structure"];
+        assert!(
+            structure
+                .validate_in(&toml::Value::from("Cargo.toml"), crate_dir)
+                .is_ok()
+        );
+        assert_eq!(
+            structure.validate_in(&toml::Value::from("missing.pdb"), crate_dir),
+            Err("File 'missing.pdb' does not exist".to_string())
+        );
+
+        // Errors name the parameter; required parameters must be set
+        let mut params = meta.generate_default_params();
+        params.insert("epochs".to_string(), serde_json::json!(5000));
+        assert_eq!(
+            crate::params::validate_values(&meta.params, &params, crate_dir),
+            Err("Parameter 'epochs': Value 5000 is above the maximum of 1000\nParameter 'pdb_id' is required but has no value".to_string())
+        );
+    }
+
     #[test]
     fn test_generate_default_params() {
         let toml_str = r#"
@@ -560,6 +1735,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_container_with_dockerfile() {
+        let toml_str = r#"
+            name = "Test Job"
+            description = "A test job"
+
+            [container]
+            dockerfile = "Dockerfile"
+            target = "runtime"
+
+            [container.build_args]
+            PYTHON_VERSION = "3.12"
+        "#;
+
+        let meta: JobMeta = toml::from_str(toml_str).unwrap();
+        assert!(meta.container.image.is_empty());
+        assert_eq!(meta.container.target, Some("runtime".to_string()));
+        assert_eq!(
+            meta.container.build_args.get("PYTHON_VERSION"),
+            Some(&"3.12".to_string())
+        );
+        assert_eq!(
+            meta.container.get_image_source(),
+            ImageSource::Dockerfile("Dockerfile".to_string())
+        );
+    }
+
+    #[test]
+    fn test_dockerfile_takes_precedence_over_image() {
+        let mut container = Container::new("lab/tool".to_string());
+        container.dockerfile = Some("docker/Dockerfile".to_string());
+        assert_eq!(
+            container.get_image_source(),
+            ImageSource::Dockerfile("docker/Dockerfile".to_string())
+        );
+    }
+
     #[test]
     fn test_image_source_local_image() {
         let mut container = Container::new("aso-rna:latest".to_string());
@@ -19,6 +19,8 @@
 // run = "compute.sh"
 // post = "cleanup.sh"
 
+pub mod condition;
+pub mod interpolate;
 pub mod job;
 pub mod params;
 pub mod workflow;
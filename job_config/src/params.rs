@@ -8,10 +8,12 @@
 //! while parameter *values* use JSON for runtime storage.
 
 use serde_json;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::Path;
 
+use crate::job::ParamDefinition;
+
 /// Error type for parameter operations.
 #[derive(Debug)]
 pub enum ParamsError {
@@ -176,10 +178,131 @@ pub fn json_to_toml(value: &serde_json::Value) -> toml::Value {
     }
 }
 
+/// Prefix of the environment variables parameters are passed as, unless a
+/// workflow sets `param_env_prefix`.
+pub const DEFAULT_ENV_PREFIX: &str = "PARAM_";
+
+/// Name of the environment variable parameter `name` is passed as: `prefix`,
+/// then the name in upper case with every character other than ASCII letters,
+/// digits and `_` replaced by `_`, e.g. `my-param` becomes `PARAM_MY_PARAM`.
+/// A name that would start with a digit gets a leading `_`.
+pub fn env_var_name(prefix: &str, name: &str) -> String {
+    let mut var = prefix.to_string();
+    var.extend(name.chars().map(|c| {
+        if c.is_ascii_alphanumeric() {
+            c.to_ascii_uppercase()
+        } else {
+            '_'
+        }
+    }));
+    if var.starts_with(|c: char| c.is_ascii_digit()) {
+        var.insert(0, '_');
+    }
+    var
+}
+
+/// Text a parameter value is passed as in its environment variable: strings as
+/// they are, other values as JSON, e.g. `42`, `true`, `[1,2]` or `{"a":1}`.
+pub fn env_var_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Checks that no two of the parameters `names` are passed as the same
+/// environment variable with `prefix`.
+///
+/// Returns `Err` with one line per clash.
+pub fn check_env_var_names<'a>(
+    prefix: &str,
+    names: impl IntoIterator<Item = &'a String>,
+) -> Result<(), String> {
+    let mut by_var: BTreeMap<String, Vec<&String>> = BTreeMap::new();
+    for name in names {
+        by_var
+            .entry(env_var_name(prefix, name))
+            .or_default()
+            .push(name);
+    }
+
+    let mut errors = Vec::new();
+    for (var, mut names) in by_var {
+        if names.len() < 2 {
+            continue;
+        }
+        names.sort();
+        names.dedup();
+        let names: Vec<String> = names.iter().map(|name| format!("'{name}'")).collect();
+        errors.push(format!(
+            "Parameters {} are all passed as {var}",
+            names.join(", ")
+        ));
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+/// Checks parameter values against their definitions: every value must be valid
+/// and every required parameter must have one. Relative paths of `must_exist`
+/// parameters are resolved against `dir`. Values without a definition are not
+/// checked.
+///
+/// Returns `Err` with one line per invalid parameter, each starting with its name.
+pub fn validate_values(
+    definitions: &HashMap<String, ParamDefinition>,
+    values: &HashMap<String, serde_json::Value>,
+    dir: &Path,
+) -> Result<(), String> {
+    let mut names: Vec<&String> = definitions.keys().collect();
+    names.sort();
+
+    let mut errors = Vec::new();
+    for name in names {
+        let definition = &definitions[name];
+        match values.get(name) {
+            Some(value) => {
+                if let Err(e) = definition.validate_in(&json_to_toml(value), dir) {
+                    errors.push(format!("Parameter '{name}': {e}"));
+                }
+            }
+            None if definition.is_required() => {
+                errors.push(format!("Parameter '{name}' is required but has no value"));
+            }
+            None => {}
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_env_var_names() {
+        assert_eq!(env_var_name("PARAM_", "batch_size"), "PARAM_BATCH_SIZE");
+        assert_eq!(env_var_name("PARAM_", "my-param.v2"), "PARAM_MY_PARAM_V2");
+        assert_eq!(env_var_name("", "3d_model"), "_3D_MODEL");
+        assert_eq!(env_var_value(&serde_json::json!("a b")), "a b");
+        assert_eq!(env_var_value(&serde_json::json!([1, "x"])), r#"[1,"x"]"#);
+
+        let names = ["my-param", "my_param", "MY_PARAM", "other"].map(String::from);
+        assert_eq!(
+            check_env_var_names("PARAM_", &names).unwrap_err(),
+            "Parameters 'MY_PARAM', 'my-param', 'my_param' are all passed as PARAM_MY_PARAM"
+        );
+        assert!(check_env_var_names("PARAM_", &names[2..]).is_ok());
+    }
+
     #[test]
     fn test_job_params_json() {
         let json_str = r#"{
@@ -3,8 +3,8 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
-use crate::job::{JobError, ParamDefinition};
-use crate::params::{WorkflowParams, json_to_toml, toml_to_json};
+use crate::job::{JobError, Matrix, Mount, ParamDefinition};
+use crate::params::{DEFAULT_ENV_PREFIX, WorkflowParams, toml_to_json, validate_values};
 
 // Re-export WorkflowParams from params module for convenience
 pub use crate::params::WorkflowParams as WorkflowParamsType;
@@ -25,6 +25,65 @@ pub struct WorkflowMeta {
     /// Host environment variable names to forward into the container exec environment.
     #[serde(default)]
     pub env_passthrough: Option<Vec<String>>,
+    /// Maximum number of independent jobs to run at the same time.
+    /// Unset means jobs run one at a time; `silva --jobs N` overrides this value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_parallel: Option<usize>,
+    /// Restore job outputs from the result cache when a job's scripts, config,
+    /// image, params and inputs match an earlier run. `silva --cache` enables
+    /// it for a single run.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cache: bool,
+    /// How jobs share containers. Files without the key keep the shared
+    /// `workflow` mode; workflows created by silva use `job`.
+    #[serde(default)]
+    pub isolation: Isolation,
+    /// Whether parameters are passed to scripts as environment variables. With
+    /// `false`, scripts read them from `.silva/params.json` only.
+    #[serde(
+        default = "default_param_env",
+        skip_serializing_if = "is_default_param_env"
+    )]
+    pub param_env: bool,
+    /// Prefix of the parameter environment variables; `PARAM_` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub param_env_prefix: Option<String>,
+    /// Host paths mounted into the container of every job.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mounts: Vec<Mount>,
+    /// Parameter sweeps by job name, e.g. `[matrix.02_dock]`. An axis replaces
+    /// the job.toml `[matrix]` axis of the same name.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub matrix: HashMap<String, Matrix>,
+}
+
+fn default_param_env() -> bool {
+    true
+}
+
+fn is_default_param_env(param_env: &bool) -> bool {
+    *param_env
+}
+
+/// Container isolation mode of a workflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Isolation {
+    /// Jobs with the same image share one container that mounts the whole workflow folder.
+    #[default]
+    Workflow,
+    /// Every job gets a fresh container that mounts only its own job folder,
+    /// with `inputs/` read-only and `outputs/` writable.
+    Job,
+}
+
+impl Isolation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Isolation::Workflow => "workflow",
+            Isolation::Job => "job",
+        }
+    }
 }
 
 impl WorkflowMeta {
@@ -36,9 +95,26 @@ impl WorkflowMeta {
             dependencies: HashMap::new(),
             params: HashMap::new(),
             env_passthrough: None,
+            max_parallel: None,
+            cache: false,
+            isolation: Isolation::Job,
+            mounts: Vec::new(),
+            matrix: HashMap::new(),
+            param_env: true,
+            param_env_prefix: None,
         }
     }
 
+    /// Prefix of the environment variables parameters are passed as, or `None`
+    /// when the workflow opts out with `param_env = false`.
+    pub fn env_prefix(&self) -> Option<&str> {
+        self.param_env.then(|| {
+            self.param_env_prefix
+                .as_deref()
+                .unwrap_or(DEFAULT_ENV_PREFIX)
+        })
+    }
+
     /// Gets the dependencies for a specific job.
     /// Returns an empty slice if the job has no dependencies.
     pub fn get_job_dependencies(&self, job_name: &str) -> &[String] {
@@ -75,17 +151,12 @@ impl WorkflowMeta {
 
     /// Validates a params HashMap against this workflow's parameter definitions.
     /// Accepts JSON-based WorkflowParams and converts values for validation against TOML definitions.
+    /// Every required parameter must be set; see `params::validate_values`.
     pub fn validate_params(&self, params: &WorkflowParams) -> Result<(), String> {
-        for (param_name, param_value) in params {
-            if let Some(param_def) = self.params.get(param_name) {
-                // Convert JSON value to TOML for validation
-                let toml_value = json_to_toml(param_value);
-                param_def.validate(&toml_value)?;
-            } else {
-                return Err(format!("Unknown parameter: {param_name}"));
-            }
+        if let Some(param_name) = params.keys().find(|name| !self.params.contains_key(*name)) {
+            return Err(format!("Unknown parameter: {param_name}"));
         }
-        Ok(())
+        validate_values(&self.params, params, Path::new(""))
     }
 
     /// Generates default parameters based on the parameter definitions.
@@ -93,7 +164,7 @@ impl WorkflowMeta {
     pub fn generate_default_params(&self) -> WorkflowParams {
         self.params
             .iter()
-            .map(|(name, def)| (name.clone(), toml_to_json(&def.default)))
+            .filter_map(|(name, def)| Some((name.clone(), toml_to_json(def.default_value()?))))
             .collect()
     }
 }
@@ -127,6 +198,130 @@ mod tests {
         assert_eq!(metadata.name, "ML Pipeline");
         assert_eq!(metadata.description, "A machine learning pipeline");
         assert!(metadata.env_passthrough.is_none());
+        assert!(metadata.max_parallel.is_none());
+    }
+
+    #[test]
+    fn test_workflow_meta_param_env() {
+        let metadata = WorkflowMeta::new("wf".to_string(), String::new());
+        assert_eq!(metadata.env_prefix(), Some("PARAM_"));
+        assert!(!toml::to_string(&metadata).unwrap().contains("param_env"));
+
+        let metadata: WorkflowMeta = toml::from_str(
+            r#"
+            name = "wf"
+            description = ""
+            param_env_prefix = "SILVA_"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(metadata.env_prefix(), Some("SILVA_"));
+
+        let metadata: WorkflowMeta = toml::from_str(
+            r#"
+            name = "wf"
+            description = ""
+            param_env = false
+        "#,
+        )
+        .unwrap();
+        assert_eq!(metadata.env_prefix(), None);
+        assert!(
+            toml::to_string(&metadata)
+                .unwrap()
+                .contains("param_env = false")
+        );
+    }
+
+    #[test]
+    fn test_workflow_meta_with_max_parallel() {
+        let toml_str = r#"
+            name = "ML Pipeline"
+            description = "A machine learning pipeline"
+            max_parallel = 4
+        "#;
+
+        let metadata: WorkflowMeta = toml::from_str(toml_str).unwrap();
+        assert_eq!(metadata.max_parallel, Some(4));
+        assert!(!metadata.cache);
+    }
+
+    #[test]
+    fn test_workflow_meta_with_mounts() {
+        let toml_str = r#"
+            name = "ML Pipeline"
+            description = "A machine learning pipeline"
+
+            [[mounts]]
+            host = "datasets"
+            container = "/datasets"
+            read_only = true
+        "#;
+
+        let metadata: WorkflowMeta = toml::from_str(toml_str).unwrap();
+        assert_eq!(metadata.mounts.len(), 1);
+        assert_eq!(metadata.mounts[0].host, "datasets");
+        assert_eq!(metadata.mounts[0].container, "/datasets");
+        assert!(metadata.mounts[0].read_only);
+    }
+
+    #[test]
+    fn test_workflow_meta_with_matrix() {
+        let toml_str = r#"
+            name = "Screening"
+            description = "Docks a ligand library"
+
+            [matrix.02_dock]
+            ligand = ["a.sdf", "b.sdf"]
+        "#;
+
+        let metadata: WorkflowMeta = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            metadata.matrix["02_dock"]["ligand"],
+            crate::job::MatrixAxis::Values(vec![
+                toml::Value::String("a.sdf".to_string()),
+                toml::Value::String("b.sdf".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_workflow_meta_with_cache() {
+        let toml_str = r#"
+            name = "ML Pipeline"
+            description = "A machine learning pipeline"
+            cache = true
+        "#;
+
+        let metadata: WorkflowMeta = toml::from_str(toml_str).unwrap();
+        assert!(metadata.cache);
+    }
+
+    #[test]
+    fn test_workflow_meta_isolation() {
+        // Existing files without the key keep sharing containers
+        let toml_str = r#"
+            name = "ML Pipeline"
+            description = "A machine learning pipeline"
+        "#;
+        let metadata: WorkflowMeta = toml::from_str(toml_str).unwrap();
+        assert_eq!(metadata.isolation, Isolation::Workflow);
+
+        let metadata: WorkflowMeta =
+            toml::from_str(&format!("{toml_str}\nisolation = \"job\"")).unwrap();
+        assert_eq!(metadata.isolation, Isolation::Job);
+
+        // New workflows isolate jobs, and keep doing so once saved
+        let metadata = WorkflowMeta::new("New".to_string(), String::new());
+        assert_eq!(metadata.isolation, Isolation::Job);
+        let content = toml::to_string(&metadata).unwrap();
+        assert!(content.contains("isolation = \"job\""));
+        assert!(
+            toml::from_str::<WorkflowMeta>(
+                "name = \"x\"\ndescription = \"\"\nisolation = \"container\""
+            )
+            .is_err()
+        );
     }
 
     #[test]
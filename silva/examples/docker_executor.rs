@@ -7,6 +7,8 @@ use silva::components::{
     docker::{executor::DockerExecutor, job::JobStatus, logs::LogLine},
     workflow,
 };
+use silva::executor::{EnvironmentSpec, Executor, ScriptCommand};
+use silva::runner::{job_env_vars, merge_params};
 use tokio::sync::mpsc;
 
 /// Example of running a Docker job programmatically.
@@ -103,25 +105,55 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("Starting job execution...");
             println!("─────────────────────────────\n");
 
-            let mut container_registry = std::collections::HashMap::new();
-            let container_id = executor
-                .run_job(
-                    (&workflow_meta, &workflow_path, &workflow_params),
-                    (&job, &config, &job_params),
-                    &[],
-                    &mut container_registry,
-                    &mut cancel_rx,
-                )
+            let image = executor
+                .prepare_image(&config, &job_folder_path)
                 .await
-                .map_err(|e| {
-                    eprintln!("✗ Job execution error: {e}");
+                .map_err(|e| eprintln!("✗ Image error: {e}"))
+                .unwrap();
+            let container_id = executor
+                .start_environment(EnvironmentSpec {
+                    config: &config,
+                    image: &image,
+                    workflow_folder: &workflow_path,
+                    job_name: &job_name,
+                    isolation: workflow_meta.isolation,
+                    mounts: &[],
                 })
+                .await
+                .map_err(|e| eprintln!("✗ Container error: {e}"))
                 .unwrap();
 
+            let merged_params = merge_params(&workflow_params, &job_params);
+            let env_vars = job_env_vars(&workflow_meta, &merged_params, &[]);
+            for script in [
+                &config.scripts.pre,
+                &config.scripts.run,
+                &config.scripts.post,
+            ] {
+                if script.is_empty() {
+                    continue;
+                }
+                let script = ScriptCommand::new(&config.scripts, script);
+                match executor
+                    .exec_script(&container_id, &job_name, &script, &env_vars, &mut cancel_rx)
+                    .await
+                {
+                    Ok(0) => {}
+                    Ok(code) => {
+                        eprintln!("✗ {script} exited with code {code}");
+                        break;
+                    }
+                    Err(e) => {
+                        eprintln!("✗ Job execution error: {e}");
+                        break;
+                    }
+                }
+            }
+
             println!("\n─────────────────────────────");
             println!("Job Execution Complete\n");
             println!("Cleaning up container {container_id}...");
-            executor.cleanup_containers(&[container_id]).await;
+            executor.cleanup().await;
             println!("✓ Container cleaned up\n");
         });
 
@@ -3,10 +3,13 @@ use std::io;
 use crossterm::event::{KeyCode, KeyEvent};
 
 use crate::{
-    components::{application, health_check, workflow},
+    components::{application, health_check, runs, workflow},
     layout,
 };
 
+/// Number of tabs in the header.
+const TAB_COUNT: usize = 4;
+
 pub struct App {
     pub selected_tab: usize,
     pub show_help: bool,
@@ -14,6 +17,7 @@ pub struct App {
     pub application_state: application::state::State,
     pub health_check_state: health_check::state::State,
     pub workflow_state: workflow::state::State,
+    pub runs_state: runs::state::State,
     /// Available update version (if any), set during startup check
     pub update_available: Option<String>,
 }
@@ -39,6 +43,7 @@ impl App {
             application_state: application::state::State::new(catalog),
             health_check_state: health_check::state::State::default(),
             workflow_state: workflow::state::State::default(),
+            runs_state: runs::state::State::default(),
             update_available,
         }
     }
@@ -59,14 +64,16 @@ impl App {
             }
             _ => match key.code {
                 KeyCode::Right | KeyCode::Char('l') => {
-                    self.selected_tab = (self.selected_tab + 1) % 3
+                    self.selected_tab = (self.selected_tab + 1) % TAB_COUNT;
+                    self.on_tab_selected();
                 }
                 KeyCode::Left | KeyCode::Char('h') => {
                     self.selected_tab = if self.selected_tab > 0 {
                         self.selected_tab - 1
                     } else {
-                        2
-                    }
+                        TAB_COUNT - 1
+                    };
+                    self.on_tab_selected();
                 }
                 _ => {
                     if self.selected_tab == 0 {
@@ -74,6 +81,8 @@ impl App {
                     } else if self.selected_tab == 1 {
                         self.workflow_state.handle_input(key).await;
                     } else if self.selected_tab == 2 {
+                        self.runs_state.handle_input(key);
+                    } else if self.selected_tab == 3 {
                         self.health_check_state.handle_input(key);
                     }
                 }
@@ -82,4 +91,12 @@ impl App {
 
         Ok(false)
     }
+
+    /// Refreshes the newly selected tab where its content may have changed.
+    fn on_tab_selected(&mut self) {
+        if self.selected_tab == 2 {
+            // Pick up runs finished since the tab was last shown
+            self.runs_state.refresh();
+        }
+    }
 }
@@ -0,0 +1,178 @@
+//! Execution backend selection.
+//!
+//! Jobs run through Docker by default. Workflows whose jobs use Singularity/Apptainer
+//! `.sif` images run through Apptainer instead, which is also the only option on
+//! hosts (typically HPC clusters) where Docker is not allowed. The local backend
+//! skips containers altogether and runs scripts directly on the host.
+
+use clap::ValueEnum;
+use job_config::job::{ImageSource, JobMeta};
+use tokio::sync::mpsc;
+
+use crate::components::apptainer::executor::ApptainerExecutor;
+use crate::components::docker::executor::DockerExecutor;
+use crate::components::docker::job::JobStatus;
+use crate::components::docker::logs::{LogLine, LogSource};
+use crate::components::local::executor::LocalExecutor;
+use crate::runner::{self, Message, PreparedWorkflow, RunOptions};
+
+/// Container runtime used to execute jobs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    /// Apptainer if any job uses a `.sif` image, Docker otherwise
+    #[default]
+    Auto,
+    /// Docker daemon
+    Docker,
+    /// Apptainer (or Singularity) command line
+    Apptainer,
+    /// Plain host processes, without a container
+    Local,
+}
+
+impl Backend {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Backend::Auto => "auto",
+            Backend::Docker => "docker",
+            Backend::Apptainer => "apptainer",
+            Backend::Local => "local",
+        }
+    }
+}
+
+/// Resolves `Backend::Auto` from the image sources of the workflow's jobs.
+///
+/// An explicitly requested backend is returned unchanged; `Auto` never selects
+/// `Local`. The result is never `Auto`.
+pub fn select_backend<'a>(
+    requested: Backend,
+    job_configs: impl IntoIterator<Item = &'a JobMeta>,
+) -> Backend {
+    match requested {
+        Backend::Auto => {
+            let uses_sif = job_configs.into_iter().any(|config| {
+                matches!(config.container.get_image_source(), ImageSource::SifFile(_))
+            });
+            if uses_sif {
+                Backend::Apptainer
+            } else {
+                Backend::Docker
+            }
+        }
+        backend => backend,
+    }
+}
+
+/// Resolves `options.backend` for `workflow`; see `select_backend`.
+pub fn resolve_backend(requested: Backend, workflow: &PreparedWorkflow) -> Backend {
+    match requested {
+        Backend::Auto => select_backend(requested, &workflow.job_configs()),
+        backend => backend,
+    }
+}
+
+/// Runs `workflow` on the executor of the backend selected by `options.backend`.
+///
+/// If `options.history` is set, the run and all of its messages are recorded
+/// there (see `crate::history`). If the executor cannot be created, the error is
+/// reported as a failure of the first job followed by the end-of-run message,
+/// like any other failed run.
+///
+/// # Returns
+///
+/// `true` if every job succeeded.
+pub async fn execute(
+    workflow: PreparedWorkflow,
+    options: &RunOptions,
+    tx: mpsc::Sender<Message>,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> bool {
+    let Some(history) = &options.history else {
+        return execute_on_backend(workflow, options, tx, cancel_rx).await;
+    };
+    match history.start(&workflow) {
+        Ok(recorder) => {
+            let (tx, recording) = recorder.tap(tx);
+            let succeeded = execute_on_backend(workflow, options, tx, cancel_rx).await;
+            // Every sender is dropped by now; wait for the last messages to be written
+            let _ = recording.await;
+            succeeded
+        }
+        Err(e) => {
+            let log_line = LogLine::new(
+                LogSource::Stderr,
+                format!("Warning: Run is not recorded in the history: {e}"),
+            );
+            let _ = tx.send((0, JobStatus::Idle, log_line)).await;
+            execute_on_backend(workflow, options, tx, cancel_rx).await
+        }
+    }
+}
+
+/// Runs `workflow` on the selected backend without recording it.
+async fn execute_on_backend(
+    workflow: PreparedWorkflow,
+    options: &RunOptions,
+    tx: mpsc::Sender<Message>,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> bool {
+    let jobs_len = workflow.jobs.len();
+    let error = match resolve_backend(options.backend, &workflow) {
+        Backend::Apptainer => match ApptainerExecutor::new(tx.clone()).await {
+            Ok(mut executor) => {
+                executor.detect_host_gpu();
+                return runner::execute(executor, workflow, options, tx, cancel_rx).await;
+            }
+            Err(e) => format!("Failed to create Apptainer executor: {e}"),
+        },
+        Backend::Local => {
+            let executor = LocalExecutor::new(tx.clone());
+            return runner::execute(executor, workflow, options, tx, cancel_rx).await;
+        }
+        Backend::Docker | Backend::Auto => match DockerExecutor::new(tx.clone()) {
+            Ok(mut executor) => {
+                executor.detect_host_gpu().await;
+                return runner::execute(executor, workflow, options, tx, cancel_rx).await;
+            }
+            Err(e) => format!("Failed to create Docker executor: {e}"),
+        },
+    };
+
+    let log_line = LogLine::new(LogSource::Stderr, error);
+    let _ = tx.send((0, JobStatus::Failed, log_line)).await;
+    let _ = tx
+        .send((jobs_len, JobStatus::Failed, LogLine::empty()))
+        .await;
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job_with_image(image: &str) -> JobMeta {
+        JobMeta::new(
+            "job".to_string(),
+            String::new(),
+            job_config::job::Container::new(image.to_string()),
+        )
+    }
+
+    #[test]
+    fn test_select_backend_auto_detects_sif_images() {
+        let docker_only = [job_with_image("ubuntu:22.04")];
+        assert_eq!(select_backend(Backend::Auto, &docker_only), Backend::Docker);
+
+        let mixed = [job_with_image("ubuntu:22.04"), job_with_image("app.sif")];
+        assert_eq!(select_backend(Backend::Auto, &mixed), Backend::Apptainer);
+    }
+
+    #[test]
+    fn test_select_backend_keeps_explicit_choice() {
+        let sif = [job_with_image("app.sif")];
+        assert_eq!(select_backend(Backend::Docker, &sif), Backend::Docker);
+        assert_eq!(select_backend(Backend::Apptainer, &[]), Backend::Apptainer);
+        assert_eq!(select_backend(Backend::Local, &sif), Backend::Local);
+    }
+}
@@ -0,0 +1,382 @@
+//! Content-addressed cache of job results across runs.
+//!
+//! When caching is enabled (`cache = true` in workflow.toml or `silva --cache`),
+//! a job's `outputs/` folder is stored under a key that hashes everything the
+//! job's result depends on: the job folder (scripts, `.chiral/job.toml`, params
+//! and the staged `inputs/`), the exact image it runs in, its merged
+//! parameters, its environment variables and its `[[mounts]]`. A later run of a job with the same key restores `outputs/` from
+//! the cache instead of starting a container.
+//!
+//! Entries live in `$SILVA_CACHE_DIR` (default `~/.cache/silva`), one folder per
+//! key under `jobs/`, and are managed with `silva cache ls` and `silva cache prune`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use job_config::job::Mount;
+use job_config::params::JobParams;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{ContentHasher, copy_dir_recursive};
+
+/// Job folder entries that are produced by running the job.
+const KEY_SKIP: &[&str] = &["outputs", crate::runner::PARAMS_FILE_DIR];
+
+/// Name of the metadata file inside each cache entry.
+const ENTRY_FILE: &str = "entry.json";
+
+/// Description of a cached job result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub key: String,
+    /// Job the result was stored for
+    pub job: String,
+    /// Workflow the job belonged to
+    pub workflow: String,
+    pub created_at: String,
+    /// Number of files in the cached `outputs/`
+    pub files: usize,
+    /// Total size of the cached `outputs/` in bytes
+    pub size: u64,
+}
+
+/// Entries removed by `JobCache::prune`.
+#[derive(Debug, Default, PartialEq)]
+pub struct PruneSummary {
+    pub removed: usize,
+    pub freed: u64,
+}
+
+/// Cache of job outputs rooted at a folder on the host.
+#[derive(Debug, Clone)]
+pub struct JobCache {
+    root: PathBuf,
+}
+
+impl JobCache {
+    /// Creates a cache rooted at `root`. Nothing is created until the first store.
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Opens the cache at `$SILVA_CACHE_DIR`, `$XDG_CACHE_HOME/silva` or
+    /// `~/.cache/silva`, whichever is set first.
+    pub fn open_default() -> Self {
+        let root = std::env::var_os(crate::SILVA_CACHE_DIR)
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("XDG_CACHE_HOME").map(|d| PathBuf::from(d).join("silva")))
+            .or_else(|| std::env::var_os("HOME").map(|d| PathBuf::from(d).join(".cache/silva")))
+            .unwrap_or_else(|| PathBuf::from(".silva-cache"));
+        Self::new(root)
+    }
+
+    /// Returns the folder the cache lives in.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn entries_dir(&self) -> PathBuf {
+        self.root.join("jobs")
+    }
+
+    /// Copies the cached outputs stored under `key` into `outputs_dir`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(n))` - Cache hit, `n` files were restored
+    /// * `Ok(None)` - No entry for `key`
+    /// * `Err(String)` - The entry exists but could not be restored
+    pub fn restore(&self, key: &str, outputs_dir: &Path) -> Result<Option<usize>, String> {
+        let entry_dir = self.entries_dir().join(key);
+        if !entry_dir.join(ENTRY_FILE).is_file() {
+            return Ok(None);
+        }
+        copy_dir_recursive(&entry_dir.join("outputs"), outputs_dir)
+            .map(Some)
+            .map_err(|e| format!("Failed to restore cached outputs: {e}"))
+    }
+
+    /// Stores the contents of `outputs_dir` under `key`.
+    ///
+    /// An existing entry for `key` is kept as it is.
+    pub fn store(
+        &self,
+        key: &str,
+        job: &str,
+        workflow: &str,
+        outputs_dir: &Path,
+    ) -> Result<(), String> {
+        let entries_dir = self.entries_dir();
+        let entry_dir = entries_dir.join(key);
+        if entry_dir.exists() {
+            return Ok(());
+        }
+        fs::create_dir_all(&entries_dir)
+            .map_err(|e| format!("Failed to create cache folder: {e}"))?;
+
+        // Fill a staging folder first, so a concurrent run never sees half an entry
+        let staging = tempfile::Builder::new()
+            .prefix(".staging-")
+            .tempdir_in(&entries_dir)
+            .map_err(|e| format!("Failed to create cache folder: {e}"))?;
+        let files = if outputs_dir.is_dir() {
+            copy_dir_recursive(outputs_dir, &staging.path().join("outputs"))
+        } else {
+            fs::create_dir_all(staging.path().join("outputs")).map(|_| 0)
+        }
+        .map_err(|e| format!("Failed to copy outputs to the cache: {e}"))?;
+
+        let entry = CacheEntry {
+            key: key.to_string(),
+            job: job.to_string(),
+            workflow: workflow.to_string(),
+            created_at: chrono::Local::now().to_rfc3339(),
+            files,
+            size: dir_size(&staging.path().join("outputs")),
+        };
+        let content = serde_json::to_string_pretty(&entry)
+            .map_err(|e| format!("Failed to serialize cache entry: {e}"))?;
+        fs::write(staging.path().join(ENTRY_FILE), content)
+            .map_err(|e| format!("Failed to write cache entry: {e}"))?;
+
+        match fs::rename(staging.path(), &entry_dir) {
+            Ok(()) => Ok(()),
+            // Another run stored the same key in the meantime
+            Err(_) if entry_dir.exists() => Ok(()),
+            Err(e) => Err(format!("Failed to store cache entry: {e}")),
+        }
+    }
+
+    /// Lists all entries, oldest first.
+    pub fn list(&self) -> Result<Vec<CacheEntry>, String> {
+        let entries_dir = self.entries_dir();
+        if !entries_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut entries = Vec::new();
+        for dir_entry in
+            fs::read_dir(&entries_dir).map_err(|e| format!("Failed to read cache: {e}"))?
+        {
+            let path = dir_entry
+                .map_err(|e| format!("Failed to read cache: {e}"))?
+                .path();
+            let Ok(content) = fs::read_to_string(path.join(ENTRY_FILE)) else {
+                continue;
+            };
+            if let Ok(entry) = serde_json::from_str::<CacheEntry>(&content) {
+                entries.push(entry);
+            }
+        }
+        entries.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        Ok(entries)
+    }
+
+    /// Removes entries stored more than `older_than` ago, or all entries if `None`.
+    pub fn prune(&self, older_than: Option<Duration>) -> Result<PruneSummary, String> {
+        let mut summary = PruneSummary::default();
+        let cutoff = older_than.map(|age| SystemTime::now() - age);
+        for entry in self.list()? {
+            if let Some(cutoff) = cutoff {
+                let created = chrono::DateTime::parse_from_rfc3339(&entry.created_at)
+                    .map(SystemTime::from)
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                if created >= cutoff {
+                    continue;
+                }
+            }
+            fs::remove_dir_all(self.entries_dir().join(&entry.key))
+                .map_err(|e| format!("Failed to remove cache entry {}: {e}", entry.key))?;
+            summary.removed += 1;
+            summary.freed += entry.size;
+        }
+        Ok(summary)
+    }
+}
+
+/// Computes the cache key of a job about to run.
+///
+/// Covers everything in `job_dir` except `outputs/` (scripts, `.chiral/job.toml`,
+/// params files and the staged `inputs/`), the image identifier, the merged
+/// parameters, the script environment from `job_env_vars` (`-e/--env` values and
+/// `env_passthrough` host variables) and the resolved mounts. Mounts are hashed
+/// by host and container path only: their contents are not read, so a job whose
+/// mounted data changes in place gets a stale hit.
+pub fn cache_key(
+    job_dir: &Path,
+    image_id: &str,
+    merged_params: &JobParams,
+    env_vars: &[String],
+    mounts: &[Mount],
+) -> Result<String, String> {
+    let mut hasher = ContentHasher::new();
+    hasher
+        .update_dir(job_dir, KEY_SKIP)
+        .map_err(|e| format!("Failed to read job folder: {e}"))?;
+    hasher.update(image_id.as_bytes());
+    let sorted_params: BTreeMap<_, _> = merged_params.iter().collect();
+    let params_json = serde_json::to_string(&sorted_params)
+        .map_err(|e| format!("Failed to serialize parameters: {e}"))?;
+    hasher.update(params_json.as_bytes());
+    // Later entries win, as in the script environment; params come in hash order
+    let env: BTreeMap<_, _> = env_vars
+        .iter()
+        .map(|entry| entry.split_once('=').unwrap_or((entry, "")))
+        .collect();
+    let env_json =
+        serde_json::to_string(&env).map_err(|e| format!("Failed to serialize environment: {e}"))?;
+    hasher.update(env_json.as_bytes());
+    let mounts_json =
+        serde_json::to_string(mounts).map_err(|e| format!("Failed to serialize mounts: {e}"))?;
+    hasher.update(mounts_json.as_bytes());
+    Ok(hasher.finish())
+}
+
+/// Returns the total size in bytes of the files below `dir`.
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let path = entry.path();
+            if path.is_dir() {
+                dir_size(&path)
+            } else {
+                entry.metadata().map(|m| m.len()).unwrap_or(0)
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_outputs(dir: &Path) -> PathBuf {
+        let outputs = dir.join("outputs");
+        fs::create_dir_all(outputs.join("plots")).unwrap();
+        fs::write(outputs.join("result.csv"), "a,b").unwrap();
+        fs::write(outputs.join("plots/loss.png"), "png").unwrap();
+        outputs
+    }
+
+    #[test]
+    fn test_store_and_restore_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cache = JobCache::new(tmp.path().join("cache"));
+        let outputs = write_outputs(tmp.path());
+
+        assert_eq!(cache.restore("k1", &tmp.path().join("out")).unwrap(), None);
+        cache.store("k1", "train", "wf", &outputs).unwrap();
+
+        let restored = tmp.path().join("restored");
+        assert_eq!(cache.restore("k1", &restored).unwrap(), Some(2));
+        assert_eq!(
+            fs::read_to_string(restored.join("plots/loss.png")).unwrap(),
+            "png"
+        );
+
+        let entries = cache.list().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].job, "train");
+        assert_eq!(entries[0].files, 2);
+        assert_eq!(entries[0].size, 6);
+    }
+
+    #[test]
+    fn test_prune_respects_age() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cache = JobCache::new(tmp.path().join("cache"));
+        let outputs = write_outputs(tmp.path());
+        cache.store("k1", "a", "wf", &outputs).unwrap();
+        cache.store("k2", "b", "wf", &outputs).unwrap();
+
+        let summary = cache.prune(Some(Duration::from_secs(3600))).unwrap();
+        assert_eq!(summary, PruneSummary::default());
+        assert_eq!(cache.list().unwrap().len(), 2);
+
+        let summary = cache.prune(None).unwrap();
+        assert_eq!(summary.removed, 2);
+        assert_eq!(summary.freed, 12);
+        assert!(cache.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_cache_key_covers_inputs_image_and_params_but_not_outputs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let job_dir = tmp.path().join("job");
+        fs::create_dir_all(job_dir.join("inputs")).unwrap();
+        fs::write(job_dir.join("run.sh"), "echo run").unwrap();
+        fs::write(job_dir.join("inputs/data.csv"), "1").unwrap();
+        let params = JobParams::new();
+        let key = cache_key(&job_dir, "sha256:abc", &params, &[], &[]).unwrap();
+
+        write_outputs(&job_dir);
+        assert_eq!(
+            cache_key(&job_dir, "sha256:abc", &params, &[], &[]).unwrap(),
+            key
+        );
+
+        assert_ne!(
+            cache_key(&job_dir, "sha256:def", &params, &[], &[]).unwrap(),
+            key
+        );
+
+        let mut changed_params = JobParams::new();
+        changed_params.insert("epochs".to_string(), serde_json::json!(5));
+        assert_ne!(
+            cache_key(&job_dir, "sha256:abc", &changed_params, &[], &[]).unwrap(),
+            key
+        );
+
+        fs::write(job_dir.join("inputs/data.csv"), "2").unwrap();
+        assert_ne!(
+            cache_key(&job_dir, "sha256:abc", &params, &[], &[]).unwrap(),
+            key
+        );
+    }
+
+    #[test]
+    fn test_cache_key_covers_env_vars_and_mounts() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("run.sh"), "echo run").unwrap();
+        let params = JobParams::new();
+        let env = vec!["PARAM_A=1".to_string(), "TOKEN=x".to_string()];
+        let mounts = vec![Mount {
+            host: "/data/v1".to_string(),
+            container: "/data".to_string(),
+            read_only: true,
+        }];
+        let key = cache_key(tmp.path(), "sha256:abc", &params, &env, &mounts).unwrap();
+
+        // Order does not matter, but the value that wins does
+        let reordered = vec!["TOKEN=x".to_string(), "PARAM_A=1".to_string()];
+        assert_eq!(
+            cache_key(tmp.path(), "sha256:abc", &params, &reordered, &mounts).unwrap(),
+            key
+        );
+        let overridden = vec![
+            "PARAM_A=1".to_string(),
+            "TOKEN=x".to_string(),
+            "TOKEN=y".to_string(),
+        ];
+        assert_ne!(
+            cache_key(tmp.path(), "sha256:abc", &params, &overridden, &mounts).unwrap(),
+            key
+        );
+        assert_ne!(
+            cache_key(tmp.path(), "sha256:abc", &params, &env, &[]).unwrap(),
+            key
+        );
+
+        let mut moved = mounts.clone();
+        moved[0].host = "/data/v2".to_string();
+        assert_ne!(
+            cache_key(tmp.path(), "sha256:abc", &params, &env, &moved).unwrap(),
+            key
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use job_config::job::{ByteSize, Gpus, Resources};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -7,6 +8,32 @@ pub struct Requirements {
     pub cuda_version: Option<String>,
 }
 
+impl Requirements {
+    /// Returns the `[resources]` limits matching these requirements: the memory
+    /// limit, and one GPU when the application needs one.
+    pub fn resources(&self) -> Resources {
+        Resources {
+            memory: (self.memory_gb > 0).then(|| ByteSize(u64::from(self.memory_gb) << 30)),
+            gpus: self.gpu.then_some(Gpus::Count(1)),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the `[resources]` table to paste into a job.toml using this
+    /// application, or `None` when there is nothing to limit.
+    pub fn resources_toml(&self) -> Option<String> {
+        let resources = self.resources();
+        if resources.is_empty() {
+            return None;
+        }
+        #[derive(Serialize)]
+        struct Table {
+            resources: Resources,
+        }
+        toml::to_string(&Table { resources }).ok()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Application {
     pub id: String,
@@ -59,3 +86,32 @@ impl ApplicationCatalog {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requirements_prefill_resources() {
+        let requirements = Requirements {
+            gpu: true,
+            memory_gb: 16,
+            cuda_version: None,
+        };
+        let resources = requirements.resources();
+        assert_eq!(resources.memory, Some(ByteSize(16 << 30)));
+        assert_eq!(resources.gpus, Some(Gpus::Count(1)));
+        assert_eq!(
+            requirements.resources_toml().unwrap(),
+            "[resources]\nmemory = \"16g\"\ngpus = 1\n"
+        );
+
+        let none = Requirements {
+            gpu: false,
+            memory_gb: 0,
+            cuda_version: None,
+        };
+        assert!(none.resources().is_empty());
+        assert_eq!(none.resources_toml(), None);
+    }
+}
@@ -192,6 +192,22 @@ fn render_popup(frame: &mut Frame, area: Rect, app: &App) {
             Style::default().fg(Color::Yellow),
         )]));
 
+        if let Some(resources) = selected_app.requirements.resources_toml() {
+            text_lines.push(Line::from(""));
+            text_lines.push(Line::from(vec![Span::styled(
+                "Job Resources (job.toml):",
+                Style::default()
+                    .add_modifier(Modifier::BOLD)
+                    .fg(Color::Cyan),
+            )]));
+            for line in resources.lines() {
+                text_lines.push(Line::from(vec![Span::styled(
+                    format!("  {line}"),
+                    Style::default().fg(Color::Yellow),
+                )]));
+            }
+        }
+
         text_lines.push(Line::from(""));
         text_lines.push(Line::from(vec![
             Span::styled(
@@ -0,0 +1,56 @@
+use std::fmt;
+
+use crate::process::ProcessError;
+
+/// Error type for Apptainer operations.
+#[derive(Debug)]
+pub enum ApptainerError {
+    NotInstalled,
+    UnsupportedImage(String),
+    ImageNotFound(String),
+    GpuUnavailable,
+    SpawnFailed(String),
+    IoError(std::io::Error),
+    ChannelSendMessageError(String),
+}
+
+impl fmt::Display for ApptainerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApptainerError::NotInstalled => {
+                write!(f, "Neither `apptainer` nor `singularity` was found in PATH")
+            }
+            ApptainerError::UnsupportedImage(msg) => write!(f, "Unsupported image: {msg}"),
+            ApptainerError::ImageNotFound(path) => write!(f, "Image not found: {path}"),
+            ApptainerError::GpuUnavailable => write!(
+                f,
+                "The job requires a GPU (gpu = \"required\") but no NVIDIA GPU was found on the host"
+            ),
+            ApptainerError::SpawnFailed(msg) => write!(f, "Failed to start apptainer: {msg}"),
+            ApptainerError::IoError(err) => write!(f, "IO error: {err}"),
+            ApptainerError::ChannelSendMessageError(err) => {
+                write!(f, "MPSC channel send message error: {err}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ApptainerError {}
+
+impl From<ProcessError> for ApptainerError {
+    fn from(err: ProcessError) -> Self {
+        match err {
+            ProcessError::SpawnFailed(err) => ApptainerError::SpawnFailed(err.to_string()),
+            ProcessError::IoError(err) => ApptainerError::IoError(err),
+            ProcessError::ChannelSendMessageError(err) => {
+                ApptainerError::ChannelSendMessageError(err)
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for ApptainerError {
+    fn from(err: std::io::Error) -> Self {
+        ApptainerError::IoError(err)
+    }
+}
@@ -0,0 +1,484 @@
+//! Apptainer (formerly Singularity) job execution.
+//!
+//! Runs a job's `pre_run.sh` → `run.sh` → `post_run.sh` through `apptainer exec`
+//! for hosts where Docker is not available, typically HPC clusters. The workflow
+//! folder is bound to `/workspace` exactly as with Docker, parameters reach the
+//! scripts as the same `PARAM_*` variables, and status and log lines are sent over
+//! the same channel, so the TUI and headless runners cannot tell the backends apart.
+//!
+//! Each script runs in its own `apptainer exec` process; there is no long-running
+//! container to reuse or clean up. Processes run with `--contain`, so they get
+//! their own empty `/tmp` and `$HOME` instead of the host's, and bind only the
+//! `[[mounts]]` of the job next to the workspace; with `isolation = "job"` the
+//! workspace is just the job's folder, with `inputs/` read-only.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+use crate::components::docker::executor::{resolve_job_path, script_argv};
+use crate::components::docker::job::JobStatus;
+use crate::components::docker::logs::{LogLine, LogSource};
+use crate::executor::{EnvironmentSpec, Executor, ScriptCommand};
+use crate::gpu::GpuRuntime;
+use crate::process::run_streaming;
+use job_config::job::{GpuPolicy, Gpus, ImageSource, JobMeta, Resources};
+use job_config::workflow::Isolation;
+
+use super::error::ApptainerError;
+
+/// Mount point of the workflow folder inside the container.
+const WORK_DIR: &str = "/workspace";
+
+/// Binaries tried in order when looking for an Apptainer installation.
+const BINARIES: &[&str] = &["apptainer", "singularity"];
+
+/// Apptainer executor for running jobs from `.sif` images.
+///
+/// Cheap to clone: each clone shares the message channel but keeps its own job
+/// index, so concurrently running jobs can each tag their own messages.
+#[derive(Clone)]
+pub struct ApptainerExecutor {
+    tx: mpsc::Sender<(usize, JobStatus, LogLine)>,
+    job_idx: usize,
+    binary: String,
+    use_gpu: bool,
+}
+
+impl ApptainerExecutor {
+    /// Creates a new Apptainer executor.
+    ///
+    /// Looks for `apptainer` in `PATH` first and falls back to `singularity`.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - Message channel sender for streaming job status and logs
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ApptainerExecutor)` - An Apptainer or Singularity binary was found
+    /// * `Err(ApptainerError::NotInstalled)` - Neither binary is available
+    pub async fn new(
+        tx: mpsc::Sender<(usize, JobStatus, LogLine)>,
+    ) -> Result<Self, ApptainerError> {
+        for binary in BINARIES {
+            let found = Command::new(binary)
+                .arg("--version")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .await
+                .is_ok_and(|status| status.success());
+            if found {
+                return Ok(Self {
+                    tx,
+                    job_idx: 0,
+                    binary: binary.to_string(),
+                    use_gpu: false,
+                });
+            }
+        }
+        Err(ApptainerError::NotInstalled)
+    }
+
+    /// Detects an NVIDIA GPU on the host. Call once before running jobs.
+    ///
+    /// When a GPU is present, every `apptainer exec` gets `--nv`, unless the job's
+    /// `gpu` policy is `none`.
+    pub fn detect_host_gpu(&mut self) {
+        self.use_gpu = job_config::job::has_nvidia_gpu();
+    }
+
+    /// Updates the job index used for sending messages.
+    pub fn set_job_idx(&mut self, new_job_idx: usize) {
+        self.job_idx = new_job_idx;
+    }
+
+    /// Sends a message via the channel with the current job index.
+    async fn tx_send(&self, status: JobStatus, log_line: LogLine) -> Result<(), ApptainerError> {
+        self.tx
+            .send((self.job_idx, status, log_line))
+            .await
+            .map_err(|e| {
+                ApptainerError::ChannelSendMessageError(format!(
+                    "Message channel receiver dropped: {e}"
+                ))
+            })
+    }
+
+    /// Runs one `apptainer exec` process, streaming its output until it exits.
+    ///
+    /// The process is killed when a cancel signal arrives.
+    async fn exec_process(
+        &self,
+        args: &[String],
+        env_vars: &[String],
+        cancel_rx: &mut mpsc::Receiver<()>,
+    ) -> Result<i64, ApptainerError> {
+        let mut command = Command::new(&self.binary);
+        command
+            .args(args)
+            .envs(container_env(&self.binary, env_vars));
+        Ok(run_streaming(command, &self.tx, self.job_idx, cancel_rx).await?)
+    }
+}
+
+/// Image, workflow folder and GPUs a job's scripts run with.
+pub struct ApptainerEnvironment {
+    image: String,
+    workflow_folder: PathBuf,
+    isolation: Isolation,
+    /// `[[mounts]]` of the job as `host:container[:ro]` binds
+    mounts: Vec<String>,
+    use_gpu: bool,
+    /// `CUDA_VISIBLE_DEVICES` of the job, when it was given specific GPUs
+    visible_devices: Option<String>,
+}
+
+impl Executor for ApptainerExecutor {
+    type Error = ApptainerError;
+    type Environment = ApptainerEnvironment;
+
+    fn set_job_idx(&mut self, job_idx: usize) {
+        ApptainerExecutor::set_job_idx(self, job_idx);
+    }
+
+    fn gpu_runtime(&self) -> GpuRuntime {
+        if self.use_gpu {
+            GpuRuntime::Nvidia
+        } else {
+            GpuRuntime::None
+        }
+    }
+
+    async fn prepare_image(
+        &self,
+        config: &JobMeta,
+        job_dir: &Path,
+    ) -> Result<String, ApptainerError> {
+        let image = image_reference(config.container.get_image_source(), job_dir)?;
+        let log_line = LogLine::new(
+            LogSource::Stdout,
+            format!("Using {} image: {image}", self.binary),
+        );
+        self.tx_send(JobStatus::CreatingContainer, log_line).await?;
+        Ok(image)
+    }
+
+    async fn image_id(&self, image: &str) -> Result<String, ApptainerError> {
+        // Hashing a multi-gigabyte .sif on every run would be slow; its size and
+        // modification time change whenever it is rebuilt
+        match std::fs::metadata(image) {
+            Ok(metadata) => {
+                let modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .unwrap_or_default();
+                Ok(format!(
+                    "{image}:{}:{}",
+                    metadata.len(),
+                    modified.as_nanos()
+                ))
+            }
+            Err(_) => Ok(image.to_string()),
+        }
+    }
+
+    async fn start_environment(
+        &self,
+        spec: EnvironmentSpec<'_>,
+    ) -> Result<ApptainerEnvironment, ApptainerError> {
+        let resources = &spec.config.resources;
+        let limits = Resources {
+            gpu: None,
+            gpus: None,
+            ..resources.clone()
+        };
+        if !limits.is_empty() {
+            let log_line = LogLine::new(
+                LogSource::Stdout,
+                format!(
+                    "[resources] limits are only applied by the Docker backend; {} runs the job without them",
+                    self.binary
+                ),
+            );
+            self.tx_send(JobStatus::CreatingContainer, log_line).await?;
+        }
+        if resources.gpu_policy() == Some(GpuPolicy::Required) && !self.use_gpu {
+            return Err(ApptainerError::GpuUnavailable);
+        }
+
+        let use_gpu = crate::gpu::uses_gpu(resources, self.gpu_runtime()).unwrap_or(self.use_gpu);
+        let visible_devices = match &resources.gpus {
+            Some(Gpus::Devices(ids)) if use_gpu => Some(ids.join(",")),
+            _ => None,
+        };
+        if use_gpu {
+            let log_line = LogLine::new(
+                LogSource::Stdout,
+                match &visible_devices {
+                    Some(ids) => format!("NVIDIA GPU on host — enabling --nv with GPUs {ids}"),
+                    None => "GPU auto-detected: NVIDIA GPU on host — enabling --nv".to_string(),
+                },
+            );
+            self.tx_send(JobStatus::CreatingContainer, log_line).await?;
+        }
+        if spec.isolation == Isolation::Job {
+            // Bind sources must exist
+            let inputs = spec.workflow_folder.join(spec.job_name).join("inputs");
+            std::fs::create_dir_all(&inputs)?;
+        }
+        Ok(ApptainerEnvironment {
+            image: spec.image.to_string(),
+            workflow_folder: spec.workflow_folder.to_path_buf(),
+            isolation: spec.isolation,
+            mounts: spec.mounts.iter().map(crate::mounts::bind_spec).collect(),
+            use_gpu,
+            visible_devices,
+        })
+    }
+
+    async fn exec_script(
+        &self,
+        environment: &ApptainerEnvironment,
+        job_name: &str,
+        script: &ScriptCommand,
+        env_vars: &[String],
+        cancel_rx: &mut mpsc::Receiver<()>,
+    ) -> Result<i64, ApptainerError> {
+        let mut binds = bind_paths(
+            &environment.workflow_folder,
+            job_name,
+            environment.isolation,
+        );
+        binds.extend(environment.mounts.iter().cloned());
+        let args = exec_args(
+            environment.use_gpu,
+            &binds,
+            &format!("{WORK_DIR}/{job_name}"),
+            &environment.image,
+            script,
+        );
+        match &environment.visible_devices {
+            Some(ids) => {
+                let mut env_vars = env_vars.to_vec();
+                env_vars.push(format!("CUDA_VISIBLE_DEVICES={ids}"));
+                self.exec_process(&args, &env_vars, cancel_rx).await
+            }
+            None => self.exec_process(&args, env_vars, cancel_rx).await,
+        }
+    }
+
+    async fn cleanup(&self) {
+        // Every script ran in its own process; nothing is left running
+    }
+}
+
+/// Maps a job's image source to an image reference `apptainer exec` understands.
+///
+/// `.sif` files are used directly; registry images, local Docker images and Docker
+/// tar archives go through Apptainer's `docker://`, `docker-daemon://` and
+/// `docker-archive://` transports. Dockerfile builds need Docker and are rejected.
+pub(crate) fn image_reference(
+    source: ImageSource,
+    job_dir: &Path,
+) -> Result<String, ApptainerError> {
+    match source {
+        ImageSource::SifFile(path) => {
+            let resolved = resolve_job_path(job_dir, &path);
+            if !resolved.exists() {
+                return Err(ApptainerError::ImageNotFound(
+                    resolved.display().to_string(),
+                ));
+            }
+            Ok(resolved.display().to_string())
+        }
+        ImageSource::TarFile(path) => Ok(format!(
+            "docker-archive://{}",
+            resolve_job_path(job_dir, &path).display()
+        )),
+        ImageSource::Registry(image) => Ok(format!("docker://{image}")),
+        ImageSource::LocalImage(image) => Ok(format!("docker-daemon://{image}")),
+        ImageSource::Dockerfile(path) => Err(ApptainerError::UnsupportedImage(format!(
+            "Dockerfile '{path}' requires the Docker backend"
+        ))),
+    }
+}
+
+/// Returns the `--bind` values for a script of job `job_name`: the whole workflow
+/// folder, or with `Isolation::Job` only the job's folder with `inputs/` read-only.
+pub(crate) fn bind_paths(
+    workflow_folder: &Path,
+    job_name: &str,
+    isolation: Isolation,
+) -> Vec<String> {
+    let workflow_folder = workflow_folder.display();
+    match isolation {
+        Isolation::Workflow => vec![format!("{workflow_folder}:{WORK_DIR}")],
+        Isolation::Job => {
+            let host_dir = format!("{workflow_folder}/{job_name}");
+            let container_dir = format!("{WORK_DIR}/{job_name}");
+            vec![
+                format!("{host_dir}:{container_dir}"),
+                format!("{host_dir}/inputs:{container_dir}/inputs:ro"),
+            ]
+        }
+    }
+}
+
+/// Builds the `apptainer exec` arguments for running one script.
+pub(crate) fn exec_args(
+    use_gpu: bool,
+    binds: &[String],
+    job_workdir: &str,
+    image: &str,
+    script: &ScriptCommand,
+) -> Vec<String> {
+    // --contain: private /tmp and $HOME instead of the host's
+    let mut args = vec![
+        "exec".to_string(),
+        "--cleanenv".to_string(),
+        "--contain".to_string(),
+    ];
+    if use_gpu {
+        args.push("--nv".to_string());
+    }
+    for bind in binds {
+        args.extend(["--bind".to_string(), bind.clone()]);
+    }
+    args.extend([
+        "--pwd".to_string(),
+        job_workdir.to_string(),
+        image.to_string(),
+    ]);
+    args.extend(script_argv(job_workdir, script));
+    args
+}
+
+/// Turns `KEY=VALUE` strings into the host variables Apptainer injects into the
+/// container, e.g. `PARAM_X=1` becomes `APPTAINERENV_PARAM_X=1`.
+///
+/// `--cleanenv` keeps the host environment out of the container; only variables
+/// carrying the binary's `*ENV_` prefix are passed through.
+pub(crate) fn container_env(binary: &str, env_vars: &[String]) -> Vec<(String, String)> {
+    let prefix = if binary == "singularity" {
+        "SINGULARITYENV_"
+    } else {
+        "APPTAINERENV_"
+    };
+    env_vars
+        .iter()
+        .filter_map(|var| var.split_once('='))
+        .map(|(key, value)| (format!("{prefix}{key}"), value.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_reference_maps_sources_to_transports() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("app.sif"), "").unwrap();
+
+        let sif = image_reference(ImageSource::SifFile("app.sif".into()), tmp.path()).unwrap();
+        assert_eq!(sif, tmp.path().join("app.sif").display().to_string());
+        assert_eq!(
+            image_reference(ImageSource::Registry("ubuntu:22.04".into()), tmp.path()).unwrap(),
+            "docker://ubuntu:22.04"
+        );
+        assert_eq!(
+            image_reference(ImageSource::LocalImage("my-image".into()), tmp.path()).unwrap(),
+            "docker-daemon://my-image"
+        );
+        assert!(
+            image_reference(ImageSource::TarFile("img.tar".into()), tmp.path())
+                .unwrap()
+                .starts_with("docker-archive://")
+        );
+    }
+
+    #[test]
+    fn test_image_reference_rejects_missing_sif_and_dockerfile() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            image_reference(ImageSource::SifFile("missing.sif".into()), tmp.path()),
+            Err(ApptainerError::ImageNotFound(_))
+        ));
+        assert!(matches!(
+            image_reference(ImageSource::Dockerfile("Dockerfile".into()), tmp.path()),
+            Err(ApptainerError::UnsupportedImage(_))
+        ));
+    }
+
+    fn bash(script: &str) -> ScriptCommand {
+        ScriptCommand {
+            name: script.to_string(),
+            argv: vec!["bash".to_string(), script.to_string()],
+        }
+    }
+
+    #[test]
+    fn test_exec_args_binds_workspace_and_adds_nv() {
+        let args = exec_args(
+            true,
+            &bind_paths(Path::new("/tmp/wf"), "job_1", Isolation::Workflow),
+            "/workspace/job_1",
+            "/tmp/wf/job_1/app.sif",
+            &bash("run.sh"),
+        );
+        // The host's /tmp and $HOME are never shared
+        assert_eq!(&args[..4], &["exec", "--cleanenv", "--contain", "--nv"]);
+        assert!(
+            args.windows(2)
+                .any(|w| w == ["--bind", "/tmp/wf:/workspace"])
+        );
+        assert!(args.windows(2).any(|w| w == ["--pwd", "/workspace/job_1"]));
+        assert_eq!(args[args.len() - 2..], ["bash", "run.sh"]);
+
+        let args = exec_args(
+            false,
+            &bind_paths(Path::new("/w"), "j", Isolation::Workflow),
+            "/workspace/j",
+            "i",
+            &bash("r"),
+        );
+        assert!(!args.contains(&"--nv".to_string()));
+    }
+
+    #[test]
+    fn test_exec_args_isolated_job_binds_only_its_folder() {
+        let binds = bind_paths(Path::new("/tmp/wf"), "job_1", Isolation::Job);
+        assert_eq!(
+            binds,
+            [
+                "/tmp/wf/job_1:/workspace/job_1",
+                "/tmp/wf/job_1/inputs:/workspace/job_1/inputs:ro",
+            ]
+        );
+        let args = exec_args(false, &binds, "/workspace/job_1", "i", &bash("r"));
+        assert!(!args.iter().any(|arg| arg == "/tmp/wf:/workspace"));
+        assert_eq!(args.iter().filter(|arg| *arg == "--bind").count(), 2);
+    }
+
+    #[test]
+    fn test_container_env_prefixes_variables() {
+        let env = container_env(
+            "apptainer",
+            &["PARAM_EPOCHS=10".to_string(), "URL=a=b".to_string()],
+        );
+        assert_eq!(
+            env,
+            vec![
+                ("APPTAINERENV_PARAM_EPOCHS".to_string(), "10".to_string()),
+                ("APPTAINERENV_URL".to_string(), "a=b".to_string()),
+            ]
+        );
+        let env = container_env("singularity", &["A=1".to_string()]);
+        assert_eq!(env[0].0, "SINGULARITYENV_A");
+    }
+}
@@ -1,147 +1,35 @@
-//! # Docker Integration Usage Guide
+//! Docker job execution.
 //!
-//! This project now includes comprehensive Docker support via the Bollard library, allowing you to build images, run containers, and execute jobs with sequential script execution.
+//! Prepares the image a job's `[container]` section names — pulled from a
+//! registry, loaded from a `docker save` archive or built from a Dockerfile — and
+//! runs the job's scripts in it with `docker exec`, from `/workspace/<job>`. Status
+//! and log lines are streamed over the job channel as they are produced.
 //!
-//! ## Features
-//!
-//! ### Core Functionality
-//! - **Image Building**: Build Docker images from Dockerfiles
-//! - **Image Pulling**: Pull Docker images from registries
-//! - **Container Management**: Create, start, and manage containers
-//! - **Sequential Script Execution**: Run pre_run.sh → run.sh → post_run.sh in order
-//! - **Log Streaming**: Real-time log capture with stdout/stderr separation
-//! - **UI Integration**: Visual log viewer with scrolling and status display
-//!
-//! ### Job Configuration
-//!
-//! Jobs are configured via `@job.toml` file:
-//!
-//! ```toml
-//! [container]
-//! # Option 1: Use a Docker image from a registry
-//! docker_image = "ubuntu:22.04"
-//!
-//! # Option 2: Build from a Dockerfile (uncomment to use)
-//! # dockerfile = "./Dockerfile"
-//!
-//! [scripts]
-//! # All fields are optional with defaults shown
-//! pre = "pre_run.sh"    # default: "pre_run.sh"
-//! run = "run.sh"        # default: "run.sh"
-//! post = "post_run.sh"  # default: "post_run.sh"
-//! ```
-//!
-//! ## Programmatic Usage
-//!
-//! ### UI Keyboard Shortcuts
-//!
-//! When using the terminal UI:
-//!
-//! - **`d`** - Toggle Docker logs popup
-//! - **`↑`** - Scroll logs up
-//! - **`↓`** - Scroll logs down
-//! - **`b`** - Jump to bottom of logs
-//! - **`q`** - Quit application
-//!
-//! ## Architecture
-//!
-//! ### Module Structure
-//!
-//! src/docker
-//!  - mod.rs          # Module exports
-//!  - error.rs        # Docker-specific errors
-//!  - executor.rs     # Core Docker operations
-//!  - logs.rs         # Log buffer management
-//!  - state.rs        # Job state tracking
-//!
-//! ### Docker Executor
-//!
-//! The `DockerExecutor` handles:
-//! - Docker client initialization
-//! - Image building from Dockerfile
-//! - Image pulling from registries
-//! - Container lifecycle management
-//! - Script execution with output capture
-//!
-//! ### Log Management
-//!
-//! Logs are stored in a circular buffer (default 10,000 lines) with:
-//! - Timestamps for each line
-//! - Source identification (stdout/stderr)
-//! - Efficient rotation when buffer fills
-//!
-//! ### State Management
-//!
-//! Job execution tracks:
-//! - Current status (Idle, Building, Running scripts, etc.)
-//! - Start/end timestamps
-//! - Container ID
-//! - Error messages
-//! - Log buffer
-//!
-//! ## Script Execution
-//!
-//! Scripts are executed sequentially inside the container:
-//!
-//! 1. **Pre-run script** (`pre_run.sh`) - Setup/preparation
-//! 2. **Main script** (`run.sh`) - Primary computation
-//! 3. **Post-run script** (`post_run.sh`) - Cleanup/finalization
-//!
-//! Execution stops immediately if any script fails (returns non-zero exit code).
-//!
-//! ## Error Handling
-//!
-//! The system provides detailed error types:
-//! - `BollardError` - Docker API errors
-//! - `ImageBuildFailed` - Image build failures
-//! - `ContainerCreateFailed` - Container creation errors
-//! - `ScriptExecutionFailed` - Script execution failures with exit codes
-//! - `LogStreamError` - Log streaming issues
-//!
-//! ## Testing
-//!
-//! Run tests with:
-//! ```bash
-//! cargo test docker
-//! ```
-//!
-//! Current test coverage:
-//! - Log buffer operations (push, rotate, tail)
-//! - Job state transitions
-//! - Status tracking
-//! - Error handling
-//!
-//! ## Requirements
-//!
-//! - Docker daemon running locally
-//! - Bollard 0.17+
-//! - Tokio async runtime
-//! - Scripts must be available in container (mount volume or include in image)
-//!
-//! ## Future Enhancements
-//!
-//! Potential improvements:
-//! - Volume mounting for script injection
-//! - Environment variable passing
-//! - Network configuration
-//! - Resource limits (CPU/memory)
-//! - Multiple container orchestration
-//! - Background job execution
-//! - Job queue management
+//! Containers are kept alive with `tail -f /dev/null` and, by default, shared by
+//! every job that uses the same image, `[resources]` limits and `[[mounts]]`; the
+//! whole workflow folder is bound to `/workspace`. With `isolation = "job"` a job
+//! gets a private container that binds only its own folder, with `inputs/`
+//! read-only, and that is removed as soon as the job is done. CPU and memory
+//! limits map onto the container's host config, and GPUs are passed through when
+//! the job asks for them or the image needs them and the host has a runtime.
+//! Parameters reach the scripts as `PARAM_*` variables of each exec.
 
 use bollard::Docker;
 use bollard::container::{Config, LogOutput, RemoveContainerOptions};
 use bollard::exec::{CreateExecOptions, StartExecResults};
-use bollard::image::{BuildImageOptions, CreateImageOptions};
+use bollard::image::{BuildImageOptions, CreateImageOptions, ImportImageOptions};
 use futures_util::stream::StreamExt;
 use std::collections::HashMap;
 use std::default::Default;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncReadExt;
 use tokio::sync::mpsc;
 
-use crate::components::workflow;
-use job_config::job::JobMeta;
-use job_config::workflow::WorkflowMeta;
+use crate::executor::{EnvironmentSpec, Executor, ScriptCommand};
+use crate::utils::ContentHasher;
+use job_config::job::{GpuPolicy, Gpus, ImageSource, JobMeta, Mount, Resources};
+use job_config::workflow::Isolation;
 
 use super::error::DockerError;
 use super::job::JobStatus;
@@ -186,23 +74,73 @@ impl JobResult {
     }
 }
 
-/// Detected GPU runtime on the host.
-#[derive(Debug, Clone, PartialEq)]
-pub enum GpuRuntime {
-    /// NVIDIA GPU available (via NVIDIA Container Toolkit runtime).
-    Nvidia,
-    /// AMD GPU available (via ROCm, /dev/kfd + /dev/dri).
-    Rocm,
-    /// No GPU runtime detected on the host.
-    None,
+pub use crate::gpu::GpuRuntime;
+
+/// Mount point of the workflow folder inside job containers.
+const WORK_DIR: &str = "/workspace";
+
+/// Containers started during a workflow run, shared by concurrently running jobs.
+///
+/// Jobs using the same image and `[resources]` limits share one container, keyed
+/// by `container_key`. Every container seen is remembered for cleanup, even when
+/// two jobs raced to start a container for the same key. Containers private to a
+/// job (`isolation = "job"`) are remembered too, but never handed out again.
+#[derive(Debug, Default)]
+pub struct ContainerPool {
+    registry: HashMap<String, String>,
+    started: Vec<String>,
+}
+
+impl ContainerPool {
+    /// Returns the container registered under `key`, if any.
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.registry.get(key).cloned()
+    }
+
+    /// Merges `key -> container id` entries into the pool.
+    pub fn merge(&mut self, registry: HashMap<String, String>) {
+        for (key, container_id) in registry {
+            if !self.started.contains(&container_id) {
+                self.started.push(container_id.clone());
+            }
+            self.registry.entry(key).or_insert(container_id);
+        }
+    }
+
+    /// Adds a container private to one job.
+    pub fn add_private(&mut self, container_id: String) {
+        self.started.push(container_id);
+    }
+
+    /// Forgets `container_id` if it is private to a job. Returns `true` if it was,
+    /// in which case the caller removes it; shared containers stay until cleanup.
+    pub fn release(&mut self, container_id: &str) -> bool {
+        if self.registry.values().any(|id| id == container_id) {
+            return false;
+        }
+        let before = self.started.len();
+        self.started.retain(|id| id != container_id);
+        self.started.len() < before
+    }
+
+    /// Returns every container started during the run.
+    pub fn container_ids(&self) -> Vec<String> {
+        self.started.clone()
+    }
 }
 
 /// Docker executor for building images and running jobs.
+///
+/// Cheap to clone: each clone shares the Docker client, message channel and started
+/// containers, but keeps its own job index, so concurrently running jobs can each tag
+/// their own messages.
+#[derive(Clone)]
 pub struct DockerExecutor {
     client: Docker,
     tx: mpsc::Sender<(usize, JobStatus, LogLine)>,
     job_idx: usize,
     host_gpu: GpuRuntime,
+    containers: Arc<Mutex<ContainerPool>>,
 }
 
 impl DockerExecutor {
@@ -225,6 +163,7 @@ impl DockerExecutor {
             tx,
             job_idx: 0,
             host_gpu: GpuRuntime::None,
+            containers: Arc::new(Mutex::new(ContainerPool::default())),
         })
     }
 
@@ -323,44 +262,83 @@ impl DockerExecutor {
             })
     }
 
-    /// Builds a Docker image from a Dockerfile.
+    /// Builds a Docker image from a Dockerfile, reusing a previous build when possible.
+    ///
+    /// The image is tagged `{repository}:{hash}`, where the hash covers the Dockerfile,
+    /// `build_args`, `target` and every file of the build context (the Dockerfile's
+    /// folder, without `inputs/` and `outputs/`). If an image with that tag already
+    /// exists, the build is skipped.
     ///
     /// # Arguments
     ///
+    /// * `repository` - Repository name for the image tag
     /// * `dockerfile_path` - Path to the Dockerfile
+    /// * `build_args` - Values for the Dockerfile's `ARG`s
+    /// * `target` - Build stage to stop at, if any
     ///
     /// # Returns
     ///
-    /// * `Ok(String)` - Image ID of the built image
+    /// * `Ok(String)` - Tag of the built (or cached) image
     /// * `Err(DockerError)` - Build error
     pub async fn build_image(
         &self,
-        image_name: &str,
+        repository: &str,
         dockerfile_path: &Path,
+        build_args: &HashMap<String, String>,
+        target: Option<&str>,
     ) -> Result<String, DockerError> {
-        // update job entry
+        let context_path = dockerfile_path
+            .parent()
+            .ok_or_else(|| DockerError::ImageBuildFailed("Invalid Dockerfile path".to_string()))?;
+        let dockerfile = std::fs::read_to_string(dockerfile_path).map_err(|e| {
+            DockerError::ImageBuildFailed(format!("Cannot read {}: {e}", dockerfile_path.display()))
+        })?;
+        // bollard's BuildImageOptions has no `target`, so the Dockerfile is cut
+        // after the target stage instead; later stages cannot affect it.
+        let dockerfile = match target {
+            Some(target) => truncate_dockerfile_at_target(&dockerfile, target)
+                .map_err(DockerError::ImageBuildFailed)?,
+            None => dockerfile,
+        };
+
+        let mut hasher = ContentHasher::new();
+        hasher.update(dockerfile.as_bytes());
+        let mut sorted_args: Vec<_> = build_args.iter().collect();
+        sorted_args.sort();
+        for (key, value) in sorted_args {
+            hasher.update(key.as_bytes());
+            hasher.update(value.as_bytes());
+        }
+        hasher.update(target.unwrap_or_default().as_bytes());
+        hasher.update_dir(context_path, BUILD_CONTEXT_SKIP)?;
+        let image_tag = format!("{repository}:{}", &hasher.finish()[..12]);
+
+        if self.client.inspect_image(&image_tag).await.is_ok() {
+            let log_line = LogLine::new(
+                LogSource::Stdout,
+                format!("Dockerfile unchanged, reusing image {image_tag}"),
+            );
+            self.tx_send(JobStatus::BuildingImage, log_line).await?;
+            return Ok(image_tag);
+        }
+
         let log_line = LogLine::new(
             LogSource::Stdout,
-            format!("Building image from: {dockerfile_path:?}"),
+            format!(
+                "Building image {image_tag} from {}",
+                dockerfile_path.display()
+            ),
         );
         self.tx_send(JobStatus::BuildingImage, log_line).await?;
 
-        // docker builds image
-        let path = Path::new(dockerfile_path);
-        let context_path = path
-            .parent()
-            .ok_or_else(|| DockerError::ImageBuildFailed("Invalid Dockerfile path".to_string()))?;
-
-        // Create tar archive of the build context
-        let tar_file = self.create_tar_archive(context_path)?;
-        let image_tag = format!("{image_name}:latest");
+        // Create tar archive of the build context, with the (possibly truncated)
+        // Dockerfile added under a name that cannot clash with the job's files
+        let tar_file = self.create_build_context(context_path, &dockerfile)?;
 
         let build_options = BuildImageOptions {
-            dockerfile: path
-                .file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("Dockerfile"),
-            t: &image_tag,
+            dockerfile: GENERATED_DOCKERFILE.to_string(),
+            t: image_tag.clone(),
+            buildargs: build_args.clone(),
             rm: true,
             ..Default::default()
         };
@@ -369,23 +347,19 @@ impl DockerExecutor {
             .client
             .build_image(build_options, None, Some(tar_file.into()));
 
-        let mut image_id = String::new();
         while let Some(result) = stream.next().await {
             match result {
                 Ok(output) => {
-                    if let Some(id) = output.stream
-                        && id.contains("Successfully built")
-                    {
-                        image_id = id
-                            .split_whitespace()
-                            .last()
-                            .unwrap_or("")
-                            .trim()
-                            .to_string();
-                    }
                     if let Some(error) = output.error {
                         return Err(DockerError::ImageBuildFailed(error));
                     }
+                    if let Some(line) = output.stream {
+                        let line = line.trim_end();
+                        if !line.is_empty() {
+                            let log_line = LogLine::new(LogSource::Stdout, line.to_string());
+                            self.tx_send(JobStatus::BuildingImage, log_line).await?;
+                        }
+                    }
                 }
                 Err(e) => return Err(DockerError::ImageBuildFailed(e.to_string())),
             }
@@ -393,7 +367,7 @@ impl DockerExecutor {
 
         let log_line = LogLine::new(
             LogSource::Stdout,
-            format!("Building image complete with image id: {image_id}"),
+            format!("Building image complete: {image_tag}"),
         );
         self.tx_send(JobStatus::BuildingImage, log_line).await?;
 
@@ -475,374 +449,368 @@ impl DockerExecutor {
         Ok(())
     }
 
-    /// Runs a job based on the provided configuration.
+    /// Loads a Docker image from a `docker save` tarball, unless it is already present.
+    ///
+    /// The image reference is read from the archive's `manifest.json`, so the tarball
+    /// is only streamed to the daemon's image-load API when that image is missing.
     ///
     /// # Arguments
     ///
-    /// * `job_folder` - Path to the job folder (mounted as /workspace in container)
-    /// * `config` - Job configuration specifying container image/Dockerfile and scripts
-    /// * `cli_env_vars` - `KEY=VALUE` strings from `-e/--env`, injected unprefixed
-    ///   regardless of `workflow_meta.env_passthrough`
-    /// * `cancel_rx` - Channel receiver for cancellation signals
+    /// * `tar_path` - Path to the image tarball
     ///
     /// # Returns
     ///
-    /// * `Ok(String)` - Container ID of the job container (for cleanup later)
-    /// * `Err(DockerError)` - Fatal execution error
+    /// * `Ok(String)` - Image reference to create containers from
+    /// * `Err(DockerError)` - The archive could not be read or the load failed
+    pub async fn load_image(&self, tar_path: &Path) -> Result<String, DockerError> {
+        let archive_path = tar_path.to_path_buf();
+        let image_ref = tokio::task::spawn_blocking(move || read_archive_image_ref(&archive_path))
+            .await
+            .map_err(|e| DockerError::ImageLoadFailed(e.to_string()))?
+            .map_err(DockerError::ImageLoadFailed)?;
+
+        if self.client.inspect_image(&image_ref).await.is_ok() {
+            let log_line = LogLine::new(
+                LogSource::Stdout,
+                format!("Image already exists locally: {image_ref}"),
+            );
+            self.tx_send(JobStatus::PullingImage, log_line).await?;
+            return Ok(image_ref);
+        }
+
+        let log_line = LogLine::new(
+            LogSource::Stdout,
+            format!("Loading image {image_ref} from {}", tar_path.display()),
+        );
+        self.tx_send(JobStatus::PullingImage, log_line).await?;
+
+        // Stream the tarball in chunks; image archives are often several GB
+        let file = tokio::fs::File::open(tar_path).await?;
+        let read_error = Arc::new(Mutex::new(None));
+        let body = archive_chunks(file, read_error.clone());
+
+        let mut stream =
+            self.client
+                .import_image_stream(ImportImageOptions { quiet: false }, body, None);
+
+        // A truncated upload must not be reported as a Docker error, or imported
+        let take_read_error = || read_error.lock().unwrap().take();
+        while let Some(result) = stream.next().await {
+            if let Some(e) = take_read_error() {
+                return Err(DockerError::IoError(e));
+            }
+            match result {
+                Ok(info) => {
+                    let message = info
+                        .stream
+                        .or(info.status.map(|status| match info.progress {
+                            Some(progress) => format!("{status}: {progress}"),
+                            None => status,
+                        }));
+                    if let Some(message) = message {
+                        let message = message.trim();
+                        if !message.is_empty() {
+                            let log_line = LogLine::new(LogSource::Stdout, message.to_string());
+                            self.tx_send(JobStatus::PullingImage, log_line).await?;
+                        }
+                    }
+                }
+                Err(e) => return Err(DockerError::ImageLoadFailed(e.to_string())),
+            }
+        }
+        if let Some(e) = take_read_error() {
+            return Err(DockerError::IoError(e));
+        }
+
+        // The daemon may not report a failure for archives it could not tag
+        if self.client.inspect_image(&image_ref).await.is_err() {
+            return Err(DockerError::ImageLoadFailed(format!(
+                "{} did not provide image {image_ref}",
+                tar_path.display()
+            )));
+        }
+
+        Ok(image_ref)
+    }
+    /// Returns a running container for the job described by `spec`, creating one if needed.
+    ///
+    /// With `Isolation::Workflow`, containers mount the whole workflow folder as
+    /// `/workspace` and are shared by every job using the same image and resource
+    /// limits. With `Isolation::Job`, every job gets a fresh container that mounts
+    /// only `/workspace/<job>`, with `inputs/` read-only, and `release_environment`
+    /// removes it when the job ends. Either way the container is recorded in the
+    /// executor's container pool, so `cleanup_containers` can remove it once the
+    /// workflow has finished.
     ///
-    /// # Behavior
+    /// # Arguments
     ///
-    /// 1. Pulls/builds the Docker image specified in config
-    /// 2. Creates and starts a container with job_folder mounted
-    /// 3. Executes pre-run, run, and post-run scripts sequentially
-    /// 4. Returns the container ID (container is NOT stopped/removed)
-    /// 5. Can be interrupted via cancel_rx channel
+    /// * `spec` - Image, job, workflow folder, isolation mode and `[resources]` limits
+    ///
+    /// # Returns
     ///
-    /// Job status and logs are streamed via the message channel throughout execution.
+    /// * `Ok(String)` - ID of the running container
+    /// * `Err(DockerError)` - The container could not be created or started
     ///
     /// **Note**: The container is left running. Call `cleanup_containers()` after all jobs complete.
-    pub async fn run_job(
-        &self,
-        (workflow_meta, workflow_folder, workflow_params): (
-            &WorkflowMeta,
-            &Path,
-            &job_config::params::WorkflowParams,
-        ),
-        (job, config, job_params): (
-            &workflow::JobFolder,
-            &JobMeta,
-            &job_config::params::JobParams,
-        ),
-        cli_env_vars: &[String],
-        container_registry: &mut std::collections::HashMap<String, String>,
-        cancel_rx: &mut mpsc::Receiver<()>,
-    ) -> Result<String, DockerError> {
-        let work_dir = "/workspace";
-
-        // Pull the Docker image
-        let image_name = &config.container.image;
-        self.pull_image(image_name).await?;
-
-        // Check if we already have a container for this image
-        let container_id = if let Some(existing_id) = container_registry.get(image_name) {
+    pub async fn start_container(&self, spec: EnvironmentSpec<'_>) -> Result<String, DockerError> {
+        let image_name = spec.image;
+        let resources = &spec.config.resources;
+        let workflow_folder = spec.workflow_folder;
+        let isolated = spec.isolation == Isolation::Job;
+
+        // Check if we already have a container for this image, limits and mounts
+        let key = container_key(image_name, resources, spec.mounts);
+        let existing_id = if isolated {
+            None
+        } else {
+            self.containers.lock().unwrap().get(&key)
+        };
+        if let Some(existing_id) = existing_id {
             let log_line = LogLine::new(
                 LogSource::Stdout,
                 format!("Reusing existing container {existing_id} for image {image_name}"),
             );
             self.tx_send(JobStatus::Running, log_line).await?;
-            existing_id.clone()
+            return Ok(existing_id);
+        }
+
+        // Create new container
+        let log_line = LogLine::new(
+            LogSource::Stdout,
+            format!("Creating container with image: {image_name}"),
+        );
+        self.tx_send(JobStatus::CreatingContainer, log_line).await?;
+
+        // Follow the job's GPU policy; without one, auto-detect whether the image
+        // needs a GPU and the host has it
+        let policy = resources.gpu_policy();
+        if policy == Some(GpuPolicy::Required) && self.host_gpu == GpuRuntime::None {
+            return Err(DockerError::ContainerCreateFailed(
+                "the job requires a GPU (gpu = \"required\") but the host has no GPU runtime"
+                    .to_string(),
+            ));
+        }
+        let image_gpu = if policy.is_none() {
+            self.detect_image_gpu(image_name).await
         } else {
-            // Create new container
+            GpuRuntime::None
+        };
+        let use_gpu = crate::gpu::uses_gpu(resources, self.host_gpu)
+            .unwrap_or(image_gpu != GpuRuntime::None && self.host_gpu != GpuRuntime::None);
+
+        let mut env = Vec::new();
+        let mut host_config = if use_gpu && self.host_gpu == GpuRuntime::Nvidia {
             let log_line = LogLine::new(
                 LogSource::Stdout,
-                format!("Creating container with image: {image_name}"),
+                if policy.is_some() {
+                    "GPUs requested in [resources]: NVIDIA runtime on host — enabling GPU passthrough"
+                        .to_string()
+                } else {
+                    "GPU auto-detected: NVIDIA runtime on host, CUDA image — enabling GPU passthrough"
+                        .to_string()
+                },
             );
             self.tx_send(JobStatus::CreatingContainer, log_line).await?;
 
-            // Auto-detect GPU: check if image needs GPU and host has it
-            let image_gpu = self.detect_image_gpu(image_name).await;
-            let use_gpu = image_gpu != GpuRuntime::None && self.host_gpu != GpuRuntime::None;
+            bollard::models::HostConfig {
+                extra_hosts: Some(vec!["host.docker.internal:host-gateway".into()]),
+                device_requests: Some(vec![nvidia_device_request(resources.gpus.as_ref())]),
+                ..Default::default()
+            }
+        } else if use_gpu && self.host_gpu == GpuRuntime::Rocm {
+            let log_line = LogLine::new(
+                LogSource::Stdout,
+                if policy.is_some() {
+                    "GPUs requested in [resources]: AMD/ROCm runtime on host — enabling GPU passthrough".to_string()
+                } else {
+                    "GPU auto-detected: AMD/ROCm runtime on host, ROCm image — enabling GPU passthrough".to_string()
+                },
+            );
+            self.tx_send(JobStatus::CreatingContainer, log_line).await?;
 
-            let mut host_config = if use_gpu && self.host_gpu == GpuRuntime::Nvidia {
+            // ROCm containers see every GPU; selected devices are made visible to the runtime
+            if let Some(Gpus::Devices(ids)) = &resources.gpus {
+                env.push(format!("ROCR_VISIBLE_DEVICES={}", ids.join(",")));
+            }
+            bollard::models::HostConfig {
+                extra_hosts: Some(vec!["host.docker.internal:host-gateway".into()]),
+                devices: Some(vec![
+                    bollard::models::DeviceMapping {
+                        path_on_host: Some("/dev/kfd".to_string()),
+                        path_in_container: Some("/dev/kfd".to_string()),
+                        cgroup_permissions: Some("rw".to_string()),
+                    },
+                    bollard::models::DeviceMapping {
+                        path_on_host: Some("/dev/dri".to_string()),
+                        path_in_container: Some("/dev/dri".to_string()),
+                        cgroup_permissions: Some("rw".to_string()),
+                    },
+                ]),
+                ..Default::default()
+            }
+        } else {
+            if policy == Some(GpuPolicy::Optional) {
                 let log_line = LogLine::new(
                     LogSource::Stdout,
-                    "GPU auto-detected: NVIDIA runtime on host, CUDA image — enabling GPU passthrough".to_string(),
+                    "GPU is optional for this job and the host has no GPU runtime — running on CPU"
+                        .to_string(),
                 );
                 self.tx_send(JobStatus::CreatingContainer, log_line).await?;
-
-                bollard::models::HostConfig {
-                    extra_hosts: Some(vec!["host.docker.internal:host-gateway".into()]),
-                    device_requests: Some(vec![bollard::models::DeviceRequest {
-                        driver: Some("".into()),
-                        count: Some(-1),
-                        device_ids: None,
-                        capabilities: Some(vec![vec!["gpu".into()]]),
-                        options: Some(HashMap::new()),
-                    }]),
-                    ..Default::default()
-                }
-            } else if use_gpu && self.host_gpu == GpuRuntime::Rocm {
+            } else if image_gpu != GpuRuntime::None {
                 let log_line = LogLine::new(
                     LogSource::Stdout,
-                    "GPU auto-detected: AMD/ROCm runtime on host, ROCm image — enabling GPU passthrough".to_string(),
+                    format!(
+                        "Image is GPU-capable ({image_gpu:?}) but host has no matching GPU runtime — running on CPU"
+                    ),
                 );
                 self.tx_send(JobStatus::CreatingContainer, log_line).await?;
+            }
 
-                bollard::models::HostConfig {
-                    extra_hosts: Some(vec!["host.docker.internal:host-gateway".into()]),
-                    devices: Some(vec![
-                        bollard::models::DeviceMapping {
-                            path_on_host: Some("/dev/kfd".to_string()),
-                            path_in_container: Some("/dev/kfd".to_string()),
-                            cgroup_permissions: Some("rw".to_string()),
-                        },
-                        bollard::models::DeviceMapping {
-                            path_on_host: Some("/dev/dri".to_string()),
-                            path_in_container: Some("/dev/dri".to_string()),
-                            cgroup_permissions: Some("rw".to_string()),
-                        },
-                    ]),
-                    ..Default::default()
-                }
-            } else {
-                if image_gpu != GpuRuntime::None {
-                    let log_line = LogLine::new(
-                        LogSource::Stdout,
-                        format!(
-                            "Image is GPU-capable ({image_gpu:?}) but host has no matching GPU runtime — running on CPU"
-                        ),
-                    );
-                    self.tx_send(JobStatus::CreatingContainer, log_line).await?;
-                }
-
-                bollard::models::HostConfig {
-                    extra_hosts: Some(vec!["host.docker.internal:host-gateway".into()]),
-                    ..Default::default()
-                }
-            };
-            let workflow_folder_str = workflow_folder.to_str().unwrap();
-            let volume_binds = vec![
-                format!("{workflow_folder_str}:{work_dir}"),
-                "/tmp:/tmp".to_string(),
-            ];
-            host_config.binds = Some(volume_binds);
-
-            // Run container as host user to avoid permission issues on bind mounts
-            #[cfg(unix)]
-            let user = {
-                use std::os::unix::fs::MetadataExt;
-                let meta = std::fs::metadata(workflow_folder).ok();
-                meta.map(|m| format!("{}:{}", m.uid(), m.gid()))
-            };
-            #[cfg(not(unix))]
-            let user: Option<String> = None;
-
-            let container_config = Config {
-                image: Some(image_name.clone()),
-                tty: Some(true),
-                attach_stdout: Some(true),
-                attach_stderr: Some(true),
-                host_config: Some(host_config),
-                working_dir: Some(work_dir.to_string()),
-                user,
-                // Keep container alive with a long-running command
-                // This allows multiple execs without the container exiting
-                cmd: Some(vec![
-                    "tail".to_string(),
-                    "-f".to_string(),
-                    "/dev/null".to_string(),
-                ]),
+            bollard::models::HostConfig {
+                extra_hosts: Some(vec!["host.docker.internal:host-gateway".into()]),
                 ..Default::default()
-            };
-
-            let container = self
-                .client
-                .create_container::<String, String>(None, container_config)
-                .await
-                .map_err(|e| DockerError::ContainerCreateFailed(e.to_string()))?;
-            let log_line = LogLine::new(
-                LogSource::Stdout,
-                format!(
-                    "Container created: {}, binding {workflow_folder_str} to {work_dir}",
-                    container.id
-                ),
-            );
-            self.tx_send(JobStatus::CreatingContainer, log_line).await?;
-
-            // Start container
-            self.client
-                .start_container::<String>(&container.id, None)
-                .await
-                .map_err(|e| DockerError::ContainerStartFailed(e.to_string()))?;
-
-            let log_line = LogLine::new(
-                LogSource::Stdout,
-                format!("Waiting for Container {} running ... ", container.id),
-            );
-            self.tx_send(JobStatus::CreatingContainer, log_line).await?;
-
-            // Wait for container to be running (timeout: 30 seconds)
-            self.wait_for_container_running(&container.id, 30).await?;
-
-            let log_line = LogLine::new(
-                LogSource::Stdout,
-                format!("Now container {} is running ... ", container.id),
-            );
-            self.tx_send(
-                JobStatus::ContainerRunning(container.id.to_string()),
-                log_line,
-            )
-            .await?;
-
-            let log_line =
-                LogLine::new(LogSource::Stdout, "Container started and ready".to_string());
-            self.tx_send(JobStatus::Running, log_line).await?;
-
-            // Register the new container in the registry
-            container_registry.insert(image_name.clone(), container.id.clone());
-            container.id
+            }
         };
-
-        // Merge workflow parameters with job parameters
-        // Start with workflow params, then overlay job params (job params take precedence)
-        let mut merged_params = workflow_params.clone();
-        for (param_name, param_value) in job_params {
-            merged_params.insert(param_name.clone(), param_value.clone());
+        if isolated {
+            // Docker would create missing bind sources owned by root
+            let job_dir = workflow_folder.join(spec.job_name);
+            for dir in ["inputs", "outputs"] {
+                std::fs::create_dir_all(job_dir.join(dir)).map_err(|e| {
+                    DockerError::ContainerCreateFailed(format!(
+                        "Failed to create {}: {e}",
+                        job_dir.join(dir).display()
+                    ))
+                })?;
+            }
         }
-
-        // Convert merged parameters to environment variables
-        let mut env_vars: Vec<String> = Vec::new();
-        for (param_name, param_value) in &merged_params {
-            // Convert JSON value to string
-            let value_str = match param_value {
-                serde_json::Value::String(s) => s.clone(),
-                serde_json::Value::Number(n) => n.to_string(),
-                serde_json::Value::Bool(b) => b.to_string(),
-                serde_json::Value::Null => "null".to_string(),
-                v => v.to_string(),
-            };
-            // Add with PARAM_ prefix to avoid conflicts
-            env_vars.push(format!("PARAM_{}={}", param_name.to_uppercase(), value_str));
+        let volume_binds = volume_binds(&spec);
+        let log_line = LogLine::new(
+            LogSource::Stdout,
+            format!("Mounts: {}", volume_binds.join(", ")),
+        );
+        self.tx_send(JobStatus::CreatingContainer, log_line).await?;
+        host_config.binds = Some(volume_binds);
+        // A private /tmp instead of the host's
+        host_config.tmpfs = Some(HashMap::from([(
+            "/tmp".to_string(),
+            "rw,exec,mode=1777".to_string(),
+        )]));
+        apply_resources(&mut host_config, resources);
+        if let Some(limits) = describe_resources(resources) {
+            let log_line = LogLine::new(LogSource::Stdout, format!("Resource limits: {limits}"));
+            self.tx_send(JobStatus::CreatingContainer, log_line).await?;
         }
 
-        // Forward selected host environment variables into the container exec environment.
-        for key in workflow_meta.env_passthrough.as_deref().unwrap_or(&[]) {
-            if let Ok(val) = std::env::var(key) {
-                env_vars.push(format!("{key}={val}"));
-            }
-        }
+        // Run container as host user to avoid permission issues on bind mounts
+        #[cfg(unix)]
+        let user = {
+            use std::os::unix::fs::MetadataExt;
+            let meta = std::fs::metadata(workflow_folder).ok();
+            meta.map(|m| format!("{}:{}", m.uid(), m.gid()))
+        };
+        #[cfg(not(unix))]
+        let user: Option<String> = None;
 
-        // Inject -e/--env CLI values unconditionally, unprefixed — independent of
-        // (and taking precedence over) the env_passthrough allowlist above.
-        for entry in cli_env_vars {
-            env_vars.push(entry.clone());
-        }
+        let container_config = Config {
+            image: Some(image_name.to_string()),
+            tty: Some(true),
+            attach_stdout: Some(true),
+            attach_stderr: Some(true),
+            host_config: Some(host_config),
+            working_dir: Some(WORK_DIR.to_string()),
+            env: (!env.is_empty()).then_some(env),
+            user,
+            // Keep container alive with a long-running command
+            // This allows multiple execs without the container exiting
+            cmd: Some(vec![
+                "tail".to_string(),
+                "-f".to_string(),
+                "/dev/null".to_string(),
+            ]),
+            ..Default::default()
+        };
 
-        if !cli_env_vars.is_empty() {
-            let log_line = LogLine::new(
-                LogSource::Stdout,
-                format!("Setting {} CLI env var(s) via -e/--env", cli_env_vars.len()),
-            );
-            self.tx_send(JobStatus::CreatingContainer, log_line).await?;
+        let container = self
+            .client
+            .create_container::<String, String>(None, container_config)
+            .await
+            .map_err(|e| DockerError::ContainerCreateFailed(e.to_string()))?;
+        if isolated {
+            // Remembered right away, so cleanup removes it even if starting fails
+            self.containers
+                .lock()
+                .unwrap()
+                .add_private(container.id.clone());
         }
+        let log_line = LogLine::new(
+            LogSource::Stdout,
+            format!(
+                "Container created: {} ({} isolation)",
+                container.id,
+                spec.isolation.as_str()
+            ),
+        );
+        self.tx_send(JobStatus::CreatingContainer, log_line).await?;
 
-        if !env_vars.is_empty() {
-            let global_count = workflow_params.len();
-            let job_count = job_params.len();
-            let total_count = merged_params.len();
-            let log_line = LogLine::new(
-                LogSource::Stdout,
-                format!(
-                    "Setting {} parameter environment variable(s) ({} global + {} job = {} total)",
-                    env_vars.len(),
-                    global_count,
-                    job_count,
-                    total_count
-                ),
-            );
-            self.tx_send(JobStatus::CreatingContainer, log_line).await?;
-        }
+        // Start container
+        self.client
+            .start_container::<String>(&container.id, None)
+            .await
+            .map_err(|e| DockerError::ContainerStartFailed(e.to_string()))?;
 
-        // Execute scripts sequentially
-        let scripts = vec![
-            ("pre_run.sh", &config.scripts.pre),
-            ("run.sh", &config.scripts.run),
-            ("post_run.sh", &config.scripts.post),
-        ];
-
-        let mut all_scripts_succeeded = true;
-        for (name, script) in scripts {
-            if ["pre_run.sh", "post_run.sh"].contains(&name)
-                && !workflow_folder.join(&job.name).join(script).exists()
-            {
-                let log_line = LogLine::new(
-                    LogSource::Stdout,
-                    format!("Script {script} not found ... skip"),
-                );
-                self.tx_send(JobStatus::Running, log_line).await?;
-                continue;
-            }
+        let log_line = LogLine::new(
+            LogSource::Stdout,
+            format!("Waiting for Container {} running ... ", container.id),
+        );
+        self.tx_send(JobStatus::CreatingContainer, log_line).await?;
 
-            let log_line = LogLine::new(LogSource::Stdout, format!("Executing script: {script}"));
-            self.tx_send(JobStatus::Running, log_line).await?;
+        // Wait for container to be running (timeout: 30 seconds)
+        self.wait_for_container_running(&container.id, 30).await?;
 
-            let job_workdir = format!("{}/{}", work_dir, job.name);
-            match self
-                .exec_script(&container_id, &job_workdir, script, &env_vars, cancel_rx)
-                .await
-            {
-                Ok(exit_code) => {
-                    if exit_code != 0 {
-                        let log_line = LogLine::new(
-                            LogSource::Stderr,
-                            format!("Script {script} failed with exit code {exit_code}"),
-                        );
-                        self.tx_send(JobStatus::Failed, log_line).await?;
-                        all_scripts_succeeded = false;
-                        break;
-                    } else {
-                        let log_line = LogLine::new(
-                            LogSource::Stdout,
-                            format!("Script {script} completed successfully"),
-                        );
-                        self.tx_send(JobStatus::Completed, log_line).await?;
-                    }
-                }
-                Err(e) => {
-                    let log_line = LogLine::new(LogSource::Stderr, format!("Error: {e}"));
-                    self.tx_send(JobStatus::Failed, log_line).await?;
-                    all_scripts_succeeded = false;
-                    break;
-                }
-            }
-        }
+        let log_line = LogLine::new(
+            LogSource::Stdout,
+            format!("Now container {} is running ... ", container.id),
+        );
+        self.tx_send(
+            JobStatus::ContainerRunning(container.id.to_string()),
+            log_line,
+        )
+        .await?;
 
-        // Collect output files if all scripts succeeded
-        if all_scripts_succeeded && !config.outputs.is_empty() {
-            let log_line =
-                LogLine::new(LogSource::Stdout, "Collecting output files...".to_string());
-            self.tx_send(JobStatus::Running, log_line).await?;
+        let log_line = LogLine::new(LogSource::Stdout, "Container started and ready".to_string());
+        self.tx_send(JobStatus::Running, log_line).await?;
 
-            let job_workdir = format!("{}/{}", work_dir, job.name);
-            match self
-                .collect_output_files(&container_id, &job_workdir, &config.outputs, cancel_rx)
-                .await
-            {
-                Ok(file_count) => {
-                    let log_line = LogLine::new(
-                        LogSource::Stdout,
-                        format!("Collected {file_count} output file(s) to outputs/ folder"),
-                    );
-                    self.tx_send(JobStatus::Completed, log_line).await?;
-                }
-                Err(e) => {
-                    let log_line = LogLine::new(
-                        LogSource::Stderr,
-                        format!("Warning: Failed to collect output files: {e}"),
-                    );
-                    self.tx_send(JobStatus::Running, log_line).await?;
-                }
-            }
+        // Register the new container in the pool
+        if !isolated {
+            self.containers
+                .lock()
+                .unwrap()
+                .merge(HashMap::from([(key, container.id.clone())]));
         }
+        Ok(container.id)
+    }
 
-        // Return container ID for cleanup later, or error if scripts failed
-        if all_scripts_succeeded {
-            let log_line = LogLine::new(
+    /// Stops and removes a job's private container once the job is done.
+    async fn remove_private_container(&self, container_id: &str) {
+        let remove_options = RemoveContainerOptions {
+            force: true,
+            ..Default::default()
+        };
+        let log_line = match self
+            .client
+            .remove_container(container_id, Some(remove_options))
+            .await
+        {
+            Ok(_) => LogLine::new(
                 LogSource::Stdout,
-                format!(
-                    "Job completed, container {container_id} will be cleaned up after workflow finishes"
-                ),
-            );
-            self.tx_send(JobStatus::Completed, log_line).await?;
-            Ok(container_id)
-        } else {
-            // Register container for cleanup even on failure
-            container_registry.insert(image_name.clone(), container_id.clone());
-            Err(DockerError::ScriptExecutionFailed {
-                script: "job scripts".to_string(),
-                exit_code: 1,
-            })
-        }
+                format!("Removed container {container_id}"),
+            ),
+            Err(e) => LogLine::new(
+                LogSource::Stderr,
+                format!("Failed to remove container {container_id}: {e}"),
+            ),
+        };
+        let _ = self.tx_send(JobStatus::Running, log_line).await;
     }
 
     /// Cleans up (stops and removes) multiple containers.
@@ -918,40 +886,213 @@ impl DockerExecutor {
         let _ = self.tx_send(JobStatus::Completed, log_line).await;
     }
 
-    /// Executes a script inside a running container.
-    async fn exec_script(
+    /// Creates a tar archive from a directory for Docker build context.
+    ///
+    /// Skips the job's `inputs/` and `outputs/` folders and adds `dockerfile`
+    /// as `GENERATED_DOCKERFILE`.
+    fn create_build_context(&self, path: &Path, dockerfile: &str) -> Result<Vec<u8>, DockerError> {
+        let mut tar = tar::Builder::new(Vec::new());
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if BUILD_CONTEXT_SKIP.iter().any(|skip| name == **skip) {
+                continue;
+            }
+            if entry.path().is_dir() {
+                tar.append_dir_all(&name, entry.path())?;
+            } else {
+                tar.append_path_with_name(entry.path(), &name)?;
+            }
+        }
+
+        let mut header = tar::Header::new_gnu();
+        header.set_size(dockerfile.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, GENERATED_DOCKERFILE, dockerfile.as_bytes())?;
+
+        let data = tar
+            .into_inner()
+            .map_err(|e| DockerError::IoError(std::io::Error::other(e)))?;
+        Ok(data)
+    }
+
+    /// Kills the script that exec `exec_id` of job `job_name` runs in
+    /// `container_id`, with every process it started.
+    ///
+    /// Docker cannot stop an exec. When the exec's process is visible on this host,
+    /// as with a local daemon on Linux, its process tree is killed from here;
+    /// otherwise a second exec kills every process carrying the script's tag,
+    /// which needs `/bin/sh` in the image.
+    async fn kill_script(
         &self,
         container_id: &str,
-        job_work_dir: &str,
-        script: &str,
+        exec_id: &str,
+        job_name: &str,
+    ) -> Result<(), DockerError> {
+        let inspect = self.client.inspect_exec(exec_id).await?;
+        if inspect.running == Some(false) {
+            return Ok(());
+        }
+        // Falls back to the second exec when this host cannot see or signal the process
+        if let Some(pid) = inspect.pid
+            && kill_host_process_tree(pid, container_id).unwrap_or(false)
+        {
+            return Ok(());
+        }
+
+        let kill_cmd = kill_tagged_command(&script_tag(job_name));
+        let exec_config = CreateExecOptions {
+            attach_stdout: Some(true),
+            attach_stderr: Some(true),
+            cmd: Some(vec!["/bin/sh", "-c", &kill_cmd]),
+            ..Default::default()
+        };
+        let exec = self.client.create_exec(container_id, exec_config).await?;
+        if let StartExecResults::Attached { mut output, .. } =
+            self.client.start_exec(&exec.id, None).await?
+        {
+            while output.next().await.is_some() {}
+        }
+        match self.client.inspect_exec(&exec.id).await?.exit_code {
+            Some(0) => Ok(()),
+            exit_code => Err(DockerError::ScriptExecutionFailed {
+                script: "/bin/sh".to_string(),
+                exit_code: exit_code.unwrap_or(1),
+            }),
+        }
+    }
+
+    /// Waits for a container to reach running state.
+    ///
+    /// # Arguments
+    ///
+    /// * `container_id` - The container ID to monitor
+    /// * `timeout_secs` - Maximum seconds to wait for the container to start
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Container is running
+    /// * `Err(DockerError)` - Container failed to start or timeout reached
+    async fn wait_for_container_running(
+        &self,
+        container_id: &str,
+        timeout_secs: u64,
+    ) -> Result<(), DockerError> {
+        use tokio::time::{Duration, sleep};
+
+        let start = std::time::Instant::now();
+        let timeout = Duration::from_secs(timeout_secs);
+
+        loop {
+            let inspect = self.client.inspect_container(container_id, None).await?;
+
+            if let Some(state) = inspect.state {
+                if state.running == Some(true) {
+                    return Ok(());
+                }
+                if state.dead == Some(true) || state.oom_killed == Some(true) {
+                    return Err(DockerError::ContainerStartFailed(
+                        "Container died during startup".to_string(),
+                    ));
+                }
+            }
+
+            if start.elapsed() > timeout {
+                return Err(DockerError::ContainerStartFailed(format!(
+                    "Container did not start within {timeout_secs} seconds",
+                )));
+            }
+
+            sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
+impl Executor for DockerExecutor {
+    type Error = DockerError;
+    type Environment = String;
+
+    fn set_job_idx(&mut self, job_idx: usize) {
+        DockerExecutor::set_job_idx(self, job_idx);
+    }
+
+    fn gpu_runtime(&self) -> GpuRuntime {
+        self.host_gpu
+    }
+
+    /// Makes the job's image available and returns the reference to run it from.
+    ///
+    /// Registry and local images are pulled (or reused), `.tar` archives are loaded
+    /// through the image-load API and Dockerfiles are built. Relative paths resolve
+    /// against `job_dir`.
+    async fn prepare_image(&self, config: &JobMeta, job_dir: &Path) -> Result<String, DockerError> {
+        match config.container.get_image_source() {
+            ImageSource::Registry(image) | ImageSource::LocalImage(image) => {
+                self.pull_image(&image).await?;
+                Ok(image)
+            }
+            ImageSource::TarFile(path) => self.load_image(&resolve_job_path(job_dir, &path)).await,
+            ImageSource::SifFile(path) => Err(DockerError::ImageLoadFailed(format!(
+                "{path} is an Apptainer/Singularity image, which Docker cannot run"
+            ))),
+            ImageSource::Dockerfile(path) => {
+                let repository = if config.container.image.is_empty() {
+                    format!("silva-{}", config.name)
+                } else {
+                    config.container.image.clone()
+                };
+                self.build_image(
+                    &image_repository(&repository),
+                    &resolve_job_path(job_dir, &path),
+                    &config.container.build_args,
+                    config.container.target.as_deref(),
+                )
+                .await
+            }
+        }
+    }
+
+    async fn image_id(&self, image: &str) -> Result<String, DockerError> {
+        // The image ID is the digest of its config, so it changes whenever the
+        // image content does, even if the tag stays the same
+        let image_info = self.client.inspect_image(image).await?;
+        Ok(image_info.id.unwrap_or_else(|| image.to_string()))
+    }
+
+    async fn start_environment(&self, spec: EnvironmentSpec<'_>) -> Result<String, DockerError> {
+        self.start_container(spec).await
+    }
+
+    async fn release_environment(&self, container_id: String) {
+        let private = self.containers.lock().unwrap().release(&container_id);
+        if private {
+            self.remove_private_container(&container_id).await;
+        }
+    }
+
+    /// Executes a script inside a running container.
+    async fn exec_script(
+        &self,
+        container_id: &String,
+        job_name: &str,
+        script: &ScriptCommand,
         env_vars: &[String],
         cancel_rx: &mut mpsc::Receiver<()>,
     ) -> Result<i64, DockerError> {
-        // Convert relative script path to absolute path for reliable execution
-        let script_path = if let Some(stripped) = script.strip_prefix("./") {
-            format!("{job_work_dir}/{stripped}")
-        } else if script.starts_with('/') {
-            // Already absolute
-            script.to_string()
-        } else {
-            // Relative without "./" prefix
-            format!("{job_work_dir}/{script}")
-        };
-
-        // Strip Windows CRLF line endings to ensure compatibility when scripts are created on Windows
-        // but executed in Linux containers. Uses sed to remove \r characters before piping to bash.
-        let script_cmd = format!("sed 's/\\r$//' '{script_path}' | /bin/bash -s");
+        let job_work_dir = &format!("{WORK_DIR}/{job_name}");
+        let argv = script_argv(job_work_dir, script);
+        // Lets `kill_script` find the script's processes from inside the container
+        let tag = script_tag(job_name);
+        let mut env: Vec<&str> = env_vars.iter().map(String::as_str).collect();
+        env.push(&tag);
 
         let exec_config = CreateExecOptions {
             attach_stdout: Some(true),
             attach_stderr: Some(true),
-            cmd: Some(vec!["/bin/bash", "-c", &script_cmd]),
+            cmd: Some(argv.iter().map(String::as_str).collect()),
             working_dir: Some(job_work_dir),
-            env: if env_vars.is_empty() {
-                None
-            } else {
-                Some(env_vars.iter().map(|s| s.as_str()).collect())
-            },
+            env: Some(env),
             ..Default::default()
         };
 
@@ -993,7 +1134,14 @@ impl DockerExecutor {
                                 _ => {}
                             }
                         }
-                        _ = cancel_rx.recv() => {
+                        Some(()) = cancel_rx.recv() => {
+                            if let Err(e) = self.kill_script(container_id, &exec.id, job_name).await {
+                                let log_line = LogLine::new(
+                                    LogSource::Stderr,
+                                    format!("Failed to stop script {script}: {e}"),
+                                );
+                                self.tx_send(JobStatus::Running, log_line).await?;
+                            }
                             break;
                         }
                     }
@@ -1013,213 +1161,491 @@ impl DockerExecutor {
         Ok(exit_code)
     }
 
-    /// Collects output files from the container based on glob patterns.
-    ///
-    /// Creates an outputs/ directory in the job folder and copies matching files.
-    /// Since the job folder is bind-mounted, files will automatically appear on the host.
-    ///
-    /// # Arguments
-    ///
-    /// * `container_id` - The running container ID
-    /// * `job_work_dir` - Working directory inside the container (e.g., "/workspace/job1")
-    /// * `output_patterns` - List of glob patterns to match files (e.g., ["*.csv", "results/*.json"])
-    /// * `cancel_rx` - Channel receiver for cancellation signals
-    ///
-    /// # Returns
-    ///
-    /// * `Ok(usize)` - Number of files collected
-    /// * `Err(DockerError)` - Collection error
-    async fn collect_output_files(
-        &self,
-        container_id: &str,
-        job_work_dir: &str,
-        output_patterns: &[String],
-        cancel_rx: &mut mpsc::Receiver<()>,
-    ) -> Result<usize, DockerError> {
-        // Build a bash script that creates outputs/ dir and copies matching files
-        // Note: We don't use 'set -e' to make it more forgiving when patterns don't match
-        let mut script = String::from("mkdir -p outputs\n");
-        script.push_str("file_count=0\n");
-        script.push_str("echo 'Collecting output files...'\n");
-
-        for pattern in output_patterns {
-            // Use shopt -s nullglob to handle cases where pattern doesn't match anything
-            script.push_str("shopt -s nullglob\n");
-            script.push_str(&format!("matched_files=({pattern})\n"));
-            script.push_str("shopt -u nullglob\n");
-            script.push_str("if [ ${#matched_files[@]} -eq 0 ]; then\n");
-            script.push_str(&format!(
-                "  echo 'Pattern \"{pattern}\" matched no files'\n"
-            ));
-            script.push_str("else\n");
-            script.push_str("  for file in \"${matched_files[@]}\"; do\n");
-            script.push_str("    if [ -f \"$file\" ]; then\n");
-            script.push_str("      if cp -v \"$file\" outputs/ 2>&1; then\n");
-            script.push_str("        file_count=$((file_count + 1))\n");
-            script.push_str("      else\n");
-            script.push_str("        echo \"Warning: Failed to copy file: $file\"\n");
-            script.push_str("      fi\n");
-            script.push_str("    elif [ -d \"$file\" ]; then\n");
-            script.push_str("      if cp -rv \"$file\" outputs/ 2>&1; then\n");
-            script.push_str("        file_count=$((file_count + 1))\n");
-            script.push_str("      else\n");
-            script.push_str("        echo \"Warning: Failed to copy directory: $file\"\n");
-            script.push_str("      fi\n");
-            script.push_str("    fi\n");
-            script.push_str("  done\n");
-            script.push_str("fi\n");
+    async fn cleanup(&self) {
+        let container_ids = self.containers.lock().unwrap().container_ids();
+        self.cleanup_containers(&container_ids).await;
+    }
+}
+
+/// Top-level job folders left out of Docker build contexts: they hold per-run data.
+const BUILD_CONTEXT_SKIP: &[&str] = &["inputs", "outputs"];
+
+/// Name of the Dockerfile silva adds to the build context it sends to the daemon.
+const GENERATED_DOCKERFILE: &str = ".silva.Dockerfile";
+
+/// Turns a name into a valid Docker repository name: lowercase, with characters
+/// outside `[a-z0-9._/-]` replaced by `-`, and any tag (`:...`) removed.
+fn image_repository(name: &str) -> String {
+    let without_tag = match name.rsplit_once(':') {
+        Some((repo, tag)) if !tag.contains('/') => repo,
+        _ => name,
+    };
+    let sanitized: String = without_tag
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '/' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    sanitized
+        .trim_matches(|c: char| !c.is_ascii_alphanumeric())
+        .to_string()
+}
+
+/// Cuts a multi-stage Dockerfile after the stage named `target`.
+///
+/// Equivalent to `docker build --target`: stages can only depend on earlier
+/// ones, so everything after the target stage is irrelevant to it.
+fn truncate_dockerfile_at_target(dockerfile: &str, target: &str) -> Result<String, String> {
+    let is_from = |line: &str| {
+        line.split_whitespace()
+            .next()
+            .is_some_and(|word| word.eq_ignore_ascii_case("FROM"))
+    };
+    let names_stage = |line: &str| {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        words
+            .windows(2)
+            .any(|pair| pair[0].eq_ignore_ascii_case("AS") && pair[1].eq_ignore_ascii_case(target))
+    };
+
+    let mut kept = Vec::new();
+    let mut in_target = false;
+    let mut found = false;
+    let mut continued = false;
+    for line in dockerfile.lines() {
+        if !continued && is_from(line) {
+            if in_target {
+                break;
+            }
+            if names_stage(line) {
+                in_target = true;
+                found = true;
+            }
         }
+        continued = line.trim_end().ends_with('\\');
+        kept.push(line);
+    }
 
-        script.push_str("echo \"Total files collected: $file_count\"\n");
-        script.push_str("exit 0\n");
+    if found {
+        Ok(kept.join("\n") + "\n")
+    } else {
+        Err(format!("Dockerfile has no build stage named '{target}'"))
+    }
+}
 
-        let exec_config = CreateExecOptions {
-            attach_stdout: Some(true),
-            attach_stderr: Some(true),
-            cmd: Some(vec!["/bin/bash", "-c", &script]),
-            working_dir: Some(job_work_dir),
-            ..Default::default()
-        };
+/// Program and arguments running `script` in the folder `job_work_dir`, with a
+/// relative program path (e.g. `./run.sh` of a `shebang` script) made absolute.
+///
+/// Scripts are run as they are: CRLF line endings are converted on the host when
+/// the workflow is prepared, so images need neither `sed` nor `bash`.
+pub(crate) fn script_argv(job_work_dir: &str, script: &ScriptCommand) -> Vec<String> {
+    let mut argv = script.argv.clone();
+    if let Some(program) = argv.first_mut()
+        && program.contains('/')
+        && !program.starts_with('/')
+    {
+        *program = format!("{job_work_dir}/{}", program.trim_start_matches("./"));
+    }
+    argv
+}
 
-        let exec = self.client.create_exec(container_id, exec_config).await?;
+/// Returns the `KEY=VALUE` variable that marks the processes of a script of job
+/// `job_name`, inherited by everything the script starts.
+///
+/// The name is hex-encoded, so distinct jobs get distinct tags that are safe to
+/// quote in `sh`, and the value ends in `:`, so the tag of one job is never a
+/// prefix of another's.
+fn script_tag(job_name: &str) -> String {
+    let name: String = job_name.bytes().map(|b| format!("{b:02x}")).collect();
+    format!("SILVA_SCRIPT={name}:")
+}
 
-        let mut file_count = 0;
-        let mut last_line = String::new();
-        match self.client.start_exec(&exec.id, None).await? {
-            StartExecResults::Attached { mut output, .. } => {
-                loop {
-                    tokio::select! {
-                        result = output.next() => {
-                            match result {
-                                Some(Ok(LogOutput::StdOut { message })) => {
-                                    let content = String::from_utf8_lossy(&message)
-                                        .trim_end_matches("\n").to_string();
+/// Builds the `sh -c` command line that kills every process of the container
+/// whose environment holds `tag`.
+fn kill_tagged_command(tag: &str) -> String {
+    format!(
+        "for p in /proc/[0-9]*; do \
+         grep -qF '{tag}' \"$p/environ\" 2>/dev/null && kill -KILL \"${{p#/proc/}}\" 2>/dev/null; \
+         done; true"
+    )
+}
 
-                                    // Save the last line to parse file count at the end
-                                    if !content.is_empty() {
-                                        last_line = content.clone();
-                                        let log_line = LogLine::new(LogSource::Stdout, content);
-                                        self.tx_send(JobStatus::Running, log_line).await?;
-                                    }
-                                }
-                                Some(Ok(LogOutput::StdErr { message })) => {
-                                    let content = String::from_utf8_lossy(&message)
-                                        .trim_end_matches("\n").to_string();
-                                    if !content.is_empty() {
-                                        let log_line = LogLine::new(LogSource::Stderr, content);
-                                        self.tx_send(JobStatus::Running, log_line).await?;
-                                    }
-                                }
-                                Some(Err(e)) => {
-                                    return Err(DockerError::LogStreamError(e.to_string()));
-                                }
-                                None => {
-                                    break;
-                                }
-                                _ => {}
-                            }
-                        }
-                        _ = cancel_rx.recv() => {
-                            break;
-                        }
-                    }
-                }
-            }
-            StartExecResults::Detached => {
-                return Err(DockerError::LogStreamError(
-                    "Exec started in detached mode".to_string(),
-                ));
-            }
-        }
+/// Kills process `pid` of the Docker host, a script's exec, with all of its
+/// descendants.
+///
+/// Returns `Ok(false)` without killing anything unless the process is visible
+/// here and belongs to the cgroup of `container_id`, which is the case with a
+/// local daemon on Linux but not with, e.g., Docker Desktop.
+#[cfg(target_os = "linux")]
+fn kill_host_process_tree(pid: i64, container_id: &str) -> std::io::Result<bool> {
+    match std::fs::read_to_string(format!("/proc/{pid}/cgroup")) {
+        Ok(cgroup) if cgroup.contains(container_id) => {}
+        Ok(_) => return Ok(false),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    }
+    kill_process_tree(pid)?;
+    Ok(true)
+}
 
-        // Parse the file count from the last line
-        if let Some(count) = last_line
-            .rsplit_once(':')
-            .and_then(|(_, num)| num.trim().parse().ok())
-        {
-            file_count = count;
+#[cfg(not(target_os = "linux"))]
+fn kill_host_process_tree(_pid: i64, _container_id: &str) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+/// Kills process `pid` and all of its descendants, found through `/proc`.
+///
+/// Each process is stopped before its children are listed, so none can start
+/// new ones while the tree is walked.
+#[cfg(target_os = "linux")]
+fn kill_process_tree(pid: i64) -> std::io::Result<()> {
+    let signal = |pid: i64, signal: libc::c_int| {
+        // SAFETY: kill only sends a signal; it does not touch our memory
+        if unsafe { libc::kill(pid as libc::pid_t, signal) } == 0 {
+            return Ok(());
+        }
+        let err = std::io::Error::last_os_error();
+        // The process has already exited
+        if err.raw_os_error() == Some(libc::ESRCH) {
+            Ok(())
         } else {
-            let log_line = LogLine::new(
-                LogSource::Stderr,
-                format!("parse file count from -{last_line}- error"),
+            Err(err)
+        }
+    };
+
+    let mut tree = vec![pid];
+    let mut next = 0;
+    while let Some(&pid) = tree.get(next) {
+        signal(pid, libc::SIGSTOP)?;
+        let tasks = std::fs::read_dir(format!("/proc/{pid}/task"));
+        for task in tasks.into_iter().flatten().flatten() {
+            let children =
+                std::fs::read_to_string(task.path().join("children")).unwrap_or_default();
+            tree.extend(
+                children
+                    .split_whitespace()
+                    .filter_map(|c| c.parse::<i64>().ok()),
             );
-            self.tx_send(JobStatus::Running, log_line).await?;
         }
+        next += 1;
+    }
+    for pid in tree {
+        signal(pid, libc::SIGKILL)?;
+    }
+    Ok(())
+}
 
-        // Get exit code to ensure the script ran successfully
-        let inspect = self.client.inspect_exec(&exec.id).await?;
-        let exit_code = inspect.exit_code.unwrap_or(1);
-
-        if exit_code != 0 {
-            return Err(DockerError::ScriptExecutionFailed {
-                script: "output_collection".to_string(),
-                exit_code,
-            });
+/// Bind mounts of the container for `spec`.
+///
+/// A shared container sees the whole workflow folder; an isolated one sees only
+/// its job folder, with `inputs/` mounted read-only. The `[[mounts]]` follow.
+fn volume_binds(spec: &EnvironmentSpec<'_>) -> Vec<String> {
+    let workflow_folder = spec.workflow_folder.display();
+    let mut binds = match spec.isolation {
+        Isolation::Workflow => vec![format!("{workflow_folder}:{WORK_DIR}")],
+        Isolation::Job => {
+            let host_dir = format!("{workflow_folder}/{}", spec.job_name);
+            let container_dir = format!("{WORK_DIR}/{}", spec.job_name);
+            vec![
+                format!("{host_dir}:{container_dir}"),
+                format!("{host_dir}/inputs:{container_dir}/inputs:ro"),
+                format!("{host_dir}/outputs:{container_dir}/outputs"),
+            ]
         }
+    };
+    binds.extend(spec.mounts.iter().map(crate::mounts::bind_spec));
+    binds
+}
 
-        Ok(file_count)
+/// Key under which the container for `image` with `resources` and `mounts` is pooled.
+///
+/// Jobs with different limits or mounts must not share a container, so these are
+/// part of the key whenever any is set.
+fn container_key(image: &str, resources: &Resources, mounts: &[Mount]) -> String {
+    let mut key = image.to_string();
+    if !resources.is_empty() {
+        key.push(' ');
+        key.push_str(&serde_json::to_string(resources).unwrap_or_default());
+    }
+    if !mounts.is_empty() {
+        key.push(' ');
+        key.push_str(&serde_json::to_string(mounts).unwrap_or_default());
     }
+    key
+}
 
-    /// Creates a tar archive from a directory for Docker build context.
-    fn create_tar_archive(&self, path: &Path) -> Result<Vec<u8>, DockerError> {
-        let mut tar = tar::Builder::new(Vec::new());
-        tar.append_dir_all(".", path)?;
-        let data = tar
-            .into_inner()
-            .map_err(|e| DockerError::IoError(std::io::Error::other(e)))?;
-        Ok(data)
+/// Builds the NVIDIA device request for `gpus`, or for every GPU when unset.
+fn nvidia_device_request(gpus: Option<&Gpus>) -> bollard::models::DeviceRequest {
+    let (count, device_ids) = match gpus {
+        None | Some(Gpus::All) => (Some(-1), None),
+        Some(Gpus::Count(count)) => (Some(*count as i64), None),
+        Some(Gpus::Devices(ids)) => (None, Some(ids.clone())),
+    };
+    bollard::models::DeviceRequest {
+        driver: Some("".into()),
+        count,
+        device_ids,
+        capabilities: Some(vec![vec!["gpu".into()]]),
+        options: Some(HashMap::new()),
     }
+}
 
-    /// Waits for a container to reach running state.
-    ///
-    /// # Arguments
-    ///
-    /// * `container_id` - The container ID to monitor
-    /// * `timeout_secs` - Maximum seconds to wait for the container to start
-    ///
-    /// # Returns
-    ///
-    /// * `Ok(())` - Container is running
-    /// * `Err(DockerError)` - Container failed to start or timeout reached
-    async fn wait_for_container_running(
-        &self,
-        container_id: &str,
-        timeout_secs: u64,
-    ) -> Result<(), DockerError> {
-        use tokio::time::{Duration, sleep};
+/// Sets the CPU, memory, shared memory and process limits of `resources` on `host_config`.
+fn apply_resources(host_config: &mut bollard::models::HostConfig, resources: &Resources) {
+    if let Some(cpus) = resources.cpus {
+        host_config.nano_cpus = Some((cpus * 1e9) as i64);
+    }
+    if let Some(memory) = resources.memory {
+        host_config.memory = Some(memory.0 as i64);
+    }
+    if let Some(shm_size) = resources.shm_size {
+        host_config.shm_size = Some(shm_size.0 as i64);
+    }
+    if let Some(pids_limit) = resources.pids_limit {
+        host_config.pids_limit = Some(pids_limit);
+    }
+}
 
-        let start = std::time::Instant::now();
-        let timeout = Duration::from_secs(timeout_secs);
+/// Describes the limits set in `resources`, e.g. `cpus=2, memory=8g`.
+fn describe_resources(resources: &Resources) -> Option<String> {
+    let mut limits = Vec::new();
+    if let Some(cpus) = resources.cpus {
+        limits.push(format!("cpus={cpus}"));
+    }
+    if let Some(memory) = resources.memory {
+        limits.push(format!("memory={memory}"));
+    }
+    if let Some(shm_size) = resources.shm_size {
+        limits.push(format!("shm_size={shm_size}"));
+    }
+    if let Some(pids_limit) = resources.pids_limit {
+        limits.push(format!("pids_limit={pids_limit}"));
+    }
+    match &resources.gpus {
+        Some(Gpus::All) => limits.push("gpus=all".to_string()),
+        Some(Gpus::Count(count)) => limits.push(format!("gpus={count}")),
+        Some(Gpus::Devices(ids)) => limits.push(format!("gpus={}", ids.join(","))),
+        None => {}
+    }
+    (!limits.is_empty()).then(|| limits.join(", "))
+}
 
-        loop {
-            let inspect = self.client.inspect_container(container_id, None).await?;
+/// Resolves a path from job.toml: absolute paths are kept, relative ones are
+/// taken relative to the job folder.
+pub(crate) fn resolve_job_path(job_dir: &Path, path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        job_dir.join(path)
+    }
+}
 
-            if let Some(state) = inspect.state {
-                if state.running == Some(true) {
-                    return Ok(());
+/// Streams `reader` in 1 MiB chunks. The stream ends at the end of the input, or
+/// at the first read error, which is stored in `read_error` for the caller.
+fn archive_chunks<R>(
+    reader: R,
+    read_error: Arc<Mutex<Option<std::io::Error>>>,
+) -> impl futures_util::Stream<Item = bytes::Bytes>
+where
+    R: tokio::io::AsyncRead + Unpin + Send + 'static,
+{
+    futures_util::stream::unfold(reader, move |mut reader| {
+        let read_error = read_error.clone();
+        async move {
+            let mut buf = vec![0u8; 1024 * 1024];
+            match reader.read(&mut buf).await {
+                Ok(0) => None,
+                Ok(n) => {
+                    buf.truncate(n);
+                    Some((bytes::Bytes::from(buf), reader))
                 }
-                if state.dead == Some(true) || state.oom_killed == Some(true) {
-                    return Err(DockerError::ContainerStartFailed(
-                        "Container died during startup".to_string(),
-                    ));
+                Err(e) => {
+                    *read_error.lock().unwrap() = Some(e);
+                    None
                 }
             }
+        }
+    })
+}
 
-            if start.elapsed() > timeout {
-                return Err(DockerError::ContainerStartFailed(format!(
-                    "Container did not start within {timeout_secs} seconds",
-                )));
-            }
+/// Reads the image reference stored in a `docker save` archive.
+///
+/// Returns the first entry of `RepoTags` from `manifest.json`, or the image ID
+/// derived from its `Config` entry when the image was saved untagged.
+fn read_archive_image_ref(tar_path: &Path) -> Result<String, String> {
+    #[derive(serde::Deserialize)]
+    struct ManifestEntry {
+        #[serde(rename = "Config")]
+        config: String,
+        #[serde(rename = "RepoTags", default)]
+        repo_tags: Option<Vec<String>>,
+    }
 
-            sleep(Duration::from_millis(100)).await;
+    let file = std::fs::File::open(tar_path)
+        .map_err(|e| format!("Cannot open {}: {e}", tar_path.display()))?;
+    let mut archive = tar::Archive::new(file);
+    let entries = archive
+        .entries_with_seek()
+        .map_err(|e| format!("Cannot read {}: {e}", tar_path.display()))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Cannot read {}: {e}", tar_path.display()))?;
+        let is_manifest = entry
+            .path()
+            .map(|p| p.as_ref() == Path::new("manifest.json"))
+            .unwrap_or(false);
+        if !is_manifest {
+            continue;
+        }
+
+        let manifest: Vec<ManifestEntry> = serde_json::from_reader(entry)
+            .map_err(|e| format!("Invalid manifest.json in {}: {e}", tar_path.display()))?;
+        let first = manifest
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("{} contains no images", tar_path.display()))?;
+
+        if let Some(tag) = first.repo_tags.and_then(|tags| tags.into_iter().next()) {
+            return Ok(tag);
         }
+        // Untagged image: Config is "<id>.json" or "blobs/sha256/<id>"
+        let id = first
+            .config
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches(".json");
+        return Ok(format!("sha256:{id}"));
     }
+
+    Err(format!(
+        "{} is not a Docker image archive (no manifest.json)",
+        tar_path.display()
+    ))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_resources_map_to_host_config() {
+        let resources = Resources {
+            cpus: Some(1.5),
+            memory: Some(job_config::job::ByteSize(8 << 30)),
+            shm_size: None,
+            pids_limit: Some(256),
+            gpu: None,
+            gpus: Some(Gpus::Devices(vec!["0".to_string(), "1".to_string()])),
+        };
+        let mut host_config = bollard::models::HostConfig::default();
+        apply_resources(&mut host_config, &resources);
+        assert_eq!(host_config.nano_cpus, Some(1_500_000_000));
+        assert_eq!(host_config.memory, Some(8 << 30));
+        assert_eq!(host_config.shm_size, None);
+        assert_eq!(host_config.pids_limit, Some(256));
+
+        let request = nvidia_device_request(resources.gpus.as_ref());
+        assert_eq!(request.count, None);
+        assert_eq!(
+            request.device_ids,
+            Some(vec!["0".to_string(), "1".to_string()])
+        );
+        assert_eq!(nvidia_device_request(None).count, Some(-1));
+        assert_eq!(nvidia_device_request(Some(&Gpus::Count(2))).count, Some(2));
+
+        assert_eq!(
+            describe_resources(&resources).unwrap(),
+            "cpus=1.5, memory=8g, pids_limit=256, gpus=0,1"
+        );
+        assert_eq!(describe_resources(&Resources::default()), None);
+    }
+
+    #[test]
+    fn test_container_key_separates_resource_limits() {
+        let limited = Resources {
+            cpus: Some(2.0),
+            ..Default::default()
+        };
+        assert_eq!(
+            container_key("ubuntu", &Resources::default(), &[]),
+            "ubuntu"
+        );
+        assert_ne!(container_key("ubuntu", &limited, &[]), "ubuntu");
+        let mounts = [Mount {
+            host: "/data".to_string(),
+            container: "/data".to_string(),
+            read_only: true,
+        }];
+        assert_ne!(
+            container_key("ubuntu", &Resources::default(), &mounts),
+            "ubuntu"
+        );
+    }
+
+    #[test]
+    fn test_volume_binds_follow_isolation() {
+        let config = JobMeta::new(
+            "train".to_string(),
+            String::new(),
+            job_config::job::Container::new("ubuntu".to_string()),
+        );
+        let mut spec = EnvironmentSpec {
+            config: &config,
+            image: "ubuntu",
+            workflow_folder: Path::new("/home/user/wf"),
+            job_name: "train",
+            isolation: Isolation::Workflow,
+            mounts: &[],
+        };
+        // The host's /tmp is never mounted
+        assert_eq!(volume_binds(&spec), vec!["/home/user/wf:/workspace"]);
+
+        let mounts = [Mount {
+            host: "/home/user/datasets".to_string(),
+            container: "/datasets".to_string(),
+            read_only: true,
+        }];
+        spec.isolation = Isolation::Job;
+        spec.mounts = &mounts;
+        assert_eq!(
+            volume_binds(&spec),
+            vec![
+                "/home/user/wf/train:/workspace/train",
+                "/home/user/wf/train/inputs:/workspace/train/inputs:ro",
+                "/home/user/wf/train/outputs:/workspace/train/outputs",
+                "/home/user/datasets:/datasets:ro",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_container_pool_releases_only_private_containers() {
+        let mut pool = ContainerPool::default();
+        pool.merge(HashMap::from([(
+            "ubuntu".to_string(),
+            "shared".to_string(),
+        )]));
+        pool.add_private("job1".to_string());
+        pool.add_private("job2".to_string());
+
+        // Private containers are never handed out to other jobs
+        assert_eq!(pool.get("ubuntu"), Some("shared".to_string()));
+        assert!(!pool.release("shared"));
+        assert!(pool.release("job1"));
+        assert!(!pool.release("job1"));
+        assert_eq!(
+            pool.container_ids(),
+            vec!["shared".to_string(), "job2".to_string()]
+        );
+    }
+
     #[test]
     fn test_docker_executor_creation() {
         // This test will fail if Docker is not available
@@ -1232,6 +1658,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_container_pool_keeps_every_container_for_cleanup() {
+        let mut pool = ContainerPool::default();
+        pool.merge(HashMap::from([("ubuntu".to_string(), "c1".to_string())]));
+        // A parallel job started its own container for the same image
+        pool.merge(HashMap::from([("ubuntu".to_string(), "c2".to_string())]));
+        pool.merge(HashMap::from([("ubuntu".to_string(), "c1".to_string())]));
+
+        assert_eq!(pool.get("ubuntu"), Some("c1".to_string()));
+        assert_eq!(
+            pool.container_ids(),
+            vec!["c1".to_string(), "c2".to_string()]
+        );
+    }
+
     #[tokio::test]
     async fn test_pull_image_uses_local_image() {
         // Test that pull_image uses local image when it already exists
@@ -1273,4 +1714,192 @@ mod tests {
             "Expected 'already exists locally' message for cached image"
         );
     }
+
+    fn write_image_archive(path: &Path, manifest: &str) {
+        let mut builder = tar::Builder::new(std::fs::File::create(path).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "manifest.json", manifest.as_bytes())
+            .unwrap();
+        builder.finish().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_archive_chunks_keep_read_errors() {
+        struct Failing;
+        impl tokio::io::AsyncRead for Failing {
+            fn poll_read(
+                self: std::pin::Pin<&mut Self>,
+                _: &mut std::task::Context<'_>,
+                _: &mut tokio::io::ReadBuf<'_>,
+            ) -> std::task::Poll<std::io::Result<()>> {
+                std::task::Poll::Ready(Err(std::io::Error::other("disk gone")))
+            }
+        }
+
+        let read_error = Arc::new(Mutex::new(None));
+        let chunks: Vec<bytes::Bytes> = archive_chunks(b"abc".chain(Failing), read_error.clone())
+            .collect()
+            .await;
+        assert_eq!(chunks, vec![bytes::Bytes::from_static(b"abc")]);
+        assert_eq!(
+            read_error.lock().unwrap().take().unwrap().to_string(),
+            "disk gone"
+        );
+
+        let read_error = Arc::new(Mutex::new(None));
+        let chunks: Vec<bytes::Bytes> = archive_chunks(&b"abc"[..], read_error.clone())
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 1);
+        assert!(read_error.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_archive_image_ref_uses_repo_tag() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tar_path = tmp.path().join("image.tar");
+        write_image_archive(
+            &tar_path,
+            r#"[{"Config":"blobs/sha256/abc123","RepoTags":["lab/tool:1.2"],"Layers":[]}]"#,
+        );
+
+        assert_eq!(read_archive_image_ref(&tar_path).unwrap(), "lab/tool:1.2");
+    }
+
+    #[test]
+    fn test_read_archive_image_ref_untagged_uses_image_id() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tar_path = tmp.path().join("image.tar");
+        write_image_archive(
+            &tar_path,
+            r#"[{"Config":"abc123.json","RepoTags":null,"Layers":[]}]"#,
+        );
+
+        assert_eq!(read_archive_image_ref(&tar_path).unwrap(), "sha256:abc123");
+    }
+
+    #[test]
+    fn test_read_archive_image_ref_rejects_plain_tarball() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tar_path = tmp.path().join("data.tar");
+        let mut builder = tar::Builder::new(std::fs::File::create(&tar_path).unwrap());
+        builder.finish().unwrap();
+        drop(builder);
+
+        assert!(read_archive_image_ref(&tar_path).is_err());
+    }
+
+    #[test]
+    fn test_resolve_job_path() {
+        let job_dir = Path::new("/tmp/wf/01_job");
+        assert_eq!(
+            resolve_job_path(job_dir, "./image.tar"),
+            PathBuf::from("/tmp/wf/01_job/./image.tar")
+        );
+        assert_eq!(
+            resolve_job_path(job_dir, "/data/image.tar"),
+            PathBuf::from("/data/image.tar")
+        );
+    }
+
+    #[test]
+    fn test_image_repository_sanitizes_names() {
+        assert_eq!(
+            image_repository("silva-01_Preprocess"),
+            "silva-01_preprocess"
+        );
+        assert_eq!(image_repository("lab/tool:1.2"), "lab/tool");
+        assert_eq!(image_repository("registry:5000/tool"), "registry-5000/tool");
+        assert_eq!(image_repository("My Job!"), "my-job");
+    }
+
+    #[test]
+    fn test_truncate_dockerfile_at_target() {
+        let dockerfile = "FROM python:3.12 AS build\nRUN pip wheel . \\\n  from-here\nFROM python:3.12-slim AS runtime\nCOPY --from=build /wheels /wheels\nFROM runtime AS test\nRUN pytest\n";
+
+        let runtime = truncate_dockerfile_at_target(dockerfile, "runtime").unwrap();
+        assert!(runtime.contains("COPY --from=build"));
+        assert!(!runtime.contains("pytest"));
+
+        let build = truncate_dockerfile_at_target(dockerfile, "build").unwrap();
+        assert!(build.contains("from-here"));
+        assert!(!build.contains("runtime"));
+
+        assert!(truncate_dockerfile_at_target(dockerfile, "missing").is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_kill_tagged_command_kills_only_tagged_processes() {
+        let spawn = |tag: &str| {
+            let (key, value) = tag.split_once('=').unwrap();
+            std::process::Command::new("sleep")
+                .arg("30")
+                .env(key, value)
+                .spawn()
+                .unwrap()
+        };
+        let mut tagged = spawn(&script_tag("a"));
+        let mut other = spawn(&script_tag("ab"));
+
+        let status = std::process::Command::new("/bin/sh")
+            .arg("-c")
+            .arg(kill_tagged_command(&script_tag("a")))
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert!(!tagged.wait().unwrap().success());
+        assert!(other.try_wait().unwrap().is_none());
+        other.kill().unwrap();
+        other.wait().unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_kill_process_tree_kills_descendants() {
+        use std::io::BufRead;
+
+        // A script that reports the pid of its background child
+        let mut script = std::process::Command::new("/bin/sh")
+            .arg("-c")
+            .arg("sleep 30 & echo $!; wait")
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        std::io::BufReader::new(script.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let child: i64 = line.trim().parse().unwrap();
+
+        // Not in a container, so left alone
+        assert!(!kill_host_process_tree(script.id() as i64, "0123456789abcdef").unwrap());
+        assert!(script.try_wait().unwrap().is_none());
+
+        kill_process_tree(script.id() as i64).unwrap();
+        assert!(!script.wait().unwrap().success());
+        // The orphaned child dies too, leaving at most a zombie waiting to be reaped
+        let dead = || {
+            let state = std::fs::read_to_string(format!("/proc/{child}/stat")).unwrap_or_default();
+            state.is_empty() || state.contains(") Z ")
+        };
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !dead() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(dead());
+    }
+
+    #[test]
+    fn test_script_tag_is_unique_per_job_name() {
+        assert_eq!(script_tag("01_fetch"), "SILVA_SCRIPT=30315f6665746368:");
+        assert_eq!(script_tag("it's"), "SILVA_SCRIPT=69742773:");
+        assert_ne!(script_tag("a b"), script_tag("a_b"));
+        assert_ne!(script_tag("dock_a.sdf"), script_tag("dock@a.sdf"));
+        assert!(!script_tag("ab").starts_with(&script_tag("a")));
+    }
 }
@@ -1,8 +1,10 @@
 use super::logs::LogBuffer;
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
 /// Status of a Docker job execution.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum JobStatus {
     Idle,
     Pending,
@@ -14,9 +16,38 @@ pub enum JobStatus {
     Running,
     Completed,
     Failed,
+    /// Stopped by a cancel, or never started because the run was cancelled
+    Cancelled,
+    /// Not run because its `when` condition was false or a dependency was skipped
+    Skipped,
 }
 
 impl JobStatus {
+    /// Snake-case name of the status, as used in the JSON event stream.
+    pub fn key(&self) -> &'static str {
+        match self {
+            JobStatus::Idle => "idle",
+            JobStatus::Pending => "pending",
+            JobStatus::PullingImage => "pulling_image",
+            JobStatus::BuildingImage => "building_image",
+            JobStatus::CreatingContainer => "creating_container",
+            JobStatus::ContainerRunning(_) => "container_running",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+            JobStatus::Skipped => "skipped",
+        }
+    }
+
+    /// ID of the job's container, once it is running.
+    pub fn container_id(&self) -> Option<&str> {
+        match self {
+            JobStatus::ContainerRunning(id) => Some(id),
+            _ => None,
+        }
+    }
+
     pub fn as_str(&self) -> &str {
         match self {
             JobStatus::Idle => "Idle",
@@ -28,6 +59,8 @@ impl JobStatus {
             JobStatus::Running => "Running",
             JobStatus::Completed => "Completed",
             JobStatus::Failed => "Failed",
+            JobStatus::Cancelled => "Cancelled",
+            JobStatus::Skipped => "Skipped",
         }
     }
 
@@ -43,7 +76,10 @@ impl JobStatus {
     }
 
     pub fn is_finished(&self) -> bool {
-        matches!(self, JobStatus::Completed | JobStatus::Failed)
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled | JobStatus::Skipped
+        )
     }
 }
 
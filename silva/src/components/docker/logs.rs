@@ -1,8 +1,10 @@
 use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::{collections::VecDeque, fmt::Display};
 
 /// Source of a log line (stdout or stderr).
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LogSource {
     Stdout,
     Stderr,
@@ -9,6 +9,7 @@ use textwrap::wrap;
 
 use super::{job::JobStatus, logs::LogSource};
 use crate::app::App;
+use crate::matrix::split_instance_name;
 
 /// Renders the Docker logs popup.
 pub fn render(f: &mut Frame, app: &mut App, area: Rect) {
@@ -94,8 +95,19 @@ fn render_status_section(f: &mut Frame, app: &mut App, area: Rect) {
         ),
     ];
 
-    let status_text = vec![Line::from(path_spans)];
-    let title = "Workflow Working Path";
+    let status_text = if docker_state.confirm_cancel {
+        vec![Line::from(Span::styled(
+            "Cancel the running workflow? Press y to confirm, any other key to keep it running",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ))]
+    } else {
+        vec![Line::from(path_spans)]
+    };
+    let title = if docker_state.cancel_requested {
+        "Workflow Working Path - Cancelling..."
+    } else {
+        "Workflow Working Path"
+    };
 
     let status_paragraph = Paragraph::new(status_text).block(
         Block::default()
@@ -111,11 +123,29 @@ fn render_status_section(f: &mut Frame, app: &mut App, area: Rect) {
 fn render_job_list_section(f: &mut Frame, app: &mut App, area: Rect) {
     let docker_state = &app.workflow_state.docker_state;
 
-    let job_items: Vec<ListItem> = docker_state
-        .job_entries
-        .iter()
-        .enumerate()
-        .map(|(idx, job)| {
+    // Instances of a matrix job are listed together under the job's name
+    let group_of = |idx: usize| {
+        docker_state
+            .job_entries
+            .get(idx)
+            .and_then(|job| split_instance_name(&job.name))
+    };
+    let mut job_items: Vec<ListItem> = Vec::new();
+    for (idx, job) in docker_state.job_entries.iter().enumerate() {
+        let instance = group_of(idx);
+        if let Some((group, _)) = instance
+            && idx.checked_sub(1).and_then(group_of).map(|(g, _)| g) != Some(group)
+        {
+            let count = (idx..docker_state.job_entries.len())
+                .take_while(|i| group_of(*i).map(|(g, _)| g) == Some(group))
+                .count();
+            job_items.push(ListItem::new(Line::from(Span::styled(
+                format!("    {group} ({count} instances)"),
+                Style::default().add_modifier(Modifier::BOLD),
+            ))));
+        }
+
+        job_items.push({
             let is_selected = docker_state.selected_job_index == Some(idx);
             let (symbol, color) = get_job_status_symbol_and_color(&job.status);
 
@@ -148,16 +178,24 @@ fn render_job_list_section(f: &mut Frame, app: &mut App, area: Rect) {
             } else {
                 Style::default().fg(color)
             };
-            spans.push(Span::styled(&job.name, name_style));
+            match instance {
+                Some((group, label)) => {
+                    let is_last = group_of(idx + 1).map(|(g, _)| g) != Some(group);
+                    let branch = if is_last { "└ " } else { "├ " };
+                    spans.push(Span::styled(branch, Style::default().fg(Color::DarkGray)));
+                    spans.push(Span::styled(label, name_style));
+                }
+                None => spans.push(Span::styled(&job.name, name_style)),
+            }
 
             ListItem::new(Line::from(spans))
-        })
-        .collect();
+        });
+    }
 
     let title = format!(
         "Jobs ({}/{})",
-        docker_state.jobs.len(),
-        docker_state.jobs.len()
+        docker_state.job_entries.len(),
+        docker_state.job_entries.len()
     );
 
     let jobs_list = List::new(job_items).block(
@@ -286,7 +324,7 @@ fn render_job_logs_section(f: &mut Frame, app: &mut App, area: Rect) {
 }
 
 /// Returns the visual symbol and color for a job status.
-fn get_job_status_symbol_and_color(status: &JobStatus) -> (&'static str, Color) {
+pub(crate) fn get_job_status_symbol_and_color(status: &JobStatus) -> (&'static str, Color) {
     match status {
         JobStatus::Idle => ("○", Color::Gray),
         JobStatus::Pending => ("⬜", Color::Gray),
@@ -297,5 +335,7 @@ fn get_job_status_symbol_and_color(status: &JobStatus) -> (&'static str, Color)
         JobStatus::Running => ("⟳", Color::Yellow),
         JobStatus::Completed => ("✓", Color::Green),
         JobStatus::Failed => ("✗", Color::Red),
+        JobStatus::Cancelled => ("⊘", Color::Magenta),
+        JobStatus::Skipped => ("↷", Color::DarkGray),
     }
 }
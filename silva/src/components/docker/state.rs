@@ -1,6 +1,6 @@
 use std::{
     collections::HashMap,
-    path::{Path, PathBuf},
+    path::PathBuf,
     sync::{Arc, Mutex},
 };
 
@@ -9,10 +9,10 @@ use tempfile::TempDir;
 use tokio::sync::mpsc;
 
 use crate::components::workflow::{self, JobFolder};
-use crate::utils::copy_dir_recursive;
+use crate::history::RunHistory;
+use crate::runner::{RunOptions, create_temp_workflow_folder, prepare_resume, prepare_workflow};
 
 use super::{
-    executor::DockerExecutor,
     job::{JobEntry, JobStatus},
     logs::{LogLine, LogSource},
 };
@@ -26,12 +26,21 @@ pub struct State {
     pub rx: Option<mpsc::Receiver<(usize, JobStatus, LogLine)>>,
     pub cancel_tx: Option<mpsc::Sender<()>>,
     pub is_executing_workflow: bool,
+    /// `c` was pressed and the next key confirms (`y`) or dismisses the cancel
+    pub confirm_cancel: bool,
+    /// The run was told to stop and is waiting for its jobs to end
+    pub cancel_requested: bool,
     pub workflow_temp_dirs: Arc<Mutex<HashMap<String, Vec<TempDir>>>>,
     pub auto_scroll_enabled: bool,
     pub last_viewport_width: usize,
     pub last_viewport_height: usize,
     pub pending_workflow: Option<workflow::WorkflowFolder>,
+    /// Workflow of the most recent run, which `r` resumes
+    pub last_workflow: Option<workflow::WorkflowFolder>,
     pub current_temp_workflow_path: Arc<Mutex<Option<PathBuf>>>,
+    /// Jobs of the current run once it is prepared, with matrix jobs expanded
+    /// into their instances; replaces `job_entries` before the run's first message
+    pub prepared_job_names: Arc<Mutex<Option<Vec<String>>>>,
 }
 
 impl Default for State {
@@ -44,41 +53,34 @@ impl Default for State {
             rx: None,
             cancel_tx: None,
             is_executing_workflow: false,
+            confirm_cancel: false,
+            cancel_requested: false,
             workflow_temp_dirs: Arc::new(Mutex::new(HashMap::new())),
             auto_scroll_enabled: true,
             last_viewport_width: 80,
             last_viewport_height: 20,
             pending_workflow: None,
+            last_workflow: None,
             current_temp_workflow_path: Arc::new(Mutex::new(None)),
+            prepared_job_names: Arc::new(Mutex::new(None)),
         }
     }
 }
 
-fn create_tmp_workflow_folder<P: AsRef<Path>>(source_path: P) -> Result<TempDir, std::io::Error> {
-    // Create temp directory
-    let now = chrono::Local::now();
-    let timestamp = now.format("%Y-%m-%d-%H-%M-%S").to_string();
-    let prefix = format!("silva-{timestamp}-");
-    let temp_dir = tempfile::Builder::new().prefix(&prefix).tempdir()?;
-
-    // Copy source folder contents to temp directory
-    let mut options = fs_extra::dir::CopyOptions::new();
-    options.overwrite = true;
-    options.copy_inside = true; // Copy contents, not the folder itself
-    options.content_only = true;
-
-    fs_extra::dir::copy(source_path, temp_dir.path(), &options)
-        .map_err(|e| std::io::Error::other(format!("copy folder error {e}")))?;
-
-    Ok(temp_dir)
-}
-
 impl State {
     pub fn new() -> Self {
         Self::default()
     }
 
     pub fn handle_input(&mut self, key: KeyEvent) {
+        if self.confirm_cancel {
+            self.confirm_cancel = false;
+            if key.code == KeyCode::Char('y') {
+                self.cancel_workflow();
+            }
+            return;
+        }
+
         let shift = key.modifiers.contains(KeyModifiers::SHIFT);
 
         match key.code {
@@ -101,14 +103,21 @@ impl State {
             KeyCode::Char('b') => self.scroll_to_bottom(),
             KeyCode::Char('o') => self.open_temp_folder(),
             KeyCode::Enter if !self.is_executing_workflow => self.run_workflow(),
+            KeyCode::Char('r') if !self.is_executing_workflow => self.resume_workflow(),
+            KeyCode::Char('c') if self.is_executing_workflow && !self.cancel_requested => {
+                self.confirm_cancel = true
+            }
             _ => {}
         }
     }
 
     pub fn update(&mut self) {
-        if let Some(rx) = self.rx.as_mut()
+        // Drain everything queued since the last tick: with parallel jobs several
+        // jobs report at once, and one message per tick would lag behind.
+        while let Some(rx) = self.rx.as_mut()
             && let Ok((idx, status, log_line)) = rx.try_recv()
         {
+            self.apply_prepared_job_names();
             if let Some(job_entry) = self.job_entries.get_mut(idx) {
                 job_entry.status = status;
                 job_entry.logs.push(log_line);
@@ -120,10 +129,42 @@ impl State {
             } else {
                 // idx == jobs.len()
                 self.is_executing_workflow = false;
+                self.confirm_cancel = false;
+                self.cancel_requested = false;
             }
         }
     }
 
+    /// Lists the jobs of the prepared run, in which matrix jobs are expanded into
+    /// their instances, so message indices refer to the right entries.
+    fn apply_prepared_job_names(&mut self) {
+        let Some(names) = self.prepared_job_names.lock().unwrap().take() else {
+            return;
+        };
+        if names
+            .iter()
+            .eq(self.job_entries.iter().map(|entry| &entry.name))
+        {
+            return;
+        }
+        self.job_entries = names.into_iter().map(JobEntry::new).collect();
+        if self
+            .selected_job_index
+            .is_some_and(|idx| idx >= self.job_entries.len())
+        {
+            self.selected_job_index = Some(0);
+        }
+    }
+
+    /// Gets the job folder of the selected entry; for a matrix instance, the
+    /// folder of its job.
+    pub fn get_selected_job_folder(&self) -> Option<&JobFolder> {
+        let entry = self.get_selected_job_entry()?;
+        let name = crate::matrix::split_instance_name(&entry.name)
+            .map_or(entry.name.as_str(), |(job, _)| job);
+        self.jobs.iter().find(|job| job.name == name)
+    }
+
     pub fn run_workflow(&mut self) {
         // Get the pending workflow, return early if none
         let workflow_folder = match self.pending_workflow.take() {
@@ -131,15 +172,54 @@ impl State {
             None => return,
         };
 
-        self.is_executing_workflow = true;
-        self.select_next_job();
-
         // Clear previous temp path
         {
             let mut temp_path = self.current_temp_workflow_path.lock().unwrap();
             *temp_path = None;
         }
 
+        self.start_run(workflow_folder, None);
+    }
+
+    /// Stops the running workflow: running jobs are cancelled and the others skipped.
+    pub fn cancel_workflow(&mut self) {
+        if let Some(cancel_tx) = &self.cancel_tx
+            && self.is_executing_workflow
+        {
+            let _ = cancel_tx.try_send(());
+            self.cancel_requested = true;
+        }
+    }
+
+    /// Resumes the most recent run in its temp folder.
+    ///
+    /// Unchanged jobs the run completed are reused; see `runner::prepare_resume`.
+    pub fn resume_workflow(&mut self) {
+        let temp_path = self.current_temp_workflow_path.lock().unwrap().clone();
+        let (Some(workflow_folder), Some(temp_path)) = (self.last_workflow.clone(), temp_path)
+        else {
+            return;
+        };
+
+        self.job_entries = self
+            .jobs
+            .iter()
+            .map(|job| JobEntry::new(job.name.to_string()))
+            .collect();
+        self.selected_job_index = None;
+        self.start_run(workflow_folder, Some(temp_path));
+    }
+
+    /// Runs `workflow_folder` in a new temp folder, or resumes it in `resume_path`.
+    fn start_run(
+        &mut self,
+        workflow_folder: workflow::WorkflowFolder,
+        resume_path: Option<PathBuf>,
+    ) {
+        self.is_executing_workflow = true;
+        self.select_next_job();
+        self.last_workflow = Some(workflow_folder.clone());
+
         let (tx, rx) = mpsc::channel::<(usize, JobStatus, LogLine)>(32);
         let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
         self.rx = Some(rx);
@@ -147,123 +227,61 @@ impl State {
         let jobs = self.jobs.clone();
         let workflow_temp_dirs = self.workflow_temp_dirs.clone();
         let temp_path_for_task = self.current_temp_workflow_path.clone();
+        let prepared_job_names = self.prepared_job_names.clone();
 
         tokio::spawn(async move {
-            let mut docker_executor = match DockerExecutor::new(tx.clone()) {
-                Ok(docker_executor) => docker_executor,
-                Err(e) => {
-                    let log_line = LogLine::new(
-                        LogSource::Stderr,
-                        format!("Faild to create docker executor: {e}"),
-                    );
-                    tx.send((0, JobStatus::Failed, log_line)).await.unwrap();
-                    // run workflow completes
-                    tx.send((jobs.len(), JobStatus::Failed, LogLine::empty()))
-                        .await
-                        .unwrap();
-                    return;
-                }
-            };
-            docker_executor.detect_host_gpu().await;
-
-            // Create a temp workflow path
-            let temp_workflow_dir = match create_tmp_workflow_folder(&workflow_folder.path) {
-                Ok(temp_dir) => {
-                    let temp_dir_path = temp_dir.path().to_path_buf();
-
-                    // Store the temp path in the Arc for main thread access
+            let prepared = match resume_path {
+                Some(temp_workflow_dir) => prepare_resume(
+                    &workflow_folder,
+                    &temp_workflow_dir,
+                    &jobs,
+                    &Default::default(),
+                ),
+                None => {
+                    // Create a temp workflow path
+                    let temp_workflow_dir = match create_temp_workflow_folder(&workflow_folder.path)
                     {
-                        let mut temp_path = temp_path_for_task.lock().unwrap();
-                        *temp_path = Some(temp_dir_path.clone());
-                    }
-
-                    let workflow_temp_dirs_arc = workflow_temp_dirs.clone();
-                    let mut workflow_temp_dirs = workflow_temp_dirs_arc.lock().unwrap();
-                    let workflow_dir_vec = workflow_temp_dirs
-                        .entry(workflow_folder.name.to_string())
-                        .or_default();
-                    workflow_dir_vec.push(temp_dir);
-                    temp_dir_path
-                }
-                Err(e) => {
-                    let log_line =
-                        LogLine::new(LogSource::Stderr, format!("Create temp dir error: {e}"));
-                    tx.send((jobs.len(), JobStatus::Failed, log_line))
-                        .await
-                        .unwrap();
-                    return;
-                }
-            };
+                        Ok(temp_dir) => {
+                            let temp_dir_path = temp_dir.path().to_path_buf();
 
-            // Load workflow metadata (contains dependencies and param definitions)
-            let workflow_metadata = workflow_folder
-                .load_workflow_metadata()
-                .ok()
-                .flatten()
-                .unwrap_or_else(|| {
-                    job_config::workflow::WorkflowMeta::new(
-                        workflow_folder.name.clone(),
-                        String::new(),
-                    )
-                });
-
-            // Load workflow parameters (global parameters)
-            let workflow_params = workflow_folder
-                .load_workflow_params()
-                .ok()
-                .flatten()
-                .unwrap_or_default();
-
-            if !workflow_params.is_empty() {
-                let log_line = LogLine::new(
-                    LogSource::Stdout,
-                    format!(
-                        "Loaded {} global workflow parameter(s)",
-                        workflow_params.len()
-                    ),
-                );
-                tx.send((0, JobStatus::Idle, log_line)).await.unwrap();
-            }
+                            // Store the temp path in the Arc for main thread access
+                            {
+                                let mut temp_path = temp_path_for_task.lock().unwrap();
+                                *temp_path = Some(temp_dir_path.clone());
+                            }
 
-            // Sort jobs in dependency order (topological sort)
-            let sorted_jobs = match topological_sort_jobs(&jobs, &workflow_metadata) {
-                Ok(sorted) => {
-                    let log_line = LogLine::new(
-                        LogSource::Stdout,
-                        format!(
-                            "Jobs will execute in dependency order: {}",
-                            sorted
-                                .iter()
-                                .map(|j| j.name.as_str())
-                                .collect::<Vec<_>>()
-                                .join(" → ")
-                        ),
-                    );
-                    tx.send((0, JobStatus::Idle, log_line)).await.unwrap();
-                    sorted
-                }
-                Err(e) => {
-                    let log_line =
-                        LogLine::new(LogSource::Stderr, format!("Dependency error: {e}"));
-                    tx.send((0, JobStatus::Failed, log_line)).await.unwrap();
-                    tx.send((jobs.len(), JobStatus::Failed, LogLine::empty()))
-                        .await
-                        .unwrap();
-                    return;
+                            let workflow_temp_dirs_arc = workflow_temp_dirs.clone();
+                            let mut workflow_temp_dirs = workflow_temp_dirs_arc.lock().unwrap();
+                            let workflow_dir_vec = workflow_temp_dirs
+                                .entry(workflow_folder.name.to_string())
+                                .or_default();
+                            workflow_dir_vec.push(temp_dir);
+                            temp_dir_path
+                        }
+                        Err(e) => {
+                            let log_line = LogLine::new(
+                                LogSource::Stderr,
+                                format!("Create temp dir error: {e}"),
+                            );
+                            tx.send((jobs.len(), JobStatus::Failed, log_line))
+                                .await
+                                .unwrap();
+                            return;
+                        }
+                    };
+                    prepare_workflow(
+                        &workflow_folder,
+                        &temp_workflow_dir,
+                        &jobs,
+                        &Default::default(),
+                    )
                 }
             };
 
-            // Pre-checks: reject workflows that violate conventions
-            for check_result in [
-                crate::precheck::check_install_commands(&sorted_jobs),
-                crate::precheck::check_cross_node_references(&sorted_jobs),
-                crate::precheck::check_input_files_folder(
-                    &workflow_folder.path,
-                    &sorted_jobs,
-                    &workflow_metadata,
-                ),
-            ] {
-                if let Err(e) = check_result {
+            // Load metadata and parameters, sort jobs and run the pre-checks
+            let workflow = match prepared {
+                Ok(workflow) => workflow,
+                Err(e) => {
                     let log_line = LogLine::new(LogSource::Stderr, e);
                     tx.send((0, JobStatus::Failed, log_line)).await.unwrap();
                     tx.send((jobs.len(), JobStatus::Failed, LogLine::empty()))
@@ -271,94 +289,20 @@ impl State {
                         .unwrap();
                     return;
                 }
+            };
+            *prepared_job_names.lock().unwrap() =
+                Some(workflow.jobs.iter().map(|job| job.name.clone()).collect());
+            for note in &workflow.notes {
+                let _ = tx.send((0, JobStatus::Idle, note.clone())).await;
             }
 
-            // Execute jobs sequentially in dependency order
-            let jobs_length = jobs.len();
-
-            // Create a map from job name to original index for UI updates
-            let job_name_to_idx: HashMap<String, usize> = jobs
-                .iter()
-                .enumerate()
-                .map(|(idx, job)| (job.name.clone(), idx))
-                .collect();
-
-            // Container registry: image_name -> container_id (for reusing containers)
-            let mut container_registry: HashMap<String, String> = HashMap::new();
-            let mut workflow_failed = false;
-
-            for job in sorted_jobs.iter() {
-                // Get the original index for this job (for UI updates)
-                let idx = *job_name_to_idx.get(&job.name).unwrap();
-
-                match job.load_meta() {
-                    Ok(config) => {
-                        docker_executor.set_job_idx(idx);
-
-                        // Load job parameters (if they exist)
-                        let job_params = job.load_params().ok().flatten().unwrap_or_default();
-
-                        // Copy input files from dependencies before running the job
-                        let job_deps = workflow_metadata.get_job_dependencies(&job.name);
-                        copy_input_files_from_dependencies(
-                            &temp_workflow_dir,
-                            job,
-                            &jobs,
-                            &config,
-                            job_deps,
-                            &tx,
-                            idx,
-                        )
-                        .await;
-
-                        match docker_executor
-                            .run_job(
-                                (&workflow_metadata, &temp_workflow_dir, &workflow_params),
-                                (job, &config, &job_params),
-                                &[],
-                                &mut container_registry,
-                                &mut cancel_rx,
-                            )
-                            .await
-                        {
-                            Ok(container_id) => {
-                                // Container is tracked in the registry and will be cleaned up at the end
-                                let _ = container_id; // Suppress unused variable warning
-                            }
-                            Err(e) => {
-                                let log_line = LogLine::new(
-                                    LogSource::Stderr,
-                                    format!("docker run job error: {e}"),
-                                );
-                                tx.send((idx, JobStatus::Failed, log_line)).await.unwrap();
-                                workflow_failed = true;
-                                break;
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        let log_line =
-                            LogLine::new(LogSource::Stderr, format!("Load job config error: {e}"));
-                        tx.send((idx, JobStatus::Failed, log_line)).await.unwrap();
-                        workflow_failed = true;
-                        break;
-                    }
-                }
-            }
-
-            // Cleanup all containers after workflow completes (success or failure)
-            let container_ids: Vec<String> = container_registry.values().cloned().collect();
-            docker_executor.cleanup_containers(&container_ids).await;
-
-            // Send workflow completion status
-            let final_status = if workflow_failed {
-                JobStatus::Failed
-            } else {
-                JobStatus::Completed
+            // Execute jobs in dependency order, running independent jobs in parallel;
+            // sends the workflow completion status when done
+            let options = RunOptions {
+                history: Some(RunHistory::open_default()),
+                ..Default::default()
             };
-            tx.send((jobs_length, final_status, LogLine::empty()))
-                .await
-                .unwrap();
+            crate::backend::execute(workflow, &options, tx, &mut cancel_rx).await;
         });
     }
 
@@ -433,13 +377,13 @@ impl State {
 
     /// Selects the next job in the list.
     pub fn select_next_job(&mut self) {
-        if self.jobs.is_empty() {
+        if self.job_entries.is_empty() {
             self.selected_job_index = None;
             return;
         }
 
         self.selected_job_index = Some(match self.selected_job_index {
-            Some(idx) => (idx + 1) % self.jobs.len(),
+            Some(idx) => (idx + 1) % self.job_entries.len(),
             None => 0,
         });
 
@@ -450,7 +394,7 @@ impl State {
 
     /// Selects the previous job in the list.
     pub fn select_previous_job(&mut self) {
-        if self.jobs.is_empty() {
+        if self.job_entries.is_empty() {
             self.selected_job_index = None;
             return;
         }
@@ -460,10 +404,10 @@ impl State {
                 if idx > 0 {
                     idx - 1
                 } else {
-                    self.jobs.len() - 1
+                    self.job_entries.len() - 1
                 }
             }
-            None => self.jobs.len() - 1,
+            None => self.job_entries.len() - 1,
         });
 
         // Reset scroll and re-enable auto-scroll when changing jobs
@@ -492,364 +436,7 @@ impl State {
             return;
         };
 
-        // Try to open the folder with platform-specific command
-        let result = if cfg!(target_os = "windows") {
-            // Windows: use explorer
-            std::process::Command::new("explorer").arg(&path).spawn()
-        } else if cfg!(target_os = "macos") {
-            // macOS: use open
-            std::process::Command::new("open").arg(&path).spawn()
-        } else {
-            // Linux: check if GUI is available
-            let has_display =
-                std::env::var("DISPLAY").is_ok() || std::env::var("WAYLAND_DISPLAY").is_ok();
-
-            if has_display {
-                // Try xdg-open
-                std::process::Command::new("xdg-open").arg(&path).spawn()
-            } else {
-                // Server mode: copy to clipboard silently
-                let _ = Self::copy_to_clipboard(&path);
-                return;
-            }
-        };
-
-        // On failure, try to copy to clipboard as fallback
-        if result.is_err() {
-            let _ = Self::copy_to_clipboard(&path);
-        }
-    }
-
-    /// Helper function to copy path to clipboard
-    fn copy_to_clipboard(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        use arboard::Clipboard;
-        let mut clipboard = Clipboard::new()?;
-        clipboard.set_text(path.to_string_lossy().to_string())?;
-        Ok(())
-    }
-}
-
-/// Performs topological sort on jobs based on their dependencies.
-///
-/// # Arguments
-///
-/// * `jobs` - List of jobs to sort
-///
-/// # Returns
-///
-/// * `Ok(Vec<JobFolder>)` - Jobs sorted in dependency order (dependencies first)
-/// * `Err(String)` - Error message if circular dependency detected or invalid dependency
-///
-/// # Algorithm
-///
-/// Uses Kahn's algorithm for topological sorting:
-/// 1. Build dependency graph and calculate in-degrees
-/// 2. Start with jobs that have no dependencies (in-degree = 0)
-/// 3. Process jobs in order, removing edges as we go
-/// 4. If we can't process all jobs, there's a cycle
-fn topological_sort_jobs(
-    jobs: &[JobFolder],
-    workflow_metadata: &job_config::workflow::WorkflowMeta,
-) -> Result<Vec<JobFolder>, String> {
-    use std::collections::{HashMap, VecDeque};
-
-    if jobs.is_empty() {
-        return Ok(Vec::new());
-    }
-
-    // Build a map of job names to job data for quick lookup
-    let job_map: HashMap<String, JobFolder> =
-        jobs.iter().map(|j| (j.name.clone(), j.clone())).collect();
-
-    // Build dependency graph: job_name -> Vec<jobs that depend on it>
-    let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
-    let mut in_degree: HashMap<String, usize> = HashMap::new();
-
-    // Initialize all jobs with in-degree 0
-    for job in jobs {
-        in_degree.insert(job.name.clone(), 0);
-        dependents.entry(job.name.clone()).or_default();
-    }
-
-    // Build the graph using dependencies from workflow metadata
-    for job in jobs {
-        let job_deps = workflow_metadata.get_job_dependencies(&job.name);
-
-        for dep_name in job_deps {
-            // Validate that the dependency exists
-            if !job_map.contains_key(dep_name) {
-                return Err(format!(
-                    "Job '{}' depends on '{}', but '{}' does not exist in the workflow",
-                    job.name, dep_name, dep_name
-                ));
-            }
-
-            // Add edge: dep_name -> job.name
-            dependents
-                .entry(dep_name.clone())
-                .or_default()
-                .push(job.name.clone());
-
-            // Increment in-degree of current job
-            *in_degree.get_mut(&job.name).unwrap() += 1;
-        }
-    }
-
-    // Kahn's algorithm: start with jobs that have no dependencies
-    let mut queue: VecDeque<String> = in_degree
-        .iter()
-        .filter(|&(_, &degree)| degree == 0)
-        .map(|(name, _)| name.clone())
-        .collect();
-
-    let mut sorted_jobs = Vec::new();
-
-    while let Some(job_name) = queue.pop_front() {
-        // Add this job to the sorted list
-        if let Some(job) = job_map.get(&job_name) {
-            sorted_jobs.push(job.clone());
-        }
-
-        // Process all jobs that depend on this one
-        if let Some(deps) = dependents.get(&job_name) {
-            for dependent in deps {
-                // Decrease in-degree
-                let degree = in_degree.get_mut(dependent).unwrap();
-                *degree -= 1;
-
-                // If in-degree becomes 0, add to queue
-                if *degree == 0 {
-                    queue.push_back(dependent.clone());
-                }
-            }
-        }
-    }
-
-    // Check if all jobs were processed (no cycles)
-    if sorted_jobs.len() != jobs.len() {
-        // Find jobs that are part of the cycle
-        let processed: std::collections::HashSet<_> = sorted_jobs.iter().map(|j| &j.name).collect();
-        let unprocessed: Vec<_> = jobs
-            .iter()
-            .filter(|j| !processed.contains(&j.name))
-            .map(|j| j.name.as_str())
-            .collect();
-
-        return Err(format!(
-            "Circular dependency detected involving jobs: {}",
-            unprocessed.join(", ")
-        ));
-    }
-
-    Ok(sorted_jobs)
-}
-
-/// Helper function to copy input files from dependency jobs' outputs to the current job folder.
-///
-/// # Arguments
-///
-/// * `temp_workflow_dir` - The temporary workflow directory containing all job folders
-/// * `current_job` - The current job that needs input files
-/// * `all_jobs` - List of all jobs in the workflow (to look up dependencies by name)
-/// * `config` - The job configuration containing input patterns
-/// * `dependencies` - List of job names this job depends on (from WorkflowMeta)
-/// * `tx` - Message channel for logging
-/// * `job_idx` - Current job index for message tagging
-///
-/// # Behavior
-///
-/// For each dependency:
-/// - Looks for the dependency's outputs/ folder
-/// - If config.inputs is specified: copies only matching files (supports globs)
-/// - If config.inputs is empty: copies all files from dependency outputs
-/// - Handles conflicts: uses first match and sends warning
-async fn copy_input_files_from_dependencies(
-    temp_workflow_dir: &Path,
-    current_job: &JobFolder,
-    all_jobs: &[JobFolder],
-    config: &job_config::job::JobMeta,
-    dependencies: &[String],
-    tx: &mpsc::Sender<(usize, JobStatus, LogLine)>,
-    job_idx: usize,
-) {
-    use globset::GlobSetBuilder;
-    use std::collections::HashSet;
-    use std::fs;
-    use std::path::PathBuf;
-
-    if dependencies.is_empty() {
-        return;
-    }
-
-    let current_job_dir = temp_workflow_dir.join(&current_job.name);
-    let mut copied_files: HashSet<String> = HashSet::new();
-
-    for dep_job_name in dependencies {
-        // Find the dependency job
-        let dep_job = all_jobs.iter().find(|j| &j.name == dep_job_name);
-        if dep_job.is_none() {
-            let log_line = LogLine::new(
-                LogSource::Stderr,
-                format!("Warning: Dependency job '{dep_job_name}' not found"),
-            );
-            let _ = tx.send((job_idx, JobStatus::Running, log_line)).await;
-            continue;
-        }
-
-        let dep_outputs_dir = temp_workflow_dir.join(dep_job_name).join("outputs");
-        if !dep_outputs_dir.exists() {
-            let log_line = LogLine::new(
-                LogSource::Stdout,
-                format!("No outputs found for dependency '{dep_job_name}', skipping"),
-            );
-            let _ = tx.send((job_idx, JobStatus::Running, log_line)).await;
-            continue;
-        }
-
-        // Determine which files to copy
-        let files_to_copy: Vec<PathBuf> = if config.inputs.is_empty() {
-            // Copy all files from outputs/
-            match fs::read_dir(&dep_outputs_dir) {
-                Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
-                Err(e) => {
-                    let log_line = LogLine::new(
-                        LogSource::Stderr,
-                        format!("Error reading outputs from '{dep_job_name}': {e}"),
-                    );
-                    let _ = tx.send((job_idx, JobStatus::Running, log_line)).await;
-                    continue;
-                }
-            }
-        } else {
-            // Copy only matching files based on input patterns
-            let mut builder = GlobSetBuilder::new();
-            for pattern in &config.inputs {
-                match globset::Glob::new(pattern) {
-                    Ok(g) => {
-                        builder.add(g);
-                    }
-                    Err(e) => {
-                        let log_line = LogLine::new(
-                            LogSource::Stderr,
-                            format!("Invalid glob pattern '{pattern}': {e}"),
-                        );
-                        let _ = tx.send((job_idx, JobStatus::Running, log_line)).await;
-                    }
-                }
-            }
-            match builder.build() {
-                Ok(matcher) => match fs::read_dir(&dep_outputs_dir) {
-                    Ok(entries) => entries
-                        .filter_map(|e| e.ok())
-                        .map(|e| e.path())
-                        .filter(|p| p.file_name().map(|n| matcher.is_match(n)).unwrap_or(false))
-                        .collect(),
-                    Err(e) => {
-                        let log_line = LogLine::new(
-                            LogSource::Stderr,
-                            format!("Error reading outputs from '{dep_job_name}': {e}"),
-                        );
-                        let _ = tx.send((job_idx, JobStatus::Running, log_line)).await;
-                        continue;
-                    }
-                },
-                Err(e) => {
-                    let log_line = LogLine::new(
-                        LogSource::Stderr,
-                        format!("Failed to build glob matcher for '{dep_job_name}': {e}"),
-                    );
-                    let _ = tx.send((job_idx, JobStatus::Running, log_line)).await;
-                    continue;
-                }
-            }
-        };
-
-        // Copy files to current job directory
-        for source_path in files_to_copy {
-            if let Some(filename) = source_path.file_name() {
-                let filename_str = filename.to_string_lossy().to_string();
-                let dest_path = current_job_dir.join(filename);
-
-                // Check for conflicts
-                if copied_files.contains(&filename_str) {
-                    let log_line = LogLine::new(
-                        LogSource::Stderr,
-                        format!(
-                            "Warning: File '{filename_str}' already copied from another dependency, skipping from '{dep_job_name}'"
-                        ),
-                    );
-                    let _ = tx.send((job_idx, JobStatus::Running, log_line)).await;
-                    continue;
-                }
-
-                // Copy the file or directory
-                if source_path.is_file() {
-                    // Copy single file
-                    match fs::copy(&source_path, &dest_path) {
-                        Ok(_) => {
-                            copied_files.insert(filename_str.clone());
-                            let log_line = LogLine::new(
-                                LogSource::Stdout,
-                                format!("Copied file '{filename_str}' from '{dep_job_name}'"),
-                            );
-                            let _ = tx.send((job_idx, JobStatus::Running, log_line)).await;
-                        }
-                        Err(e) => {
-                            let log_line = LogLine::new(
-                                LogSource::Stderr,
-                                format!(
-                                    "Error copying file '{filename_str}' from '{dep_job_name}': {e}"
-                                ),
-                            );
-                            let _ = tx.send((job_idx, JobStatus::Running, log_line)).await;
-                        }
-                    }
-                } else if source_path.is_dir() {
-                    // Copy directory recursively
-                    match copy_dir_recursive(&source_path, &dest_path) {
-                        Ok(file_count) => {
-                            copied_files.insert(filename_str.clone());
-                            let log_line = LogLine::new(
-                                LogSource::Stdout,
-                                format!(
-                                    "Copied directory '{filename_str}/' ({file_count} file(s)) from '{dep_job_name}'"
-                                ),
-                            );
-                            let _ = tx.send((job_idx, JobStatus::Running, log_line)).await;
-                        }
-                        Err(e) => {
-                            let log_line = LogLine::new(
-                                LogSource::Stderr,
-                                format!(
-                                    "Error copying directory '{filename_str}/' from '{dep_job_name}': {e}"
-                                ),
-                            );
-                            let _ = tx.send((job_idx, JobStatus::Running, log_line)).await;
-                        }
-                    }
-                } else {
-                    // Not a regular file or directory (e.g., symlink)
-                    let log_line = LogLine::new(
-                        LogSource::Stderr,
-                        format!(
-                            "Warning: Skipping '{filename_str}' from '{dep_job_name}' (not a regular file or directory)"
-                        ),
-                    );
-                    let _ = tx.send((job_idx, JobStatus::Running, log_line)).await;
-                }
-            }
-        }
-    }
-
-    if !copied_files.is_empty() {
-        let log_line = LogLine::new(
-            LogSource::Stdout,
-            format!(
-                "Copied {} input file(s) from dependencies",
-                copied_files.len()
-            ),
-        );
-        let _ = tx.send((job_idx, JobStatus::Running, log_line)).await;
+        crate::utils::open_folder(&path);
     }
 }
 
@@ -880,6 +467,29 @@ mod tests {
         assert!(!JobStatus::Running.is_finished());
         assert!(JobStatus::Completed.is_finished());
         assert!(JobStatus::Failed.is_finished());
+        assert!(JobStatus::Cancelled.is_finished());
+        assert!(JobStatus::Skipped.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_needs_confirmation() {
+        let mut state = State::new();
+        let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+        state.cancel_tx = Some(cancel_tx);
+        state.is_executing_workflow = true;
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+
+        // Any key other than `y` dismisses the prompt
+        state.handle_input(key('c'));
+        assert!(state.confirm_cancel);
+        state.handle_input(key('n'));
+        assert!(!state.confirm_cancel);
+        assert!(cancel_rx.try_recv().is_err());
+
+        state.handle_input(key('c'));
+        state.handle_input(key('y'));
+        assert!(state.cancel_requested);
+        assert!(cancel_rx.try_recv().is_ok());
     }
 
     #[test]
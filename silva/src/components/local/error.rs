@@ -0,0 +1,41 @@
+use std::fmt;
+
+use crate::process::ProcessError;
+
+/// Error type for running jobs as host processes.
+#[derive(Debug)]
+pub enum LocalError {
+    SpawnFailed(String),
+    IoError(std::io::Error),
+    ChannelSendMessageError(String),
+}
+
+impl fmt::Display for LocalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LocalError::SpawnFailed(msg) => write!(f, "Failed to start script: {msg}"),
+            LocalError::IoError(err) => write!(f, "IO error: {err}"),
+            LocalError::ChannelSendMessageError(err) => {
+                write!(f, "MPSC channel send message error: {err}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LocalError {}
+
+impl From<ProcessError> for LocalError {
+    fn from(err: ProcessError) -> Self {
+        match err {
+            ProcessError::SpawnFailed(err) => LocalError::SpawnFailed(err.to_string()),
+            ProcessError::IoError(err) => LocalError::IoError(err),
+            ProcessError::ChannelSendMessageError(err) => LocalError::ChannelSendMessageError(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for LocalError {
+    fn from(err: std::io::Error) -> Self {
+        LocalError::IoError(err)
+    }
+}
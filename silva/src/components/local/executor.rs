@@ -0,0 +1,370 @@
+//! Job execution as plain host processes.
+//!
+//! Runs a job's `pre_run.sh` → `run.sh` → `post_run.sh` with the job's interpreter
+//! directly on the host, without any container runtime. This is meant for quick iteration
+//! on scripts and for machines where Docker is not installed. Each script runs
+//! in the job's folder inside the temp workflow folder, with the host environment
+//! plus the same `PARAM_*`, `env_passthrough` and `-e` variables a container gets.
+//!
+//! The job's `[container]` and `[resources]` settings are ignored.
+
+use std::path::{Path, PathBuf};
+
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+use crate::components::docker::executor::script_argv;
+use crate::components::docker::job::JobStatus;
+use crate::components::docker::logs::{LogLine, LogSource};
+use crate::executor::{EnvironmentSpec, Executor, ScriptCommand};
+use crate::gpu::GpuRuntime;
+use crate::process::run_streaming;
+use crate::runner::Message;
+use job_config::job::JobMeta;
+use job_config::workflow::Isolation;
+
+use super::error::LocalError;
+
+/// Executor that runs job scripts as host processes.
+///
+/// Cheap to clone: each clone shares the message channel but keeps its own job
+/// index, so concurrently running jobs can each tag their own messages.
+#[derive(Clone)]
+pub struct LocalExecutor {
+    tx: mpsc::Sender<Message>,
+    job_idx: usize,
+}
+
+impl LocalExecutor {
+    /// Creates a new local executor.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - Message channel sender for streaming job status and logs
+    pub fn new(tx: mpsc::Sender<Message>) -> Self {
+        Self { tx, job_idx: 0 }
+    }
+
+    /// Updates the job index used for sending messages.
+    pub fn set_job_idx(&mut self, new_job_idx: usize) {
+        self.job_idx = new_job_idx;
+    }
+
+    /// Sends a message via the channel with the current job index.
+    async fn tx_send(&self, status: JobStatus, log_line: LogLine) -> Result<(), LocalError> {
+        self.tx
+            .send((self.job_idx, status, log_line))
+            .await
+            .map_err(|e| {
+                LocalError::ChannelSendMessageError(format!(
+                    "Message channel receiver dropped: {e}"
+                ))
+            })
+    }
+}
+
+impl Executor for LocalExecutor {
+    type Error = LocalError;
+    type Environment = PathBuf;
+
+    fn set_job_idx(&mut self, job_idx: usize) {
+        LocalExecutor::set_job_idx(self, job_idx);
+    }
+
+    fn gpu_runtime(&self) -> GpuRuntime {
+        // Scripts see the host's GPUs directly
+        if job_config::job::has_nvidia_gpu() {
+            GpuRuntime::Nvidia
+        } else {
+            GpuRuntime::None
+        }
+    }
+
+    async fn prepare_image(&self, config: &JobMeta, _job_dir: &Path) -> Result<String, LocalError> {
+        let log_line = LogLine::new(
+            LogSource::Stdout,
+            format!(
+                "Running on the host; container image '{}' is not used",
+                config.container.image
+            ),
+        );
+        self.tx_send(JobStatus::CreatingContainer, log_line).await?;
+        Ok(config.container.image.clone())
+    }
+
+    async fn image_id(&self, _image: &str) -> Result<String, LocalError> {
+        // Scripts run on the host, whatever image the job names
+        Ok("host".to_string())
+    }
+
+    async fn start_environment(&self, spec: EnvironmentSpec<'_>) -> Result<PathBuf, LocalError> {
+        if !spec.config.resources.is_empty() {
+            let log_line = LogLine::new(
+                LogSource::Stdout,
+                "Running on the host; [resources] limits are not applied".to_string(),
+            );
+            self.tx_send(JobStatus::CreatingContainer, log_line).await?;
+        }
+        if !spec.mounts.is_empty() {
+            let log_line = LogLine::new(
+                LogSource::Stdout,
+                "Running on the host; [[mounts]] are not applied, scripts see host paths as they are"
+                    .to_string(),
+            );
+            self.tx_send(JobStatus::CreatingContainer, log_line).await?;
+        }
+        if spec.isolation == Isolation::Job {
+            let log_line = LogLine::new(
+                LogSource::Stdout,
+                "Running on the host; isolation = \"job\" cannot hide sibling job folders"
+                    .to_string(),
+            );
+            self.tx_send(JobStatus::CreatingContainer, log_line).await?;
+        }
+        Ok(spec.workflow_folder.to_path_buf())
+    }
+
+    async fn exec_script(
+        &self,
+        workflow_folder: &PathBuf,
+        job_name: &str,
+        script: &ScriptCommand,
+        env_vars: &[String],
+        cancel_rx: &mut mpsc::Receiver<()>,
+    ) -> Result<i64, LocalError> {
+        let job_dir = workflow_folder.join(job_name);
+        let argv = script_argv(&job_dir.display().to_string(), script);
+        let Some((program, args)) = argv.split_first() else {
+            return Err(LocalError::SpawnFailed("empty command".to_string()));
+        };
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .current_dir(&job_dir)
+            .envs(env_vars.iter().filter_map(|var| var.split_once('=')));
+        Ok(run_streaming(command, &self.tx, self.job_idx, cancel_rx).await?)
+    }
+
+    async fn cleanup(&self) {
+        // Every script ran in its own process; nothing is left running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::SystemTime;
+
+    use tempfile::TempDir;
+
+    use crate::components::workflow::{JobFolder, JobScanner, WorkflowFolder};
+    use crate::runner::{self, RunOptions};
+    use job_config::job::{Container, Interpreter, Outputs, Scripts};
+    use job_config::workflow::WorkflowMeta;
+
+    async fn exec(
+        workflow_folder: &Path,
+        script: &str,
+        env_vars: &[String],
+    ) -> (Result<i64, LocalError>, Vec<Message>) {
+        let (tx, mut rx) = mpsc::channel::<Message>(64);
+        let (_cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+        let executor = LocalExecutor::new(tx);
+        let result = executor
+            .exec_script(
+                &workflow_folder.to_path_buf(),
+                "job",
+                &ScriptCommand::new(&Scripts::default(), script),
+                env_vars,
+                &mut cancel_rx,
+            )
+            .await;
+        let mut messages = Vec::new();
+        while let Ok(message) = rx.try_recv() {
+            messages.push(message);
+        }
+        (result, messages)
+    }
+
+    #[tokio::test]
+    async fn test_exec_script_runs_in_job_folder_with_env() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir(tmp.path().join("job")).unwrap();
+        fs::write(
+            tmp.path().join("job/run.sh"),
+            "echo \"$PARAM_EPOCHS\" > epochs.txt\necho done\necho oops >&2\n",
+        )
+        .unwrap();
+
+        let (result, messages) = exec(tmp.path(), "run.sh", &["PARAM_EPOCHS=10".to_string()]).await;
+
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("job/epochs.txt")).unwrap(),
+            "10\n"
+        );
+        let lines: Vec<_> = messages
+            .iter()
+            .map(|(_, _, log_line)| (log_line.source, log_line.content.as_str()))
+            .collect();
+        assert!(lines.contains(&(LogSource::Stdout, "done")));
+        assert!(lines.contains(&(LogSource::Stderr, "oops")));
+    }
+
+    #[tokio::test]
+    async fn test_exec_script_returns_exit_code() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir(tmp.path().join("job")).unwrap();
+        fs::write(tmp.path().join("job/run.sh"), "exit 3\n").unwrap();
+
+        let (result, _) = exec(tmp.path(), "run.sh", &[]).await;
+
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    /// Creates a workflow in `root` where each `(job, deps)` of `dependencies`
+    /// depends on `deps`.
+    fn make_workflow(root: &Path, dependencies: &[(&str, &[&str])]) -> WorkflowFolder {
+        let workflow_folder = WorkflowFolder::new(
+            "wf".to_string(),
+            root.to_path_buf(),
+            Some(SystemTime::now()),
+        );
+        let mut metadata = WorkflowMeta::new("wf".to_string(), String::new());
+        for (job, deps) in dependencies {
+            metadata.set_job_dependencies(
+                job.to_string(),
+                deps.iter().map(|d| d.to_string()).collect(),
+            );
+        }
+        workflow_folder.save_workflow_metadata(&metadata).unwrap();
+        fs::create_dir(root.join("input_files")).unwrap();
+        workflow_folder
+    }
+
+    /// Creates job `name` in `root` with `run_script` as its run.sh (unless empty),
+    /// after `configure` adjusted its job.toml.
+    fn make_job(root: &Path, name: &str, run_script: &str, configure: impl FnOnce(&mut JobMeta)) {
+        let job = JobFolder::new(name.to_string(), root.join(name));
+        let mut meta = JobMeta::new(
+            name.to_string(),
+            String::new(),
+            Container::new("ubuntu:22.04".to_string()),
+        );
+        meta.outputs = Outputs::Patterns(vec!["*.txt".to_string()]);
+        configure(&mut meta);
+        job.save_meta(&meta).unwrap();
+        if !run_script.is_empty() {
+            fs::write(job.path.join("run.sh"), run_script).unwrap();
+        }
+    }
+
+    /// Runs the workflow in `workflow_folder` on the local executor, in a temp copy.
+    async fn run_locally(workflow_folder: &WorkflowFolder) -> (TempDir, bool, Vec<Message>) {
+        let temp = runner::create_temp_workflow_folder(&workflow_folder.path).unwrap();
+        let jobs = JobScanner::scan_jobs(temp.path()).unwrap();
+        let workflow =
+            runner::prepare_workflow(workflow_folder, temp.path(), &jobs, &Default::default())
+                .unwrap();
+        let (tx, mut rx) = mpsc::channel::<Message>(1024);
+        let (_cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+        let executor = LocalExecutor::new(tx.clone());
+        let succeeded = runner::execute(
+            executor,
+            workflow,
+            &RunOptions::default(),
+            tx,
+            &mut cancel_rx,
+        )
+        .await;
+        let mut messages = Vec::new();
+        while let Ok(message) = rx.try_recv() {
+            messages.push(message);
+        }
+        (temp, succeeded, messages)
+    }
+
+    #[tokio::test]
+    async fn test_runner_kills_scripts_that_time_out() {
+        let source = tempfile::tempdir().unwrap();
+        let workflow_folder = make_workflow(source.path(), &[]);
+        // The background child would outlive a kill of the script's shell alone
+        make_job(
+            source.path(),
+            "slow",
+            "(sleep 1.5; echo $$ >> late.txt) &\nsleep 30\n",
+            |meta| {
+                meta.timeout = Some(job_config::job::HumanDuration::parse("1s").unwrap());
+                meta.retries = 1;
+            },
+        );
+
+        let started = std::time::Instant::now();
+        let (temp, succeeded, messages) = run_locally(&workflow_folder).await;
+
+        assert!(!succeeded);
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+        assert!(messages.iter().any(|(_, status, log_line)| {
+            *status == JobStatus::Failed
+                && log_line
+                    .content
+                    .ends_with("Script ./run.sh timed out after 1s (2 attempts)")
+        }));
+        assert!(
+            messages.iter().any(|(_, _, log_line)| {
+                log_line.content.contains("retrying in 0s (attempt 2/2)")
+            })
+        );
+
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        assert!(!temp.path().join("slow/late.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_runner_stages_and_completes_jobs_locally() {
+        let source = tempfile::tempdir().unwrap();
+        let workflow_folder = make_workflow(source.path(), &[("b", &["a"])]);
+        make_job(source.path(), "a", "echo hello > greeting.txt", |_| {});
+        make_job(
+            source.path(),
+            "b",
+            "tr a-z A-Z < inputs/greeting.txt > shout.txt",
+            |_| {},
+        );
+
+        let (temp, succeeded, _) = run_locally(&workflow_folder).await;
+
+        assert!(succeeded);
+        assert_eq!(
+            fs::read_to_string(temp.path().join("@complete/b/outputs/shout.txt")).unwrap(),
+            "HELLO\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_runner_runs_shebang_scripts_and_commands() {
+        let source = tempfile::tempdir().unwrap();
+        let workflow_folder = make_workflow(source.path(), &[("b", &["a"])]);
+        // Saved on Windows: the shebang line only works once the host drops the \r
+        make_job(
+            source.path(),
+            "a",
+            "#!/bin/sh\r\necho hello > greeting.txt\r\n",
+            |meta| meta.scripts.interpreter = Interpreter::Shebang,
+        );
+        make_job(source.path(), "b", "", |meta| {
+            meta.scripts.command = ["sh", "-c", "tr a-z A-Z < inputs/greeting.txt > shout.txt"]
+                .map(String::from)
+                .to_vec();
+        });
+
+        let (temp, succeeded, _) = run_locally(&workflow_folder).await;
+
+        assert!(succeeded);
+        assert_eq!(
+            fs::read_to_string(temp.path().join("@complete/b/outputs/shout.txt")).unwrap(),
+            "HELLO\n"
+        );
+    }
+}
@@ -1,4 +1,7 @@
 pub mod application;
+pub mod apptainer;
 pub mod docker;
 pub mod health_check;
+pub mod local;
+pub mod runs;
 pub mod workflow;
@@ -0,0 +1,310 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph},
+};
+
+use crate::app::App;
+use crate::components::docker::render::get_job_status_symbol_and_color;
+use crate::history::{RunOutcome, RunRecord};
+
+pub fn render(frame: &mut Frame, area: Rect, app: &mut App) {
+    if app.runs_state.show_details
+        && let Some(run) = app.runs_state.get_selected_run().cloned()
+    {
+        render_details(frame, area, app, &run);
+    } else {
+        render_run_list(frame, area, app);
+    }
+}
+
+/// Renders the list of recorded runs, newest first.
+fn render_run_list(frame: &mut Frame, area: Rect, app: &App) {
+    let state = &app.runs_state;
+
+    let mut items: Vec<ListItem> = Vec::new();
+    if state.runs.is_empty() {
+        items.push(ListItem::new(Line::from(vec![Span::styled(
+            "No runs recorded yet",
+            Style::default().fg(Color::Yellow),
+        )])));
+        items.push(ListItem::new(""));
+        items.push(ListItem::new(Line::from(vec![Span::styled(
+            "Runs started from the Workflows tab or headless mode are listed here",
+            Style::default().fg(Color::DarkGray),
+        )])));
+    }
+
+    for (idx, run) in state.runs.iter().enumerate() {
+        let is_selected = state.selected_run == Some(idx);
+        let (symbol, color) = outcome_symbol_and_color(run.outcome);
+        let completed = run
+            .jobs
+            .iter()
+            .filter(|job| job.exit_code == Some(0))
+            .count();
+
+        let spans = vec![
+            Span::styled(
+                symbol,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" "),
+            Span::styled(
+                format_timestamp(&run.started_at),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::raw("  "),
+            Span::styled(
+                &run.workflow,
+                Style::default().fg(if is_selected {
+                    Color::Cyan
+                } else {
+                    Color::White
+                }),
+            ),
+            Span::raw("  "),
+            Span::styled(
+                format!("{completed}/{} jobs", run.jobs.len()),
+                Style::default().fg(color),
+            ),
+            Span::raw("  "),
+            Span::styled(duration(run), Style::default().fg(Color::DarkGray)),
+        ];
+
+        let item = if is_selected {
+            ListItem::new(Line::from(spans)).style(Style::default().bg(Color::DarkGray))
+        } else {
+            ListItem::new(Line::from(spans))
+        };
+        items.push(item);
+    }
+
+    let title = if let Some(err) = &state.last_error {
+        format!("Runs - Error: {err}")
+    } else {
+        format!(
+            "Runs ({}) - {}",
+            state.runs.len(),
+            state.history.root().display()
+        )
+    };
+
+    let list = List::new(items).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded),
+    );
+    frame.render_widget(list, area);
+}
+
+/// Renders an opened run: its summary, its jobs and the selected job's logs.
+fn render_details(frame: &mut Frame, area: Rect, app: &mut App, run: &RunRecord) {
+    let vertical_sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(6), // Summary
+            Constraint::Min(5),    // Jobs list + Logs area
+        ])
+        .split(area);
+
+    render_summary(frame, vertical_sections[0], run);
+
+    let horizontal_sections = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(30), // Job list
+            Constraint::Percentage(70), // Logs
+        ])
+        .split(vertical_sections[1]);
+
+    render_job_list(frame, horizontal_sections[0], app, run);
+    render_logs(frame, horizontal_sections[1], app, run);
+}
+
+fn render_summary(frame: &mut Frame, area: Rect, run: &RunRecord) {
+    let (_, color) = outcome_symbol_and_color(run.outcome);
+    let label = Style::default().add_modifier(Modifier::BOLD);
+
+    let folder = run.output_folder();
+    let folder_note = if folder.exists() { "" } else { " (removed)" };
+    let params = serde_json::to_string(&run.params).unwrap_or_default();
+
+    let lines = vec![
+        Line::from(vec![
+            Span::styled("Outcome: ", label),
+            Span::styled(run.outcome.as_str(), Style::default().fg(color)),
+            Span::raw("  "),
+            Span::styled("Started: ", label),
+            Span::raw(format_timestamp(&run.started_at)),
+            Span::raw("  "),
+            Span::styled("Ended: ", label),
+            Span::raw(
+                run.ended_at
+                    .as_deref()
+                    .map(format_timestamp)
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            Span::raw("  "),
+            Span::styled("Duration: ", label),
+            Span::raw(duration(run)),
+        ]),
+        Line::from(vec![
+            Span::styled("Output folder: ", label),
+            Span::styled(
+                format!("{}{folder_note}", folder.display()),
+                Style::default().fg(Color::Green),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Global params: ", label),
+            Span::styled(params, Style::default().fg(Color::DarkGray)),
+        ]),
+    ];
+
+    let summary = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!("{} - {}", run.workflow, run.id))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(summary, area);
+}
+
+fn render_job_list(frame: &mut Frame, area: Rect, app: &App, run: &RunRecord) {
+    let selected_job = app.runs_state.selected_job;
+
+    let items: Vec<ListItem> = run
+        .jobs
+        .iter()
+        .enumerate()
+        .map(|(idx, job)| {
+            let is_selected = idx == selected_job;
+            let (symbol, color) = get_job_status_symbol_and_color(&job.status);
+
+            let mut spans = vec![if is_selected {
+                Span::styled(
+                    "> ",
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                Span::raw("  ")
+            }];
+            spans.push(Span::styled(
+                symbol,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ));
+            spans.push(Span::raw(" "));
+            let name_style = if is_selected {
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(color)
+            };
+            spans.push(Span::styled(&job.name, name_style));
+            if let Some(code) = job.exit_code {
+                spans.push(Span::styled(
+                    format!(" (exit {code})"),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let jobs_list = List::new(items).block(
+        Block::default()
+            .title(format!("Jobs ({})", run.jobs.len()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(jobs_list, area);
+}
+
+fn render_logs(frame: &mut Frame, area: Rect, app: &mut App, run: &RunRecord) {
+    let state = &mut app.runs_state;
+    state.last_viewport_height = area.height.saturating_sub(2) as usize;
+
+    let job_name = run
+        .jobs
+        .get(state.selected_job)
+        .map(|job| job.name.as_str())
+        .unwrap_or_default();
+
+    let items: Vec<ListItem> = state
+        .logs
+        .iter()
+        .skip(state.scroll_offset)
+        .take(state.last_viewport_height)
+        .map(|line| {
+            let color = if line.contains(" [ERR] ") {
+                Color::Red
+            } else {
+                Color::White
+            };
+            ListItem::new(Line::from(Span::styled(
+                line.as_str(),
+                Style::default().fg(color),
+            )))
+        })
+        .collect();
+
+    let title = format!(
+        "{job_name} - Logs ({}/{})",
+        state.scroll_offset.min(state.logs.len()),
+        state.logs.len()
+    );
+    let logs_list = List::new(items).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(logs_list, area);
+}
+
+fn outcome_symbol_and_color(outcome: RunOutcome) -> (&'static str, Color) {
+    match outcome {
+        RunOutcome::Running => ("⟳", Color::Yellow),
+        RunOutcome::Succeeded => ("✓", Color::Green),
+        RunOutcome::Failed => ("✗", Color::Red),
+        RunOutcome::Cancelled => ("⊘", Color::Magenta),
+    }
+}
+
+/// Formats an RFC 3339 timestamp as local `YYYY-MM-DD HH:MM:SS`.
+fn format_timestamp(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+/// Returns the run's duration as e.g. `2m 05s`, or `-` while it has not ended.
+fn duration(run: &RunRecord) -> String {
+    let (Some(ended_at), Ok(started)) = (
+        run.ended_at.as_deref(),
+        chrono::DateTime::parse_from_rfc3339(&run.started_at),
+    ) else {
+        return "-".to_string();
+    };
+    let Ok(ended) = chrono::DateTime::parse_from_rfc3339(ended_at) else {
+        return "-".to_string();
+    };
+    let secs = (ended - started).num_seconds().max(0);
+    if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{secs}s")
+    }
+}
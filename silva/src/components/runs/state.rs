@@ -0,0 +1,218 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::history::{RunHistory, RunRecord};
+
+/// State of the Runs tab: the recorded runs and, when one is opened, its jobs
+/// and the logs of the selected job.
+pub struct State {
+    pub history: RunHistory,
+    pub runs: Vec<RunRecord>,
+    pub selected_run: Option<usize>,
+    pub show_details: bool,
+    pub selected_job: usize,
+    /// Log of the selected job of the opened run
+    pub logs: Vec<String>,
+    pub scroll_offset: usize,
+    pub last_viewport_height: usize,
+    pub last_error: Option<String>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new(RunHistory::open_default())
+    }
+}
+
+impl State {
+    pub fn new(history: RunHistory) -> Self {
+        let mut state = Self {
+            history,
+            runs: Vec::new(),
+            selected_run: None,
+            show_details: false,
+            selected_job: 0,
+            logs: Vec::new(),
+            scroll_offset: 0,
+            last_viewport_height: 20,
+            last_error: None,
+        };
+        state.refresh();
+        state
+    }
+
+    pub fn handle_input(&mut self, key: KeyEvent) {
+        if self.show_details {
+            self.handle_details_input(key);
+            return;
+        }
+
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => self.select_next_run(),
+            KeyCode::Up | KeyCode::Char('k') => self.select_previous_run(),
+            KeyCode::Enter => self.open_details(),
+            KeyCode::Char('o') => self.open_output_folder(),
+            KeyCode::Char('r') => self.refresh(),
+            _ => {}
+        }
+    }
+
+    fn handle_details_input(&mut self, key: KeyEvent) {
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+
+        match key.code {
+            KeyCode::Esc => self.show_details = false,
+            KeyCode::Down | KeyCode::Char('j') if !shift => self.select_job(1),
+            KeyCode::Up | KeyCode::Char('k') if !shift => self.select_job(-1),
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::PageDown => self.scroll_down(),
+            KeyCode::Up | KeyCode::Char('k') | KeyCode::PageUp => self.scroll_up(),
+            KeyCode::Char('b') => {
+                self.scroll_offset = self.logs.len().saturating_sub(self.last_viewport_height)
+            }
+            KeyCode::Char('o') => self.open_output_folder(),
+            _ => {}
+        }
+    }
+
+    /// Reloads the runs from the history, keeping the selected run if it still exists.
+    pub fn refresh(&mut self) {
+        let selected_id = self.get_selected_run().map(|run| run.id.clone());
+        match self.history.list() {
+            Ok(runs) => {
+                self.runs = runs;
+                self.last_error = None;
+            }
+            Err(e) => self.last_error = Some(e),
+        }
+        self.selected_run = selected_id
+            .and_then(|id| self.runs.iter().position(|run| run.id == id))
+            .or(if self.runs.is_empty() { None } else { Some(0) });
+    }
+
+    /// Gets the currently selected run.
+    pub fn get_selected_run(&self) -> Option<&RunRecord> {
+        self.selected_run.and_then(|idx| self.runs.get(idx))
+    }
+
+    pub fn select_next_run(&mut self) {
+        if self.runs.is_empty() {
+            return;
+        }
+        self.selected_run = Some(match self.selected_run {
+            Some(idx) => (idx + 1) % self.runs.len(),
+            None => 0,
+        });
+    }
+
+    pub fn select_previous_run(&mut self) {
+        if self.runs.is_empty() {
+            return;
+        }
+        self.selected_run = Some(match self.selected_run {
+            Some(0) | None => self.runs.len() - 1,
+            Some(idx) => idx - 1,
+        });
+    }
+
+    /// Opens the selected run, showing its first job.
+    pub fn open_details(&mut self) {
+        if self.get_selected_run().is_none() {
+            return;
+        }
+        self.show_details = true;
+        self.selected_job = 0;
+        self.load_logs();
+    }
+
+    /// Moves the job selection of the opened run by `step`, wrapping around.
+    fn select_job(&mut self, step: isize) {
+        let Some(jobs_len) = self.get_selected_run().map(|run| run.jobs.len()) else {
+            return;
+        };
+        if jobs_len == 0 {
+            return;
+        }
+        self.selected_job =
+            (self.selected_job as isize + step).rem_euclid(jobs_len as isize) as usize;
+        self.load_logs();
+    }
+
+    fn load_logs(&mut self) {
+        self.scroll_offset = 0;
+        let Some(run) = self.get_selected_run() else {
+            self.logs.clear();
+            return;
+        };
+        let Some(job) = run.jobs.get(self.selected_job) else {
+            self.logs.clear();
+            return;
+        };
+        match self.history.read_logs(&run.id, &job.name) {
+            Ok(logs) => self.logs = logs,
+            Err(e) => self.logs = vec![e],
+        }
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll_offset = self.scroll_offset.saturating_sub(1);
+    }
+
+    pub fn scroll_down(&mut self) {
+        let max_scroll = self.logs.len().saturating_sub(self.last_viewport_height);
+        if self.scroll_offset < max_scroll {
+            self.scroll_offset += 1;
+        }
+    }
+
+    /// Opens the output folder of the selected run, if it still exists.
+    pub fn open_output_folder(&self) {
+        if let Some(run) = self.get_selected_run() {
+            let folder = run.output_folder();
+            if folder.exists() {
+                crate::utils::open_folder(&folder);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write_run(root: &std::path::Path, id: &str, started_at: &str) {
+        let run = RunRecord {
+            id: id.to_string(),
+            workflow: "wf".to_string(),
+            started_at: started_at.to_string(),
+            ended_at: None,
+            outcome: crate::history::RunOutcome::Succeeded,
+            temp_folder: root.join("missing"),
+            params: Default::default(),
+            jobs: Vec::new(),
+        };
+        fs::create_dir_all(root.join(id)).unwrap();
+        fs::write(
+            root.join(id).join("run.json"),
+            serde_json::to_string(&run).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_runs_state_lists_newest_first_and_keeps_selection() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_run(tmp.path(), "old", "2026-01-01T10:00:00+00:00");
+        write_run(tmp.path(), "new", "2026-01-02T10:00:00+00:00");
+
+        let mut state = State::new(RunHistory::new(tmp.path().to_path_buf()));
+        assert_eq!(state.get_selected_run().unwrap().id, "new");
+
+        state.select_next_run();
+        assert_eq!(state.get_selected_run().unwrap().id, "old");
+
+        write_run(tmp.path(), "newest", "2026-01-03T10:00:00+00:00");
+        state.refresh();
+        assert_eq!(state.runs.len(), 3);
+        assert_eq!(state.get_selected_run().unwrap().id, "old");
+    }
+}
@@ -96,6 +96,11 @@ impl WorkflowManager {
                     {
                         let name_str = name.to_string_lossy().to_string();
 
+                        // Skip hidden folders such as the run history in .silva/
+                        if name_str.starts_with('.') {
+                            continue;
+                        }
+
                         // Get creation time
                         let created = entry.metadata().ok().and_then(|m| m.created().ok());
 
@@ -286,6 +291,23 @@ mod tests {
         teardown_test_env(&test_path);
     }
 
+    #[test]
+    #[serial]
+    fn test_scan_workflows_ignores_hidden_folders() {
+        let (test_path, home) = setup_test_env();
+
+        fs::create_dir_all(format!("{test_path}/workflow_1")).unwrap();
+        fs::create_dir_all(format!("{test_path}/.silva/runs")).unwrap();
+
+        let mut manager = WorkflowManager::new(home);
+        manager.scan_workflows().unwrap();
+
+        assert_eq!(manager.count(), 1);
+        assert_eq!(manager.get_workflows()[0].name, "workflow_1");
+
+        teardown_test_env(&test_path);
+    }
+
     #[test]
     #[serial]
     fn test_workflow_folder_created_display() {
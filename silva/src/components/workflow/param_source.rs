@@ -4,9 +4,10 @@
 //! allowing the same editor UI to be used for both job-level and workflow-level parameters.
 
 use std::collections::HashMap;
+use std::path::Path;
 
 use job_config::job::{JobMeta, ParamDefinition};
-use job_config::params::JobParams;
+use job_config::params::{JobParams, WorkflowParams};
 use job_config::workflow::WorkflowMeta;
 
 use super::job_folder::JobFolder;
@@ -36,6 +37,13 @@ pub trait ParamSource: Clone {
 
     /// Returns true if this is a global/workflow-level editor.
     fn is_global(&self) -> bool;
+
+    /// Returns the folder relative `must_exist` paths are resolved against.
+    fn folder(&self) -> &Path;
+
+    /// Loads the workflow parameters `${global.x}` refers to in a job's values.
+    /// Global editors refer to their own values instead.
+    fn global_params(&self) -> WorkflowParams;
 }
 
 /// Wrapper for JobFolder with its metadata for parameter editing.
@@ -83,6 +91,20 @@ impl ParamSource for JobParamSource {
     fn is_global(&self) -> bool {
         false
     }
+
+    fn folder(&self) -> &Path {
+        &self.job.path
+    }
+
+    fn global_params(&self) -> WorkflowParams {
+        // Jobs live directly in their workflow folder
+        self.job
+            .path
+            .parent()
+            .map(|path| WorkflowFolder::new(String::new(), path.to_path_buf(), None))
+            .and_then(|workflow| workflow.load_workflow_params().ok().flatten())
+            .unwrap_or_default()
+    }
 }
 
 /// Wrapper for WorkflowFolder with its metadata for parameter editing.
@@ -130,4 +152,12 @@ impl ParamSource for WorkflowParamSource {
     fn is_global(&self) -> bool {
         true
     }
+
+    fn folder(&self) -> &Path {
+        &self.workflow.path
+    }
+
+    fn global_params(&self) -> WorkflowParams {
+        WorkflowParams::new()
+    }
 }
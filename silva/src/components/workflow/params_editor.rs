@@ -6,8 +6,10 @@ use ratatui::{
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
 };
 
-use job_config::job::ParamType;
-use job_config::params::{JobParams, json_to_toml, toml_to_json};
+use std::collections::HashMap;
+
+use job_config::interpolate::{Interpolator, Scope, has_references};
+use job_config::params::{JobParams, json_to_toml, toml_to_json, validate_values};
 
 use super::param_source::ParamSource;
 
@@ -37,13 +39,16 @@ impl<T: ParamSource> ParamsEditorState<T> {
             .load_params()?
             .unwrap_or_else(|| source.generate_default_params());
 
-        // Convert params to editable strings
+        // Convert params to editable strings; required params without a value stay empty
         let mut param_values = Vec::new();
         for (param_name, param_def) in source.param_definitions() {
             // Get value from current params or convert default from TOML to JSON
-            let default_json = toml_to_json(&param_def.default);
-            let value = current_params.get(param_name).unwrap_or(&default_json);
-            let value_str = param_value_to_string(value);
+            let default_json = param_def.default_value().map(toml_to_json);
+            let value_str = current_params
+                .get(param_name)
+                .or(default_json.as_ref())
+                .map(param_value_to_string)
+                .unwrap_or_default();
             param_values.push((param_name.clone(), value_str));
         }
 
@@ -97,12 +102,24 @@ impl<T: ParamSource> ParamsEditorState<T> {
 
             // Validate the input
             if let Some(param_def) = self.source.param_definitions().get(param_name) {
-                match string_to_param_value(&self.input_buffer, &param_def.param_type) {
+                if param_def.is_required() && self.input_buffer.trim().is_empty() {
+                    self.error_message = Some(format!("Parameter '{param_name}' is required"));
+                    return;
+                }
+                // Values with `${...}` references are checked once resolved, before a run
+                if has_references(&serde_json::Value::String(self.input_buffer.clone())) {
+                    self.param_values[self.selected_index].1 = self.input_buffer.clone();
+                    self.editing = false;
+                    self.input_buffer.clear();
+                    self.error_message = None;
+                    return;
+                }
+                match param_def.parse_value(&self.input_buffer) {
                     Ok(json_value) => {
                         // Convert JSON to TOML for validation against TOML-based ParamDefinition
                         let toml_value = json_to_toml(&json_value);
-                        if let Err(e) = param_def.validate(&toml_value) {
-                            self.error_message = Some(e);
+                        if let Err(e) = param_def.validate_in(&toml_value, self.source.folder()) {
+                            self.error_message = Some(format!("Parameter '{param_name}': {e}"));
                             return;
                         }
 
@@ -113,7 +130,7 @@ impl<T: ParamSource> ParamsEditorState<T> {
                         self.error_message = None;
                     }
                     Err(e) => {
-                        self.error_message = Some(e);
+                        self.error_message = Some(format!("Parameter '{param_name}': {e}"));
                     }
                 }
             }
@@ -130,24 +147,76 @@ impl<T: ParamSource> ParamsEditorState<T> {
         self.input_buffer.pop();
     }
 
+    /// Resolves the current values that contain `${...}` references, for the
+    /// preview below them. `${run.id}` is only known once a run starts and stays
+    /// as it is.
+    pub fn resolved_preview(&self) -> HashMap<String, Result<String, String>> {
+        let definitions = self.source.param_definitions();
+        let values: JobParams = self
+            .param_values
+            .iter()
+            .filter_map(|(name, text)| {
+                let literal = serde_json::Value::String(text.clone());
+                let value = if has_references(&literal) {
+                    literal
+                } else {
+                    definitions.get(name)?.parse_value(text).ok()?
+                };
+                Some((name.clone(), value))
+            })
+            .collect();
+        if !values.values().any(has_references) {
+            return HashMap::new();
+        }
+
+        let interpolator = Interpolator::new(crate::runner::run_info("${run.id}"));
+        let results = if self.source.is_global() {
+            interpolator.resolve_each(&values, Scope::Workflow)
+        } else {
+            // Broken workflow values are shown as they are
+            let raw_global = self.source.global_params();
+            let mut global = raw_global.clone();
+            for (name, result) in interpolator.resolve_each(&raw_global, Scope::Workflow) {
+                if let Ok(value) = result {
+                    global.insert(name, value);
+                }
+            }
+            interpolator.resolve_each(&values, Scope::Job { global: &global })
+        };
+        results
+            .into_iter()
+            .filter(|(name, _)| has_references(&values[name]))
+            .map(|(name, result)| (name, result.map(|value| param_value_to_string(&value))))
+            .collect()
+    }
+
     /// Saves all parameters to the params file.
+    ///
+    /// Refuses to save while a value is invalid or a required parameter is empty.
     pub fn save_params(&mut self) -> Result<(), String> {
         let mut params = JobParams::new();
+        let mut checked = self.source.param_definitions().clone();
 
         for (param_name, param_value_str) in &self.param_values {
             if let Some(param_def) = self.source.param_definitions().get(param_name) {
-                let json_value = string_to_param_value(param_value_str, &param_def.param_type)
+                // An empty required param has no value, reported below
+                if param_def.is_required() && param_value_str.trim().is_empty() {
+                    continue;
+                }
+                let text = serde_json::Value::String(param_value_str.clone());
+                if has_references(&text) {
+                    checked.remove(param_name);
+                    params.insert(param_name.clone(), text);
+                    continue;
+                }
+                let json_value = param_def
+                    .parse_value(param_value_str)
                     .map_err(|e| format!("Invalid value for {param_name}: {e}"))?;
-
-                // Convert JSON to TOML for validation against TOML-based ParamDefinition
-                let toml_value = json_to_toml(&json_value);
-                param_def
-                    .validate(&toml_value)
-                    .map_err(|e| format!("Validation failed for {param_name}: {e}"))?;
-
                 params.insert(param_name.clone(), json_value);
             }
         }
+        // Values with references are checked once resolved, before a run
+        validate_values(&checked, &params, self.source.folder())?;
 
         self.source.save_params(&params)
     }
@@ -236,15 +305,18 @@ pub fn render<T: ParamSource>(f: &mut Frame, state: &mut ParamsEditorState<T>, a
 }
 
 fn render_params_list<T: ParamSource>(f: &mut Frame, state: &ParamsEditorState<T>, area: Rect) {
+    let previews = state.resolved_preview();
     let items: Vec<ListItem> = state
         .param_values
         .iter()
         .enumerate()
         .map(|(i, (name, value))| {
             let param_def = state.source.param_definitions().get(name);
-            let type_str = param_def
-                .map(|d| d.param_type.to_string())
-                .unwrap_or_else(|| "?".to_string());
+            let type_str = match param_def {
+                Some(d) if d.is_required() => format!("{}, required", d.param_type),
+                Some(d) => d.param_type.to_string(),
+                None => "?".to_string(),
+            };
             let hint = param_def.map(|d| d.hint.as_str()).unwrap_or("");
 
             let is_selected = i == state.selected_index;
@@ -296,7 +368,21 @@ fn render_params_list<T: ParamSource>(f: &mut Frame, state: &ParamsEditorState<T
                 Span::styled(display_value, value_style),
             ]));
 
-            // Second line: hint (if not editing or selected)
+            // Resolved value of a value with references
+            if let Some(preview) = previews.get(name)
+                && !is_editing
+            {
+                let (text, color) = match preview {
+                    Ok(resolved) => (format!("= {resolved}"), Color::Blue),
+                    Err(e) => (format!("! {e}"), Color::Red),
+                };
+                lines.push(Line::from(vec![
+                    Span::raw("    "),
+                    Span::styled(text, Style::default().fg(color)),
+                ]));
+            }
+
+            // Hint line (if not editing)
             if !hint.is_empty() && !is_editing {
                 let hint_style = if is_selected {
                     Style::default()
@@ -346,45 +432,3 @@ fn param_value_to_string(value: &serde_json::Value) -> String {
         serde_json::Value::Object(obj) => format!("{:?}", obj),
     }
 }
-
-/// Converts a string to a JSON value based on the parameter type.
-fn string_to_param_value(s: &str, param_type: &ParamType) -> Result<serde_json::Value, String> {
-    let trimmed = s.trim();
-
-    match param_type {
-        ParamType::String | ParamType::File | ParamType::Directory | ParamType::Enum => {
-            Ok(serde_json::Value::String(trimmed.to_string()))
-        }
-        ParamType::Integer => trimmed
-            .parse::<i64>()
-            .map(|n| serde_json::json!(n))
-            .map_err(|_| format!("Invalid integer: {trimmed}")),
-        ParamType::Float => trimmed
-            .parse::<f64>()
-            .map(|f| serde_json::json!(f))
-            .map_err(|_| format!("Invalid float: {trimmed}")),
-        ParamType::Boolean => match trimmed.to_lowercase().as_str() {
-            "true" | "yes" | "1" => Ok(serde_json::Value::Bool(true)),
-            "false" | "no" | "0" => Ok(serde_json::Value::Bool(false)),
-            _ => Err(format!("Invalid boolean: {trimmed} (use true/false)")),
-        },
-        ParamType::Array => {
-            // Simple array parsing: split by comma
-            if trimmed.starts_with('[') && trimmed.ends_with(']') {
-                let inner = &trimmed[1..trimmed.len() - 1];
-                let items: Vec<serde_json::Value> = inner
-                    .split(',')
-                    .map(|item| serde_json::Value::String(item.trim().to_string()))
-                    .collect();
-                Ok(serde_json::Value::Array(items))
-            } else {
-                // Also accept comma-separated without brackets
-                let items: Vec<serde_json::Value> = trimmed
-                    .split(',')
-                    .map(|item| serde_json::Value::String(item.trim().to_string()))
-                    .collect();
-                Ok(serde_json::Value::Array(items))
-            }
-        }
-    }
-}
@@ -153,9 +153,7 @@ impl State {
         }
 
         // Get the selected job from docker_state
-        if let Some(selected_job_idx) = self.docker_state.selected_job_index
-            && let Some(job) = self.docker_state.jobs.get(selected_job_idx)
-        {
+        if let Some(job) = self.docker_state.get_selected_job_folder() {
             // Load job metadata
             let job_meta: job_config::job::JobMeta = match job.load_meta() {
                 Ok(meta) => meta,
@@ -241,10 +239,14 @@ impl State {
                     Ok(Some(metadata)) => metadata,
                     Ok(None) => {
                         // Create default metadata
-                        let metadata = job_config::workflow::WorkflowMeta::new(
-                            workflow_folder.name.clone(),
-                            "Global workflow parameters".to_string(),
-                        );
+                        // Keep the shared containers the workflow ran with so far
+                        let metadata = job_config::workflow::WorkflowMeta {
+                            isolation: job_config::workflow::Isolation::Workflow,
+                            ..job_config::workflow::WorkflowMeta::new(
+                                workflow_folder.name.clone(),
+                                "Global workflow parameters".to_string(),
+                            )
+                        };
                         // Save it for future use
                         let _ = workflow_folder.save_workflow_metadata(&metadata);
                         metadata
@@ -0,0 +1,153 @@
+//! Execution backend interface.
+//!
+//! An `Executor` knows how to get an image, start an environment to run scripts
+//! in, and run a single script. The order in which these steps happen, and
+//! everything around them (dependency staging, parameters, output collection,
+//! status reporting), lives in `crate::runner` and is shared by every backend.
+
+use std::fmt::Display;
+use std::future::Future;
+use std::path::Path;
+
+use job_config::job::{JobMeta, Mount, Scripts};
+use job_config::workflow::Isolation;
+use tokio::sync::mpsc;
+
+use crate::gpu::GpuRuntime;
+
+/// What `Executor::start_environment` starts for a job.
+#[derive(Debug, Clone, Copy)]
+pub struct EnvironmentSpec<'a> {
+    /// The job's configuration, whose `[resources]` apply to the environment.
+    pub config: &'a JobMeta,
+    /// Image returned by `prepare_image`.
+    pub image: &'a str,
+    /// Workflow folder holding the job folders.
+    pub workflow_folder: &'a Path,
+    /// Name of the job's folder inside `workflow_folder`.
+    pub job_name: &'a str,
+    /// Whether the environment is shared with other jobs or private to this one.
+    pub isolation: Isolation,
+    /// Host paths to mount, already resolved and checked against the allowlist.
+    pub mounts: &'a [Mount],
+}
+
+/// A program `Executor::exec_script` runs from a job's folder: a script with the
+/// job's interpreter, or the job's `command`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptCommand {
+    /// Name shown in logs: the script, e.g. `./run.sh`, or the command line.
+    pub name: String,
+    /// Program and arguments; relative paths are relative to the job folder. The
+    /// program runs directly, not through a shell, so it works in images without one.
+    pub argv: Vec<String>,
+}
+
+impl ScriptCommand {
+    /// The command running step `script` of a job with `scripts`: the `command`
+    /// for the `run` script when one is set, otherwise the script with the
+    /// job's interpreter.
+    pub fn new(scripts: &Scripts, script: &str) -> Self {
+        if script == scripts.run && !scripts.command.is_empty() {
+            return Self {
+                name: scripts.command.join(" "),
+                argv: scripts.command.clone(),
+            };
+        }
+        Self {
+            name: script.to_string(),
+            argv: scripts.interpreter.argv(script),
+        }
+    }
+}
+
+impl Display for ScriptCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// A container runtime (or the host itself) that runs job scripts.
+///
+/// Executors are cloned once per job. Clones share the underlying client and
+/// message channel, but each keeps its own job index set by `set_job_idx`, which
+/// tags every status and log message the executor sends.
+pub trait Executor: Clone + Send + Sync + 'static {
+    /// Error returned by the executor's operations.
+    type Error: Display + Send;
+
+    /// Handle to a started environment, e.g. a container id.
+    type Environment: Send + Sync;
+
+    /// Updates the job index used for sending messages.
+    fn set_job_idx(&mut self, job_idx: usize);
+
+    /// GPU runtime jobs can use. Jobs with `gpu = "required"` are rejected before
+    /// any job starts when this is `GpuRuntime::None`, the default.
+    fn gpu_runtime(&self) -> GpuRuntime {
+        GpuRuntime::None
+    }
+
+    /// Makes the job's image available and returns a reference to it.
+    ///
+    /// `job_dir` is the job's folder, against which relative image paths resolve.
+    fn prepare_image(
+        &self,
+        config: &JobMeta,
+        job_dir: &Path,
+    ) -> impl Future<Output = Result<String, Self::Error>> + Send;
+
+    /// Identifies the exact contents of `image`, as returned by `prepare_image`.
+    ///
+    /// Part of the result cache key. Defaults to `image` itself; backends that can
+    /// resolve a content digest should return it instead.
+    fn image_id(&self, image: &str) -> impl Future<Output = Result<String, Self::Error>> + Send {
+        let image = image.to_string();
+        async move { Ok(image) }
+    }
+
+    /// Starts an environment for the job described by `spec`, applying the job's
+    /// `[resources]` limits.
+    ///
+    /// With `Isolation::Workflow` the environment may be reused by other jobs and
+    /// mounts the whole workflow folder as the workspace. With `Isolation::Job` it
+    /// is fresh, private to the job, and mounts only the job's folder. Either way
+    /// `spec.mounts` are added and `/tmp` is private to the environment.
+    ///
+    /// GPUs the runner reserved for the job are passed as `resources.gpus`
+    /// device IDs.
+    fn start_environment(
+        &self,
+        spec: EnvironmentSpec<'_>,
+    ) -> impl Future<Output = Result<Self::Environment, Self::Error>> + Send;
+
+    /// Releases an environment once its job is done, whether it succeeded or not.
+    ///
+    /// Backends remove environments private to the job here; shared ones stay
+    /// until `cleanup`. Does nothing by default.
+    fn release_environment(
+        &self,
+        environment: Self::Environment,
+    ) -> impl Future<Output = ()> + Send {
+        drop(environment);
+        async {}
+    }
+
+    /// Runs `script` from the folder of job `job_name` and returns its exit code.
+    ///
+    /// `env_vars` are `KEY=VALUE` strings. A message on `cancel_rx` kills the script,
+    /// together with the processes it started; the runner sends one on a cancel and
+    /// when the job's `timeout` expires. A backend that cannot reach the script's
+    /// processes reports an error, and they run until the environment is removed.
+    fn exec_script(
+        &self,
+        environment: &Self::Environment,
+        job_name: &str,
+        script: &ScriptCommand,
+        env_vars: &[String],
+        cancel_rx: &mut mpsc::Receiver<()>,
+    ) -> impl Future<Output = Result<i64, Self::Error>> + Send;
+
+    /// Releases everything started during the run. Called once, after the last job.
+    fn cleanup(&self) -> impl Future<Output = ()> + Send;
+}
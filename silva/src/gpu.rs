@@ -0,0 +1,283 @@
+//! GPU runtime detection and device reservation.
+//!
+//! Jobs with an explicit `gpu` policy in `[resources]` get their own GPUs: before
+//! a job starts, the runner reserves free devices from a `GpuAllocator` and hands
+//! the backend their IDs instead of passing every GPU through. A reservation is a
+//! lock file per device in `$SILVA_GPU_LOCK_DIR` (default `<temp dir>/silva-gpu-locks`),
+//! so concurrent jobs of one run and jobs of other silva processes on the same host
+//! never share a card. Lock files of processes that are gone are reclaimed.
+
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+
+use job_config::job::{GpuPolicy, Gpus, Resources};
+
+/// Detected GPU runtime on the host.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GpuRuntime {
+    /// NVIDIA GPU available (via NVIDIA Container Toolkit runtime).
+    Nvidia,
+    /// AMD GPU available (via ROCm, /dev/kfd + /dev/dri).
+    Rocm,
+    /// No GPU runtime detected on the host.
+    None,
+}
+
+/// Decides whether a job with `resources` runs with GPUs on a host with `runtime`.
+///
+/// Returns `None` when the job has no GPU policy, leaving the decision to the
+/// backend's image detection.
+pub fn uses_gpu(resources: &Resources, runtime: GpuRuntime) -> Option<bool> {
+    match resources.gpu_policy()? {
+        GpuPolicy::Required | GpuPolicy::Optional => Some(runtime != GpuRuntime::None),
+        GpuPolicy::None => Some(false),
+    }
+}
+
+/// Hands out distinct GPUs to concurrently running jobs.
+#[derive(Debug, Clone)]
+pub struct GpuAllocator {
+    devices: Vec<String>,
+    lock_dir: PathBuf,
+}
+
+impl GpuAllocator {
+    /// Creates an allocator for `devices`, keeping its lock files in `lock_dir`.
+    pub fn new(devices: Vec<String>, lock_dir: PathBuf) -> Self {
+        Self { devices, lock_dir }
+    }
+
+    /// Creates an allocator for the NVIDIA GPUs listed by `nvidia-smi`, with lock
+    /// files in `$SILVA_GPU_LOCK_DIR`. Without `nvidia-smi` no device is known.
+    pub fn detect() -> Self {
+        let devices = std::process::Command::new("nvidia-smi")
+            .args(["--query-gpu=index", "--format=csv,noheader"])
+            .stderr(std::process::Stdio::null())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .map(|line| line.trim().to_string())
+                    .filter(|line| !line.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let lock_dir = std::env::var_os(crate::SILVA_GPU_LOCK_DIR)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| std::env::temp_dir().join("silva-gpu-locks"));
+        Self::new(devices, lock_dir)
+    }
+
+    /// IDs of the GPUs the allocator hands out.
+    pub fn devices(&self) -> &[String] {
+        &self.devices
+    }
+
+    /// Reserves the GPUs `request` asks for, or a single GPU when it is unset.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(lease))` - The GPUs are reserved until the lease is dropped
+    /// * `Ok(None)` - Some of the GPUs are reserved by other jobs; try again later
+    /// * `Err(message)` - The request can never be met on this host
+    pub fn try_acquire(&self, request: Option<&Gpus>) -> Result<Option<GpuLease>, String> {
+        let wanted: Vec<&String> = match request.unwrap_or(&Gpus::Count(1)) {
+            Gpus::All => self.devices.iter().collect(),
+            Gpus::Count(count) => {
+                if *count as usize > self.devices.len() {
+                    return Err(format!(
+                        "{count} GPU(s) requested, but the host has {}",
+                        self.devices.len()
+                    ));
+                }
+                // Any free devices will do
+                let mut lease = GpuLease::default();
+                for device in &self.devices {
+                    if lease.device_ids.len() == *count as usize {
+                        break;
+                    }
+                    if let Some(lock) = self.lock(device)? {
+                        lease.add(device, lock);
+                    }
+                }
+                return Ok((lease.device_ids.len() == *count as usize).then_some(lease));
+            }
+            Gpus::Devices(ids) => {
+                if let Some(unknown) = ids.iter().find(|id| !self.devices.contains(id)) {
+                    return Err(format!(
+                        "GPU '{unknown}' requested, but the host has GPUs {}",
+                        self.devices.join(",")
+                    ));
+                }
+                ids.iter().collect()
+            }
+        };
+
+        let mut lease = GpuLease::default();
+        for device in wanted {
+            match self.lock(device)? {
+                Some(lock) => lease.add(device, lock),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(lease))
+    }
+
+    /// Creates the lock file of `device`. Returns `None` if another live process holds it.
+    fn lock(&self, device: &str) -> Result<Option<PathBuf>, String> {
+        fs::create_dir_all(&self.lock_dir).map_err(|e| {
+            format!(
+                "Failed to create GPU lock folder {}: {e}",
+                self.lock_dir.display()
+            )
+        })?;
+        let path = self.lock_dir.join(format!("gpu-{device}.lock"));
+        // Retry once after reclaiming a lock left behind by a dead process
+        for _ in 0..2 {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    let _ = write!(file, "{}", std::process::id());
+                    return Ok(Some(path));
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    if !is_stale(&path) {
+                        return Ok(None);
+                    }
+                    let _ = fs::remove_file(&path);
+                }
+                Err(e) => {
+                    return Err(format!("Failed to create GPU lock {}: {e}", path.display()));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Returns `true` if the lock file at `path` names a process that no longer runs.
+fn is_stale(path: &std::path::Path) -> bool {
+    let Some(pid) = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| content.trim().parse::<u32>().ok())
+    else {
+        // Being written right now, or not a lock this version wrote
+        return false;
+    };
+    if cfg!(target_os = "linux") {
+        !std::path::Path::new(&format!("/proc/{pid}")).exists()
+    } else {
+        false
+    }
+}
+
+/// GPUs reserved for one job. The reservation ends when the lease is dropped.
+#[derive(Debug, Default)]
+pub struct GpuLease {
+    device_ids: Vec<String>,
+    locks: Vec<PathBuf>,
+}
+
+impl GpuLease {
+    fn add(&mut self, device: &str, lock: PathBuf) {
+        self.device_ids.push(device.to_string());
+        self.locks.push(lock);
+    }
+
+    /// IDs of the reserved GPUs.
+    pub fn device_ids(&self) -> &[String] {
+        &self.device_ids
+    }
+}
+
+impl Drop for GpuLease {
+    fn drop(&mut self) {
+        for lock in &self.locks {
+            let _ = fs::remove_file(lock);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allocator(lock_dir: &std::path::Path, devices: &[&str]) -> GpuAllocator {
+        GpuAllocator::new(
+            devices.iter().map(|d| d.to_string()).collect(),
+            lock_dir.to_path_buf(),
+        )
+    }
+
+    #[test]
+    fn test_concurrent_leases_get_distinct_devices() {
+        let tmp = tempfile::tempdir().unwrap();
+        let gpus = allocator(tmp.path(), &["0", "1", "2"]);
+
+        let first = gpus.try_acquire(None).unwrap().unwrap();
+        let second = gpus.try_acquire(Some(&Gpus::Count(2))).unwrap().unwrap();
+        assert_eq!(first.device_ids(), ["0"]);
+        assert_eq!(second.device_ids(), ["1", "2"]);
+
+        // Every device is taken until a lease is dropped
+        assert!(gpus.try_acquire(None).unwrap().is_none());
+        let devices = Gpus::Devices(vec!["1".to_string()]);
+        assert!(gpus.try_acquire(Some(&devices)).unwrap().is_none());
+        drop(second);
+        let third = gpus.try_acquire(Some(&devices)).unwrap().unwrap();
+        assert_eq!(third.device_ids(), ["1"]);
+
+        // Another allocator sharing the lock folder, e.g. another silva process
+        let other = allocator(tmp.path(), &["0", "1", "2"]);
+        assert_eq!(
+            other.try_acquire(None).unwrap().unwrap().device_ids(),
+            ["2"]
+        );
+    }
+
+    #[test]
+    fn test_impossible_requests_are_errors() {
+        let tmp = tempfile::tempdir().unwrap();
+        let gpus = allocator(tmp.path(), &["0", "1"]);
+        assert!(gpus.try_acquire(Some(&Gpus::Count(3))).is_err());
+        let unknown = Gpus::Devices(vec!["7".to_string()]);
+        assert!(gpus.try_acquire(Some(&unknown)).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_locks_of_dead_processes_are_reclaimed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let gpus = allocator(tmp.path(), &["0"]);
+        // No process has this pid: pid_max is at most 2^22 on Linux
+        fs::write(tmp.path().join("gpu-0.lock"), "4194305").unwrap();
+        assert!(gpus.try_acquire(None).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_uses_gpu_follows_policy() {
+        let resources = |gpu| Resources {
+            gpu,
+            ..Default::default()
+        };
+        assert_eq!(uses_gpu(&resources(None), GpuRuntime::Nvidia), None);
+        assert_eq!(
+            uses_gpu(&resources(Some(GpuPolicy::Optional)), GpuRuntime::None),
+            Some(false)
+        );
+        assert_eq!(
+            uses_gpu(&resources(Some(GpuPolicy::Required)), GpuRuntime::Rocm),
+            Some(true)
+        );
+        assert_eq!(
+            uses_gpu(&resources(Some(GpuPolicy::None)), GpuRuntime::Nvidia),
+            Some(false)
+        );
+    }
+}
@@ -1,49 +1,237 @@
 //! Headless workflow execution mode.
 //!
 //! This module provides functionality to run workflows without the TUI,
-//! outputting logs directly to stdout/stderr.
+//! outputting logs directly to stdout/stderr, or as newline-delimited JSON
+//! events with `--output json` (see `Event`).
+//!
+//! Ctrl-C (SIGINT) or SIGTERM cancels the run: running jobs are stopped, the
+//! remaining jobs are skipped and silva exits with `CANCELLED_EXIT_CODE`. A second
+//! signal exits immediately.
 
-use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
-use globset::GlobSetBuilder;
-
-use tempfile::TempDir;
+use clap::ValueEnum;
+use serde::Serialize;
 use tokio::sync::mpsc;
 
+use crate::backend::Backend;
 use crate::components::docker::{
-    executor::DockerExecutor,
     job::JobStatus,
     logs::{LogLine, LogSource},
 };
-use crate::components::workflow::{JobFolder, JobScanner, WorkflowFolder};
-use crate::utils::copy_dir_recursive;
-use job_config::job::JobMeta;
+use crate::components::workflow::{JobScanner, WorkflowFolder};
+use crate::runner::{Message, PreparedWorkflow, RunOptions};
+
+/// Exit status of a run cancelled by SIGINT or SIGTERM (128 + SIGINT).
+pub const CANCELLED_EXIT_CODE: i32 = 130;
+
+/// Why a headless run did not succeed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunError {
+    /// The workflow could not be prepared, or a job failed
+    Failed(String),
+    /// The run was stopped by SIGINT or SIGTERM
+    Cancelled,
+}
+
+impl RunError {
+    /// Returns silva's exit status for this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunError::Failed(_) => 1,
+            RunError::Cancelled => CANCELLED_EXIT_CODE,
+        }
+    }
+}
+
+impl std::fmt::Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunError::Failed(message) => write!(f, "{message}"),
+            RunError::Cancelled => write!(f, "Workflow cancelled"),
+        }
+    }
+}
+
+impl From<String> for RunError {
+    fn from(message: String) -> Self {
+        RunError::Failed(message)
+    }
+}
+
+/// Format of the headless output, from `--output`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable log lines
+    #[default]
+    Text,
+    /// One JSON event per line on stdout
+    Json,
+}
+
+/// Event of the `--output json` stream, written as one JSON object per line.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// The workflow passed its pre-checks and is about to run
+    RunStarted {
+        workflow: &'a str,
+        temp_folder: &'a Path,
+        backend: &'a str,
+        resume: bool,
+        jobs: Vec<&'a str>,
+    },
+    /// A job's status changed; `container_id` is only set for `container_running`
+    JobStatus {
+        job: &'a str,
+        status: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        container_id: Option<&'a str>,
+    },
+    /// A log line; `job` is `None` for messages about the run as a whole
+    Log {
+        job: Option<&'a str>,
+        source: LogSource,
+        /// RFC 3339, UTC
+        timestamp: String,
+        message: &'a str,
+    },
+    /// A completed job's `outputs/` folder and the files in it
+    OutputsCollected {
+        job: &'a str,
+        folder: PathBuf,
+        files: Vec<String>,
+    },
+    /// The run ended; `exit_code` is silva's exit status
+    RunFinished {
+        success: bool,
+        exit_code: i32,
+        output_folder: Option<&'a Path>,
+        error: Option<&'a str>,
+    },
+}
+
+/// Writes the progress of a headless run in the selected format.
+struct Reporter {
+    format: OutputFormat,
+}
+
+impl Reporter {
+    fn emit(&self, event: &Event) {
+        if let Ok(line) = serde_json::to_string(event) {
+            println!("{line}");
+        }
+    }
+
+    /// Prints an informational line; JSON output has events for everything it needs.
+    fn info(&self, line: &str) {
+        if self.format == OutputFormat::Text {
+            println!("{line}");
+        }
+    }
+
+    fn log(&self, job: Option<&str>, log_line: &LogLine) {
+        if !log_line.content.is_empty() {
+            self.emit(&Event::Log {
+                job,
+                source: log_line.source,
+                timestamp: log_line.timestamp.to_rfc3339(),
+                message: &log_line.content,
+            });
+        }
+    }
+
+    /// Reports the start of the run, followed by the notes from preparing it.
+    fn run_started(&self, workflow: &PreparedWorkflow, backend: Backend, resume: bool) {
+        if self.format == OutputFormat::Json {
+            self.emit(&Event::RunStarted {
+                workflow: &workflow.metadata.name,
+                temp_folder: &workflow.temp_workflow_path,
+                backend: backend.as_str(),
+                resume,
+                jobs: workflow.jobs.iter().map(|job| job.name.as_str()).collect(),
+            });
+            for note in &workflow.notes {
+                self.log(None, note);
+            }
+        }
+    }
+
+    /// Reports the outputs of a completed job, found in `@complete/<job>/outputs`.
+    fn outputs_collected(&self, temp_path: &Path, job_name: &str) {
+        if self.format != OutputFormat::Json {
+            return;
+        }
+        let folder = temp_path.join("@complete").join(job_name).join("outputs");
+        let files = crate::utils::list_files(&folder)
+            .map(|files| {
+                files
+                    .iter()
+                    .map(|file| file.to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.emit(&Event::OutputsCollected {
+            job: job_name,
+            folder,
+            files,
+        });
+    }
+
+    fn run_finished(&self, result: &Result<(), RunError>, temp_path: Option<&Path>) {
+        if self.format == OutputFormat::Json {
+            let error = result.as_ref().err().map(RunError::to_string);
+            self.emit(&Event::RunFinished {
+                success: result.is_ok(),
+                exit_code: result.as_ref().err().map_or(0, RunError::exit_code),
+                output_folder: temp_path,
+                error: error.as_deref(),
+            });
+        }
+    }
+}
 
 /// Runs a workflow in headless mode, outputting logs to stdout/stderr.
 ///
 /// # Arguments
 ///
 /// * `workflow_path` - Path to the workflow directory
-/// * `cli_env_vars` - `KEY=VALUE` strings from `-e/--env`, injected unprefixed into
-///   every job's container exec environment, independent of `env_passthrough`
+/// * `options` - `-e/--env`, `-j/--jobs`, `--backend` and `--resume` values for this run
+/// * `format` - Text or JSON event output, from `--output`
 ///
 /// # Returns
 ///
 /// * `Ok(())` - Workflow completed successfully
-/// * `Err(String)` - Error message if workflow failed
-pub async fn run_workflow(workflow_path: &Path, cli_env_vars: &[String]) -> Result<(), String> {
+/// * `Err(RunError)` - The workflow failed or was cancelled
+pub async fn run_workflow(
+    workflow_path: &Path,
+    options: &RunOptions,
+    format: OutputFormat,
+) -> Result<(), RunError> {
+    let reporter = Reporter { format };
+    let mut temp_path = None;
+    let result = run(workflow_path, options, &reporter, &mut temp_path).await;
+    reporter.run_finished(&result, temp_path.as_deref());
+    result
+}
+
+async fn run(
+    workflow_path: &Path,
+    options: &RunOptions,
+    reporter: &Reporter,
+    kept_temp_path: &mut Option<PathBuf>,
+) -> Result<(), RunError> {
     // Validate workflow path
     let workflow_path = workflow_path
         .canonicalize()
         .map_err(|e| format!("Invalid workflow path: {e}"))?;
 
     if !workflow_path.is_dir() {
-        return Err(format!(
+        return Err(RunError::Failed(format!(
             "Workflow path is not a directory: {}",
             workflow_path.display()
-        ));
+        )));
     }
 
     // Create WorkflowFolder
@@ -59,251 +247,144 @@ pub async fn run_workflow(workflow_path: &Path, cli_env_vars: &[String]) -> Resu
         Some(SystemTime::now()),
     );
 
-    // Create temp workflow folder
-    let temp_workflow_dir = create_temp_workflow_folder(&workflow_path)
-        .map_err(|e| format!("Failed to create temp workflow: {e}"))?;
-    let temp_workflow_path = temp_workflow_dir.path().to_path_buf();
+    // Create temp workflow folder, or reuse the folder of the run to resume
+    let (temp_workflow_dir, temp_workflow_path) = match &options.resume {
+        Some(resume_path) => {
+            let resume_path = resume_path
+                .canonicalize()
+                .map_err(|e| format!("Invalid resume folder: {e}"))?;
+            (None, resume_path)
+        }
+        None => {
+            let temp_workflow_dir = crate::runner::create_temp_workflow_folder(&workflow_path)
+                .map_err(|e| format!("Failed to create temp workflow: {e}"))?;
+            let temp_workflow_path = temp_workflow_dir.path().to_path_buf();
+            (Some(temp_workflow_dir), temp_workflow_path)
+        }
+    };
 
-    let docker_socket =
-        std::env::var("DOCKER_HOST").unwrap_or_else(|_| "unix:///var/run/docker.sock".to_string());
-    println!("Docker socket: {docker_socket}");
-    println!("Running workflow: {workflow_name}");
-    println!("Temp folder: {}", temp_workflow_path.display());
+    reporter.info(&format!("Running workflow: {workflow_name}"));
+    if options.resume.is_some() {
+        reporter.info(&format!("Resuming in: {}", temp_workflow_path.display()));
+    } else {
+        reporter.info(&format!("Temp folder: {}", temp_workflow_path.display()));
+    }
 
-    // Scan for jobs in temp folder
-    let jobs = JobScanner::scan_jobs(&temp_workflow_path)
-        .map_err(|e| format!("Failed to scan jobs: {e}"))?;
+    // Scan for jobs in temp folder; a resumed run has moved completed jobs to
+    // @complete/, so its jobs are scanned in the original workflow instead
+    let scan_path = if options.resume.is_some() {
+        &workflow_path
+    } else {
+        &temp_workflow_path
+    };
+    let jobs = JobScanner::scan_jobs(scan_path).map_err(|e| format!("Failed to scan jobs: {e}"))?;
 
     if jobs.is_empty() {
-        return Err("No jobs found in workflow".to_string());
+        return Err(RunError::Failed("No jobs found in workflow".to_string()));
     }
 
-    println!("Found {} job(s)", jobs.len());
-
-    // Load workflow metadata (dependencies are managed here, not in job.toml)
-    let workflow_metadata = workflow_folder
-        .load_workflow_metadata()
-        .ok()
-        .flatten()
-        .unwrap_or_else(|| {
-            job_config::workflow::WorkflowMeta::new(workflow_name.clone(), String::new())
-        });
-
-    // Load workflow parameters
-    let workflow_params = workflow_folder
-        .load_workflow_params()
-        .ok()
-        .flatten()
-        .unwrap_or_default();
-
-    if !workflow_params.is_empty() {
-        println!(
-            "Loaded {} global workflow parameter(s)",
-            workflow_params.len()
-        );
+    reporter.info(&format!("Found {} job(s)", jobs.len()));
+
+    let workflow = if options.resume.is_some() {
+        crate::runner::prepare_resume(
+            &workflow_folder,
+            &temp_workflow_path,
+            &jobs,
+            &options.params,
+        )?
+    } else {
+        crate::runner::prepare_workflow(
+            &workflow_folder,
+            &temp_workflow_path,
+            &jobs,
+            &options.params,
+        )?
+    };
+    if reporter.format == OutputFormat::Text {
+        for note in &workflow.notes {
+            print_log_line(note);
+        }
     }
 
-    // Sort jobs in dependency order
-    let sorted_jobs = topological_sort_jobs(&jobs, &workflow_metadata)?;
-
-    println!(
-        "Execution order: {}",
-        sorted_jobs
-            .iter()
-            .map(|j| j.name.as_str())
-            .collect::<Vec<_>>()
-            .join(" -> ")
-    );
+    let mut options = options.clone();
+    options.backend = crate::backend::resolve_backend(options.backend, &workflow);
+    reporter.info(&format!("Backend: {}", options.backend.as_str()));
+    if options.backend == Backend::Docker {
+        let docker_socket = std::env::var("DOCKER_HOST")
+            .unwrap_or_else(|_| "unix:///var/run/docker.sock".to_string());
+        reporter.info(&format!("Docker socket: {docker_socket}"));
+    }
 
-    // Pre-checks: reject workflows that violate conventions
-    crate::precheck::check_install_commands(&sorted_jobs)?;
-    crate::precheck::check_cross_node_references(&sorted_jobs)?;
-    crate::precheck::check_input_files_folder(&workflow_path, &sorted_jobs, &workflow_metadata)?;
-
-    // Copy input_files to all jobs without dependencies
-    copy_input_files_to_dependency_free_jobs(
-        &workflow_path,
-        &temp_workflow_path,
-        &sorted_jobs,
-        &workflow_metadata,
-    );
+    let max_parallel =
+        crate::scheduler::resolve_max_parallel(options.max_parallel, &workflow.metadata);
+    if max_parallel > 1 {
+        reporter.info(&format!(
+            "Running up to {max_parallel} independent jobs in parallel"
+        ));
+    }
 
-    println!();
+    reporter.info("");
+    reporter.run_started(&workflow, options.backend, options.resume.is_some());
 
     // Create message channel for logs
-    let (tx, mut rx) = mpsc::channel::<(usize, JobStatus, LogLine)>(32);
-    let (_cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
-
-    // Create job name to index map
-    let job_name_to_idx: HashMap<String, usize> = jobs
-        .iter()
-        .enumerate()
-        .map(|(idx, job)| (job.name.clone(), idx))
-        .collect();
-
-    let jobs_len = jobs.len();
-    let sorted_jobs_clone = sorted_jobs.clone();
-    let temp_workflow_path_clone = temp_workflow_path.clone();
-    let cli_env_vars = cli_env_vars.to_vec();
+    let (tx, mut rx) = mpsc::channel::<Message>(32);
+    let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+    let jobs = workflow.jobs.clone();
 
     // Spawn workflow execution task
     let exec_handle = tokio::spawn(async move {
-        let mut docker_executor = match DockerExecutor::new(tx.clone()) {
-            Ok(executor) => executor,
-            Err(e) => {
-                let log_line = LogLine::new(
-                    LogSource::Stderr,
-                    format!("Failed to create Docker executor: {e}"),
-                );
-                let _ = tx.send((0, JobStatus::Failed, log_line)).await;
-                let _ = tx
-                    .send((jobs_len, JobStatus::Failed, LogLine::empty()))
-                    .await;
-                return Err(format!("Docker initialization failed: {e}"));
-            }
-        };
-        docker_executor.detect_host_gpu().await;
-
-        let mut container_registry: HashMap<String, String> = HashMap::new();
-        let mut workflow_failed = false;
-
-        for job in sorted_jobs_clone.iter() {
-            let idx = *job_name_to_idx.get(&job.name).unwrap();
-
-            match job.load_meta() {
-                Ok(config) => {
-                    docker_executor.set_job_idx(idx);
-
-                    let job_params = job
-                        .load_params()
-                        .ok()
-                        .flatten()
-                        .unwrap_or_else(|| config.generate_default_params());
-
-                    // Copy input files from dependencies before running
-                    let job_deps = workflow_metadata.get_job_dependencies(&job.name);
-                    if let Err(e) = copy_input_files_from_dependencies(
-                        &temp_workflow_path_clone,
-                        job,
-                        &sorted_jobs_clone,
-                        &config,
-                        job_deps,
-                    ) {
-                        let log_line = LogLine::new(
-                            LogSource::Stderr,
-                            format!("Warning: Failed to copy input files: {e}"),
-                        );
-                        let _ = tx.send((idx, JobStatus::Running, log_line)).await;
-                    }
-
-                    // If this job runs with RUN_MODE=use_dok, prepare and inject the
-                    // presigned bundle URLs run_dok.sh needs (script dir + the inputs/
-                    // dir copy_input_files_from_dependencies just populated above).
-                    let mut job_env_vars = cli_env_vars.clone();
-                    let run_mode = crate::infra::dok::resolve_run_mode(
-                        &cli_env_vars,
-                        workflow_metadata.env_passthrough.as_deref().unwrap_or(&[]),
-                    );
-                    if run_mode.as_deref() == Some("use_dok") {
-                        match crate::infra::dok::prepare_bundle_env_vars(&job.path).await {
-                            Ok(extra) => job_env_vars.extend(extra),
-                            Err(e) => {
-                                let log_line = LogLine::new(
-                                    LogSource::Stderr,
-                                    format!("DOK bundle preparation failed: {e}"),
-                                );
-                                let _ = tx.send((idx, JobStatus::Failed, log_line)).await;
-                                workflow_failed = true;
-                                break;
-                            }
-                        }
-                    }
-
-                    match docker_executor
-                        .run_job(
-                            (
-                                &workflow_metadata,
-                                &temp_workflow_path_clone,
-                                &workflow_params,
-                            ),
-                            (job, &config, &job_params),
-                            &job_env_vars,
-                            &mut container_registry,
-                            &mut cancel_rx,
-                        )
-                        .await
-                    {
-                        Ok(_container_id) => {
-                            // Move completed job to @complete/ to prevent cross-node path access
-                            if let Err(e) =
-                                move_job_to_complete(&temp_workflow_path_clone, &job.name)
-                            {
-                                let log_line = LogLine::new(
-                                    LogSource::Stderr,
-                                    format!(
-                                        "Warning: Failed to move '{}' to @complete: {e}",
-                                        job.name
-                                    ),
-                                );
-                                let _ = tx.send((idx, JobStatus::Running, log_line)).await;
-                            }
-                        }
-                        Err(e) => {
-                            let log_line = LogLine::new(
-                                LogSource::Stderr,
-                                format!("Job '{}' failed: {e}", job.name),
-                            );
-                            let _ = tx.send((idx, JobStatus::Failed, log_line)).await;
-                            workflow_failed = true;
-                            break;
-                        }
-                    }
-                }
-                Err(e) => {
-                    let log_line = LogLine::new(
-                        LogSource::Stderr,
-                        format!("Failed to load config for '{}': {e}", job.name),
-                    );
-                    let _ = tx.send((idx, JobStatus::Failed, log_line)).await;
-                    workflow_failed = true;
-                    break;
-                }
-            }
-        }
-
-        // Cleanup containers
-        let container_ids: Vec<String> = container_registry.values().cloned().collect();
-        docker_executor.cleanup_containers(&container_ids).await;
-
-        let final_status = if workflow_failed {
-            JobStatus::Failed
-        } else {
-            JobStatus::Completed
-        };
-        let _ = tx.send((jobs_len, final_status, LogLine::empty())).await;
+        crate::backend::execute(workflow, &options, tx, &mut cancel_rx).await
+    });
 
-        if workflow_failed {
-            Err("Workflow failed".to_string())
-        } else {
-            Ok(())
-        }
+    // Cancel the run on the first SIGINT/SIGTERM, exit on the second
+    let signal_handle = tokio::spawn(async move {
+        shutdown_signal().await;
+        eprintln!("Cancelling workflow, press Ctrl-C again to exit immediately");
+        let _ = cancel_tx.send(()).await;
+        shutdown_signal().await;
+        std::process::exit(CANCELLED_EXIT_CODE);
     });
 
     // Process log messages and output to stdout/stderr
     let mut current_job: Option<String> = None;
+    let mut statuses = vec![JobStatus::Idle; jobs.len()];
     let mut workflow_result = Ok(());
 
     while let Some((idx, status, log_line)) = rx.recv().await {
         // Check if workflow is complete
         if idx == jobs.len() {
-            if status == JobStatus::Failed {
-                workflow_result = Err("Workflow failed".to_string());
+            match status {
+                JobStatus::Failed => {
+                    workflow_result = Err(RunError::Failed("Workflow failed".to_string()))
+                }
+                JobStatus::Cancelled => workflow_result = Err(RunError::Cancelled),
+                _ => {}
             }
             break;
         }
 
         // Get job name
         let job_name = jobs.get(idx).map(|j| j.name.as_str()).unwrap_or("unknown");
+        if status == JobStatus::Failed {
+            workflow_result = Err(RunError::Failed(format!("Job '{job_name}' failed")));
+        }
+
+        if reporter.format == OutputFormat::Json {
+            let status_changed = statuses.get(idx).is_some_and(|last| *last != status);
+            if status_changed {
+                statuses[idx] = status.clone();
+                reporter.emit(&Event::JobStatus {
+                    job: job_name,
+                    status: status.key(),
+                    container_id: status.container_id(),
+                });
+            }
+            reporter.log(Some(job_name), &log_line);
+            if status_changed && status == JobStatus::Completed {
+                reporter.outputs_collected(&temp_workflow_path, job_name);
+            }
+            continue;
+        }
 
         // Print job header when switching jobs
         if current_job.as_deref() != Some(job_name) {
@@ -315,27 +396,34 @@ pub async fn run_workflow(workflow_path: &Path, cli_env_vars: &[String]) -> Resu
         }
 
         // Print log line
-        if !log_line.content.is_empty() {
-            match log_line.source {
-                LogSource::Stdout => println!("{}", log_line.content),
-                LogSource::Stderr => eprintln!("{}", log_line.content),
-            }
-        }
+        print_log_line(&log_line);
 
         // Print status changes
         if status == JobStatus::Completed {
             println!("[{job_name}] Completed");
         } else if status == JobStatus::Failed {
             eprintln!("[{job_name}] Failed");
-            workflow_result = Err(format!("Job '{job_name}' failed"));
+        } else if status == JobStatus::Cancelled {
+            eprintln!("[{job_name}] Cancelled");
+        } else if status == JobStatus::Skipped {
+            println!("[{job_name}] Skipped");
         }
     }
 
     // Wait for execution to finish
     let _ = exec_handle.await;
+    signal_handle.abort();
 
     // Keep the temp folder for user inspection
-    let temp_path = temp_workflow_dir.keep();
+    let temp_path = match temp_workflow_dir {
+        Some(temp_workflow_dir) => temp_workflow_dir.keep(),
+        None => temp_workflow_path,
+    };
+    *kept_temp_path = Some(temp_path.clone());
+
+    if reporter.format == OutputFormat::Json {
+        return workflow_result;
+    }
 
     println!();
     match &workflow_result {
@@ -345,351 +433,66 @@ pub async fn run_workflow(workflow_path: &Path, cli_env_vars: &[String]) -> Resu
             println!("Output folder: {}", temp_path.display());
             println!("  (This folder will persist until you delete it manually)");
         }
+        Err(RunError::Cancelled) => {
+            eprintln!("Workflow cancelled");
+            println!();
+            println!("Working folder: {}", temp_path.display());
+            println!(
+                "  (Resume with: silva {} --resume {})",
+                workflow_path.display(),
+                temp_path.display()
+            );
+        }
         Err(e) => {
             eprintln!("Workflow failed: {e}");
             println!();
             println!("Working folder: {}", temp_path.display());
             println!("  (You can inspect this folder to debug the issue)");
+            println!(
+                "  (Resume with: silva {} --resume {})",
+                workflow_path.display(),
+                temp_path.display()
+            );
         }
     }
 
     workflow_result
 }
 
-/// Performs topological sort on jobs based on their dependencies.
-fn topological_sort_jobs(
-    jobs: &[JobFolder],
-    workflow_metadata: &job_config::workflow::WorkflowMeta,
-) -> Result<Vec<JobFolder>, String> {
-    use std::collections::VecDeque;
-
-    if jobs.is_empty() {
-        return Ok(Vec::new());
-    }
-
-    // Build a map of job names to job data
-    let job_map: HashMap<String, JobFolder> =
-        jobs.iter().map(|j| (j.name.clone(), j.clone())).collect();
-
-    // Build dependency graph
-    let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
-    let mut in_degree: HashMap<String, usize> = HashMap::new();
-
-    // Initialize
-    for job in jobs {
-        in_degree.insert(job.name.clone(), 0);
-        dependents.entry(job.name.clone()).or_default();
-    }
-
-    // Build the graph
-    for job in jobs {
-        let job_deps = workflow_metadata.get_job_dependencies(&job.name);
-
-        for dep_name in job_deps {
-            if !job_map.contains_key(dep_name) {
-                return Err(format!(
-                    "Job '{}' depends on '{}', but '{}' does not exist",
-                    job.name, dep_name, dep_name
-                ));
-            }
-
-            dependents
-                .entry(dep_name.clone())
-                .or_default()
-                .push(job.name.clone());
-
-            *in_degree.get_mut(&job.name).unwrap() += 1;
-        }
-    }
-
-    // Kahn's algorithm
-    let mut queue: VecDeque<String> = in_degree
-        .iter()
-        .filter(|&(_, &degree)| degree == 0)
-        .map(|(name, _)| name.clone())
-        .collect();
-
-    let mut sorted_jobs = Vec::new();
-
-    while let Some(job_name) = queue.pop_front() {
-        if let Some(job) = job_map.get(&job_name) {
-            sorted_jobs.push(job.clone());
+/// Resolves on Ctrl-C (SIGINT) or, on Unix, SIGTERM.
+///
+/// Never resolves if the signal handlers cannot be installed.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
         }
+    };
 
-        if let Some(deps) = dependents.get(&job_name) {
-            for dependent in deps {
-                let degree = in_degree.get_mut(dependent).unwrap();
-                *degree -= 1;
-                if *degree == 0 {
-                    queue.push_back(dependent.clone());
-                }
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
             }
+            Err(_) => std::future::pending::<()>().await,
         }
-    }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-    if sorted_jobs.len() != jobs.len() {
-        let processed: std::collections::HashSet<_> = sorted_jobs.iter().map(|j| &j.name).collect();
-        let unprocessed: Vec<_> = jobs
-            .iter()
-            .filter(|j| !processed.contains(&j.name))
-            .map(|j| j.name.as_str())
-            .collect();
-
-        return Err(format!(
-            "Circular dependency detected: {}",
-            unprocessed.join(", ")
-        ));
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
     }
-
-    Ok(sorted_jobs)
 }
 
-/// Copies input files from dependency jobs' outputs to the current job's inputs folder.
-fn copy_input_files_from_dependencies(
-    workflow_path: &Path,
-    current_job: &JobFolder,
-    all_jobs: &[JobFolder],
-    config: &JobMeta,
-    dependencies: &[String],
-) -> Result<usize, String> {
-    use std::collections::HashSet;
-    use std::fs;
-
-    if dependencies.is_empty() {
-        return Ok(0);
-    }
-
-    // Copy to inputs/ subfolder for clear separation
-    let inputs_dir = workflow_path.join(&current_job.name).join("inputs");
-    fs::create_dir_all(&inputs_dir).map_err(|e| format!("Failed to create inputs dir: {e}"))?;
-
-    let mut copied_files: HashSet<String> = HashSet::new();
-
-    for dep_job_name in dependencies {
-        // Find the dependency job
-        let dep_job = all_jobs.iter().find(|j| &j.name == dep_job_name);
-        if dep_job.is_none() {
-            println!("Warning: Dependency job '{dep_job_name}' not found");
-            continue;
-        }
-
-        // Look in @complete/ first (job already finished), fall back to original location
-        let complete_outputs_dir = workflow_path
-            .join("@complete")
-            .join(dep_job_name)
-            .join("outputs");
-        let dep_outputs_dir = if complete_outputs_dir.exists() {
-            complete_outputs_dir
-        } else {
-            workflow_path.join(dep_job_name).join("outputs")
-        };
-        if !dep_outputs_dir.exists() {
-            println!("No outputs found for dependency '{dep_job_name}', skipping");
-            continue;
-        }
-
-        // Determine which files to copy
-        let files_to_copy: Vec<std::path::PathBuf> = if config.inputs.is_empty() {
-            // Copy all files from outputs/
-            match fs::read_dir(&dep_outputs_dir) {
-                Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
-                Err(e) => {
-                    println!("Error reading outputs from '{dep_job_name}': {e}");
-                    continue;
-                }
-            }
-        } else {
-            // Copy only matching files based on input patterns
-            let mut builder = GlobSetBuilder::new();
-            for pattern in &config.inputs {
-                match globset::Glob::new(pattern) {
-                    Ok(g) => {
-                        builder.add(g);
-                    }
-                    Err(e) => println!("Invalid glob pattern '{pattern}': {e}"),
-                }
-            }
-            match builder.build() {
-                Ok(matcher) => match fs::read_dir(&dep_outputs_dir) {
-                    Ok(entries) => entries
-                        .filter_map(|e| e.ok())
-                        .map(|e| e.path())
-                        .filter(|p| p.file_name().map(|n| matcher.is_match(n)).unwrap_or(false))
-                        .collect(),
-                    Err(e) => {
-                        println!("Error reading outputs from '{dep_job_name}': {e}");
-                        continue;
-                    }
-                },
-                Err(e) => {
-                    println!("Failed to build glob matcher for '{dep_job_name}': {e}");
-                    continue;
-                }
-            }
-        };
-
-        // Copy files to inputs/ directory
-        for source_path in files_to_copy {
-            if let Some(filename) = source_path.file_name() {
-                let filename_str = filename.to_string_lossy().to_string();
-                let dest_path = inputs_dir.join(filename);
-
-                // Check for conflicts
-                if copied_files.contains(&filename_str) {
-                    println!(
-                        "Warning: File '{filename_str}' already copied, skipping from '{dep_job_name}'"
-                    );
-                    continue;
-                }
-
-                // Copy the file or directory
-                if source_path.is_file() {
-                    match fs::copy(&source_path, &dest_path) {
-                        Ok(_) => {
-                            copied_files.insert(filename_str.clone());
-                            println!("Copied '{filename_str}' from '{dep_job_name}'");
-                        }
-                        Err(e) => {
-                            println!("Error copying '{filename_str}': {e}");
-                        }
-                    }
-                } else if source_path.is_dir() {
-                    match copy_dir_recursive(&source_path, &dest_path) {
-                        Ok(count) => {
-                            copied_files.insert(filename_str.clone());
-                            println!(
-                                "Copied directory '{filename_str}/' ({count} files) from '{dep_job_name}'"
-                            );
-                        }
-                        Err(e) => {
-                            println!("Error copying directory '{filename_str}': {e}");
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    Ok(copied_files.len())
-}
-
-/// Creates a temporary folder and copies the workflow contents to it.
-fn create_temp_workflow_folder(source_path: &Path) -> std::io::Result<TempDir> {
-    let now = chrono::Local::now();
-    let timestamp = now.format("%Y-%m-%d-%H-%M-%S").to_string();
-    let prefix = format!("silva-{timestamp}-");
-    let temp_dir = tempfile::Builder::new().prefix(&prefix).tempdir()?;
-
-    // Copy source folder contents to temp directory
-    let mut options = fs_extra::dir::CopyOptions::new();
-    options.overwrite = true;
-    options.copy_inside = true;
-    options.content_only = true;
-
-    fs_extra::dir::copy(source_path, temp_dir.path(), &options)
-        .map_err(|e| std::io::Error::other(format!("copy folder error {e}")))?;
-
-    Ok(temp_dir)
-}
-
-/// Moves a completed job folder to `@complete/` to prevent cross-node path access.
-///
-/// After a job finishes, its folder is moved from the temp workflow root into
-/// `@complete/{job_name}/`. This ensures that subsequent jobs cannot use relative
-/// paths (e.g., `../01-data-prep/`) to access sibling node folders directly,
-/// forcing them to use the proper `inputs/` contract.
-fn move_job_to_complete(workflow_path: &Path, job_name: &str) -> Result<(), String> {
-    use std::fs;
-
-    let source = workflow_path.join(job_name);
-    let complete_dir = workflow_path.join("@complete");
-    let dest = complete_dir.join(job_name);
-
-    fs::create_dir_all(&complete_dir)
-        .map_err(|e| format!("Failed to create @complete directory: {e}"))?;
-
-    fs::rename(&source, &dest)
-        .map_err(|e| format!("Failed to move '{job_name}' to @complete: {e}"))?;
-
-    println!("[{job_name}] Moved to @complete/");
-    Ok(())
-}
-
-/// Copies files from the workflow's `input_files/` folder to all jobs without dependencies.
-///
-/// If the `input_files/` folder exists, all its contents are copied to each dependency-free
-/// job's `inputs/` subfolder. If the folder doesn't exist, a hint is printed.
-fn copy_input_files_to_dependency_free_jobs(
-    workflow_path: &Path,
-    temp_workflow_path: &Path,
-    sorted_jobs: &[JobFolder],
-    workflow_metadata: &job_config::workflow::WorkflowMeta,
-) {
-    use std::fs;
-
-    let input_files_path = workflow_path.join("input_files");
-
-    if !input_files_path.is_dir() {
-        println!("Hint: No 'input_files' folder found in workflow");
-        return;
-    }
-
-    // Find all jobs with no dependencies
-    let jobs_without_deps: Vec<&JobFolder> = sorted_jobs
-        .iter()
-        .filter(|job| workflow_metadata.get_job_dependencies(&job.name).is_empty())
-        .collect();
-
-    if jobs_without_deps.is_empty() {
-        println!("Warning: No jobs to copy input files to");
-        return;
-    }
-
-    // Copy input_files to each job without dependencies
-    for job in &jobs_without_deps {
-        // Copy to inputs/ subfolder for clear separation
-        let inputs_dir = temp_workflow_path.join(&job.name).join("inputs");
-        if let Err(e) = fs::create_dir_all(&inputs_dir) {
-            eprintln!("Error creating inputs folder for '{}': {e}", job.name);
-            continue;
-        }
-
-        // Read and copy all files from input_files/
-        let entries = match fs::read_dir(&input_files_path) {
-            Ok(entries) => entries,
-            Err(e) => {
-                eprintln!("Error reading input_files folder: {e}");
-                return;
-            }
-        };
-
-        let mut copied_count = 0;
-        for entry in entries.flatten() {
-            let source = entry.path();
-            let dest = inputs_dir.join(entry.file_name());
-
-            let result = if source.is_file() {
-                fs::copy(&source, &dest).map(|_| ())
-            } else if source.is_dir() {
-                copy_dir_recursive(&source, &dest).map(|_| ())
-            } else {
-                continue;
-            };
-
-            match result {
-                Ok(()) => copied_count += 1,
-                Err(e) => eprintln!(
-                    "Error copying '{}': {e}",
-                    entry.file_name().to_string_lossy()
-                ),
-            }
-        }
-
-        if copied_count > 0 {
-            println!(
-                "Copied {} item(s) from 'input_files/' to '{}/inputs/'",
-                copied_count, job.name
-            );
+/// Prints a non-empty log line to stdout or stderr, depending on its source.
+fn print_log_line(log_line: &LogLine) {
+    if !log_line.content.is_empty() {
+        match log_line.source {
+            LogSource::Stdout => println!("{}", log_line.content),
+            LogSource::Stderr => eprintln!("{}", log_line.content),
         }
     }
 }
@@ -697,151 +500,76 @@ fn copy_input_files_to_dependency_free_jobs(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use job_config::job::{Container, JobMeta, Scripts};
-    use std::collections::HashMap;
-    use std::fs;
-
-    fn make_job_meta(inputs: Vec<&str>) -> JobMeta {
-        JobMeta {
-            name: "test".to_string(),
-            description: String::new(),
-            container: Container::new("ubuntu:latest".to_string()),
-            scripts: Scripts {
-                pre: String::new(),
-                run: "run.sh".to_string(),
-                post: String::new(),
-            },
-            inputs: inputs.into_iter().map(String::from).collect(),
-            outputs: vec![],
-            params: HashMap::new(),
-        }
-    }
-
-    fn setup_dep_outputs(workflow: &Path, dep_name: &str, files: &[&str]) {
-        let outputs_dir = workflow.join(dep_name).join("outputs");
-        fs::create_dir_all(&outputs_dir).unwrap();
-        for f in files {
-            fs::write(outputs_dir.join(f), f).unwrap();
-        }
-    }
-
-    fn list_inputs(workflow: &Path, job_name: &str) -> Vec<String> {
-        let inputs_dir = workflow.join(job_name).join("inputs");
-        let mut names: Vec<String> = fs::read_dir(inputs_dir)
-            .unwrap()
-            .filter_map(|e| e.ok())
-            .map(|e| e.file_name().to_string_lossy().to_string())
-            .collect();
-        names.sort();
-        names
-    }
 
     #[test]
-    fn brace_pattern_copies_matching_extensions() {
-        let tmp = tempfile::tempdir().unwrap();
-        let wf = tmp.path();
-        setup_dep_outputs(
-            wf,
-            "01-produce",
-            &["seq.fasta", "ref.fa", "proteins.faa", "notes.txt"],
+    fn test_events_serialize_as_tagged_json() {
+        let status = JobStatus::PullingImage;
+        let event = Event::JobStatus {
+            job: "train",
+            status: status.key(),
+            container_id: status.container_id(),
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({"event": "job_status", "job": "train", "status": "pulling_image"})
         );
 
-        let producer = JobFolder::new("01-produce".to_string(), wf.join("01-produce"));
-        let consumer = JobFolder::new("02-consume".to_string(), wf.join("02-consume"));
-        let config = make_job_meta(vec!["*.{fasta,fa,faa}"]);
-
-        let n = copy_input_files_from_dependencies(
-            wf,
-            &consumer,
-            &[producer],
-            &config,
-            &["01-produce".to_string()],
-        )
-        .unwrap();
-
-        assert_eq!(n, 3);
+        let status = JobStatus::ContainerRunning("3f2a9c".to_string());
+        let event = Event::JobStatus {
+            job: "train",
+            status: status.key(),
+            container_id: status.container_id(),
+        };
         assert_eq!(
-            list_inputs(wf, "02-consume"),
-            vec!["proteins.faa", "ref.fa", "seq.fasta"]
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "event": "job_status",
+                "job": "train",
+                "status": "container_running",
+                "container_id": "3f2a9c"
+            })
         );
-    }
 
-    #[test]
-    fn brace_pattern_excludes_non_matching_files() {
-        let tmp = tempfile::tempdir().unwrap();
-        let wf = tmp.path();
-        setup_dep_outputs(wf, "01-produce", &["a.txt", "b.log", "c.fasta"]);
-
-        let producer = JobFolder::new("01-produce".to_string(), wf.join("01-produce"));
-        let consumer = JobFolder::new("02-consume".to_string(), wf.join("02-consume"));
-        let config = make_job_meta(vec!["*.{fasta,fa,faa}"]);
-
-        let n = copy_input_files_from_dependencies(
-            wf,
-            &consumer,
-            &[producer],
-            &config,
-            &["01-produce".to_string()],
-        )
-        .unwrap();
-
-        assert_eq!(n, 1);
-        assert_eq!(list_inputs(wf, "02-consume"), vec!["c.fasta"]);
+        let event = Event::RunFinished {
+            success: false,
+            exit_code: 1,
+            output_folder: None,
+            error: Some("Job 'train' failed"),
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "event": "run_finished",
+                "success": false,
+                "exit_code": 1,
+                "output_folder": null,
+                "error": "Job 'train' failed"
+            })
+        );
     }
 
     #[test]
-    fn plain_pattern_still_works() {
-        let tmp = tempfile::tempdir().unwrap();
-        let wf = tmp.path();
-        setup_dep_outputs(
-            wf,
-            "01-produce",
-            &["data.csv", "metadata.json", "other.txt"],
-        );
-
-        let producer = JobFolder::new("01-produce".to_string(), wf.join("01-produce"));
-        let consumer = JobFolder::new("02-consume".to_string(), wf.join("02-consume"));
-        let config = make_job_meta(vec!["*.csv", "*.json"]);
-
-        let n = copy_input_files_from_dependencies(
-            wf,
-            &consumer,
-            &[producer],
-            &config,
-            &["01-produce".to_string()],
-        )
-        .unwrap();
-
-        assert_eq!(n, 2);
-        assert_eq!(
-            list_inputs(wf, "02-consume"),
-            vec!["data.csv", "metadata.json"]
-        );
+    fn test_run_error_exit_codes() {
+        let failed = RunError::from("Job 'train' failed".to_string());
+        assert_eq!(failed.exit_code(), 1);
+        assert_eq!(failed.to_string(), "Job 'train' failed");
+        assert_eq!(RunError::Cancelled.exit_code(), CANCELLED_EXIT_CODE);
+        assert_eq!(RunError::Cancelled.to_string(), "Workflow cancelled");
     }
 
     #[test]
-    fn empty_inputs_copies_all_files() {
-        let tmp = tempfile::tempdir().unwrap();
-        let wf = tmp.path();
-        setup_dep_outputs(wf, "01-produce", &["a.txt", "b.csv", "c.fasta"]);
-
-        let producer = JobFolder::new("01-produce".to_string(), wf.join("01-produce"));
-        let consumer = JobFolder::new("02-consume".to_string(), wf.join("02-consume"));
-        let config = make_job_meta(vec![]);
-
-        let n = copy_input_files_from_dependencies(
-            wf,
-            &consumer,
-            &[producer],
-            &config,
-            &["01-produce".to_string()],
-        )
-        .unwrap();
-
-        assert_eq!(n, 3);
-        assert_eq!(
-            list_inputs(wf, "02-consume"),
-            vec!["a.txt", "b.csv", "c.fasta"]
-        );
+    fn test_log_event_includes_source_and_timestamp() {
+        let log_line = LogLine::new(LogSource::Stderr, "oops".to_string());
+        let event = Event::Log {
+            job: Some("train"),
+            source: log_line.source,
+            timestamp: log_line.timestamp.to_rfc3339(),
+            message: &log_line.content,
+        };
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["event"], "log");
+        assert_eq!(value["source"], "stderr");
+        assert_eq!(value["message"], "oops");
+        assert!(value["timestamp"].as_str().unwrap().contains('T'));
     }
 }
@@ -0,0 +1,485 @@
+//! Persistent history of workflow runs.
+//!
+//! Every run started through `backend::execute` is recorded under
+//! `$SILVA_WORKFLOW_HOME/.silva/runs/<run id>/`:
+//!
+//! - `run.json` — workflow name, start/end time, outcome, temp folder, the
+//!   global parameters and, per job, its status, exit code, timestamps and
+//!   merged parameters
+//! - `logs/<job>.log` — every log line of the job, with full timestamps
+//!
+//! The recorder sits between the executor and the UI: it reads the run's
+//! progress messages, writes them to disk and forwards them unchanged.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use job_config::params::{JobParams, WorkflowParams};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::components::docker::job::JobStatus;
+use crate::components::docker::logs::{LogLine, LogSource};
+use crate::components::workflow::WorkflowHome;
+use crate::runner::{Message, PreparedWorkflow, merge_params};
+
+/// Folder of the run history, relative to the workflow home.
+pub const RUNS_DIR: &str = ".silva/runs";
+
+/// Name of the run description inside each run folder.
+const RUN_FILE: &str = "run.json";
+
+/// Name of the folder holding one log file per job.
+const LOGS_DIR: &str = "logs";
+
+/// Outcome of a recorded run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunOutcome {
+    /// Still running, or silva exited before the run finished
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl RunOutcome {
+    pub fn as_str(&self) -> &str {
+        match self {
+            RunOutcome::Running => "Running",
+            RunOutcome::Succeeded => "Succeeded",
+            RunOutcome::Failed => "Failed",
+            RunOutcome::Cancelled => "Cancelled",
+        }
+    }
+}
+
+/// A recorded workflow run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Name of the run's folder in the history
+    pub id: String,
+    pub workflow: String,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub outcome: RunOutcome,
+    /// Temp folder the jobs ran in; completed jobs are in its `@complete/`
+    pub temp_folder: PathBuf,
+    /// Global parameters of the run
+    pub params: WorkflowParams,
+    /// Jobs in the order of the run's message indices
+    pub jobs: Vec<JobRecord>,
+}
+
+/// A job of a recorded run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub name: String,
+    /// Last status reported for the job
+    pub status: JobStatus,
+    /// Exit code of the failing script, or 0 once the job completed
+    pub exit_code: Option<i64>,
+    pub started_at: Option<String>,
+    pub ended_at: Option<String>,
+    /// Global parameters merged with the job's parameters
+    pub params: JobParams,
+}
+
+impl RunRecord {
+    /// Returns the folder the run's outputs were left in: `@complete/` if any
+    /// job completed, the temp folder otherwise.
+    pub fn output_folder(&self) -> PathBuf {
+        let complete = self.temp_folder.join("@complete");
+        if complete.is_dir() {
+            complete
+        } else {
+            self.temp_folder.clone()
+        }
+    }
+}
+
+/// Run history rooted at a folder on the host.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunHistory {
+    root: PathBuf,
+}
+
+impl RunHistory {
+    /// Creates a history rooted at `root`. Nothing is created until a run starts.
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Opens the history in the workflow home (`$SILVA_WORKFLOW_HOME/.silva/runs`).
+    pub fn open_default() -> Self {
+        let home = WorkflowHome::new()
+            .map(|home| home.path().to_path_buf())
+            .unwrap_or_else(|_| PathBuf::from("./home"));
+        Self::new(home.join(RUNS_DIR))
+    }
+
+    /// Returns the folder the history lives in.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Lists all recorded runs, newest first. Unreadable runs are skipped.
+    pub fn list(&self) -> Result<Vec<RunRecord>, String> {
+        if !self.root.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut runs = Vec::new();
+        for dir_entry in
+            fs::read_dir(&self.root).map_err(|e| format!("Failed to read run history: {e}"))?
+        {
+            let path = dir_entry
+                .map_err(|e| format!("Failed to read run history: {e}"))?
+                .path();
+            let Ok(content) = fs::read_to_string(path.join(RUN_FILE)) else {
+                continue;
+            };
+            if let Ok(run) = serde_json::from_str::<RunRecord>(&content) {
+                runs.push(run);
+            }
+        }
+        runs.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        Ok(runs)
+    }
+
+    /// Reads the full log of `job_name` in run `run_id`, one entry per line.
+    pub fn read_logs(&self, run_id: &str, job_name: &str) -> Result<Vec<String>, String> {
+        let path = self.log_path(run_id, job_name);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        fs::read_to_string(&path)
+            .map(|content| content.lines().map(str::to_string).collect())
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))
+    }
+
+    fn log_path(&self, run_id: &str, job_name: &str) -> PathBuf {
+        self.root
+            .join(run_id)
+            .join(LOGS_DIR)
+            .join(format!("{job_name}.log"))
+    }
+
+    /// Records the start of `workflow` and returns the recorder for its messages.
+    pub fn start(&self, workflow: &PreparedWorkflow) -> Result<RunRecorder, String> {
+        let now = chrono::Local::now();
+        let name: String = workflow
+            .metadata
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let base_id = format!("{}-{name}", now.format("%Y%m%d-%H%M%S"));
+        let mut id = base_id.clone();
+        let mut suffix = 1;
+        while self.root.join(&id).exists() {
+            suffix += 1;
+            id = format!("{base_id}-{suffix}");
+        }
+        let dir = self.root.join(&id);
+        fs::create_dir_all(dir.join(LOGS_DIR))
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+
+        let jobs = workflow
+            .jobs
+            .iter()
+            .map(|job| {
+                let job_params = job
+                    .load_meta()
+                    .map(|config| {
+                        job.load_params()
+                            .ok()
+                            .flatten()
+                            .unwrap_or_else(|| config.generate_default_params())
+                    })
+                    .unwrap_or_default();
+                JobRecord {
+                    name: job.name.clone(),
+                    status: JobStatus::Pending,
+                    exit_code: None,
+                    started_at: None,
+                    ended_at: None,
+                    params: merge_params(&workflow.params, &job_params),
+                }
+            })
+            .collect();
+        let record = RunRecord {
+            id,
+            workflow: workflow.metadata.name.clone(),
+            started_at: now.to_rfc3339(),
+            ended_at: None,
+            outcome: RunOutcome::Running,
+            temp_folder: workflow.temp_workflow_path.clone(),
+            params: workflow.params.clone(),
+            jobs,
+        };
+
+        let recorder = RunRecorder {
+            dir,
+            record,
+            log_files: HashMap::new(),
+        };
+        recorder.save()?;
+        Ok(recorder)
+    }
+}
+
+/// Writes the progress messages of one run to its history folder.
+#[derive(Debug)]
+pub struct RunRecorder {
+    dir: PathBuf,
+    record: RunRecord,
+    log_files: HashMap<usize, File>,
+}
+
+impl RunRecorder {
+    /// Returns the run as recorded so far.
+    pub fn record(&self) -> &RunRecord {
+        &self.record
+    }
+
+    /// Records one progress message; the end-of-run message finishes the run.
+    pub fn record_message(&mut self, (idx, status, log_line): &Message) {
+        if *idx == self.record.jobs.len() {
+            let outcome = match status {
+                JobStatus::Completed => RunOutcome::Succeeded,
+                JobStatus::Cancelled => RunOutcome::Cancelled,
+                _ => RunOutcome::Failed,
+            };
+            self.finish(outcome);
+            return;
+        }
+        let Some(job) = self.record.jobs.get_mut(*idx) else {
+            return;
+        };
+
+        let now = chrono::Local::now().to_rfc3339();
+        // Jobs skipped by a cancel or a `when` condition never started
+        if job.started_at.is_none()
+            && !matches!(
+                status,
+                JobStatus::Idle | JobStatus::Pending | JobStatus::Cancelled | JobStatus::Skipped
+            )
+        {
+            job.started_at = Some(now.clone());
+        }
+        let finished = status.is_finished() && job.status != *status;
+        match status {
+            JobStatus::Completed => job.exit_code = Some(0),
+            JobStatus::Failed => {
+                if let Some(code) = exit_code(&log_line.content) {
+                    job.exit_code = Some(code);
+                }
+            }
+            _ => {}
+        }
+        if finished {
+            job.ended_at = Some(now);
+        }
+        job.status = status.clone();
+
+        self.append_log(*idx, log_line);
+        if finished {
+            let _ = self.save();
+        }
+    }
+
+    /// Marks the run as ended with `outcome` and writes it.
+    pub fn finish(&mut self, outcome: RunOutcome) {
+        self.record.outcome = outcome;
+        self.record.ended_at = Some(chrono::Local::now().to_rfc3339());
+        let _ = self.save();
+    }
+
+    /// Records every message sent to the returned sender and forwards it to `tx`.
+    ///
+    /// The task ends once every clone of the returned sender is dropped. A run
+    /// that ends without an end-of-run message is recorded as failed.
+    pub fn tap(mut self, tx: mpsc::Sender<Message>) -> (mpsc::Sender<Message>, JoinHandle<()>) {
+        let (tap_tx, mut tap_rx) = mpsc::channel::<Message>(32);
+        let handle = tokio::spawn(async move {
+            while let Some(message) = tap_rx.recv().await {
+                self.record_message(&message);
+                let _ = tx.send(message).await;
+            }
+            if self.record.outcome == RunOutcome::Running {
+                self.finish(RunOutcome::Failed);
+            }
+        });
+        (tap_tx, handle)
+    }
+
+    fn append_log(&mut self, idx: usize, log_line: &LogLine) {
+        if log_line.content.is_empty() {
+            return;
+        }
+        let file = match self.log_files.get_mut(&idx) {
+            Some(file) => file,
+            None => {
+                let path = self
+                    .dir
+                    .join(LOGS_DIR)
+                    .join(format!("{}.log", self.record.jobs[idx].name));
+                let Ok(file) = fs::OpenOptions::new().create(true).append(true).open(path) else {
+                    return;
+                };
+                self.log_files.entry(idx).or_insert(file)
+            }
+        };
+        let source_prefix = match log_line.source {
+            LogSource::Stdout => "[OUT]",
+            LogSource::Stderr => "[ERR]",
+        };
+        let _ = writeln!(
+            file,
+            "{} {source_prefix} {}",
+            log_line
+                .timestamp
+                .with_timezone(&chrono::Local)
+                .to_rfc3339(),
+            log_line.content
+        );
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = self.dir.join(RUN_FILE);
+        let content = serde_json::to_string_pretty(&self.record)
+            .map_err(|e| format!("Failed to serialize run: {e}"))?;
+        fs::write(&path, content).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+    }
+}
+
+/// Extracts the exit code from a runner failure message such as
+/// "Job 'a' failed: Script run.sh failed with exit code 3 (2 attempts)".
+fn exit_code(content: &str) -> Option<i64> {
+    content
+        .rsplit_once("exit code ")
+        .and_then(|(_, rest)| rest.split_whitespace().next())
+        .and_then(|code| code.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    use crate::components::workflow::JobFolder;
+    use crate::run_state::RunState;
+    use job_config::workflow::WorkflowMeta;
+
+    fn prepared(temp: &Path, names: &[&str]) -> PreparedWorkflow {
+        let jobs: Vec<_> = names
+            .iter()
+            .map(|name| JobFolder::new(name.to_string(), temp.join(name)))
+            .collect();
+        let mut params = WorkflowParams::new();
+        params.insert("epochs".to_string(), serde_json::json!(10));
+        PreparedWorkflow {
+            temp_workflow_path: temp.to_path_buf(),
+            sorted_jobs: jobs.clone(),
+            jobs,
+            metadata: WorkflowMeta::new("my wf".to_string(), String::new()),
+            params,
+            fingerprints: HashMap::new(),
+            reused_jobs: HashSet::new(),
+            mounts: HashMap::new(),
+            instances: HashMap::new(),
+            run_state: RunState::default(),
+            notes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_recorder_writes_run_and_logs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let history = RunHistory::new(tmp.path().join("runs"));
+        let workflow = prepared(tmp.path(), &["a", "b"]);
+        let mut recorder = history.start(&workflow).unwrap();
+        let id = recorder.record().id.clone();
+        assert!(id.ends_with("-my_wf"));
+
+        let messages = [
+            (
+                0,
+                JobStatus::Running,
+                LogLine::new(LogSource::Stdout, "hello".to_string()),
+            ),
+            (
+                0,
+                JobStatus::Completed,
+                LogLine::new(LogSource::Stdout, "done".to_string()),
+            ),
+            (
+                1,
+                JobStatus::Running,
+                LogLine::new(LogSource::Stderr, "oops".to_string()),
+            ),
+            (
+                1,
+                JobStatus::Failed,
+                LogLine::new(
+                    LogSource::Stderr,
+                    "Job 'b' failed: Script run.sh failed with exit code 3 (2 attempts)"
+                        .to_string(),
+                ),
+            ),
+            (2, JobStatus::Failed, LogLine::empty()),
+        ];
+        for message in &messages {
+            recorder.record_message(message);
+        }
+
+        let runs = history.list().unwrap();
+        assert_eq!(runs.len(), 1);
+        let run = &runs[0];
+        assert_eq!(run.workflow, "my wf");
+        assert_eq!(run.outcome, RunOutcome::Failed);
+        assert!(run.ended_at.is_some());
+        assert_eq!(run.params["epochs"], serde_json::json!(10));
+        assert_eq!(run.jobs[0].status, JobStatus::Completed);
+        assert_eq!(run.jobs[0].exit_code, Some(0));
+        assert_eq!(run.jobs[1].status, JobStatus::Failed);
+        assert_eq!(run.jobs[1].exit_code, Some(3));
+        assert!(run.jobs[1].started_at.is_some() && run.jobs[1].ended_at.is_some());
+
+        let logs = history.read_logs(&id, "a").unwrap();
+        assert_eq!(logs.len(), 2);
+        assert!(logs[0].ends_with("[OUT] hello"));
+        assert!(history.read_logs(&id, "b").unwrap()[0].ends_with("[ERR] oops"));
+    }
+
+    #[tokio::test]
+    async fn test_tap_forwards_messages_and_records_unfinished_run_as_failed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let history = RunHistory::new(tmp.path().join("runs"));
+        let first = history.start(&prepared(tmp.path(), &["a"])).unwrap();
+        let second = history.start(&prepared(tmp.path(), &["a"])).unwrap();
+        assert_ne!(first.record().id, second.record().id);
+
+        let (tx, mut rx) = mpsc::channel::<Message>(8);
+        let (tap_tx, handle) = second.tap(tx);
+        tap_tx
+            .send((
+                0,
+                JobStatus::Running,
+                LogLine::new(LogSource::Stdout, "x".to_string()),
+            ))
+            .await
+            .unwrap();
+        drop(tap_tx);
+        handle.await.unwrap();
+
+        assert_eq!(rx.recv().await.unwrap().2.content, "x");
+        let runs = history.list().unwrap();
+        assert_eq!(runs.len(), 2);
+        assert!(runs.iter().any(|run| run.outcome == RunOutcome::Failed));
+        assert!(runs.iter().any(|run| run.outcome == RunOutcome::Running));
+    }
+}
@@ -2,14 +2,15 @@ use ratatui::Frame;
 
 use crate::{
     app::App,
-    components::{application, health_check, workflow},
+    components::{application, health_check, runs, workflow},
 };
 
-pub fn render(frame: &mut Frame, area: ratatui::prelude::Rect, app: &App) {
+pub fn render(frame: &mut Frame, area: ratatui::prelude::Rect, app: &mut App) {
     match app.selected_tab {
         0 => application::render::render(frame, area, app),
         1 => workflow::render::render(frame, area, app),
-        2 => health_check::render::render(frame, area, app),
+        2 => runs::render::render(frame, area, app),
+        3 => health_check::render::render(frame, area, app),
         _ => {}
     }
 }
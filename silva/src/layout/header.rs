@@ -9,7 +9,7 @@ use ratatui::{
 use crate::app::App;
 
 pub fn render(frame: &mut Frame, area: ratatui::prelude::Rect, app: &App) {
-    let tabs = ["📦 Applications", "📁 Workflows", "🎛️  Settings"];
+    let tabs = ["📦 Applications", "📁 Workflows", "🕘 Runs", "🎛️  Settings"];
     let selected_style = Style::default()
         .fg(crate::style::COLOR_FG)
         .add_modifier(Modifier::BOLD)
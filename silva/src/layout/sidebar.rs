@@ -9,21 +9,6 @@ use ratatui::{
 use crate::app::App;
 
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
-    // Calculate popup size (centered, reasonable size)
-    let popup_width = 40.min(area.width.saturating_sub(4));
-    let popup_height = 20.min(area.height.saturating_sub(4));
-    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
-    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
-
-    let popup_area = Rect {
-        x: popup_x,
-        y: popup_y,
-        width: popup_width,
-        height: popup_height,
-    };
-
-    // Clear the area first
-    frame.render_widget(Clear, popup_area);
     // Help
     let mut help_text = vec![
         Line::from(""),
@@ -171,7 +156,65 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
             ),
             Span::raw("Open Temp Folder"),
         ]));
+        help_text.push(Line::from(vec![
+            Span::styled(
+                format!("{:>12}", "r "),
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Resume Last Run"),
+        ]));
+        help_text.push(Line::from(vec![
+            Span::styled(
+                format!("{:>12}", "c "),
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Cancel Run"),
+        ]));
     } else if app.selected_tab == 2 {
+        for (key, action) in [
+            ("↑↓ or j/k ", "Navigate Runs"),
+            ("Enter ", "Open Run"),
+            ("o ", "Open Output Folder"),
+            ("r ", "Refresh Runs"),
+        ] {
+            help_text.push(Line::from(vec![
+                Span::styled(
+                    format!("{key:>12}"),
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(action),
+            ]));
+        }
+        help_text.push(Line::from(""));
+        help_text.push(Line::from(vec![Span::styled(
+            "In an Opened Run:",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::ITALIC),
+        )]));
+        for (key, action) in [
+            ("↑↓ or j/k ", "Select Job"),
+            ("Shift+↑↓ ", "Scroll Logs"),
+            ("b ", "Scroll to Bottom"),
+            ("Esc ", "Back to Runs"),
+        ] {
+            help_text.push(Line::from(vec![
+                Span::styled(
+                    format!("{key:>12}"),
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(action),
+            ]));
+        }
+    } else if app.selected_tab == 3 {
         help_text.push(Line::from(vec![
             Span::styled(
                 format!("{:>12}", "r "),
@@ -194,6 +237,22 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
         Span::raw("Toggle Help"),
     ]));
 
+    // Calculate popup size (centered, tall enough for the help of the current tab)
+    let popup_width = 40.min(area.width.saturating_sub(4));
+    let popup_height = (help_text.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = Rect {
+        x: popup_x,
+        y: popup_y,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    // Clear the area first
+    frame.render_widget(Clear, popup_area);
+
     let help = Paragraph::new(help_text)
         .block(
             Block::default()
@@ -4,11 +4,24 @@ use crossterm::event::{self, Event, KeyEventKind};
 use ratatui::Terminal;
 
 pub mod app;
+pub mod backend;
+pub mod cache;
 pub mod components;
+pub mod executor;
+pub mod gpu;
 pub mod headless;
+pub mod history;
 pub mod infra;
 mod layout;
+pub mod matrix;
+pub mod mounts;
+pub mod outputs;
+pub mod overrides;
 pub mod precheck;
+pub mod process;
+pub mod run_state;
+pub mod runner;
+pub mod scheduler;
 mod style;
 pub mod update;
 pub mod utils;
@@ -36,3 +49,6 @@ pub async fn run_app<B: ratatui::backend::Backend>(
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const SILVA_WORKFLOW_HOME: &str = "SILVA_WORKFLOW_HOME";
+const SILVA_CACHE_DIR: &str = "SILVA_CACHE_DIR";
+const SILVA_GPU_LOCK_DIR: &str = "SILVA_GPU_LOCK_DIR";
+const SILVA_ALLOWED_MOUNTS: &str = "SILVA_ALLOWED_MOUNTS";
@@ -1,20 +1,31 @@
 use std::path::PathBuf;
+use std::time::Duration;
 use std::{error::Error, io};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
+use silva::backend::Backend;
+use silva::cache::JobCache;
+use silva::headless::OutputFormat;
+use silva::history::RunHistory;
+use silva::overrides::ParamArgs;
 use silva::run_app;
+use silva::runner::RunOptions;
 
 /// Silva - Terminal UI for managing Docker-based data workflows
 #[derive(Parser, Debug)]
 #[command(name = "silva")]
 #[command(version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to a workflow folder to run directly (headless mode)
     ///
     /// If not provided, the TUI application will start.
@@ -28,14 +39,110 @@ struct Args {
     /// independent of workflow.toml's `env_passthrough` allowlist.
     #[arg(short = 'e', long = "env", value_name = "KEY=VALUE")]
     env: Vec<String>,
+
+    /// Run up to N independent jobs at the same time (headless mode only)
+    ///
+    /// Jobs still wait for their dependencies. Overrides `max_parallel` in
+    /// workflow.toml; without either, jobs run one at a time.
+    #[arg(short = 'j', long = "jobs", value_name = "N", value_parser = parse_max_parallel)]
+    jobs: Option<usize>,
+
+    /// Runtime used to run jobs (headless mode only)
+    ///
+    /// `auto` uses Apptainer when any job's image is a `.sif` file and Docker
+    /// otherwise. Use `apptainer` on hosts where Docker is not available, or
+    /// `local` to run scripts directly on the host without any container.
+    #[arg(long = "backend", value_enum, default_value_t = Backend::Auto)]
+    backend: Backend,
+
+    /// Resume an earlier run in its kept temp folder (headless mode only)
+    ///
+    /// Jobs already in `@complete/` whose scripts, params and inputs are
+    /// unchanged are reused; the run continues from the first other job.
+    #[arg(long = "resume", value_name = "TEMP_FOLDER")]
+    resume: Option<PathBuf>,
+
+    /// Output format (headless mode only)
+    ///
+    /// `json` writes one JSON event per line to stdout: run_started, job_status,
+    /// log, outputs_collected and run_finished. The update check is skipped.
+    #[arg(long = "output", value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Override a parameter for this run (headless mode only)
+    ///
+    /// Repeatable. `name=value` sets a parameter of workflow.toml for every job,
+    /// `<job>.name=value` a parameter of one job's job.toml. Values are checked
+    /// against the parameter definitions and win over every params file.
+    #[arg(long = "param", value_name = "[JOB.]NAME=VALUE")]
+    params: Vec<String>,
+
+    /// Read parameter overrides from a JSON file (headless mode only)
+    ///
+    /// Workflow parameters by name, job parameters in an object per job, e.g.
+    /// `{"batch_size": 64, "02_train": {"epochs": 5}}`. Applied after `--profile`
+    /// and before `--param`.
+    #[arg(long = "params-file", value_name = "FILE")]
+    params_file: Option<PathBuf>,
+
+    /// Apply the parameter profile `params.<NAME>.json` of the workflow folder (headless mode only)
+    ///
+    /// Same format as `--params-file`; applied on top of global_params.json and
+    /// the jobs' params.json.
+    #[arg(long = "profile", value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Reuse job results cached by earlier runs (headless mode only)
+    ///
+    /// Same as `cache = true` in workflow.toml. Jobs with `cache = false` in
+    /// their job.toml always run.
+    #[arg(long = "cache")]
+    cache: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Manage the job result cache
+    #[command(subcommand)]
+    Cache(CacheCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCommand {
+    /// List cached job results
+    Ls,
+    /// Remove cached job results
+    Prune {
+        /// Only remove results stored more than N days ago
+        #[arg(long = "older-than", value_name = "DAYS")]
+        older_than: Option<u64>,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
 
+    if let Some(Command::Cache(command)) = args.command {
+        if let Err(e) = run_cache_command(command) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Machine-readable output must not be mixed with the interactive update prompt
+    let json_output = args.workflow_path.is_some() && args.output == OutputFormat::Json;
+
     // Check for updates on startup
-    let update_result = silva::update::run_update_check().await;
+    let update_result = if json_output {
+        silva::update::UpdateCheckResult {
+            should_exit: false,
+            deferred_update: None,
+        }
+    } else {
+        silva::update::run_update_check().await
+    };
     if update_result.should_exit {
         // Update was performed, exit
         return Ok(());
@@ -53,9 +160,22 @@ async fn main() -> Result<(), Box<dyn Error>> {
         };
 
         // Headless mode: run workflow directly
-        if let Err(e) = silva::headless::run_workflow(&workflow_path, &cli_env_vars).await {
+        let options = RunOptions {
+            cli_env_vars,
+            max_parallel: args.jobs,
+            backend: args.backend,
+            resume: args.resume,
+            cache: args.cache,
+            history: Some(RunHistory::open_default()),
+            params: ParamArgs {
+                profile: args.profile,
+                params_file: args.params_file,
+                params: args.params,
+            },
+        };
+        if let Err(e) = silva::headless::run_workflow(&workflow_path, &options, args.output).await {
             eprintln!("{e}");
-            std::process::exit(1);
+            std::process::exit(e.exit_code());
         }
         Ok(())
     } else {
@@ -64,6 +184,68 @@ async fn main() -> Result<(), Box<dyn Error>> {
     }
 }
 
+/// Runs a `silva cache` subcommand against the default cache folder.
+fn run_cache_command(command: CacheCommand) -> Result<(), String> {
+    let cache = JobCache::open_default();
+    match command {
+        CacheCommand::Ls => {
+            let entries = cache.list()?;
+            if entries.is_empty() {
+                println!("No cached results in {}", cache.root().display());
+                return Ok(());
+            }
+            println!(
+                "{:<14} {:<24} {:<24} {:>6} {:>10}  CREATED",
+                "KEY", "WORKFLOW", "JOB", "FILES", "SIZE"
+            );
+            for entry in &entries {
+                println!(
+                    "{:<14} {:<24} {:<24} {:>6} {:>10}  {}",
+                    &entry.key[..12.min(entry.key.len())],
+                    entry.workflow,
+                    entry.job,
+                    entry.files,
+                    format_size(entry.size),
+                    entry.created_at
+                );
+            }
+            let total: u64 = entries.iter().map(|entry| entry.size).sum();
+            println!(
+                "\n{} result(s), {} in {}",
+                entries.len(),
+                format_size(total),
+                cache.root().display()
+            );
+        }
+        CacheCommand::Prune { older_than } => {
+            let age = older_than.map(|days| Duration::from_secs(days * 24 * 60 * 60));
+            let summary = cache.prune(age)?;
+            println!(
+                "Removed {} cached result(s), freed {}",
+                summary.removed,
+                format_size(summary.freed)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Formats a byte count for display, e.g. `1.5 MB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
 /// Validates `-e/--env` entries and returns them unchanged as `KEY=VALUE` strings.
 ///
 /// Rejects entries missing a `=` or with an empty key, so malformed flags fail
@@ -82,6 +264,14 @@ fn parse_cli_env_vars(entries: &[String]) -> Result<Vec<String>, String> {
     Ok(entries.to_vec())
 }
 
+/// Parses `-j/--jobs`, rejecting zero so a typo cannot stall the workflow.
+fn parse_max_parallel(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(n) if n >= 1 => Ok(n),
+        _ => Err(format!("expected a positive number of jobs, got '{value}'")),
+    }
+}
+
 /// Runs the TUI application
 async fn run_tui(update_available: Option<String>) -> Result<(), Box<dyn Error>> {
     enable_raw_mode()?;
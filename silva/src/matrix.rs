@@ -0,0 +1,491 @@
+//! Parameter sweeps: jobs run once per combination of their `[matrix]` values.
+//!
+//! A job's matrix comes from the `[matrix]` table of its job.toml and from
+//! `[matrix.<job>]` in workflow.toml, whose axes replace job.toml axes of the same
+//! name. When a run is prepared, every matrix job is expanded into instances named
+//! `<job>@<label>`, e.g. `02_dock@a.sdf`. Each instance is a copy of the job
+//! folder whose params.json holds the instance's values, so it gets its own
+//! `PARAM_*` variables and its own `outputs/`. Jobs depending on a matrix job
+//! depend on all of its instances and receive their outputs in
+//! `inputs/<job>/<label>/`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use job_config::job::MatrixAxis;
+use job_config::params::{JobParams, toml_to_json};
+use job_config::workflow::WorkflowMeta;
+use serde_json::Value;
+
+use crate::components::docker::logs::{LogLine, LogSource};
+use crate::components::workflow::JobFolder;
+use crate::runner::PreparedWorkflow;
+use crate::utils::{ContentHasher, copy_dir_recursive};
+
+/// Separates the job name from the label in the name of a matrix instance.
+pub const INSTANCE_SEPARATOR: char = '@';
+
+/// One run of a matrix job, with one value of every axis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixInstance {
+    /// Job the instance was expanded from
+    pub job: String,
+    /// Identifies the instance among those of its job, built from its values
+    pub label: String,
+    /// Value of every axis, set as parameters on top of the job's own
+    pub params: JobParams,
+}
+
+impl MatrixInstance {
+    /// Name of the instance's folder and job, `<job>@<label>`.
+    pub fn name(&self) -> String {
+        format!("{}{INSTANCE_SEPARATOR}{}", self.job, self.label)
+    }
+}
+
+/// Splits the name of a matrix instance into job name and label.
+pub fn split_instance_name(name: &str) -> Option<(&str, &str)> {
+    name.split_once(INSTANCE_SEPARATOR)
+}
+
+/// Expands every matrix job of `workflow` into its instances.
+///
+/// The instances take the job's place in `jobs` and `sorted_jobs`, in the
+/// dependencies of the workflow and in its mounts, and get fingerprints derived
+/// from the job's. Their folders are copied from the job's folder, which is
+/// removed, so `inputs/` must already be staged.
+///
+/// A matrix job reused from an earlier run is replaced by the instances its run
+/// state records, which are reused as well.
+///
+/// # Returns
+///
+/// * `Ok(())` - Every matrix job was expanded, or there were none
+/// * `Err(message)` - Some matrix has no values, or an instance folder could not be created
+pub fn expand(workflow: &mut PreparedWorkflow) -> Result<(), String> {
+    let jobs_to_run: Vec<JobFolder> = workflow
+        .sorted_jobs
+        .iter()
+        .filter(|job| !workflow.reused_jobs.contains(&job.name))
+        .cloned()
+        .collect();
+    let mut planned = plan_instances(&workflow.metadata, &jobs_to_run)?;
+    for job in &jobs_to_run {
+        let Some(instances) = planned.get(&job.name) else {
+            continue;
+        };
+        let job_name = &job.name;
+        create_instance_folders(&workflow.temp_workflow_path, job_name, instances)?;
+        let labels: Vec<&str> = instances.iter().map(|i| i.label.as_str()).collect();
+        workflow.notes.push(LogLine::new(
+            LogSource::Stdout,
+            format!(
+                "Matrix job '{job_name}' runs as {} instance(s): {}",
+                instances.len(),
+                labels.join(", ")
+            ),
+        ));
+    }
+    for (job_name, instances) in reused_instances(workflow) {
+        workflow
+            .reused_jobs
+            .extend(instances.iter().map(MatrixInstance::name));
+        planned.insert(job_name, instances);
+    }
+    if planned.is_empty() {
+        return Ok(());
+    }
+
+    let expand_jobs = |jobs: &[JobFolder]| -> Vec<JobFolder> {
+        jobs.iter()
+            .flat_map(|job| match planned.get(&job.name) {
+                Some(instances) => instances
+                    .iter()
+                    .map(|i| JobFolder::new(i.name(), workflow.temp_workflow_path.join(i.name())))
+                    .collect(),
+                None => vec![job.clone()],
+            })
+            .collect()
+    };
+    workflow.jobs = expand_jobs(&workflow.jobs);
+    workflow.sorted_jobs = expand_jobs(&workflow.sorted_jobs);
+
+    // Dependents wait for every instance; instances wait for the job's dependencies
+    for dependencies in workflow.metadata.dependencies.values_mut() {
+        *dependencies = dependencies
+            .iter()
+            .flat_map(|dep| match planned.get(dep) {
+                Some(instances) => instances.iter().map(MatrixInstance::name).collect(),
+                None => vec![dep.clone()],
+            })
+            .collect();
+    }
+    for (job_name, instances) in planned {
+        let dependencies = workflow.metadata.dependencies.remove(&job_name);
+        // The job keeps its fingerprint, as it is recorded once all instances complete
+        let fingerprint = workflow.fingerprints.get(&job_name).cloned();
+        let mounts = workflow.mounts.remove(&job_name);
+        for instance in instances {
+            let name = instance.name();
+            if let Some(dependencies) = &dependencies {
+                workflow
+                    .metadata
+                    .dependencies
+                    .insert(name.clone(), dependencies.clone());
+            }
+            if let Some(fingerprint) = &fingerprint {
+                let mut hasher = ContentHasher::new();
+                hasher.update(fingerprint.as_bytes());
+                hasher.update(name.as_bytes());
+                let params: BTreeMap<_, _> = instance.params.iter().collect();
+                hasher.update(
+                    serde_json::to_string(&params)
+                        .unwrap_or_default()
+                        .as_bytes(),
+                );
+                workflow.fingerprints.insert(name.clone(), hasher.finish());
+            }
+            if let Some(mounts) = &mounts {
+                workflow.mounts.insert(name.clone(), mounts.clone());
+            }
+            workflow.instances.insert(name, instance);
+        }
+    }
+    Ok(())
+}
+
+/// Instances of the reused matrix jobs of `workflow` recorded in its run state,
+/// by job name.
+///
+/// Their values are read back from the params.json of their folders in `@complete/`.
+fn reused_instances(workflow: &PreparedWorkflow) -> HashMap<String, Vec<MatrixInstance>> {
+    let mut reused = HashMap::new();
+    for job_name in &workflow.reused_jobs {
+        let names = workflow.run_state.instances(job_name);
+        if names.is_empty() {
+            continue;
+        }
+        let instances = names
+            .iter()
+            .filter_map(|name| {
+                let (_, label) = split_instance_name(name)?;
+                let folder = JobFolder::new(
+                    name.clone(),
+                    workflow.temp_workflow_path.join("@complete").join(name),
+                );
+                let mut axes: Vec<String> = folder
+                    .load_meta()
+                    .map(|meta| meta.matrix.into_keys().collect())
+                    .unwrap_or_default();
+                if let Some(matrix) = workflow.metadata.matrix.get(job_name) {
+                    axes.extend(matrix.keys().cloned());
+                }
+                let params = folder
+                    .load_params()
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|(param, _)| axes.contains(param))
+                    .collect();
+                Some(MatrixInstance {
+                    job: job_name.clone(),
+                    label: label.to_string(),
+                    params,
+                })
+            })
+            .collect();
+        reused.insert(job_name.clone(), instances);
+    }
+    reused
+}
+
+/// Computes the instances of every matrix job in `jobs`, by job name.
+///
+/// `{ inputs = "<glob>" }` axes are matched against the job's staged `inputs/`,
+/// which is only complete before the run for jobs without dependencies.
+pub fn plan_instances(
+    metadata: &WorkflowMeta,
+    jobs: &[JobFolder],
+) -> Result<HashMap<String, Vec<MatrixInstance>>, String> {
+    let mut planned = HashMap::new();
+    let mut violations = Vec::new();
+
+    for job in jobs {
+        let Ok(meta) = job.load_meta() else {
+            continue;
+        };
+        let mut matrix = meta.matrix;
+        if let Some(axes) = metadata.matrix.get(&job.name) {
+            matrix.extend(axes.clone());
+        }
+        if matrix.is_empty() {
+            continue;
+        }
+        if job.name.contains(INSTANCE_SEPARATOR) {
+            violations.push(format!(
+                "[{}] Matrix jobs cannot have '{INSTANCE_SEPARATOR}' in their name",
+                job.name
+            ));
+            continue;
+        }
+
+        let has_dependencies = !metadata.get_job_dependencies(&job.name).is_empty();
+        let mut axes = Vec::new();
+        for (name, axis) in &matrix {
+            match axis_values(job, name, axis, has_dependencies) {
+                Ok(values) => axes.push((name.as_str(), values)),
+                Err(e) => violations.push(format!("[{}] {e}", job.name)),
+            }
+        }
+        if axes.len() == matrix.len() {
+            planned.insert(job.name.clone(), instances(&job.name, &axes));
+        }
+    }
+
+    if violations.is_empty() {
+        return Ok(planned);
+    }
+
+    let mut msg = String::from("Matrix jobs in workflow.toml or job.toml cannot be expanded.\n");
+    for violation in &violations {
+        msg.push_str(&format!("\n  {violation}"));
+    }
+    msg.push_str(
+        "\n\nFix: Give every [matrix] axis at least one value, and glob inputs/ only in jobs without dependencies.",
+    );
+    Err(msg)
+}
+
+/// Resolves the values of axis `name` of `job`.
+fn axis_values(
+    job: &JobFolder,
+    name: &str,
+    axis: &MatrixAxis,
+    has_dependencies: bool,
+) -> Result<Vec<Value>, String> {
+    let values: Vec<Value> = match axis {
+        MatrixAxis::Values(values) => values.iter().map(toml_to_json).collect(),
+        MatrixAxis::Inputs { inputs } => {
+            if has_dependencies {
+                return Err(format!(
+                    "Matrix axis '{name}' globs inputs/, which is only known before the run for jobs without dependencies; list its values instead"
+                ));
+            }
+            let matcher = globset::Glob::new(inputs)
+                .map_err(|e| format!("Matrix axis '{name}' has an invalid pattern: {e}"))?
+                .compile_matcher();
+            let files = crate::utils::list_files(&job.path.join("inputs")).unwrap_or_default();
+            files
+                .iter()
+                .filter(|file| matcher.is_match(file))
+                .map(|file| Value::String(file.to_string_lossy().replace('\\', "/")))
+                .collect()
+        }
+    };
+    if values.is_empty() {
+        return Err(match axis {
+            MatrixAxis::Values(_) => format!("Matrix axis '{name}' has no values"),
+            MatrixAxis::Inputs { inputs } => {
+                format!("Matrix axis '{name}' matches no file in inputs/ for '{inputs}'")
+            }
+        });
+    }
+    Ok(values)
+}
+
+/// Builds one instance per combination of `axes`, the first axis varying slowest.
+fn instances(job: &str, axes: &[(&str, Vec<Value>)]) -> Vec<MatrixInstance> {
+    let mut combinations: Vec<Vec<(&str, &Value)>> = vec![Vec::new()];
+    for (name, values) in axes {
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combination| {
+                values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.push((*name, value));
+                    combination
+                })
+            })
+            .collect();
+    }
+
+    let mut instances: Vec<MatrixInstance> = Vec::new();
+    for (idx, combination) in combinations.into_iter().enumerate() {
+        let mut label = combination
+            .iter()
+            .map(|(_, value)| label_part(value))
+            .collect::<Vec<_>>()
+            .join("_");
+        if label.is_empty() {
+            label = idx.to_string();
+        } else if instances.iter().any(|i| i.label == label) {
+            label = format!("{label}_{idx}");
+        }
+        instances.push(MatrixInstance {
+            job: job.to_string(),
+            label,
+            params: combination
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+        });
+    }
+    instances
+}
+
+/// Part of an instance label for `value`, safe as a folder name.
+fn label_part(value: &Value) -> String {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let part: String = text
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    // No hidden folders, and no `..`
+    part.trim_start_matches('.').to_string()
+}
+
+/// Copies the folder of `job_name` to one folder per instance, then removes it.
+///
+/// Folders an earlier run left behind for the instances are replaced.
+fn create_instance_folders(
+    temp_workflow_path: &Path,
+    job_name: &str,
+    instances: &[MatrixInstance],
+) -> Result<(), String> {
+    let job = JobFolder::new(job_name.to_string(), temp_workflow_path.join(job_name));
+    let params = job
+        .load_params()
+        .ok()
+        .flatten()
+        .or_else(|| job.load_meta().ok().map(|m| m.generate_default_params()))
+        .unwrap_or_default();
+
+    for instance in instances {
+        let name = instance.name();
+        for stale in [
+            temp_workflow_path.join(&name),
+            temp_workflow_path.join("@complete").join(&name),
+        ] {
+            if stale.exists() {
+                fs::remove_dir_all(&stale)
+                    .map_err(|e| format!("Failed to remove {}: {e}", stale.display()))?;
+            }
+        }
+        let folder = JobFolder::new(name.clone(), temp_workflow_path.join(&name));
+        copy_dir_recursive(&job.path, &folder.path)
+            .map_err(|e| format!("Failed to create matrix instance '{name}': {e}"))?;
+
+        let mut instance_params = params.clone();
+        instance_params.extend(instance.params.clone());
+        folder
+            .save_params(&instance_params)
+            .map_err(|e| format!("Failed to write parameters of '{name}': {e}"))?;
+    }
+
+    fs::remove_dir_all(&job.path).map_err(|e| format!("Failed to remove '{job_name}': {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job_with_inputs(root: &Path, name: &str, inputs: &[&str]) -> JobFolder {
+        let job = JobFolder::new(name.to_string(), root.join(name));
+        for input in inputs {
+            let path = job.path.join("inputs").join(input);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, input).unwrap();
+        }
+        job.save_meta(&job_config::job::JobMeta::new(
+            name.to_string(),
+            String::new(),
+            job_config::job::Container::new("ubuntu".to_string()),
+        ))
+        .unwrap();
+        job
+    }
+
+    fn set_matrix(job: &JobFolder, matrix: &str) {
+        let mut meta = job.load_meta().unwrap();
+        meta.matrix = toml::from_str(matrix).unwrap();
+        job.save_meta(&meta).unwrap();
+    }
+
+    #[test]
+    fn test_plan_instances_combines_axes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let job = job_with_inputs(
+            tmp.path(),
+            "dock",
+            &["ligands/a.sdf", "ligands/b.sdf", "x.pdb"],
+        );
+        set_matrix(
+            &job,
+            "ligand = { inputs = \"ligands/*.sdf\" }\nseed = [1, 2]",
+        );
+        // workflow.toml replaces the job's `seed` axis
+        let mut metadata = WorkflowMeta::new("wf".to_string(), String::new());
+        metadata
+            .matrix
+            .insert("dock".to_string(), toml::from_str("seed = [7]").unwrap());
+
+        let planned = plan_instances(&metadata, &[job]).unwrap();
+
+        let instances = &planned["dock"];
+        let names: Vec<String> = instances.iter().map(MatrixInstance::name).collect();
+        assert_eq!(names, vec!["dock@ligands_a.sdf_7", "dock@ligands_b.sdf_7"]);
+        assert_eq!(
+            instances[1].params,
+            JobParams::from([
+                ("ligand".to_string(), Value::from("ligands/b.sdf")),
+                ("seed".to_string(), Value::from(7)),
+            ])
+        );
+        assert_eq!(
+            split_instance_name(&names[0]),
+            Some(("dock", "ligands_a.sdf_7"))
+        );
+    }
+
+    #[test]
+    fn test_instance_labels_are_unique_folder_names() {
+        let instances = instances(
+            "job",
+            &[(
+                "name",
+                vec![Value::from("../x"), Value::from("..|x"), Value::from("")],
+            )],
+        );
+        let labels: Vec<&str> = instances.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, vec!["_x", "_x_1", "2"]);
+    }
+
+    #[test]
+    fn test_plan_instances_rejects_empty_axes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let empty = job_with_inputs(tmp.path(), "empty", &[]);
+        set_matrix(&empty, "ligand = []");
+        let no_match = job_with_inputs(tmp.path(), "no_match", &["x.pdb"]);
+        set_matrix(&no_match, "ligand = { inputs = \"*.sdf\" }");
+        let downstream = job_with_inputs(tmp.path(), "downstream", &[]);
+        set_matrix(&downstream, "ligand = { inputs = \"*.sdf\" }");
+        let mut metadata = WorkflowMeta::new("wf".to_string(), String::new());
+        metadata.set_job_dependencies("downstream".to_string(), vec!["empty".to_string()]);
+
+        let err = plan_instances(&metadata, &[empty, no_match, downstream]).unwrap_err();
+        assert!(err.contains("[empty] Matrix axis 'ligand' has no values"));
+        assert!(err.contains("[no_match] Matrix axis 'ligand' matches no file"));
+        assert!(err.contains("[downstream] Matrix axis 'ligand' globs inputs/"));
+    }
+}
@@ -0,0 +1,242 @@
+//! Host paths mounted into job containers.
+//!
+//! `[[mounts]]` entries of workflow.toml apply to every job, those of job.toml to
+//! a single job. Before a run starts, every mount is resolved to an absolute host
+//! path and checked against an allowlist: the workflow home
+//! (`$SILVA_WORKFLOW_HOME`), the workflow's own folder and the folders listed in
+//! `$SILVA_ALLOWED_MOUNTS` (separated like `PATH`). Anything else, e.g. `/` or
+//! `~/.ssh`, is rejected, so a workflow cannot hand its scripts arbitrary host files.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use job_config::job::Mount;
+use job_config::workflow::WorkflowMeta;
+
+use crate::components::workflow::{JobFolder, WorkflowHome};
+
+/// Container folder the workflow or job folder is mounted on; mounts cannot target it.
+const RESERVED_CONTAINER_PATH: &str = "/workspace";
+
+/// Host folders mounts may come from: the workflow home and `$SILVA_ALLOWED_MOUNTS`.
+pub fn allowed_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    if let Ok(home) = WorkflowHome::new() {
+        roots.push(home.path().to_path_buf());
+    }
+    if let Some(extra) = std::env::var_os(crate::SILVA_ALLOWED_MOUNTS) {
+        roots.extend(std::env::split_paths(&extra).filter(|p| !p.as_os_str().is_empty()));
+    }
+    roots
+        .into_iter()
+        .map(|root| root.canonicalize().unwrap_or(root))
+        .collect()
+}
+
+/// Resolves the mounts of every job in `jobs`: those of workflow.toml (relative
+/// to `workflow_dir`) followed by those of the job's job.toml (relative to the job
+/// folder). A job mount replaces a workflow mount with the same container path.
+///
+/// # Returns
+///
+/// * `Ok(mounts)` - Mounts with absolute, canonical host paths, by job name
+/// * `Err(message)` - Some host path is missing or not allowed, or some container path is invalid
+pub fn resolve_workflow_mounts(
+    workflow_dir: &Path,
+    metadata: &WorkflowMeta,
+    jobs: &[JobFolder],
+    allowed: &[PathBuf],
+) -> Result<HashMap<String, Vec<Mount>>, String> {
+    let mut allowed = allowed.to_vec();
+    allowed.extend(workflow_dir.canonicalize().ok());
+
+    let mut violations = Vec::new();
+    let workflow_mounts =
+        resolve_mounts(&metadata.mounts, workflow_dir, &allowed).unwrap_or_else(|e| {
+            violations.push(format!("[workflow.toml] {e}"));
+            Vec::new()
+        });
+
+    let mut mounts = HashMap::new();
+    for job in jobs {
+        let job_mounts = job.load_meta().map(|meta| meta.mounts).unwrap_or_default();
+        // Relative to the job's folder in `workflow_dir`, not to a run's copy of it
+        match resolve_mounts(&job_mounts, &workflow_dir.join(&job.name), &allowed) {
+            Ok(job_mounts) => {
+                let mut merged = workflow_mounts.clone();
+                for mount in job_mounts {
+                    merged.retain(|m| m.container != mount.container);
+                    merged.push(mount);
+                }
+                mounts.insert(job.name.clone(), merged);
+            }
+            Err(e) => violations.push(format!("[{}] {e}", job.name)),
+        }
+    }
+
+    if violations.is_empty() {
+        return Ok(mounts);
+    }
+
+    let mut msg = String::from("Mounts in workflow.toml or job.toml are not allowed.\n");
+    for violation in &violations {
+        msg.push_str(&format!("\n  {violation}"));
+    }
+    msg.push_str(&format!(
+        "\n\nFix: Mount folders inside the workflow home, or list their parent folders in ${}.",
+        crate::SILVA_ALLOWED_MOUNTS
+    ));
+    Err(msg)
+}
+
+/// Resolves `mounts` against `base`; a later mount replaces an earlier one with
+/// the same container path.
+fn resolve_mounts(
+    mounts: &[Mount],
+    base: &Path,
+    allowed: &[PathBuf],
+) -> Result<Vec<Mount>, String> {
+    let mut resolved: Vec<Mount> = Vec::new();
+    for mount in mounts {
+        let mount = resolve_mount(mount, base, allowed)?;
+        resolved.retain(|m| m.container != mount.container);
+        resolved.push(mount);
+    }
+    Ok(resolved)
+}
+
+/// Resolves `mount` against `base` and checks it against `allowed`.
+fn resolve_mount(mount: &Mount, base: &Path, allowed: &[PathBuf]) -> Result<Mount, String> {
+    let container = mount.container.trim_end_matches('/');
+    if !container.starts_with('/') {
+        return Err(format!(
+            "Mount target '{}' must be an absolute container path",
+            mount.container
+        ));
+    }
+    if Path::new(container).starts_with(RESERVED_CONTAINER_PATH) || container == "/tmp" {
+        return Err(format!(
+            "Mount target '{}' is reserved: {RESERVED_CONTAINER_PATH} holds the job folders and /tmp is private to each container",
+            mount.container
+        ));
+    }
+
+    let host = base.join(&mount.host);
+    let host = host
+        .canonicalize()
+        .map_err(|e| format!("Mount source '{}' not found: {e}", host.display()))?;
+    if !allowed.iter().any(|root| host.starts_with(root)) {
+        return Err(format!(
+            "Mount source '{}' is outside the allowed folders",
+            host.display()
+        ));
+    }
+
+    Ok(Mount {
+        host: host.display().to_string(),
+        container: container.to_string(),
+        read_only: mount.read_only,
+    })
+}
+
+/// Formats `mount` as a `host:container[:ro]` bind, as Docker and Apptainer take it.
+pub fn bind_spec(mount: &Mount) -> String {
+    let mut spec = format!("{}:{}", mount.host, mount.container);
+    if mount.read_only {
+        spec.push_str(":ro");
+    }
+    spec
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn mount(host: &str, container: &str, read_only: bool) -> Mount {
+        Mount {
+            host: host.to_string(),
+            container: container.to_string(),
+            read_only,
+        }
+    }
+
+    #[test]
+    fn test_mounts_resolve_relative_to_declaring_folder() {
+        let tmp = tempfile::tempdir().unwrap();
+        let home = tmp.path().canonicalize().unwrap();
+        fs::create_dir_all(home.join("wf/datasets")).unwrap();
+        fs::create_dir_all(home.join("wf/job/scratch")).unwrap();
+
+        let mut metadata = WorkflowMeta::new("wf".to_string(), String::new());
+        metadata.mounts = vec![
+            mount("datasets", "/data", true),
+            mount("datasets", "/scratch", false),
+        ];
+        let job = JobFolder::new("job".to_string(), home.join("wf/job"));
+        let mut meta = job_config::job::JobMeta::new(
+            "job".to_string(),
+            String::new(),
+            job_config::job::Container::new("ubuntu".to_string()),
+        );
+        meta.mounts = vec![mount("scratch", "/scratch/", false)];
+        job.save_meta(&meta).unwrap();
+
+        let mounts = resolve_workflow_mounts(&home.join("wf"), &metadata, &[job], &[]).unwrap();
+
+        // The job's /scratch replaces the workflow's
+        let binds: Vec<String> = mounts["job"].iter().map(bind_spec).collect();
+        assert_eq!(
+            binds,
+            vec![
+                format!("{}:/data:ro", home.join("wf/datasets").display()),
+                format!("{}:/scratch", home.join("wf/job/scratch").display()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mounts_outside_allowlist_are_rejected() {
+        let home = tempfile::tempdir().unwrap();
+        let home = home.path().canonicalize().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let outside = outside.path().canonicalize().unwrap();
+        let check = |host: &str, allowed: &[PathBuf]| {
+            resolve_mount(&mount(host, "/data", false), &home, allowed)
+        };
+
+        assert!(check(outside.to_str().unwrap(), std::slice::from_ref(&home)).is_err());
+        // `..` cannot escape the workflow home either; both live in the temp folder
+        let escape = format!("../{}", outside.file_name().unwrap().to_string_lossy());
+        assert!(check(&escape, std::slice::from_ref(&home)).is_err());
+        // Explicitly allowed folders are fine
+        assert!(check(outside.to_str().unwrap(), &[home.clone(), outside.clone()]).is_ok());
+        // Missing sources are reported
+        assert!(check("missing", std::slice::from_ref(&home)).is_err());
+
+        // Every violation is listed once
+        let mut metadata = WorkflowMeta::new("wf".to_string(), String::new());
+        metadata.mounts = vec![mount(outside.to_str().unwrap(), "/data", false)];
+        let jobs = [
+            JobFolder::new("a".to_string(), home.join("a")),
+            JobFolder::new("b".to_string(), home.join("b")),
+        ];
+        let err = resolve_workflow_mounts(&home, &metadata, &jobs, &[]).unwrap_err();
+        assert_eq!(err.matches("outside the allowed folders").count(), 1);
+        assert!(err.contains("SILVA_ALLOWED_MOUNTS"));
+    }
+
+    #[test]
+    fn test_mount_targets_are_validated() {
+        let home = tempfile::tempdir().unwrap();
+        let home = home.path().canonicalize().unwrap();
+        let allowed = std::slice::from_ref(&home);
+        for target in ["data", "/workspace", "/workspace/job", "/tmp", "/tmp/"] {
+            assert!(
+                resolve_mount(&mount(".", target, false), &home, allowed).is_err(),
+                "{target} should be rejected"
+            );
+        }
+        assert!(resolve_mount(&mount(".", "/data", false), &home, allowed).is_ok());
+    }
+}
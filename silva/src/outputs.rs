@@ -0,0 +1,429 @@
+//! Host-side collection of job output files.
+//!
+//! Every backend sees the job folders on the host (the Docker and Apptainer
+//! backends bind-mount them), so the runner collects `outputs` patterns here
+//! instead of running a shell snippet inside the image, which would need bash.
+//!
+//! Plain output patterns are collected into `outputs/`, named outputs into
+//! `outputs/<name>/`, so other jobs can bind their inputs to them.
+//!
+//! Patterns follow shell glob rules: `*` does not cross `/`, `**` matches any
+//! number of folders, and a pattern matching a folder selects every file below
+//! it. Patterns starting with `!` exclude files; without a `/` they apply at any
+//! depth, so `!*.tmp` drops every `.tmp` file. The same rules select the files a
+//! job copies from its dependencies' outputs (see `crate::runner`).
+//!
+//! Symbolic links are never collected, since a job's script could point them at
+//! host files outside the job folder.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use globset::{Glob, GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
+use job_config::job::Outputs;
+
+/// Folders of a job that are never matched by output patterns: staged inputs,
+/// collected outputs and the files silva writes for the job's scripts.
+const SKIPPED_DIRS: &[&str] = &["inputs", "outputs", crate::runner::PARAMS_FILE_DIR];
+
+/// Result of collecting output files for one job.
+#[derive(Debug, Default, PartialEq)]
+pub struct OutputCollection {
+    /// Number of files copied into the outputs folder.
+    pub collected: usize,
+    /// Every include pattern with the number of files it matched.
+    pub matches: Vec<(String, usize)>,
+}
+
+impl OutputCollection {
+    /// Include patterns that did not match any file.
+    pub fn unmatched(&self) -> Vec<&str> {
+        self.matches
+            .iter()
+            .filter(|(_, count)| *count == 0)
+            .map(|(pattern, _)| pattern.as_str())
+            .collect()
+    }
+}
+
+/// Files selected by a list of patterns.
+#[derive(Debug, Default, PartialEq)]
+pub struct Selection {
+    /// Selected files as `(source, destination)`: the path relative to the
+    /// searched folder, and the path relative to the folder they are copied to.
+    pub files: Vec<(PathBuf, PathBuf)>,
+    /// Every include pattern with the number of files it matched.
+    pub matches: Vec<(String, usize)>,
+    /// Files left out as `(source, destination)`, because an earlier selected
+    /// file already has their destination.
+    pub clashes: Vec<(PathBuf, PathBuf)>,
+}
+
+/// An include pattern: its matcher and the folder its matches are relative to.
+struct Include {
+    pattern: String,
+    matcher: GlobMatcher,
+    base: PathBuf,
+}
+
+/// Selects the files in `files` (relative paths) matching `patterns`.
+///
+/// A match keeps its path below the pattern's leading folders without glob
+/// characters, so `results/*.json` copies `results/a.json` to `a.json` and
+/// `results/**/*.txt` copies `results/x/b.txt` to `x/b.txt`. A file matched by
+/// several patterns is selected once, for the first; of several files with the
+/// same destination, the first is selected and the others are clashes.
+pub fn select_files(files: &[PathBuf], patterns: &[String]) -> Result<Selection, String> {
+    let mut includes = Vec::new();
+    let mut excludes = GlobSetBuilder::new();
+    for pattern in patterns {
+        if let Some(exclude) = pattern.strip_prefix('!') {
+            let exclude = normalize(exclude);
+            let anywhere = if exclude.contains('/') {
+                exclude.to_string()
+            } else {
+                format!("**/{exclude}")
+            };
+            excludes.add(glob(&anywhere, pattern)?);
+        } else {
+            let include = normalize(pattern);
+            includes.push(Include {
+                pattern: pattern.clone(),
+                matcher: glob(include, pattern)?.compile_matcher(),
+                base: static_base(include),
+            });
+        }
+    }
+    let excludes = excludes
+        .build()
+        .map_err(|e| format!("Invalid exclude patterns: {e}"))?;
+
+    let mut selection = Selection::default();
+    let mut selected: HashSet<&Path> = HashSet::new();
+    let mut destinations: HashSet<&Path> = HashSet::new();
+    for include in &includes {
+        let mut count = 0;
+        for file in files {
+            if !matches_path(&include.matcher, file) || is_excluded(&excludes, file) {
+                continue;
+            }
+            count += 1;
+            if selected.insert(file) {
+                let dest = file.strip_prefix(&include.base).unwrap_or(file);
+                if destinations.insert(dest) {
+                    selection.files.push((file.clone(), dest.to_path_buf()));
+                } else {
+                    selection.clashes.push((file.clone(), dest.to_path_buf()));
+                }
+            }
+        }
+        selection.matches.push((include.pattern.clone(), count));
+    }
+
+    Ok(selection)
+}
+
+/// Folders of a job, relative to the job folder, that its `outputs` are collected
+/// into, each with its patterns: `outputs` for plain patterns and
+/// `outputs/<name>` for every named output.
+pub fn output_folders(outputs: &Outputs) -> Vec<(String, Vec<String>)> {
+    match outputs {
+        Outputs::Patterns(patterns) if patterns.is_empty() => Vec::new(),
+        Outputs::Patterns(patterns) => vec![("outputs".to_string(), patterns.clone())],
+        Outputs::Named(named) => named
+            .iter()
+            .map(|(name, pattern)| (format!("outputs/{name}"), vec![pattern.clone()]))
+            .collect(),
+    }
+}
+
+/// Copies the files in `job_dir` matching `patterns` into `job_dir/<folder>/`,
+/// where `folder` is one of the folders returned by `output_folders`.
+///
+/// The job's `inputs/` and `outputs/` folders are never matched. Nothing is
+/// copied if several selected files would be copied to the same path.
+pub fn collect_outputs(
+    job_dir: &Path,
+    folder: &str,
+    patterns: &[String],
+) -> Result<OutputCollection, String> {
+    let outputs_dir = job_dir.join(folder);
+    fs::create_dir_all(&outputs_dir).map_err(|e| format!("Failed to create outputs dir: {e}"))?;
+
+    let files = crate::utils::list_files_skipping(job_dir, SKIPPED_DIRS)
+        .map_err(|e| format!("Failed to read job folder: {e}"))?;
+    let selection = select_files(&files, patterns)?;
+    if !selection.clashes.is_empty() {
+        let clashes: Vec<String> = selection
+            .clashes
+            .iter()
+            .map(|(source, dest)| {
+                let first = selection
+                    .files
+                    .iter()
+                    .find(|(_, d)| d == dest)
+                    .map_or(Path::new(""), |(s, _)| s.as_path());
+                format!(
+                    "'{}' and '{}' both go to '{}'",
+                    first.display(),
+                    source.display(),
+                    dest.display()
+                )
+            })
+            .collect();
+        return Err(format!(
+            "Output patterns select several files for the same path: {}",
+            clashes.join("; ")
+        ));
+    }
+
+    for (source, dest) in &selection.files {
+        let dest = outputs_dir.join(dest);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        fs::copy(job_dir.join(source), &dest)
+            .map_err(|e| format!("Failed to copy '{}': {e}", source.display()))?;
+    }
+
+    Ok(OutputCollection {
+        collected: selection.files.len(),
+        matches: selection.matches,
+    })
+}
+
+/// Strips a leading `./` and trailing `/` from a pattern.
+fn normalize(pattern: &str) -> &str {
+    pattern.trim_start_matches("./").trim_end_matches('/')
+}
+
+fn glob(pattern: &str, original: &str) -> Result<Glob, String> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| format!("Invalid pattern '{original}': {e}"))
+}
+
+/// Leading folders of `pattern` up to the first component with glob characters,
+/// leaving out the last component, which names what is matched.
+fn static_base(pattern: &str) -> PathBuf {
+    let components: Vec<&str> = pattern.split('/').collect();
+    components[..components.len() - 1]
+        .iter()
+        .take_while(|c| !c.contains(['*', '?', '[', '{']))
+        .collect()
+}
+
+/// Returns `true` if `file` or one of the folders containing it matches.
+fn matches_path(matcher: &GlobMatcher, file: &Path) -> bool {
+    file.ancestors()
+        .filter(|p| !p.as_os_str().is_empty())
+        .any(|p| matcher.is_match(p))
+}
+
+fn is_excluded(excludes: &GlobSet, file: &Path) -> bool {
+    file.ancestors()
+        .filter(|p| !p.as_os_str().is_empty())
+        .any(|p| excludes.is_match(p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list_outputs(job_dir: &Path) -> Vec<String> {
+        crate::utils::list_files(&job_dir.join("outputs"))
+            .unwrap()
+            .into_iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_collect_outputs_matches_shell_globs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let job_dir = tmp.path();
+        fs::create_dir_all(job_dir.join("results")).unwrap();
+        fs::write(job_dir.join("summary.csv"), "a").unwrap();
+        fs::write(job_dir.join("results/metrics.json"), "{}").unwrap();
+        fs::write(job_dir.join("results/nested.csv"), "b").unwrap();
+
+        let collection = collect_outputs(
+            job_dir,
+            "outputs",
+            &["*.csv".to_string(), "results/*.json".to_string()],
+        )
+        .unwrap();
+
+        assert_eq!(collection.collected, 2);
+        assert!(collection.unmatched().is_empty());
+        assert_eq!(list_outputs(job_dir), vec!["metrics.json", "summary.csv"]);
+    }
+
+    #[test]
+    fn test_collect_outputs_reports_unmatched_patterns() {
+        let tmp = tempfile::tempdir().unwrap();
+        let job_dir = tmp.path();
+        fs::create_dir_all(job_dir.join("inputs")).unwrap();
+        fs::write(job_dir.join("inputs/data.csv"), "a").unwrap();
+
+        let collection = collect_outputs(job_dir, "outputs", &["*.csv".to_string()]).unwrap();
+
+        assert_eq!(collection.collected, 0);
+        assert_eq!(collection.unmatched(), vec!["*.csv"]);
+    }
+
+    #[test]
+    fn test_collect_outputs_skips_the_params_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let job_dir = tmp.path();
+        fs::create_dir_all(job_dir.join(".silva")).unwrap();
+        fs::write(job_dir.join(".silva/params.json"), "{}").unwrap();
+        fs::write(job_dir.join("result.json"), "{}").unwrap();
+
+        let collection = collect_outputs(job_dir, "outputs", &["**".to_string()]).unwrap();
+
+        assert_eq!(collection.collected, 1);
+        assert_eq!(list_outputs(job_dir), vec!["result.json"]);
+    }
+
+    #[test]
+    fn test_collect_outputs_rejects_files_with_the_same_destination() {
+        let tmp = tempfile::tempdir().unwrap();
+        let job_dir = tmp.path();
+        for dir in ["a", "b"] {
+            fs::create_dir_all(job_dir.join(dir)).unwrap();
+            fs::write(job_dir.join(dir).join("x.txt"), dir).unwrap();
+        }
+
+        let err = collect_outputs(
+            job_dir,
+            "outputs",
+            &["a/*.txt".to_string(), "b/*.txt".to_string()],
+        )
+        .unwrap_err();
+
+        assert!(
+            err.contains("'a/x.txt' and 'b/x.txt' both go to 'x.txt'"),
+            "{err}"
+        );
+        assert!(list_outputs(job_dir).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_outputs_skips_symlinks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let host = tmp.path().join("host");
+        fs::create_dir_all(&host).unwrap();
+        fs::write(host.join("id_rsa"), "secret").unwrap();
+        let job_dir = tmp.path().join("job");
+        fs::create_dir_all(&job_dir).unwrap();
+        fs::write(job_dir.join("result.json"), "{}").unwrap();
+        // Links out of the job folder, and back to a folder containing the link
+        std::os::unix::fs::symlink(&host, job_dir.join("keys")).unwrap();
+        std::os::unix::fs::symlink(host.join("id_rsa"), job_dir.join("key")).unwrap();
+        std::os::unix::fs::symlink(tmp.path(), job_dir.join("root")).unwrap();
+
+        let collection = collect_outputs(&job_dir, "outputs", &["**".to_string()]).unwrap();
+
+        assert_eq!(collection.collected, 1);
+        assert_eq!(list_outputs(&job_dir), vec!["result.json"]);
+    }
+
+    #[test]
+    fn test_collect_outputs_copies_directories() {
+        let tmp = tempfile::tempdir().unwrap();
+        let job_dir = tmp.path();
+        fs::create_dir_all(job_dir.join("model")).unwrap();
+        fs::write(job_dir.join("model/weights.bin"), "w").unwrap();
+
+        let collection = collect_outputs(job_dir, "outputs", &["model".to_string()]).unwrap();
+
+        assert_eq!(collection.collected, 1);
+        assert!(job_dir.join("outputs/model/weights.bin").exists());
+    }
+
+    #[test]
+    fn test_recursive_patterns_keep_folders_and_honor_excludes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let job_dir = tmp.path();
+        for file in [
+            "results/a.txt",
+            "results/run1/b.txt",
+            "results/run1/deep/c.txt",
+            "results/run1/scratch.tmp",
+            "results/cache/d.txt",
+            "notes.txt",
+        ] {
+            let path = job_dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+
+        let patterns = [
+            "results/**/*.txt",
+            "results/**",
+            "*.csv",
+            "!*.tmp",
+            "!results/cache",
+        ]
+        .map(String::from);
+        let collection = collect_outputs(job_dir, "outputs", &patterns).unwrap();
+
+        assert_eq!(
+            list_outputs(job_dir),
+            vec!["a.txt", "run1/b.txt", "run1/deep/c.txt"]
+        );
+        assert_eq!(collection.collected, 3);
+        assert_eq!(
+            collection.matches,
+            vec![
+                ("results/**/*.txt".to_string(), 3),
+                ("results/**".to_string(), 3),
+                ("*.csv".to_string(), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_named_outputs_are_collected_into_their_own_folders() {
+        let tmp = tempfile::tempdir().unwrap();
+        let job_dir = tmp.path();
+        fs::create_dir_all(job_dir.join("metrics")).unwrap();
+        fs::write(job_dir.join("model.pkl"), "m").unwrap();
+        fs::write(job_dir.join("metrics/auc.json"), "{}").unwrap();
+
+        let outputs = Outputs::Named(
+            [("model", "model.pkl"), ("metrics", "metrics/*.json")]
+                .into_iter()
+                .map(|(name, pattern)| (name.to_string(), pattern.to_string()))
+                .collect(),
+        );
+        let folders = output_folders(&outputs);
+        assert_eq!(
+            folders,
+            vec![
+                (
+                    "outputs/metrics".to_string(),
+                    vec!["metrics/*.json".to_string()]
+                ),
+                ("outputs/model".to_string(), vec!["model.pkl".to_string()]),
+            ]
+        );
+        for (folder, patterns) in &folders {
+            assert_eq!(
+                collect_outputs(job_dir, folder, patterns)
+                    .unwrap()
+                    .collected,
+                1
+            );
+        }
+
+        assert!(job_dir.join("outputs/model/model.pkl").exists());
+        assert!(job_dir.join("outputs/metrics/auc.json").exists());
+        assert!(output_folders(&Outputs::default()).is_empty());
+    }
+}
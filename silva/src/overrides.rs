@@ -0,0 +1,412 @@
+//! Parameter values set for a single run, on top of the workflow's params files.
+//!
+//! Headless runs can override parameters without editing the workflow. Values
+//! are applied in this order, each source overriding the ones before it:
+//!
+//! 1. `global_params.json` for every job, then the job's own `params.json`
+//! 2. `params.<profile>.json` in the workflow folder, selected with `--profile`
+//! 3. the JSON file given with `--params-file`
+//! 4. `--param` flags, in the order given
+//!
+//! Within one source, a value for a single job overrides a value for every job.
+//! Params files hold workflow parameters by name and job parameters in an object
+//! per job:
+//!
+//! ```json
+//! { "batch_size": 64, "02_train": { "epochs": 5 } }
+//! ```
+//!
+//! `--param name=value` sets a workflow parameter, `--param job.name=value` a
+//! parameter of one job. Every value is checked against its definition in
+//! workflow.toml or job.toml before the run starts.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use job_config::interpolate::has_references;
+use job_config::job::{JobMeta, ParamDefinition};
+use job_config::params::{JobParams, WorkflowParams, json_to_toml};
+use job_config::workflow::WorkflowMeta;
+use serde_json::Value;
+
+use crate::components::workflow::JobFolder;
+
+/// Where the parameter overrides of a run come from, as given on the command line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParamArgs {
+    /// Value of `--profile`: reads `params.<profile>.json` in the workflow folder
+    pub profile: Option<String>,
+    /// Value of `--params-file`
+    pub params_file: Option<PathBuf>,
+    /// `--param` values, `name=value` or `job.name=value`
+    pub params: Vec<String>,
+}
+
+/// Parameter values overriding those of the workflow's params files.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParamOverrides {
+    /// Values for every job, by parameter name
+    global: WorkflowParams,
+    /// Values for single jobs, by job name
+    jobs: HashMap<String, JobParams>,
+}
+
+impl ParamArgs {
+    /// Returns `true` if no override source is given.
+    pub fn is_empty(&self) -> bool {
+        self.profile.is_none() && self.params_file.is_none() && self.params.is_empty()
+    }
+
+    /// Reads every source and checks its values against the parameter definitions
+    /// of `metadata` and of `jobs`, the workflow's job folders.
+    ///
+    /// Relative `must_exist` paths are resolved against the workflow folder for
+    /// workflow parameters and against the job folder for job parameters.
+    ///
+    /// Returns `Err` listing every invalid value, unknown parameter or job, and
+    /// unreadable file.
+    pub fn resolve(
+        &self,
+        workflow_path: &Path,
+        metadata: &WorkflowMeta,
+        jobs: &[JobFolder],
+    ) -> Result<ParamOverrides, String> {
+        let mut resolver = Resolver {
+            workflow_path,
+            metadata,
+            jobs: jobs
+                .iter()
+                .filter_map(|job| Some((job.name.as_str(), (job, job.load_meta().ok()?))))
+                .collect(),
+            overrides: ParamOverrides::default(),
+            violations: Vec::new(),
+        };
+
+        if let Some(profile) = &self.profile {
+            if profile.is_empty() || profile.contains(['/', '\\']) || profile.starts_with('.') {
+                resolver
+                    .violations
+                    .push(format!("--profile '{profile}' is not a valid profile name"));
+            } else {
+                let path = workflow_path.join(format!("params.{profile}.json"));
+                if path.is_file() {
+                    resolver.read_file(&path, &format!("params.{profile}.json"));
+                } else {
+                    resolver.violations.push(format!(
+                        "--profile '{profile}' has no params.{profile}.json in the workflow folder"
+                    ));
+                }
+            }
+        }
+        if let Some(path) = &self.params_file {
+            resolver.read_file(path, &path.display().to_string());
+        }
+        for entry in &self.params {
+            resolver.read_flag(entry);
+        }
+
+        if resolver.violations.is_empty() {
+            return Ok(resolver.overrides);
+        }
+
+        let mut msg = String::from("Invalid parameter overrides.\n");
+        for violation in &resolver.violations {
+            msg.push_str(&format!("\n  {violation}"));
+        }
+        msg.push_str(
+            "\n\nFix: Use parameters defined in workflow.toml as 'name' and parameters of a job's job.toml as '<job>.name', with values of their type.",
+        );
+        Err(msg)
+    }
+}
+
+impl ParamOverrides {
+    /// Returns `true` if no value is overridden.
+    pub fn is_empty(&self) -> bool {
+        self.global.is_empty() && self.jobs.values().all(JobParams::is_empty)
+    }
+
+    /// Values overriding workflow parameters.
+    pub fn global(&self) -> &WorkflowParams {
+        &self.global
+    }
+
+    /// Values overriding parameters of `job` alone, if any.
+    pub fn job(&self, job: &str) -> Option<&JobParams> {
+        self.jobs.get(job).filter(|params| !params.is_empty())
+    }
+
+    /// Applies the overrides to `params`, the values of `job`'s params.json.
+    ///
+    /// Workflow values replace job values of the same name, so they win over the
+    /// job's params.json when the job sees both.
+    pub fn apply_to_job(&self, job: &str, params: &mut JobParams) {
+        for (name, value) in &self.global {
+            if params.contains_key(name) {
+                params.insert(name.clone(), value.clone());
+            }
+        }
+        if let Some(values) = self.jobs.get(job) {
+            params.extend(values.clone());
+        }
+    }
+
+    /// Every override as `name=value` or `job.name=value`, sorted.
+    pub fn describe(&self) -> Vec<String> {
+        let global: BTreeMap<_, _> = self.global.iter().collect();
+        let jobs: BTreeMap<_, _> = self.jobs.iter().collect();
+        global
+            .into_iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .chain(jobs.into_iter().flat_map(|(job, values)| {
+                let values: BTreeMap<_, _> = values.iter().collect();
+                values
+                    .into_iter()
+                    .map(move |(name, value)| format!("{job}.{name}={value}"))
+            }))
+            .collect()
+    }
+
+    fn set_global(&mut self, name: &str, value: Value) {
+        // A later value for every job wins over earlier values for single jobs
+        for values in self.jobs.values_mut() {
+            values.remove(name);
+        }
+        self.global.insert(name.to_string(), value);
+    }
+
+    fn set_job(&mut self, job: &str, name: &str, value: Value) {
+        self.jobs
+            .entry(job.to_string())
+            .or_default()
+            .insert(name.to_string(), value);
+    }
+}
+
+/// Collects the overrides of every source, and every problem found in them.
+struct Resolver<'a> {
+    workflow_path: &'a Path,
+    metadata: &'a WorkflowMeta,
+    jobs: HashMap<&'a str, (&'a JobFolder, JobMeta)>,
+    overrides: ParamOverrides,
+    violations: Vec<String>,
+}
+
+/// A value as written in a source: typed JSON from a file, or text from a flag.
+enum Raw<'a> {
+    Json(Value),
+    Text(&'a str),
+}
+
+impl Resolver<'_> {
+    /// Reads a params file, named `label` in messages.
+    fn read_file(&mut self, path: &Path, label: &str) {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                self.violations
+                    .push(format!("{label}: cannot be read: {e}"));
+                return;
+            }
+        };
+        let values = match serde_json::from_str::<Value>(&content) {
+            Ok(Value::Object(values)) => values,
+            Ok(_) => {
+                self.violations
+                    .push(format!("{label}: expected a JSON object"));
+                return;
+            }
+            Err(e) => {
+                self.violations.push(format!("{label}: invalid JSON: {e}"));
+                return;
+            }
+        };
+
+        // Values for every job first, so that values for single jobs win
+        let (job_tables, global): (Vec<_>, Vec<_>) =
+            values.into_iter().partition(|(_, value)| value.is_object());
+        for (name, value) in global {
+            self.set(label, None, &name, Raw::Json(value));
+        }
+        for (job, table) in job_tables {
+            let Value::Object(table) = table else {
+                continue;
+            };
+            for (name, value) in table {
+                self.set(label, Some(&job), &name, Raw::Json(value));
+            }
+        }
+    }
+
+    /// Reads a `--param` value.
+    fn read_flag(&mut self, entry: &str) {
+        let label = format!("--param {entry}");
+        let Some((key, text)) = entry.split_once('=') else {
+            self.violations
+                .push(format!("{label}: expected name=value or <job>.name=value"));
+            return;
+        };
+        match key.rsplit_once('.') {
+            Some((job, name)) => self.set(&label, Some(job), name, Raw::Text(text)),
+            None => self.set(&label, None, key, Raw::Text(text)),
+        }
+    }
+
+    /// Checks one value and records it as an override of `name`, for `job` or for
+    /// every job.
+    fn set(&mut self, label: &str, job: Option<&str>, name: &str, raw: Raw) {
+        let (definition, dir): (Option<&ParamDefinition>, &Path) = match job {
+            None => (self.metadata.params.get(name), self.workflow_path),
+            Some(job) => match self.jobs.get(job) {
+                Some((folder, meta)) => (meta.params.get(name), &folder.path),
+                None => {
+                    self.violations
+                        .push(format!("{label}: the workflow has no job '{job}'"));
+                    return;
+                }
+            },
+        };
+        let Some(definition) = definition else {
+            self.violations.push(match job {
+                None => format!("{label}: workflow.toml defines no parameter '{name}'"),
+                Some(job) => format!("{label}: job '{job}' defines no parameter '{name}'"),
+            });
+            return;
+        };
+
+        let value = match raw {
+            Raw::Json(value) => value,
+            // Values with `${...}` references are checked once resolved
+            Raw::Text(text) if has_references(&Value::String(text.to_string())) => {
+                Value::String(text.to_string())
+            }
+            Raw::Text(text) => match definition.parse_value(text) {
+                Ok(value) => value,
+                Err(e) => {
+                    self.violations
+                        .push(format!("{label}: Parameter '{name}': {e}"));
+                    return;
+                }
+            },
+        };
+        if !has_references(&value)
+            && let Err(e) = definition.validate_in(&json_to_toml(&value), dir)
+        {
+            self.violations
+                .push(format!("{label}: Parameter '{name}': {e}"));
+            return;
+        }
+
+        match job {
+            None => self.overrides.set_global(name, value),
+            Some(job) => self.overrides.set_job(job, name, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workflow() -> (tempfile::TempDir, WorkflowMeta, Vec<JobFolder>) {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut metadata = WorkflowMeta::new("wf".to_string(), String::new());
+        metadata.add_param(
+            "batch_size".to_string(),
+            ParamDefinition::new(
+                job_config::job::ParamType::Integer,
+                toml::Value::Integer(32),
+                String::new(),
+                None,
+            ),
+        );
+        let job = JobFolder::new("02_train".to_string(), tmp.path().join("02_train"));
+        let mut meta: JobMeta = toml::from_str(
+            r#"
+            name = "02_train"
+            description = ""
+            [container]
+            image = "ubuntu:22.04"
+            [params.epochs]
+            type = "integer"
+            default = 10
+            hint = ""
+            min = 1
+            [params.batch_size]
+            type = "integer"
+            default = 16
+            hint = ""
+            "#,
+        )
+        .unwrap();
+        meta.name = job.name.clone();
+        job.save_meta(&meta).unwrap();
+        (tmp, metadata, vec![job])
+    }
+
+    #[test]
+    fn test_sources_apply_in_order() {
+        let (tmp, metadata, jobs) = workflow();
+        fs::write(
+            tmp.path().join("params.ci.json"),
+            r#"{ "batch_size": 64, "02_train": { "epochs": 5, "batch_size": 8 } }"#,
+        )
+        .unwrap();
+        let params_file = tmp.path().join("overrides.json");
+        fs::write(&params_file, r#"{ "02_train": { "epochs": 7 } }"#).unwrap();
+
+        let args = ParamArgs {
+            profile: Some("ci".to_string()),
+            params_file: Some(params_file),
+            params: vec!["batch_size=128".to_string()],
+        };
+        let overrides = args.resolve(tmp.path(), &metadata, &jobs).unwrap();
+
+        assert_eq!(
+            overrides.describe(),
+            vec!["batch_size=128", "02_train.epochs=7"]
+        );
+        // The job's params.json value gives way to the later workflow value
+        let mut params = JobParams::from([
+            ("epochs".to_string(), serde_json::json!(10)),
+            ("batch_size".to_string(), serde_json::json!(16)),
+        ]);
+        overrides.apply_to_job("02_train", &mut params);
+        assert_eq!(params["epochs"], serde_json::json!(7));
+        assert_eq!(params["batch_size"], serde_json::json!(128));
+    }
+
+    #[test]
+    fn test_invalid_overrides_are_rejected() {
+        let (tmp, metadata, jobs) = workflow();
+
+        let args = ParamArgs {
+            profile: Some("missing".to_string()),
+            params_file: None,
+            params: [
+                "02_train.epochs=0",
+                "batch_size=many",
+                "learning_rate=0.1",
+                "03_eval.epochs=1",
+                "epochs",
+            ]
+            .map(String::from)
+            .to_vec(),
+        };
+        let err = args.resolve(tmp.path(), &metadata, &jobs).unwrap_err();
+
+        assert!(err.contains("--profile 'missing' has no params.missing.json"));
+        assert!(err.contains(
+            "--param 02_train.epochs=0: Parameter 'epochs': Value 0 is below the minimum of 1"
+        ));
+        assert!(
+            err.contains("--param batch_size=many: Parameter 'batch_size': Invalid integer: many")
+        );
+        assert!(err.contains(
+            "--param learning_rate=0.1: workflow.toml defines no parameter 'learning_rate'"
+        ));
+        assert!(err.contains("--param 03_eval.epochs=1: the workflow has no job '03_eval'"));
+        assert!(err.contains("--param epochs: expected name=value"));
+    }
+}
@@ -4,10 +4,18 @@
 //! - Rejects scripts containing install commands (should be in Docker images)
 //! - Requires `input_files/` folder when dependency-free jobs exist
 //! - Rejects scripts with cross-node `../` path references
+//! - Rejects `[resources]` limits beyond the host's CPUs and memory
+//! - Rejects `when` conditions that do not parse or refer to non-dependencies
+//! - Rejects named inputs that do not bind to a named output of a dependency
+//! - Rejects parameter values that break their definitions' constraints
 
 use std::fs;
 use std::path::Path;
 
+use job_config::job::{Inputs, Outputs};
+use job_config::params::{WorkflowParams, check_env_var_names, validate_values};
+use job_config::workflow::WorkflowMeta;
+
 use crate::components::workflow::JobFolder;
 
 /// A single violation found in a script.
@@ -162,6 +170,322 @@ pub fn check_input_files_folder(
     ))
 }
 
+/// CPUs and memory of the host, as reported by `sysinfo`.
+#[derive(Debug, Clone, Copy)]
+pub struct HostResources {
+    pub cpus: usize,
+    /// Total memory in bytes.
+    pub memory: u64,
+}
+
+impl HostResources {
+    /// Reads the host's logical CPU count and total memory.
+    pub fn detect() -> Self {
+        let mut sys = sysinfo::System::new();
+        sys.refresh_memory();
+        sys.refresh_cpu_specifics(sysinfo::CpuRefreshKind::new());
+        Self {
+            cpus: sys.cpus().len(),
+            memory: sys.total_memory(),
+        }
+    }
+}
+
+/// Checks that no job's `[resources]` asks for more CPUs or memory than the host has.
+///
+/// Returns `Err` listing every job whose limits cannot be met.
+pub fn check_resources(jobs: &[JobFolder]) -> Result<(), String> {
+    check_resources_against(jobs, HostResources::detect())
+}
+
+fn check_resources_against(jobs: &[JobFolder], host: HostResources) -> Result<(), String> {
+    let mut violations = Vec::new();
+
+    for job in jobs {
+        let Ok(meta) = job.load_meta() else {
+            continue;
+        };
+        let resources = &meta.resources;
+        if let Some(cpus) = resources.cpus {
+            if cpus <= 0.0 {
+                violations.push(format!("[{}] cpus = {cpus} must be positive", job.name));
+            } else if host.cpus > 0 && cpus > host.cpus as f64 {
+                violations.push(format!(
+                    "[{}] cpus = {cpus} exceeds the host's {} CPUs",
+                    job.name, host.cpus
+                ));
+            }
+        }
+        for (field, size) in [
+            ("memory", resources.memory),
+            ("shm_size", resources.shm_size),
+        ] {
+            if let Some(size) = size
+                && host.memory > 0
+                && size.0 > host.memory
+            {
+                violations.push(format!(
+                    "[{}] {field} = \"{size}\" exceeds the host's {:.1} GiB of memory",
+                    job.name,
+                    host.memory as f64 / (1u64 << 30) as f64
+                ));
+            }
+        }
+    }
+
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    let mut msg = String::from("Resource limits in job.toml cannot be met on this host.\n");
+    for violation in &violations {
+        msg.push_str(&format!("\n  {violation}"));
+    }
+    msg.push_str("\n\nFix: Lower the limits in the jobs' [resources] tables.");
+
+    Err(msg)
+}
+
+/// Checks that every job's `when` condition parses and only refers to the job's
+/// dependencies in `exists(...)`.
+///
+/// Returns `Err` listing every invalid condition.
+pub fn check_conditions(jobs: &[JobFolder], metadata: &WorkflowMeta) -> Result<(), String> {
+    let mut violations = Vec::new();
+
+    for job in jobs {
+        let Ok(meta) = job.load_meta() else {
+            continue;
+        };
+        let condition = match meta.condition() {
+            Ok(Some(condition)) => condition,
+            Ok(None) => continue,
+            Err(e) => {
+                violations.push(format!("[{}] {e}", job.name));
+                continue;
+            }
+        };
+        let dependencies = metadata.get_job_dependencies(&job.name);
+        for referenced in condition.jobs() {
+            if !dependencies.iter().any(|d| d == referenced) {
+                violations.push(format!(
+                    "[{}] 'when' refers to '{referenced}', which is not a dependency of the job",
+                    job.name
+                ));
+            }
+        }
+    }
+
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    let mut msg = String::from("Invalid 'when' conditions in job.toml.\n");
+    for violation in &violations {
+        msg.push_str(&format!("\n  {violation}"));
+    }
+    msg.push_str(
+        "\n\nFix: Correct the conditions, and add jobs checked with exists() to the job's dependencies in workflow.toml.",
+    );
+
+    Err(msg)
+}
+
+/// Checks the workflow's parameters and those of every job in `jobs` against their
+/// definitions, so invalid values never reach a job as `PARAM_*` variables.
+///
+/// A job's parameters are checked as the job sees them, on top of the workflow's.
+/// Relative paths of `must_exist` parameters are resolved against the workflow
+/// folder for workflow parameters and against the job folder for job parameters.
+///
+/// Returns `Err` listing every invalid or missing value.
+pub fn check_params(
+    workflow_path: &Path,
+    jobs: &[JobFolder],
+    metadata: &WorkflowMeta,
+    params: &WorkflowParams,
+) -> Result<(), String> {
+    let mut violations = Vec::new();
+
+    if let Err(e) = validate_values(&metadata.params, params, workflow_path) {
+        violations.extend(e.lines().map(|line| format!("[workflow] {line}")));
+    }
+    for job in jobs {
+        let Ok(meta) = job.load_meta() else {
+            continue;
+        };
+        let job_params = job
+            .load_params()
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| meta.generate_default_params());
+        let merged = crate::runner::merge_params(params, &job_params);
+        if let Err(e) = validate_values(&meta.params, &merged, &job.path) {
+            violations.extend(e.lines().map(|line| format!("[{}] {line}", job.name)));
+        }
+    }
+
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    let mut msg = String::from("Invalid parameter values.\n");
+    for violation in &violations {
+        msg.push_str(&format!("\n  {violation}"));
+    }
+    msg.push_str(
+        "\n\nFix: Set valid values in the parameter editors, or in global_params.json and the jobs' params.json.",
+    );
+
+    Err(msg)
+}
+
+/// Checks that parameters can be passed as environment variables: the workflow's
+/// `param_env_prefix` must be usable in a variable name, and no two parameters a
+/// job in `jobs` sees may become the same variable, e.g. `my-param` and
+/// `my_param`. Nothing is checked when the workflow sets `param_env = false`.
+///
+/// Returns `Err` listing every clash.
+pub fn check_param_env_names(
+    jobs: &[JobFolder],
+    metadata: &WorkflowMeta,
+    params: &WorkflowParams,
+) -> Result<(), String> {
+    let Some(prefix) = metadata.env_prefix() else {
+        return Ok(());
+    };
+    let mut violations = Vec::new();
+
+    let valid_prefix = prefix
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !prefix.starts_with(|c: char| c.is_ascii_digit());
+    if !valid_prefix {
+        violations.push(format!(
+            "[workflow] param_env_prefix '{prefix}' may only contain ASCII letters, digits and '_', and must not start with a digit"
+        ));
+    }
+    let workflow_clashes: Vec<String> = check_env_var_names(prefix, params.keys())
+        .err()
+        .map(|e| e.lines().map(String::from).collect())
+        .unwrap_or_default();
+    violations.extend(
+        workflow_clashes
+            .iter()
+            .map(|line| format!("[workflow] {line}")),
+    );
+    for job in jobs {
+        let Ok(meta) = job.load_meta() else {
+            continue;
+        };
+        let job_params = job
+            .load_params()
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| meta.generate_default_params());
+        let merged = crate::runner::merge_params(params, &job_params);
+        if let Err(e) = check_env_var_names(prefix, merged.keys()) {
+            // Clashes among the workflow's own parameters are reported once above
+            violations.extend(
+                e.lines()
+                    .filter(|line| !workflow_clashes.iter().any(|clash| clash == line))
+                    .map(|line| format!("[{}] {line}", job.name)),
+            );
+        }
+    }
+
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    let mut msg = String::from("Parameters clash as environment variables.\n");
+    for violation in &violations {
+        msg.push_str(&format!("\n  {violation}"));
+    }
+    msg.push_str(
+        "\n\nFix: Rename one of the clashing parameters, or set param_env = false in workflow.toml and read .silva/params.json instead.",
+    );
+
+    Err(msg)
+}
+
+/// Checks that every named input of the jobs in `jobs` binds to a dependency, and
+/// to one of its named outputs when it names an output, and stages into a plain
+/// folder name. `all_jobs` are the workflow's jobs, whose outputs are looked up.
+///
+/// Returns `Err` listing every invalid binding.
+pub fn check_input_bindings(
+    jobs: &[JobFolder],
+    all_jobs: &[JobFolder],
+    metadata: &WorkflowMeta,
+) -> Result<(), String> {
+    let mut violations = Vec::new();
+
+    for job in jobs {
+        let Ok(meta) = job.load_meta() else {
+            continue;
+        };
+        let Inputs::Named(bindings) = &meta.inputs else {
+            continue;
+        };
+        let dependencies = metadata.get_job_dependencies(&job.name);
+        for (name, binding) in bindings {
+            let source = binding.source();
+            if let Some(path) = binding.path()
+                && (path.is_empty() || path.starts_with('.') || path.contains(['/', '\\']))
+            {
+                violations.push(format!(
+                    "[{}] Input '{name}' has path '{path}', which is not a folder name",
+                    job.name
+                ));
+            }
+            let Some((dep, output)) = binding.split_source(dependencies) else {
+                violations.push(format!(
+                    "[{}] Input '{name}' binds to '{source}', which is not a dependency of the job",
+                    job.name
+                ));
+                continue;
+            };
+            let Some(output) = output else {
+                continue;
+            };
+            let Some(dep_meta) = all_jobs
+                .iter()
+                .find(|j| j.name == dep)
+                .and_then(|j| j.load_meta().ok())
+            else {
+                continue;
+            };
+            match &dep_meta.outputs {
+                Outputs::Named(outputs) if outputs.contains_key(output) => {}
+                Outputs::Named(outputs) => violations.push(format!(
+                    "[{}] Input '{name}' binds to '{source}', but '{dep}' only has outputs: {}",
+                    job.name,
+                    outputs.keys().cloned().collect::<Vec<_>>().join(", ")
+                )),
+                Outputs::Patterns(_) => violations.push(format!(
+                    "[{}] Input '{name}' binds to '{source}', but '{dep}' has no named outputs",
+                    job.name
+                )),
+            }
+        }
+    }
+
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    let mut msg = String::from("Invalid input bindings in job.toml.\n");
+    for violation in &violations {
+        msg.push_str(&format!("\n  {violation}"));
+    }
+    msg.push_str(
+        "\n\nFix: Bind inputs to \"<dependency>.<output>\", naming an [outputs] entry of a job listed as a dependency in workflow.toml.",
+    );
+
+    Err(msg)
+}
+
 /// Checks all job scripts for cross-node `../` path references.
 ///
 /// Jobs must use their `inputs/` folder instead of relative paths to siblings.
@@ -252,6 +576,8 @@ fn scan_script_for_cross_node_refs(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use job_config::job::ParamDefinition;
+    use job_config::params::JobParams;
     use std::fs;
     use tempfile::TempDir;
 
@@ -484,4 +810,198 @@ run = "run.sh"
         assert!(err.contains("[02-analysis]"));
         assert!(err.contains("[03-report]"));
     }
+
+    // --- resource checks ---
+
+    fn create_job_with_resources(base: &Path, name: &str, resources: &str) -> JobFolder {
+        let job = create_job(base, name, "#!/bin/bash\npython main.py\n");
+        let toml_path = job.path.join(".chiral").join("job.toml");
+        let content = fs::read_to_string(&toml_path).unwrap();
+        fs::write(&toml_path, format!("{content}\n[resources]\n{resources}\n")).unwrap();
+        job
+    }
+
+    const HOST: HostResources = HostResources {
+        cpus: 8,
+        memory: 16 << 30,
+    };
+
+    #[test]
+    fn test_resources_within_host_pass() {
+        let temp = TempDir::new().unwrap();
+        let job = create_job_with_resources(temp.path(), "01-dock", "cpus = 8\nmemory = \"16g\"");
+        let plain = create_job(temp.path(), "02-plain", "#!/bin/bash\npython main.py\n");
+        assert!(check_resources_against(&[job, plain], HOST).is_ok());
+    }
+
+    #[test]
+    fn test_resources_exceeding_host_rejected() {
+        let temp = TempDir::new().unwrap();
+        let cpus = create_job_with_resources(temp.path(), "01-dock", "cpus = 12");
+        let memory = create_job_with_resources(temp.path(), "02-md", "memory = \"32g\"");
+        let negative = create_job_with_resources(temp.path(), "03-neg", "cpus = -1");
+        let err = check_resources_against(&[cpus, memory, negative], HOST).unwrap_err();
+        assert!(err.contains("[01-dock] cpus = 12 exceeds the host's 8 CPUs"));
+        assert!(err.contains("[02-md] memory = \"32g\" exceeds the host's 16.0 GiB"));
+        assert!(err.contains("[03-neg] cpus = -1 must be positive"));
+    }
+
+    #[test]
+    fn test_conditions_must_parse_and_name_dependencies() {
+        let temp = TempDir::new().unwrap();
+        let job_with_when = |name: &str, when: &str| {
+            let job = create_job(temp.path(), name, "#!/bin/bash\n");
+            let mut meta = job.load_meta().unwrap();
+            meta.when = Some(when.to_string());
+            job.save_meta(&meta).unwrap();
+            job
+        };
+        let mut metadata = WorkflowMeta::new("wf".to_string(), String::new());
+        metadata
+            .dependencies
+            .insert("02-dock".to_string(), vec!["01-prep".to_string()]);
+
+        let valid = job_with_when("02-dock", "exists('01-prep/*.pdb') && params.dock");
+        assert!(check_conditions(&[valid], &metadata).is_ok());
+
+        let unparsable = job_with_when("03-score", "params.a ==");
+        let not_dependency = job_with_when("04-report", "!exists('01-prep/skip')");
+        let err = check_conditions(&[unparsable, not_dependency], &metadata).unwrap_err();
+        assert!(err.contains("[03-score] Invalid 'when' condition"));
+        assert!(err.contains("[04-report] 'when' refers to '01-prep'"));
+    }
+
+    #[test]
+    fn test_input_bindings_must_name_dependency_outputs() {
+        let temp = TempDir::new().unwrap();
+        let job_with_toml = |name: &str, toml: &str| {
+            let job = create_job(temp.path(), name, "#!/bin/bash\n");
+            let mut meta = job.load_meta().unwrap();
+            let io: job_config::job::JobMeta = toml::from_str(&format!(
+                "name = \"{name}\"\ndescription = \"\"\n[container]\nimage = \"ubuntu\"\n{toml}"
+            ))
+            .unwrap();
+            meta.inputs = io.inputs;
+            meta.outputs = io.outputs;
+            job.save_meta(&meta).unwrap();
+            job
+        };
+        let mut metadata = WorkflowMeta::new("wf".to_string(), String::new());
+        metadata.dependencies.insert(
+            "03-report".to_string(),
+            vec!["01-prep".to_string(), "02-train".to_string()],
+        );
+
+        let prep = job_with_toml("01-prep", "outputs = [\"*.csv\"]");
+        let train = job_with_toml("02-train", "[outputs]\nmodel = \"model.pkl\"");
+        let valid = job_with_toml(
+            "03-report",
+            "[inputs]\nmodel = \"02-train.model\"\ndata = { from = \"01-prep\", path = \"raw\" }",
+        );
+        let all = [prep.clone(), train.clone(), valid.clone()];
+        assert!(check_input_bindings(std::slice::from_ref(&valid), &all, &metadata).is_ok());
+
+        let invalid = job_with_toml(
+            "03-report",
+            "[inputs]\na = \"02-train.weights\"\nb = \"01-prep.table\"\nc = \"04-other.x\"\nd = { from = \"01-prep\", path = \"../up\" }",
+        );
+        let err = check_input_bindings(&[invalid], &all, &metadata).unwrap_err();
+        assert!(err.contains(
+            "Input 'a' binds to '02-train.weights', but '02-train' only has outputs: model"
+        ));
+        assert!(
+            err.contains("Input 'b' binds to '01-prep.table', but '01-prep' has no named outputs")
+        );
+        assert!(err.contains("Input 'c' binds to '04-other.x', which is not a dependency"));
+        assert!(err.contains("Input 'd' has path '../up'"));
+    }
+
+    #[test]
+    fn test_params_must_satisfy_their_definitions() {
+        let temp = TempDir::new().unwrap();
+        let job = create_job(temp.path(), "01-dock", "#!/bin/bash\n");
+        let mut meta = job.load_meta().unwrap();
+        let params: job_config::job::JobMeta = toml::from_str(
+            r#"
+            name = "01-dock"
+            description = ""
+            [container]
+            image = "ubuntu"
+            [params.exhaustiveness]
+            type = "integer"
+            default = 8
+            hint = ""
+            min = 1
+            [params.receptor]
+            type = "file"
+            required = true
+            hint = ""
+            must_exist = true
+            "#,
+        )
+        .unwrap();
+        meta.params = params.params;
+        job.save_meta(&meta).unwrap();
+        fs::write(job.path.join("receptor.pdb"), "ATOM").unwrap();
+        let mut metadata = WorkflowMeta::new("wf".to_string(), String::new());
+        let mut project = ParamDefinition::new(
+            job_config::job::ParamType::String,
+            toml::Value::from("default"),
+            String::new(),
+            None,
+        );
+        project.pattern = Some("[a-z]+".to_string());
+        metadata.add_param("project".to_string(), project);
+
+        let mut values = JobParams::new();
+        values.insert("receptor".to_string(), serde_json::json!("receptor.pdb"));
+        job.save_params(&values).unwrap();
+        let global = WorkflowParams::from([("project".to_string(), serde_json::json!("dock"))]);
+        assert!(check_params(temp.path(), std::slice::from_ref(&job), &metadata, &global).is_ok());
+
+        values.insert("exhaustiveness".to_string(), serde_json::json!(0));
+        values.remove("receptor");
+        job.save_params(&values).unwrap();
+        let global = WorkflowParams::from([("project".to_string(), serde_json::json!("Dock 1"))]);
+        let err = check_params(temp.path(), &[job], &metadata, &global).unwrap_err();
+        assert!(err.contains(
+            "[workflow] Parameter 'project': Value 'Dock 1' does not match pattern '[a-z]+'"
+        ));
+        assert!(
+            err.contains("[01-dock] Parameter 'exhaustiveness': Value 0 is below the minimum of 1")
+        );
+        assert!(err.contains("[01-dock] Parameter 'receptor' is required but has no value"));
+    }
+
+    #[test]
+    fn test_params_must_not_clash_as_env_vars() {
+        let temp = TempDir::new().unwrap();
+        let job = create_job(temp.path(), "01-dock", "#!/bin/bash\n");
+        let values = JobParams::from([
+            ("my-param".to_string(), serde_json::json!(1)),
+            ("rounds".to_string(), serde_json::json!(2)),
+        ]);
+        job.save_params(&values).unwrap();
+        let mut metadata = WorkflowMeta::new("wf".to_string(), String::new());
+        let global = WorkflowParams::from([
+            ("my_param".to_string(), serde_json::json!(3)),
+            ("Seed".to_string(), serde_json::json!(4)),
+            ("SEED".to_string(), serde_json::json!(5)),
+        ]);
+
+        let err =
+            check_param_env_names(std::slice::from_ref(&job), &metadata, &global).unwrap_err();
+        assert!(err.contains("[workflow] Parameters 'SEED', 'Seed' are all passed as PARAM_SEED"));
+        assert!(err.contains(
+            "[01-dock] Parameters 'my-param', 'my_param' are all passed as PARAM_MY_PARAM"
+        ));
+        assert!(!err.contains("[01-dock] Parameters 'SEED'"));
+
+        metadata.param_env_prefix = Some("1-".to_string());
+        let err = check_param_env_names(&[], &metadata, &WorkflowParams::new()).unwrap_err();
+        assert!(err.contains("[workflow] param_env_prefix '1-' may only contain"));
+
+        metadata.param_env = false;
+        assert!(check_param_env_names(&[job], &metadata, &global).is_ok());
+    }
 }
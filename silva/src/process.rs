@@ -0,0 +1,118 @@
+//! Streaming of child process output as job log lines.
+//!
+//! Backends that run scripts as host processes (Apptainer, local) spawn one
+//! process per script and forward its stdout/stderr line by line over the
+//! job's message channel, exactly as Docker exec output is forwarded.
+
+use std::fmt;
+use std::process::Stdio;
+
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+
+use crate::components::docker::job::JobStatus;
+use crate::components::docker::logs::{LogLine, LogSource};
+use crate::runner::Message;
+
+/// Error raised while running a streamed process.
+#[derive(Debug)]
+pub enum ProcessError {
+    SpawnFailed(std::io::Error),
+    IoError(std::io::Error),
+    ChannelSendMessageError(String),
+}
+
+impl fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessError::SpawnFailed(err) => write!(f, "Failed to start process: {err}"),
+            ProcessError::IoError(err) => write!(f, "IO error: {err}"),
+            ProcessError::ChannelSendMessageError(err) => {
+                write!(f, "MPSC channel send message error: {err}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProcessError {}
+
+/// Runs `command`, sending each output line as a `Running` message for `job_idx`.
+///
+/// Stdin is closed. When a cancel signal arrives the process is killed, together
+/// with everything it started (on Unix it leads its own process group).
+///
+/// # Returns
+///
+/// The process exit code, or 1 if it was killed by a signal.
+pub async fn run_streaming(
+    mut command: Command,
+    tx: &mpsc::Sender<Message>,
+    job_idx: usize,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> Result<i64, ProcessError> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    command.process_group(0);
+
+    let mut child = command.spawn().map_err(ProcessError::SpawnFailed)?;
+    let mut stdout = child.stdout.take().map(|s| BufReader::new(s).lines());
+    let mut stderr = child.stderr.take().map(|s| BufReader::new(s).lines());
+
+    let send = |source: LogSource, content: String| async move {
+        tx.send((job_idx, JobStatus::Running, LogLine::new(source, content)))
+            .await
+            .map_err(|e| {
+                ProcessError::ChannelSendMessageError(format!(
+                    "Message channel receiver dropped: {e}"
+                ))
+            })
+    };
+
+    // Reads until both streams close; the cancel sender usually outlives the process
+    while stdout.is_some() || stderr.is_some() {
+        tokio::select! {
+            line = async { stdout.as_mut().unwrap().next_line().await }, if stdout.is_some() => {
+                match line.map_err(ProcessError::IoError)? {
+                    Some(content) => send(LogSource::Stdout, content).await?,
+                    None => stdout = None,
+                }
+            }
+            line = async { stderr.as_mut().unwrap().next_line().await }, if stderr.is_some() => {
+                match line.map_err(ProcessError::IoError)? {
+                    Some(content) => send(LogSource::Stderr, content).await?,
+                    None => stderr = None,
+                }
+            }
+            Some(()) = cancel_rx.recv() => {
+                kill_process_group(&mut child).map_err(ProcessError::IoError)?;
+                break;
+            }
+        }
+    }
+
+    let status = child.wait().await.map_err(ProcessError::IoError)?;
+    Ok(status.code().map(i64::from).unwrap_or(1))
+}
+
+/// Kills `child` and, on Unix, the other processes of its process group, such as
+/// the commands a killed shell script was running.
+fn kill_process_group(child: &mut Child) -> std::io::Result<()> {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // The child leads its own process group, whose id is its pid
+        // SAFETY: killpg only sends a signal; it does not touch our memory
+        if unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) } != 0 {
+            let err = std::io::Error::last_os_error();
+            // The whole group has already exited
+            if err.raw_os_error() != Some(libc::ESRCH) {
+                return Err(err);
+            }
+        }
+    }
+    child.start_kill()
+}
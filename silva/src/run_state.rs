@@ -0,0 +1,309 @@
+//! Persisted state of a workflow run, used to resume it.
+//!
+//! Every job that finishes successfully is recorded in
+//! `<temp workflow>/.chiral/run_state.json` together with a fingerprint of
+//! everything that determines its result: the job folder (scripts, job.toml,
+//! params), its `[matrix.<job>]` sweep in workflow.toml, the global parameters,
+//! parameter overrides of the run, `input_files/` for jobs without dependencies,
+//! and the fingerprints of its dependencies. A resumed run skips jobs in
+//! `@complete/` whose fingerprint is unchanged.
+//!
+//! The instances of a matrix job are recorded as they finish, and the job itself
+//! once all of them have.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use job_config::params::WorkflowParams;
+use job_config::workflow::WorkflowMeta;
+use serde::{Deserialize, Serialize};
+
+use crate::components::workflow::JobFolder;
+use crate::overrides::ParamOverrides;
+use crate::utils::ContentHasher;
+
+/// File name of the run state, inside the temp workflow's `.chiral/` folder.
+pub const RUN_STATE_FILE: &str = "run_state.json";
+
+/// Job folder entries that are produced by a run and not part of its definition.
+const FINGERPRINT_SKIP: &[&str] = &["inputs", "outputs"];
+
+/// Jobs completed so far in a temp workflow folder.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunState {
+    /// Completed jobs by name
+    pub jobs: BTreeMap<String, CompletedJob>,
+}
+
+/// A job recorded as completed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompletedJob {
+    pub fingerprint: String,
+    pub completed_at: String,
+    /// Instances of a matrix job, each recorded as a job of its own
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub instances: Vec<String>,
+}
+
+impl RunState {
+    /// Returns the run state path of the temp workflow at `workflow_path`.
+    pub fn path(workflow_path: &Path) -> PathBuf {
+        workflow_path.join(".chiral").join(RUN_STATE_FILE)
+    }
+
+    /// Loads the run state of `workflow_path`. Returns `None` if there is none.
+    pub fn load(workflow_path: &Path) -> Result<Option<Self>, String> {
+        let path = Self::path(workflow_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse {}: {e}", path.display()))
+    }
+
+    /// Writes the run state into `workflow_path`.
+    pub fn save(&self, workflow_path: &Path) -> Result<(), String> {
+        let path = Self::path(workflow_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize run state: {e}"))?;
+        fs::write(&path, content).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+    }
+
+    /// Records `job_name` as completed with `fingerprint`.
+    pub fn record_completed(&mut self, job_name: &str, fingerprint: &str) {
+        self.record_matrix_completed(job_name, fingerprint, Vec::new());
+    }
+
+    /// Records matrix job `job_name` as completed with `fingerprint`, once all of
+    /// its `instances` have.
+    pub fn record_matrix_completed(
+        &mut self,
+        job_name: &str,
+        fingerprint: &str,
+        instances: Vec<String>,
+    ) {
+        self.jobs.insert(
+            job_name.to_string(),
+            CompletedJob {
+                fingerprint: fingerprint.to_string(),
+                completed_at: chrono::Local::now().to_rfc3339(),
+                instances,
+            },
+        );
+    }
+
+    /// Instances `job_name` completed with, if it is a matrix job.
+    pub fn instances(&self, job_name: &str) -> &[String] {
+        self.jobs
+            .get(job_name)
+            .map_or(&[], |job| job.instances.as_slice())
+    }
+
+    /// Returns `true` if `job_name` completed with the given `fingerprint`.
+    pub fn is_completed(&self, job_name: &str, fingerprint: &str) -> bool {
+        self.jobs
+            .get(job_name)
+            .is_some_and(|job| job.fingerprint == fingerprint)
+    }
+}
+
+/// Computes the fingerprint of every job in `sorted_jobs`.
+///
+/// Job folders are read from `source_path`, the original workflow folder, so
+/// the fingerprints do not depend on anything a run wrote. `sorted_jobs` must be
+/// in dependency order.
+///
+/// `params` are the global parameters, with any overrides for every job applied;
+/// the overrides for single jobs are taken from `overrides`.
+pub fn job_fingerprints(
+    source_path: &Path,
+    sorted_jobs: &[JobFolder],
+    metadata: &WorkflowMeta,
+    params: &WorkflowParams,
+    overrides: &ParamOverrides,
+) -> Result<HashMap<String, String>, String> {
+    let sorted_params: BTreeMap<_, _> = params.iter().collect();
+    let params_json = serde_json::to_string(&sorted_params)
+        .map_err(|e| format!("Failed to serialize parameters: {e}"))?;
+    let input_files = source_path.join("input_files");
+    let input_files_hash = if input_files.is_dir() {
+        let mut hasher = ContentHasher::new();
+        hasher
+            .update_dir(&input_files, &[])
+            .map_err(|e| format!("Failed to read input_files/: {e}"))?;
+        hasher.finish()
+    } else {
+        String::new()
+    };
+
+    let mut fingerprints = HashMap::new();
+    for job in sorted_jobs {
+        let mut hasher = ContentHasher::new();
+        hasher
+            .update_dir(&source_path.join(&job.name), FINGERPRINT_SKIP)
+            .map_err(|e| format!("Failed to read job '{}': {e}", job.name))?;
+        hasher.update(params_json.as_bytes());
+        if let Some(job_overrides) = overrides.job(&job.name) {
+            let sorted: BTreeMap<_, _> = job_overrides.iter().collect();
+            let json = serde_json::to_string(&sorted)
+                .map_err(|e| format!("Failed to serialize parameters: {e}"))?;
+            hasher.update(json.as_bytes());
+        }
+        if let Some(matrix) = metadata.matrix.get(&job.name) {
+            let json = serde_json::to_string(matrix)
+                .map_err(|e| format!("Failed to serialize matrix: {e}"))?;
+            hasher.update(json.as_bytes());
+        }
+
+        let dependencies = metadata.get_job_dependencies(&job.name);
+        if dependencies.is_empty() {
+            hasher.update(input_files_hash.as_bytes());
+        } else {
+            let mut dependencies = dependencies.to_vec();
+            dependencies.sort();
+            for dependency in &dependencies {
+                hasher.update(dependency.as_bytes());
+                let fingerprint = fingerprints.get(dependency).map(String::as_str);
+                hasher.update(fingerprint.unwrap_or_default().as_bytes());
+            }
+        }
+
+        fingerprints.insert(job.name.clone(), hasher.finish());
+    }
+    Ok(fingerprints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_job(root: &Path, name: &str, script: &str) -> JobFolder {
+        let job = JobFolder::new(name.to_string(), root.join(name));
+        fs::create_dir_all(&job.path).unwrap();
+        fs::write(job.path.join("run.sh"), script).unwrap();
+        job
+    }
+
+    #[test]
+    fn test_run_state_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(RunState::load(tmp.path()).unwrap(), None);
+
+        let mut state = RunState::default();
+        state.record_completed("a", "abc");
+        state.save(tmp.path()).unwrap();
+
+        let loaded = RunState::load(tmp.path()).unwrap().unwrap();
+        assert!(loaded.is_completed("a", "abc"));
+        assert!(!loaded.is_completed("a", "def"));
+        assert!(!loaded.is_completed("b", "abc"));
+    }
+
+    #[test]
+    fn test_fingerprints_follow_dependencies_and_ignore_outputs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let jobs = vec![
+            write_job(tmp.path(), "a", "echo a"),
+            write_job(tmp.path(), "b", "echo b"),
+        ];
+        let mut metadata = WorkflowMeta::new("wf".to_string(), String::new());
+        metadata.set_job_dependencies("b".to_string(), vec!["a".to_string()]);
+        let params = WorkflowParams::new();
+        let before = job_fingerprints(
+            tmp.path(),
+            &jobs,
+            &metadata,
+            &params,
+            &ParamOverrides::default(),
+        )
+        .unwrap();
+
+        // Files written by a run do not change the fingerprint
+        fs::create_dir_all(tmp.path().join("a/outputs")).unwrap();
+        fs::write(tmp.path().join("a/outputs/result.txt"), "1").unwrap();
+        let unchanged = job_fingerprints(
+            tmp.path(),
+            &jobs,
+            &metadata,
+            &params,
+            &ParamOverrides::default(),
+        )
+        .unwrap();
+        assert_eq!(before, unchanged);
+
+        // Changing an upstream script invalidates everything downstream
+        fs::write(tmp.path().join("a/run.sh"), "echo A").unwrap();
+        let changed = job_fingerprints(
+            tmp.path(),
+            &jobs,
+            &metadata,
+            &params,
+            &ParamOverrides::default(),
+        )
+        .unwrap();
+        assert_ne!(before["a"], changed["a"]);
+        assert_ne!(before["b"], changed["b"]);
+    }
+
+    #[test]
+    fn test_fingerprints_cover_params_and_input_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let jobs = vec![write_job(tmp.path(), "a", "echo a")];
+        let metadata = WorkflowMeta::new("wf".to_string(), String::new());
+        let mut params = WorkflowParams::new();
+        let before = job_fingerprints(
+            tmp.path(),
+            &jobs,
+            &metadata,
+            &params,
+            &ParamOverrides::default(),
+        )
+        .unwrap();
+
+        params.insert("epochs".to_string(), serde_json::json!(10));
+        let with_params = job_fingerprints(
+            tmp.path(),
+            &jobs,
+            &metadata,
+            &params,
+            &ParamOverrides::default(),
+        )
+        .unwrap();
+        assert_ne!(before["a"], with_params["a"]);
+
+        fs::create_dir_all(tmp.path().join("input_files")).unwrap();
+        fs::write(tmp.path().join("input_files/data.csv"), "x").unwrap();
+        let with_inputs = job_fingerprints(
+            tmp.path(),
+            &jobs,
+            &metadata,
+            &params,
+            &ParamOverrides::default(),
+        )
+        .unwrap();
+        assert_ne!(with_params["a"], with_inputs["a"]);
+
+        // So does the job's sweep in workflow.toml
+        let mut metadata = metadata;
+        metadata
+            .matrix
+            .insert("a".to_string(), toml::from_str("seed = [1, 2]").unwrap());
+        let with_matrix = job_fingerprints(
+            tmp.path(),
+            &jobs,
+            &metadata,
+            &params,
+            &ParamOverrides::default(),
+        )
+        .unwrap();
+        assert_ne!(with_inputs["a"], with_matrix["a"]);
+    }
+}
@@ -0,0 +1,3317 @@
+//! Workflow runner shared by the TUI and headless mode.
+//!
+//! A run has two phases. `prepare_workflow` loads the workflow's metadata and
+//! parameters, sorts the jobs in dependency order, runs the pre-checks and stages
+//! `input_files/` into the temporary copy of the workflow. `execute` then runs the
+//! jobs on an `Executor`: for each job it copies dependency outputs into
+//! `inputs/`, runs `pre_run.sh` → `run.sh` → `post_run.sh`, collects outputs and
+//! moves the finished job to `@complete/`.
+//!
+//! Completed jobs are recorded in the run state (see `crate::run_state`), so a
+//! failed run can be resumed in its temp folder with `prepare_resume`: jobs that
+//! completed with unchanged inputs, scripts and params are reused as they are.
+//!
+//! Progress is reported as `(job index, status, log line)` messages. A message with
+//! index `jobs.len()` marks the end of the run.
+//!
+//! A message on the cancel channel stops the scripts of the running jobs, marks
+//! them and every job not yet started as `Cancelled`, and ends the run with a
+//! `Cancelled` end-of-run message.
+//!
+//! Jobs with `gpu = "required"` fail the run before any job starts when the
+//! executor has no GPU runtime. Jobs whose `gpu` policy gives them NVIDIA GPUs
+//! reserve distinct devices (see `crate::gpu`) for as long as they run.
+//!
+//! A job whose `when` condition is false is `Skipped` instead of run, and so is
+//! every job depending on a skipped job. Skipped jobs do not stop the run.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use job_config::condition::ConditionContext;
+use job_config::interpolate::{Interpolator, RunInfo, has_references};
+use job_config::job::{
+    GpuPolicy, Gpus, HumanDuration, InputBinding, Inputs, Interpreter, JobMeta, Mount,
+};
+use job_config::params::{JobParams, WorkflowParams, env_var_name, env_var_value};
+use job_config::workflow::{Isolation, WorkflowMeta};
+use tempfile::TempDir;
+use tokio::sync::mpsc;
+
+use crate::backend::Backend;
+use crate::cache::JobCache;
+use crate::components::docker::job::JobStatus;
+use crate::components::docker::logs::{LogLine, LogSource};
+use crate::components::workflow::{JobFolder, WorkflowFolder};
+use crate::executor::{EnvironmentSpec, Executor, ScriptCommand};
+use crate::gpu::{GpuAllocator, GpuLease, GpuRuntime};
+use crate::history::RunHistory;
+use crate::matrix::MatrixInstance;
+use crate::overrides::{ParamArgs, ParamOverrides};
+use crate::run_state::RunState;
+use crate::utils::copy_dir_recursive;
+
+/// How often a job waiting for GPUs held by other jobs checks again.
+const GPU_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Folder in each job folder that silva writes files for the job's scripts to.
+pub const PARAMS_FILE_DIR: &str = ".silva";
+
+/// Progress message: `(job index, status, log line)`.
+pub type Message = (usize, JobStatus, LogLine);
+
+/// Options of a single workflow run.
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// `KEY=VALUE` strings from `-e/--env`, injected unprefixed into every job,
+    /// independent of `env_passthrough`
+    pub cli_env_vars: Vec<String>,
+    /// Value of `-j/--jobs`; overrides `max_parallel` from workflow.toml
+    pub max_parallel: Option<usize>,
+    /// Requested backend; `Backend::Auto` picks Apptainer for `.sif` images
+    pub backend: Backend,
+    /// Temp folder of an earlier run to resume, from `--resume`
+    pub resume: Option<PathBuf>,
+    /// Value of `--cache`; enables the result cache even if workflow.toml does not
+    pub cache: bool,
+    /// Run history the run is recorded in, if any
+    pub history: Option<RunHistory>,
+    /// Parameter overrides from `--profile`, `--params-file` and `--param`
+    pub params: ParamArgs,
+}
+
+/// A workflow copied to its temporary folder and ready to run.
+#[derive(Debug)]
+pub struct PreparedWorkflow {
+    /// Temporary copy of the workflow the jobs run in
+    pub temp_workflow_path: PathBuf,
+    /// Jobs in the order used for message indices
+    pub jobs: Vec<JobFolder>,
+    /// Jobs in dependency order
+    pub sorted_jobs: Vec<JobFolder>,
+    pub metadata: WorkflowMeta,
+    pub params: WorkflowParams,
+    /// Fingerprint of each job, recorded in the run state when it completes
+    pub fingerprints: HashMap<String, String>,
+    /// Jobs completed by an earlier run that are reused instead of run again
+    pub reused_jobs: HashSet<String>,
+    /// Resolved `[[mounts]]` of each job to run
+    pub mounts: HashMap<String, Vec<Mount>>,
+    /// Instances of the matrix jobs by instance name; see `crate::matrix`
+    pub instances: HashMap<String, MatrixInstance>,
+    /// Run state the completed jobs of this run are added to
+    pub run_state: RunState,
+    /// Messages produced while preparing, for the caller to show
+    pub notes: Vec<LogLine>,
+}
+
+impl PreparedWorkflow {
+    /// Loads the configuration of every job, skipping jobs whose job.toml is invalid.
+    pub fn job_configs(&self) -> Vec<JobMeta> {
+        self.sorted_jobs
+            .iter()
+            .filter_map(|job| job.load_meta().ok())
+            .collect()
+    }
+}
+
+/// Creates a temporary folder and copies the workflow contents to it.
+pub fn create_temp_workflow_folder(source_path: &Path) -> std::io::Result<TempDir> {
+    let now = chrono::Local::now();
+    let timestamp = now.format("%Y-%m-%d-%H-%M-%S").to_string();
+    let prefix = format!("silva-{timestamp}-");
+    let temp_dir = tempfile::Builder::new().prefix(&prefix).tempdir()?;
+
+    // Copy source folder contents to temp directory
+    let mut options = fs_extra::dir::CopyOptions::new();
+    options.overwrite = true;
+    options.copy_inside = true;
+    options.content_only = true;
+
+    fs_extra::dir::copy(source_path, temp_dir.path(), &options)
+        .map_err(|e| std::io::Error::other(format!("copy folder error {e}")))?;
+
+    Ok(temp_dir)
+}
+
+/// Prepares `workflow_folder` for running from `temp_workflow_path`.
+///
+/// `jobs` may point into the original workflow folder or into the temporary copy;
+/// the returned jobs always point into the temporary copy.
+///
+/// # Returns
+///
+/// * `Ok(PreparedWorkflow)` - The workflow passed all checks
+/// * `Err(String)` - Dependency error or failed pre-check
+pub fn prepare_workflow(
+    workflow_folder: &WorkflowFolder,
+    temp_workflow_path: &Path,
+    jobs: &[JobFolder],
+    param_args: &ParamArgs,
+) -> Result<PreparedWorkflow, String> {
+    prepare(workflow_folder, temp_workflow_path, jobs, None, param_args)
+}
+
+/// Prepares `workflow_folder` for resuming the earlier run in `temp_workflow_path`.
+///
+/// Jobs in `@complete/` whose fingerprint matches the run state, and whose
+/// dependencies are reused as well, are reused. Every other job is copied afresh
+/// from `workflow_folder`, replacing whatever the earlier run left behind.
+///
+/// # Returns
+///
+/// * `Ok(PreparedWorkflow)` - The workflow passed all checks
+/// * `Err(String)` - No run state in `temp_workflow_path`, dependency error or
+///   failed pre-check
+pub fn prepare_resume(
+    workflow_folder: &WorkflowFolder,
+    temp_workflow_path: &Path,
+    jobs: &[JobFolder],
+    param_args: &ParamArgs,
+) -> Result<PreparedWorkflow, String> {
+    let run_state = RunState::load(temp_workflow_path)?.ok_or_else(|| {
+        format!(
+            "No run state found in {}; only folders of earlier silva runs can be resumed",
+            temp_workflow_path.display()
+        )
+    })?;
+    prepare(
+        workflow_folder,
+        temp_workflow_path,
+        jobs,
+        Some(run_state),
+        param_args,
+    )
+}
+
+/// Shared implementation of `prepare_workflow` and `prepare_resume`.
+fn prepare(
+    workflow_folder: &WorkflowFolder,
+    temp_workflow_path: &Path,
+    jobs: &[JobFolder],
+    previous_run: Option<RunState>,
+    param_args: &ParamArgs,
+) -> Result<PreparedWorkflow, String> {
+    let mut notes = Vec::new();
+
+    // Load workflow metadata (dependencies are managed here, not in job.toml)
+    let metadata = workflow_folder
+        .load_workflow_metadata()
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| WorkflowMeta {
+            // Workflows without a workflow.toml predate job isolation
+            isolation: Isolation::Workflow,
+            ..WorkflowMeta::new(workflow_folder.name.clone(), String::new())
+        });
+
+    // Load workflow parameters
+    let mut params = workflow_folder
+        .load_workflow_params()
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    if !params.is_empty() {
+        notes.push(LogLine::new(
+            LogSource::Stdout,
+            format!("Loaded {} global workflow parameter(s)", params.len()),
+        ));
+    }
+
+    // Overrides are checked against the original job folders, which exist even
+    // for jobs a resumed run moved to @complete/
+    let source_jobs: Vec<JobFolder> = jobs
+        .iter()
+        .map(|job| JobFolder::new(job.name.clone(), workflow_folder.path.join(&job.name)))
+        .collect();
+    let overrides = param_args.resolve(&workflow_folder.path, &metadata, &source_jobs)?;
+    if !overrides.is_empty() {
+        params.extend(overrides.global().clone());
+        notes.push(LogLine::new(
+            LogSource::Stdout,
+            format!(
+                "Overriding parameter(s): {}",
+                overrides.describe().join(", ")
+            ),
+        ));
+    }
+
+    let jobs: Vec<JobFolder> = jobs
+        .iter()
+        .map(|job| JobFolder::new(job.name.clone(), temp_workflow_path.join(&job.name)))
+        .collect();
+
+    // Sort jobs in dependency order
+    let sorted_jobs = topological_sort_jobs(&jobs, &metadata)?;
+    notes.push(LogLine::new(
+        LogSource::Stdout,
+        format!(
+            "Execution order: {}",
+            sorted_jobs
+                .iter()
+                .map(|j| j.name.as_str())
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        ),
+    ));
+
+    let fingerprints = crate::run_state::job_fingerprints(
+        &workflow_folder.path,
+        &sorted_jobs,
+        &metadata,
+        &params,
+        &overrides,
+    )?;
+
+    // When resuming, keep unchanged completed jobs and start the others afresh
+    let mut run_state = RunState::default();
+    let mut reused_jobs = HashSet::new();
+    if let Some(previous_run) = previous_run {
+        reused_jobs = reusable_jobs(
+            temp_workflow_path,
+            &sorted_jobs,
+            &metadata,
+            &previous_run,
+            &fingerprints,
+        );
+        for job in sorted_jobs
+            .iter()
+            .filter(|j| !reused_jobs.contains(&j.name))
+        {
+            reset_job_folder(
+                &workflow_folder.path,
+                temp_workflow_path,
+                &job.name,
+                previous_run.instances(&job.name),
+            )?;
+        }
+        // Reused matrix jobs keep their instances, which `matrix::expand` restores
+        let reused_instances: HashSet<String> = reused_jobs
+            .iter()
+            .flat_map(|job| previous_run.instances(job))
+            .cloned()
+            .collect();
+        run_state.jobs = previous_run
+            .jobs
+            .into_iter()
+            .filter(|(name, _)| reused_jobs.contains(name) || reused_instances.contains(name))
+            .collect();
+
+        let reused: Vec<&str> = sorted_jobs
+            .iter()
+            .filter(|j| reused_jobs.contains(&j.name))
+            .map(|j| j.name.as_str())
+            .collect();
+        notes.push(LogLine::new(
+            LogSource::Stdout,
+            if reused.is_empty() {
+                "Resuming: no completed job can be reused, running all jobs".to_string()
+            } else {
+                format!(
+                    "Resuming: reusing {} unchanged completed job(s): {}",
+                    reused.len(),
+                    reused.join(", ")
+                )
+            },
+        ));
+    }
+    let jobs_to_run: Vec<JobFolder> = sorted_jobs
+        .iter()
+        .filter(|j| !reused_jobs.contains(&j.name))
+        .cloned()
+        .collect();
+    if !overrides.is_empty() {
+        apply_overrides(&jobs_to_run, &overrides)?;
+    }
+
+    // Pre-checks: reject workflows that violate conventions
+    crate::precheck::check_install_commands(&jobs_to_run)?;
+    crate::precheck::check_cross_node_references(&jobs_to_run)?;
+    crate::precheck::check_input_files_folder(&workflow_folder.path, &jobs_to_run, &metadata)?;
+    crate::precheck::check_resources(&jobs_to_run)?;
+    crate::precheck::check_conditions(&jobs_to_run, &metadata)?;
+    crate::precheck::check_input_bindings(&jobs_to_run, &sorted_jobs, &metadata)?;
+    let mounts = crate::mounts::resolve_workflow_mounts(
+        &workflow_folder.path,
+        &metadata,
+        &jobs_to_run,
+        &crate::mounts::allowed_roots(),
+    )?;
+
+    normalize_scripts(&jobs_to_run, &mut notes)?;
+
+    // Copy input_files to all jobs without dependencies
+    copy_input_files_to_dependency_free_jobs(
+        &workflow_folder.path,
+        temp_workflow_path,
+        &jobs_to_run,
+        &metadata,
+        &mut notes,
+    );
+
+    let mut workflow = PreparedWorkflow {
+        temp_workflow_path: temp_workflow_path.to_path_buf(),
+        jobs,
+        sorted_jobs,
+        metadata,
+        params,
+        fingerprints,
+        reused_jobs,
+        mounts,
+        instances: HashMap::new(),
+        run_state,
+        notes,
+    };
+    // Replace matrix jobs by their instances, now that their inputs/ are staged
+    crate::matrix::expand(&mut workflow)?;
+    // Resolved and checked after the expansion, which sets the parameters of
+    // matrix instances
+    let expanded_jobs: Vec<JobFolder> = workflow
+        .sorted_jobs
+        .iter()
+        .filter(|j| !workflow.reused_jobs.contains(&j.name))
+        .cloned()
+        .collect();
+    interpolate_params(&mut workflow, &expanded_jobs)?;
+    crate::precheck::check_params(
+        &workflow.temp_workflow_path,
+        &expanded_jobs,
+        &workflow.metadata,
+        &workflow.params,
+    )?;
+    crate::precheck::check_param_env_names(&expanded_jobs, &workflow.metadata, &workflow.params)?;
+    Ok(workflow)
+}
+
+/// Runs every job of `workflow` on `executor`.
+///
+/// Independent jobs run in parallel up to the resolved `max_parallel`. After the
+/// last job, the executor is cleaned up and the end-of-run message is sent.
+///
+/// A message on `cancel_rx` stops the running jobs and skips the others; the
+/// executor is still cleaned up.
+///
+/// # Returns
+///
+/// `true` if every job succeeded.
+pub async fn execute<E: Executor>(
+    executor: E,
+    workflow: PreparedWorkflow,
+    options: &RunOptions,
+    tx: mpsc::Sender<Message>,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> bool {
+    let max_parallel =
+        crate::scheduler::resolve_max_parallel(options.max_parallel, &workflow.metadata);
+    let jobs_len = workflow.jobs.len();
+
+    // Write the run state up front, so even a run whose first job fails can be resumed
+    if let Err(e) = workflow.run_state.save(&workflow.temp_workflow_path) {
+        let log_line = LogLine::new(LogSource::Stderr, format!("Warning: {e}"));
+        let _ = tx.send((0, JobStatus::Idle, log_line)).await;
+    }
+
+    // Fail before any job starts if a job requires a GPU the host does not have
+    let gpu_runtime = executor.gpu_runtime();
+    let gpu_policies: Vec<(usize, String, GpuPolicy)> = workflow
+        .jobs
+        .iter()
+        .enumerate()
+        .filter(|(_, job)| !workflow.reused_jobs.contains(&job.name))
+        .filter_map(|(idx, job)| {
+            let policy = job.load_meta().ok()?.resources.gpu_policy()?;
+            Some((idx, job.name.clone(), policy))
+        })
+        .collect();
+    if gpu_runtime == GpuRuntime::None {
+        let missing_gpu: Vec<&(usize, String, GpuPolicy)> = gpu_policies
+            .iter()
+            .filter(|(_, _, policy)| *policy == GpuPolicy::Required)
+            .collect();
+        if !missing_gpu.is_empty() {
+            for (idx, name, _) in missing_gpu {
+                let log_line = LogLine::new(
+                    LogSource::Stderr,
+                    format!(
+                        "Job '{name}' requires a GPU (gpu = \"required\"), but the host has no GPU runtime"
+                    ),
+                );
+                let _ = tx.send((*idx, JobStatus::Failed, log_line)).await;
+            }
+            let _ = tx
+                .send((jobs_len, JobStatus::Failed, LogLine::empty()))
+                .await;
+            return false;
+        }
+    }
+    // Hand out distinct NVIDIA GPUs to jobs that ask for them
+    let uses_gpus = gpu_policies
+        .iter()
+        .any(|(_, _, policy)| *policy != GpuPolicy::None);
+    let gpus = (gpu_runtime == GpuRuntime::Nvidia && uses_gpus)
+        .then(GpuAllocator::detect)
+        .filter(|allocator| !allocator.devices().is_empty())
+        .map(Arc::new);
+
+    let cache =
+        (options.cache || workflow.metadata.cache).then(|| Arc::new(JobCache::open_default()));
+    let runner = JobRunner {
+        executor,
+        tx: tx.clone(),
+        cache,
+        gpus,
+        run_state: Arc::new(Mutex::new(workflow.run_state.clone())),
+        workflow: Arc::new(workflow),
+        cli_env_vars: Arc::new(options.cli_env_vars.clone()),
+        cancelled: Arc::new(AtomicBool::new(false)),
+        skipped: Arc::new(Mutex::new(HashSet::new())),
+    };
+
+    let mut started = HashSet::new();
+    let all_succeeded = crate::scheduler::run_jobs(
+        &runner.workflow.sorted_jobs,
+        &runner.workflow.metadata,
+        max_parallel,
+        cancel_rx,
+        &runner.cancelled,
+        |job, job_cancel_rx| {
+            let idx = runner
+                .workflow
+                .jobs
+                .iter()
+                .position(|j| j.name == job.name)
+                .unwrap_or_default();
+            started.insert(idx);
+            runner.clone().run(job, idx, job_cancel_rx)
+        },
+    )
+    .await;
+
+    let cancelled = runner.cancelled.load(Ordering::SeqCst);
+    if cancelled {
+        for (idx, job) in runner.workflow.jobs.iter().enumerate() {
+            if !started.contains(&idx) {
+                let log_line = LogLine::new(
+                    LogSource::Stderr,
+                    format!("Job '{}' skipped: the run was cancelled", job.name),
+                );
+                runner.send(idx, JobStatus::Cancelled, log_line).await;
+            }
+        }
+    }
+
+    // Cleanup all environments after the workflow completes (success, failure or cancel)
+    runner.executor.cleanup().await;
+
+    let final_status = if all_succeeded {
+        JobStatus::Completed
+    } else if cancelled {
+        JobStatus::Cancelled
+    } else {
+        JobStatus::Failed
+    };
+    let _ = tx.send((jobs_len, final_status, LogLine::empty())).await;
+
+    all_succeeded
+}
+
+/// Everything a job task needs, shared by jobs running concurrently.
+#[derive(Clone)]
+struct JobRunner<E: Executor> {
+    executor: E,
+    tx: mpsc::Sender<Message>,
+    workflow: Arc<PreparedWorkflow>,
+    run_state: Arc<Mutex<RunState>>,
+    /// Result cache, if enabled for this run
+    cache: Option<Arc<JobCache>>,
+    /// Allocator of the host's NVIDIA GPUs, if any job asks for GPUs
+    gpus: Option<Arc<GpuAllocator>>,
+    cli_env_vars: Arc<Vec<String>>,
+    /// Set by the scheduler when the run is cancelled
+    cancelled: Arc<AtomicBool>,
+    /// Jobs skipped so far, whose dependents are skipped too
+    skipped: Arc<Mutex<HashSet<String>>>,
+}
+
+impl<E: Executor> JobRunner<E> {
+    /// Sends a message for job `idx`, ignoring a closed channel.
+    async fn send(&self, idx: usize, status: JobStatus, log_line: LogLine) {
+        let _ = self.tx.send((idx, status, log_line)).await;
+    }
+
+    /// Runs a single job, reporting progress as job `idx`. Returns `true` on success.
+    async fn run(mut self, job: JobFolder, idx: usize, mut cancel_rx: mpsc::Receiver<()>) -> bool {
+        if self.workflow.reused_jobs.contains(&job.name) {
+            let log_line = LogLine::new(
+                LogSource::Stdout,
+                format!(
+                    "[{}] Unchanged since the last run, reusing @complete/{}",
+                    job.name, job.name
+                ),
+            );
+            self.send(idx, JobStatus::Completed, log_line).await;
+            return true;
+        }
+
+        let workflow = self.workflow.clone();
+        let job_deps = workflow.metadata.get_job_dependencies(&job.name);
+        let skipped_dep = {
+            let skipped = self.skipped.lock().unwrap();
+            job_deps.iter().find(|dep| skipped.contains(*dep)).cloned()
+        };
+        if let Some(dep) = skipped_dep {
+            return self
+                .skip(idx, &job.name, format!("dependency '{dep}' was skipped"))
+                .await;
+        }
+
+        let config = match job.load_meta() {
+            Ok(config) => config,
+            Err(e) => {
+                let log_line = LogLine::new(
+                    LogSource::Stderr,
+                    format!("Failed to load config for '{}': {e}", job.name),
+                );
+                self.send(idx, JobStatus::Failed, log_line).await;
+                return false;
+            }
+        };
+
+        self.executor.set_job_idx(idx);
+
+        let job_params = job
+            .load_params()
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| config.generate_default_params());
+
+        match config.condition() {
+            Ok(Some(condition)) => {
+                let context = DependencyResults {
+                    params: merge_params(&workflow.params, &job_params),
+                    workflow_path: &workflow.temp_workflow_path,
+                    dependencies: job_deps,
+                    instances: &workflow.instances,
+                };
+                match condition.evaluate(&context) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        let when = config.when.as_deref().unwrap_or_default();
+                        return self
+                            .skip(idx, &job.name, format!("'when' condition is false: {when}"))
+                            .await;
+                    }
+                    Err(e) => {
+                        let log_line = LogLine::new(
+                            LogSource::Stderr,
+                            format!("Job '{}' failed: 'when' condition: {e}", job.name),
+                        );
+                        self.send(idx, JobStatus::Failed, log_line).await;
+                        return false;
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                let log_line =
+                    LogLine::new(LogSource::Stderr, format!("Job '{}' failed: {e}", job.name));
+                self.send(idx, JobStatus::Failed, log_line).await;
+                return false;
+            }
+        }
+
+        // Copy input files from dependencies before running; a named input
+        // without files stops the job
+        let mut log = Vec::new();
+        let staged = match &config.inputs {
+            Inputs::Named(bindings) => stage_named_inputs(
+                &workflow.temp_workflow_path,
+                &job,
+                bindings,
+                job_deps,
+                &workflow.instances,
+                &mut log,
+            ),
+            Inputs::Patterns(_) => {
+                if let Err(e) = copy_input_files_from_dependencies(
+                    &workflow.temp_workflow_path,
+                    &job,
+                    &workflow.sorted_jobs,
+                    &config,
+                    job_deps,
+                    &workflow.instances,
+                    &mut log,
+                ) {
+                    log.push(LogLine::new(
+                        LogSource::Stderr,
+                        format!("Warning: Failed to copy input files: {e}"),
+                    ));
+                }
+                Ok(0)
+            }
+        };
+        for log_line in log {
+            self.send(idx, JobStatus::Running, log_line).await;
+        }
+        if let Err(e) = staged {
+            let log_line =
+                LogLine::new(LogSource::Stderr, format!("Job '{}' failed: {e}", job.name));
+            self.send(idx, JobStatus::Failed, log_line).await;
+            return false;
+        }
+
+        // If this job runs with RUN_MODE=use_dok, prepare and inject the
+        // presigned bundle URLs run_dok.sh needs (script dir + the inputs/
+        // dir copy_input_files_from_dependencies just populated above).
+        let mut cli_env_vars = self.cli_env_vars.to_vec();
+        let run_mode = crate::infra::dok::resolve_run_mode(
+            &self.cli_env_vars,
+            workflow.metadata.env_passthrough.as_deref().unwrap_or(&[]),
+        );
+        if run_mode.as_deref() == Some("use_dok") {
+            match crate::infra::dok::prepare_bundle_env_vars(&job.path).await {
+                Ok(extra) => cli_env_vars.extend(extra),
+                Err(e) => {
+                    let log_line = LogLine::new(
+                        LogSource::Stderr,
+                        format!("DOK bundle preparation failed: {e}"),
+                    );
+                    self.send(idx, JobStatus::Failed, log_line).await;
+                    return false;
+                }
+            }
+        }
+
+        let result = self
+            .run_scripts(
+                &job,
+                &config,
+                &job_params,
+                &cli_env_vars,
+                idx,
+                &mut cancel_rx,
+            )
+            .await;
+
+        match result {
+            Ok(()) => {
+                // Move completed job to @complete/ to prevent cross-node path access
+                let log_line = match move_job_to_complete(&workflow.temp_workflow_path, &job.name) {
+                    Ok(()) => {
+                        if let Err(e) = self.record_completed(&job.name) {
+                            let log_line = LogLine::new(LogSource::Stderr, format!("Warning: {e}"));
+                            self.send(idx, JobStatus::Running, log_line).await;
+                        }
+                        LogLine::new(
+                            LogSource::Stdout,
+                            format!("[{}] Moved to @complete/", job.name),
+                        )
+                    }
+                    Err(e) => LogLine::new(
+                        LogSource::Stderr,
+                        format!("Warning: Failed to move '{}' to @complete: {e}", job.name),
+                    ),
+                };
+                self.send(idx, JobStatus::Completed, log_line).await;
+                true
+            }
+            Err(_) if self.is_cancelled() => {
+                let log_line =
+                    LogLine::new(LogSource::Stderr, format!("Job '{}' cancelled", job.name));
+                self.send(idx, JobStatus::Cancelled, log_line).await;
+                false
+            }
+            Err(e) => {
+                let log_line =
+                    LogLine::new(LogSource::Stderr, format!("Job '{}' failed: {e}", job.name));
+                self.send(idx, JobStatus::Failed, log_line).await;
+                false
+            }
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Marks job `idx` as skipped for `reason`. Returns `true`, as a skipped job
+    /// does not stop the run.
+    async fn skip(&self, idx: usize, job_name: &str, reason: String) -> bool {
+        self.skipped.lock().unwrap().insert(job_name.to_string());
+        let log_line = LogLine::new(
+            LogSource::Stdout,
+            format!("Job '{job_name}' skipped: {reason}"),
+        );
+        self.send(idx, JobStatus::Skipped, log_line).await;
+        true
+    }
+
+    /// Adds a completed job to the run state and writes it to the temp folder.
+    ///
+    /// The last instance of a matrix job to complete records the job as well.
+    fn record_completed(&self, job_name: &str) -> Result<(), String> {
+        let fingerprint = |name: &str| {
+            self.workflow
+                .fingerprints
+                .get(name)
+                .cloned()
+                .unwrap_or_default()
+        };
+        let mut run_state = self.run_state.lock().unwrap();
+        run_state.record_completed(job_name, &fingerprint(job_name));
+        if let Some(instance) = self.workflow.instances.get(job_name) {
+            let instances: Vec<String> = self
+                .workflow
+                .sorted_jobs
+                .iter()
+                .filter(|job| {
+                    self.workflow
+                        .instances
+                        .get(&job.name)
+                        .is_some_and(|i| i.job == instance.job)
+                })
+                .map(|job| job.name.clone())
+                .collect();
+            if instances
+                .iter()
+                .all(|name| run_state.jobs.contains_key(name))
+            {
+                run_state.record_matrix_completed(
+                    &instance.job,
+                    &fingerprint(&instance.job),
+                    instances,
+                );
+            }
+        }
+        run_state.save(&self.workflow.temp_workflow_path)
+    }
+
+    /// Reserves GPUs for a job whose `gpu` policy gives it GPUs, waiting while other
+    /// jobs hold them.
+    ///
+    /// Returns the lease, which keeps the GPUs reserved until it is dropped, and the
+    /// job's config with `gpus` set to the reserved device IDs. Returns `None` when
+    /// the job gets no GPUs or the host's GPUs are unknown.
+    async fn reserve_gpus(
+        &self,
+        config: &JobMeta,
+        idx: usize,
+        cancel_rx: &mut mpsc::Receiver<()>,
+    ) -> Result<Option<(GpuLease, JobMeta)>, String> {
+        let Some(allocator) = &self.gpus else {
+            return Ok(None);
+        };
+        if crate::gpu::uses_gpu(&config.resources, GpuRuntime::Nvidia) != Some(true) {
+            return Ok(None);
+        }
+
+        let mut waiting = false;
+        loop {
+            if let Some(lease) = allocator.try_acquire(config.resources.gpus.as_ref())? {
+                let device_ids = lease.device_ids().to_vec();
+                let log_line = LogLine::new(
+                    LogSource::Stdout,
+                    format!("Reserved GPU(s) {}", device_ids.join(",")),
+                );
+                self.send(idx, JobStatus::Running, log_line).await;
+                let mut config = config.clone();
+                config.resources.gpus = Some(Gpus::Devices(device_ids));
+                return Ok(Some((lease, config)));
+            }
+            if !waiting {
+                let log_line = LogLine::new(
+                    LogSource::Stdout,
+                    "Waiting for GPUs in use by other jobs...".to_string(),
+                );
+                self.send(idx, JobStatus::Running, log_line).await;
+                waiting = true;
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(GPU_POLL_INTERVAL) => {}
+                Some(()) = cancel_rx.recv() => return Err("cancelled".to_string()),
+            }
+        }
+    }
+
+    /// Computes the cache key of the job in `job_dir`, or `None` (with a warning)
+    /// if it cannot be computed.
+    async fn cache_key(
+        &self,
+        job_dir: &Path,
+        image: &str,
+        merged_params: &JobParams,
+        env_vars: &[String],
+        mounts: &[Mount],
+        idx: usize,
+    ) -> Option<String> {
+        let key = match self.executor.image_id(image).await {
+            Ok(image_id) => {
+                crate::cache::cache_key(job_dir, &image_id, merged_params, env_vars, mounts)
+            }
+            Err(e) => Err(format!("Failed to inspect image '{image}': {e}")),
+        };
+        match key {
+            Ok(key) => Some(key),
+            Err(e) => {
+                let log_line =
+                    LogLine::new(LogSource::Stderr, format!("Warning: Cache disabled: {e}"));
+                self.send(idx, JobStatus::Running, log_line).await;
+                None
+            }
+        }
+    }
+
+    /// Restores the job's `outputs/` from the cache. Returns `true` on a cache hit.
+    async fn restore_from_cache(
+        &self,
+        cache: &JobCache,
+        key: &str,
+        job_dir: &Path,
+        idx: usize,
+    ) -> bool {
+        let (hit, log_line) = match cache.restore(key, &job_dir.join("outputs")) {
+            Ok(Some(file_count)) => (
+                true,
+                LogLine::new(
+                    LogSource::Stdout,
+                    format!(
+                        "Restored {file_count} output file(s) from cache ({}), skipping scripts",
+                        &key[..12]
+                    ),
+                ),
+            ),
+            Ok(None) => (
+                false,
+                LogLine::new(
+                    LogSource::Stdout,
+                    format!("No cached result ({}), running scripts", &key[..12]),
+                ),
+            ),
+            Err(e) => (
+                false,
+                LogLine::new(LogSource::Stderr, format!("Warning: {e}")),
+            ),
+        };
+        self.send(idx, JobStatus::Running, log_line).await;
+        hit
+    }
+
+    /// Stores the job's collected `outputs/` in the cache under `key`.
+    async fn store_in_cache(
+        &self,
+        cache: &JobCache,
+        key: &str,
+        job_name: &str,
+        job_dir: &Path,
+        idx: usize,
+    ) {
+        let log_line = match cache.store(
+            key,
+            job_name,
+            &self.workflow.metadata.name,
+            &job_dir.join("outputs"),
+        ) {
+            Ok(()) => LogLine::new(
+                LogSource::Stdout,
+                format!("Stored outputs in cache ({})", &key[..12]),
+            ),
+            Err(e) => LogLine::new(LogSource::Stderr, format!("Warning: {e}")),
+        };
+        self.send(idx, JobStatus::Running, log_line).await;
+    }
+
+    /// Prepares the image and environment, then runs the job's scripts and
+    /// collects its outputs.
+    async fn run_scripts(
+        &self,
+        job: &JobFolder,
+        config: &JobMeta,
+        job_params: &JobParams,
+        cli_env_vars: &[String],
+        idx: usize,
+        cancel_rx: &mut mpsc::Receiver<()>,
+    ) -> Result<(), String> {
+        let workflow_folder = &self.workflow.temp_workflow_path;
+        let job_dir = workflow_folder.join(&job.name);
+
+        let image = self
+            .executor
+            .prepare_image(config, &job_dir)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // Merge workflow parameters with job parameters
+        let merged_params = merge_params(&self.workflow.params, job_params);
+        let env_vars = job_env_vars(&self.workflow.metadata, &merged_params, cli_env_vars);
+        let mounts = self
+            .workflow
+            .mounts
+            .get(&job.name)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        // Restore outputs from the cache instead of running, if an earlier run
+        // produced them from identical scripts, image, params, environment, mounts
+        // and inputs
+        let cache_key = match &self.cache {
+            Some(cache) if config.cache => {
+                let key = self
+                    .cache_key(&job_dir, &image, &merged_params, &env_vars, mounts, idx)
+                    .await;
+                if let Some(key) = &key
+                    && self.restore_from_cache(cache, key, &job_dir, idx).await
+                {
+                    return Ok(());
+                }
+                key
+            }
+            _ => None,
+        };
+
+        // Written before the environment starts, so a failure leaks no container
+        write_params_file(&job_dir, &merged_params)?;
+
+        // Held until the job ends, so no other job gets the same GPUs
+        let reserved = self.reserve_gpus(config, idx, cancel_rx).await?;
+        let config = reserved.as_ref().map_or(config, |(_, config)| config);
+
+        let spec = EnvironmentSpec {
+            config,
+            image: &image,
+            workflow_folder,
+            job_name: &job.name,
+            isolation: self.workflow.metadata.isolation,
+            mounts,
+        };
+        let environment = self
+            .executor
+            .start_environment(spec)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if !cli_env_vars.is_empty() {
+            let log_line = LogLine::new(
+                LogSource::Stdout,
+                format!("Setting {} CLI env var(s) via -e/--env", cli_env_vars.len()),
+            );
+            self.send(idx, JobStatus::Running, log_line).await;
+        }
+
+        if !env_vars.is_empty() {
+            let log_line = LogLine::new(
+                LogSource::Stdout,
+                format!(
+                    "Setting {} parameter environment variable(s) ({} global + {} job = {} total)",
+                    env_vars.len(),
+                    self.workflow.params.len(),
+                    job_params.len(),
+                    merged_params.len()
+                ),
+            );
+            self.send(idx, JobStatus::Running, log_line).await;
+        }
+
+        let result = self
+            .run_in_environment(
+                &environment,
+                job,
+                config,
+                &env_vars,
+                cache_key.as_deref(),
+                idx,
+                cancel_rx,
+            )
+            .await;
+        // Released whatever the outcome, so a job's container never outlives it
+        self.executor.release_environment(environment).await;
+        result
+    }
+
+    /// Runs the job's scripts in `environment`, then collects its outputs and
+    /// stores them under `cache_key` in the result cache.
+    #[allow(clippy::too_many_arguments)]
+    async fn run_in_environment(
+        &self,
+        environment: &E::Environment,
+        job: &JobFolder,
+        config: &JobMeta,
+        env_vars: &[String],
+        cache_key: Option<&str>,
+        idx: usize,
+        cancel_rx: &mut mpsc::Receiver<()>,
+    ) -> Result<(), String> {
+        let job_dir = self.workflow.temp_workflow_path.join(&job.name);
+
+        // Execute scripts sequentially
+        let scripts = vec![
+            ("pre_run.sh", &config.scripts.pre),
+            ("run.sh", &config.scripts.run),
+            ("post_run.sh", &config.scripts.post),
+        ];
+
+        for (name, script) in scripts {
+            // A cancel that arrived between scripts stops before the next one
+            if self.is_cancelled() {
+                return Err("cancelled".to_string());
+            }
+            if ["pre_run.sh", "post_run.sh"].contains(&name) && !job_dir.join(script).exists() {
+                let log_line = LogLine::new(
+                    LogSource::Stdout,
+                    format!("Script {script} not found ... skip"),
+                );
+                self.send(idx, JobStatus::Running, log_line).await;
+                continue;
+            }
+
+            let command = ScriptCommand::new(&config.scripts, script);
+            self.exec_script_with_retries(
+                environment,
+                job,
+                config,
+                &command,
+                env_vars,
+                idx,
+                cancel_rx,
+            )
+            .await?;
+
+            let log_line = LogLine::new(
+                LogSource::Stdout,
+                format!("Script {command} completed successfully"),
+            );
+            self.send(idx, JobStatus::Running, log_line).await;
+        }
+
+        // Collect output files once all scripts succeeded
+        if self.is_cancelled() {
+            return Err("cancelled".to_string());
+        }
+        let output_folders = crate::outputs::output_folders(&config.outputs);
+        if !output_folders.is_empty() {
+            let log_line =
+                LogLine::new(LogSource::Stdout, "Collecting output files...".to_string());
+            self.send(idx, JobStatus::Running, log_line).await;
+
+            // Job folders are on the host for every backend, so outputs are
+            // collected here rather than inside the image
+            let mut collected = Ok(0);
+            for (folder, patterns) in &output_folders {
+                match crate::outputs::collect_outputs(&job_dir, folder, patterns) {
+                    Ok(collection) => {
+                        for (pattern, count) in &collection.matches {
+                            let log_line = if *count == 0 {
+                                LogLine::new(
+                                    LogSource::Stderr,
+                                    format!("Warning: No files matched output pattern '{pattern}'"),
+                                )
+                            } else {
+                                LogLine::new(
+                                    LogSource::Stdout,
+                                    format!("Output pattern '{pattern}' matched {count} file(s)"),
+                                )
+                            };
+                            self.send(idx, JobStatus::Running, log_line).await;
+                        }
+                        collected = collected.map(|total| total + collection.collected);
+                    }
+                    Err(e) => {
+                        collected = Err(e);
+                        break;
+                    }
+                }
+            }
+            let log_line = match collected {
+                Ok(file_count) => {
+                    if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+                        self.store_in_cache(cache, key, &job.name, &job_dir, idx)
+                            .await;
+                    }
+                    LogLine::new(
+                        LogSource::Stdout,
+                        format!("Collected {file_count} output file(s) to outputs/ folder"),
+                    )
+                }
+                Err(e) => LogLine::new(
+                    LogSource::Stderr,
+                    format!("Warning: Failed to collect output files: {e}"),
+                ),
+            };
+            self.send(idx, JobStatus::Running, log_line).await;
+        }
+
+        Ok(())
+    }
+
+    /// Runs `script`, killing it after the job's `timeout` and running it again up
+    /// to `retries` times while it fails, waiting `retry_backoff` (doubled after
+    /// every retry) in between. Each attempt is reported in the job's logs.
+    #[allow(clippy::too_many_arguments)]
+    async fn exec_script_with_retries(
+        &self,
+        environment: &E::Environment,
+        job: &JobFolder,
+        config: &JobMeta,
+        script: &ScriptCommand,
+        env_vars: &[String],
+        idx: usize,
+        cancel_rx: &mut mpsc::Receiver<()>,
+    ) -> Result<(), String> {
+        let attempts = config.retries + 1;
+        let mut backoff = config.retry_backoff.map(|d| d.0).unwrap_or_default();
+        let mut error = String::new();
+
+        for attempt in 1..=attempts {
+            if attempt > 1 {
+                let log_line = LogLine::new(
+                    LogSource::Stderr,
+                    format!(
+                        "{error}; retrying in {} (attempt {attempt}/{attempts})",
+                        HumanDuration(backoff)
+                    ),
+                );
+                self.send(idx, JobStatus::Running, log_line).await;
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff) => {}
+                    Some(()) = cancel_rx.recv() => return Err("cancelled".to_string()),
+                }
+                backoff *= 2;
+            }
+
+            let log_line = LogLine::new(LogSource::Stdout, format!("Executing script: {script}"));
+            self.send(idx, JobStatus::Running, log_line).await;
+
+            let exit_code = self
+                .exec_script_with_timeout(
+                    environment,
+                    &job.name,
+                    script,
+                    env_vars,
+                    config.timeout,
+                    cancel_rx,
+                )
+                .await?;
+            error = match exit_code {
+                Some(0) => return Ok(()),
+                Some(exit_code) => format!("Script {script} failed with exit code {exit_code}"),
+                None => format!(
+                    "Script {script} timed out after {}",
+                    config.timeout.unwrap_or(HumanDuration(Duration::ZERO))
+                ),
+            };
+            if self.is_cancelled() {
+                break;
+            }
+        }
+
+        if attempts > 1 {
+            error.push_str(&format!(" ({attempts} attempts)"));
+        }
+        Err(error)
+    }
+
+    /// Runs `script` once. Returns its exit code, or `None` if it was killed
+    /// because it ran longer than `timeout`.
+    ///
+    /// The executor is stopped through a channel of its own, so that both the
+    /// timeout and a message on `cancel_rx` kill the script.
+    async fn exec_script_with_timeout(
+        &self,
+        environment: &E::Environment,
+        job_name: &str,
+        script: &ScriptCommand,
+        env_vars: &[String],
+        timeout: Option<HumanDuration>,
+        cancel_rx: &mut mpsc::Receiver<()>,
+    ) -> Result<Option<i64>, String> {
+        let (stop_tx, mut stop_rx) = mpsc::channel::<()>(1);
+        let exec = self
+            .executor
+            .exec_script(environment, job_name, script, env_vars, &mut stop_rx);
+        tokio::pin!(exec);
+        let deadline = async {
+            match timeout {
+                Some(timeout) => tokio::time::sleep(timeout.0).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(deadline);
+
+        let mut timed_out = false;
+        loop {
+            tokio::select! {
+                result = &mut exec => {
+                    // A script killed for running too long may also make the
+                    // executor fail, which is still a timeout
+                    if timed_out {
+                        return Ok(None);
+                    }
+                    return result.map(Some).map_err(|e| e.to_string());
+                }
+                _ = &mut deadline, if !timed_out => {
+                    timed_out = true;
+                    let _ = stop_tx.try_send(());
+                }
+                Some(()) = cancel_rx.recv() => {
+                    let _ = stop_tx.try_send(());
+                }
+            }
+        }
+    }
+}
+
+/// What a job's `when` condition is evaluated against: its merged parameters and
+/// the results of its dependencies, which have completed and moved to `@complete/`.
+///
+/// A matrix job stands for all of its instances.
+struct DependencyResults<'a> {
+    params: JobParams,
+    workflow_path: &'a Path,
+    dependencies: &'a [String],
+    instances: &'a HashMap<String, MatrixInstance>,
+}
+
+impl DependencyResults<'_> {
+    /// Dependencies named `job`: the job itself, or the instances of a matrix job.
+    fn dependency_folders(&self, job: &str) -> Result<Vec<&str>, String> {
+        let folders: Vec<&str> = self
+            .dependencies
+            .iter()
+            .filter(|d| *d == job || self.instances.get(*d).is_some_and(|i| i.job == job))
+            .map(String::as_str)
+            .collect();
+        if folders.is_empty() {
+            return Err(format!("'{job}' is not a dependency of this job"));
+        }
+        Ok(folders)
+    }
+}
+
+impl ConditionContext for DependencyResults<'_> {
+    fn param(&self, name: &str) -> Option<serde_json::Value> {
+        self.params.get(name).cloned()
+    }
+
+    fn output_exists(&self, job: &str, pattern: &str) -> Result<bool, String> {
+        let folders = self.dependency_folders(job)?;
+        let matcher = globset::Glob::new(pattern)
+            .map_err(|e| format!("Invalid pattern '{pattern}': {e}"))?
+            .compile_matcher();
+        Ok(folders.iter().any(|folder| {
+            let outputs_dir = self
+                .workflow_path
+                .join("@complete")
+                .join(folder)
+                .join("outputs");
+            let files = crate::utils::list_files(&outputs_dir).unwrap_or_default();
+            files.iter().any(|file| matcher.is_match(file))
+        }))
+    }
+}
+
+/// Merges workflow and job parameters; job parameters take precedence.
+pub fn merge_params(
+    workflow_params: &job_config::params::WorkflowParams,
+    job_params: &job_config::params::JobParams,
+) -> job_config::params::JobParams {
+    let mut merged_params = workflow_params.clone();
+    for (param_name, param_value) in job_params {
+        merged_params.insert(param_name.clone(), param_value.clone());
+    }
+    merged_params
+}
+
+/// Builds the `KEY=VALUE` environment of a job's scripts.
+///
+/// Contains `PARAM_<NAME>` (or the workflow's `param_env_prefix`) for every merged
+/// parameter unless `param_env = false`, then the host variables listed in
+/// `env_passthrough`, then the `-e/--env` values (which win).
+pub fn job_env_vars(
+    workflow_meta: &WorkflowMeta,
+    merged_params: &JobParams,
+    cli_env_vars: &[String],
+) -> Vec<String> {
+    // Convert merged parameters to environment variables, unless the workflow opts out
+    let mut env_vars: Vec<String> = Vec::new();
+    if let Some(prefix) = workflow_meta.env_prefix() {
+        for (param_name, param_value) in merged_params {
+            env_vars.push(format!(
+                "{}={}",
+                env_var_name(prefix, param_name),
+                env_var_value(param_value)
+            ));
+        }
+    }
+
+    // Forward selected host environment variables into the container exec environment.
+    for key in workflow_meta.env_passthrough.as_deref().unwrap_or(&[]) {
+        if let Ok(val) = std::env::var(key) {
+            env_vars.push(format!("{key}={val}"));
+        }
+    }
+
+    // Inject -e/--env CLI values unconditionally, unprefixed — independent of
+    // (and taking precedence over) the env_passthrough allowlist above.
+    for entry in cli_env_vars {
+        env_vars.push(entry.clone());
+    }
+
+    env_vars
+}
+
+/// Performs topological sort on jobs based on their dependencies.
+///
+/// Uses Kahn's algorithm: jobs without dependencies come first, and a job follows
+/// all of its dependencies. Jobs left over once no job is free form a cycle.
+pub fn topological_sort_jobs(
+    jobs: &[JobFolder],
+    workflow_metadata: &WorkflowMeta,
+) -> Result<Vec<JobFolder>, String> {
+    use std::collections::{HashMap, VecDeque};
+
+    if jobs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Build a map of job names to job data
+    let job_map: HashMap<String, JobFolder> =
+        jobs.iter().map(|j| (j.name.clone(), j.clone())).collect();
+
+    // Build dependency graph
+    let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
+    let mut in_degree: HashMap<String, usize> = HashMap::new();
+
+    // Initialize
+    for job in jobs {
+        in_degree.insert(job.name.clone(), 0);
+        dependents.entry(job.name.clone()).or_default();
+    }
+
+    // Build the graph
+    for job in jobs {
+        let job_deps = workflow_metadata.get_job_dependencies(&job.name);
+
+        for dep_name in job_deps {
+            if !job_map.contains_key(dep_name) {
+                return Err(format!(
+                    "Job '{}' depends on '{}', but '{}' does not exist",
+                    job.name, dep_name, dep_name
+                ));
+            }
+
+            dependents
+                .entry(dep_name.clone())
+                .or_default()
+                .push(job.name.clone());
+
+            *in_degree.get_mut(&job.name).unwrap() += 1;
+        }
+    }
+
+    // Kahn's algorithm, seeded in job order so the result is deterministic
+    let mut queue: VecDeque<String> = jobs
+        .iter()
+        .filter(|job| in_degree[&job.name] == 0)
+        .map(|job| job.name.clone())
+        .collect();
+
+    let mut sorted_jobs = Vec::new();
+
+    while let Some(job_name) = queue.pop_front() {
+        if let Some(job) = job_map.get(&job_name) {
+            sorted_jobs.push(job.clone());
+        }
+
+        if let Some(deps) = dependents.get(&job_name) {
+            for dependent in deps {
+                let degree = in_degree.get_mut(dependent).unwrap();
+                *degree -= 1;
+                if *degree == 0 {
+                    queue.push_back(dependent.clone());
+                }
+            }
+        }
+    }
+
+    if sorted_jobs.len() != jobs.len() {
+        let processed: HashSet<_> = sorted_jobs.iter().map(|j| &j.name).collect();
+        let unprocessed: Vec<_> = jobs
+            .iter()
+            .filter(|j| !processed.contains(&j.name))
+            .map(|j| j.name.as_str())
+            .collect();
+
+        return Err(format!(
+            "Circular dependency detected: {}",
+            unprocessed.join(", ")
+        ));
+    }
+
+    Ok(sorted_jobs)
+}
+
+/// Copies input files from dependency jobs' outputs to the current job's inputs folder.
+///
+/// The job's `inputs` patterns select files by their path below each dependency's
+/// `outputs/`, following the rules of `crate::outputs::select_files`; without
+/// patterns every file is copied. Folders are kept. Outputs of a matrix instance in
+/// `instances` go to `inputs/<job>/<label>/`, so the instances of a job cannot
+/// overwrite each other's files.
+///
+/// Progress and warnings are appended to `log`.
+///
+/// # Returns
+///
+/// The number of files copied.
+pub fn copy_input_files_from_dependencies(
+    workflow_path: &Path,
+    current_job: &JobFolder,
+    all_jobs: &[JobFolder],
+    config: &JobMeta,
+    dependencies: &[String],
+    instances: &HashMap<String, MatrixInstance>,
+    log: &mut Vec<LogLine>,
+) -> Result<usize, String> {
+    if dependencies.is_empty() {
+        return Ok(0);
+    }
+
+    let info = |log: &mut Vec<LogLine>, message: String| {
+        log.push(LogLine::new(LogSource::Stdout, message));
+    };
+    let warn = |log: &mut Vec<LogLine>, message: String| {
+        log.push(LogLine::new(LogSource::Stderr, message));
+    };
+
+    // Copy to inputs/ subfolder for clear separation
+    let inputs_dir = workflow_path.join(&current_job.name).join("inputs");
+    fs::create_dir_all(&inputs_dir).map_err(|e| format!("Failed to create inputs dir: {e}"))?;
+
+    let mut copied_files: HashSet<String> = HashSet::new();
+
+    for dep_job_name in dependencies {
+        // Find the dependency job
+        if !all_jobs.iter().any(|j| &j.name == dep_job_name) {
+            warn(
+                log,
+                format!("Warning: Dependency job '{dep_job_name}' not found"),
+            );
+            continue;
+        }
+
+        let dep_outputs_dir = dependency_outputs_dir(workflow_path, dep_job_name);
+        if !dep_outputs_dir.exists() {
+            info(
+                log,
+                format!("No outputs found for dependency '{dep_job_name}', skipping"),
+            );
+            continue;
+        }
+
+        let files = match crate::utils::list_files(&dep_outputs_dir) {
+            Ok(files) => files,
+            Err(e) => {
+                warn(
+                    log,
+                    format!("Error reading outputs from '{dep_job_name}': {e}"),
+                );
+                continue;
+            }
+        };
+
+        // Determine which files to copy; patterns match paths below outputs/
+        let patterns = config.inputs.patterns();
+        let files_to_copy: Vec<(PathBuf, PathBuf)> = if patterns.is_empty() {
+            // Copy all files from outputs/
+            files.into_iter().map(|file| (file.clone(), file)).collect()
+        } else {
+            match crate::outputs::select_files(&files, patterns) {
+                Ok(selection) => {
+                    for (source, dest) in &selection.clashes {
+                        warn(
+                            log,
+                            format!(
+                                "Warning: File '{}' already copied, skipping '{}' from '{dep_job_name}'",
+                                dest.display(),
+                                source.display()
+                            ),
+                        );
+                    }
+                    selection.files
+                }
+                Err(e) => {
+                    warn(
+                        log,
+                        format!("Failed to match input patterns for '{dep_job_name}': {e}"),
+                    );
+                    continue;
+                }
+            }
+        };
+
+        // Copy files to inputs/ directory, keeping their folders
+        let dest_dir = match instances.get(dep_job_name) {
+            Some(instance) => inputs_dir.join(&instance.job).join(&instance.label),
+            None => inputs_dir.clone(),
+        };
+        let mut count = 0;
+        for (source, dest) in files_to_copy {
+            let dest_path = dest_dir.join(dest);
+            let filename_str = dest_path
+                .strip_prefix(&inputs_dir)
+                .unwrap_or(&dest_path)
+                .to_string_lossy()
+                .to_string();
+
+            // Check for conflicts
+            if copied_files.contains(&filename_str) {
+                warn(
+                    log,
+                    format!(
+                        "Warning: File '{filename_str}' already copied, skipping from '{dep_job_name}'"
+                    ),
+                );
+                continue;
+            }
+
+            let copied = dest_path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::copy(dep_outputs_dir.join(&source), &dest_path));
+            match copied {
+                Ok(_) => {
+                    copied_files.insert(filename_str);
+                    count += 1;
+                }
+                Err(e) => warn(log, format!("Error copying '{filename_str}': {e}")),
+            }
+        }
+        if count > 0 {
+            info(
+                log,
+                format!("Copied {count} file(s) from '{dep_job_name}' to inputs/"),
+            );
+        }
+    }
+
+    Ok(copied_files.len())
+}
+
+/// Returns the `outputs/` folder of dependency `dep_job_name`: in `@complete/` once
+/// the job finished, in its original location otherwise.
+fn dependency_outputs_dir(workflow_path: &Path, dep_job_name: &str) -> PathBuf {
+    let complete_outputs_dir = workflow_path
+        .join("@complete")
+        .join(dep_job_name)
+        .join("outputs");
+    if complete_outputs_dir.exists() {
+        complete_outputs_dir
+    } else {
+        workflow_path.join(dep_job_name).join("outputs")
+    }
+}
+
+/// Stages the named inputs of a job. The files of the dependency output an input
+/// binds to go to `inputs/<job>/`, or `inputs/<path>/` when the binding sets `path`;
+/// those of the instances of a matrix job to `inputs/<job>/<label>/`.
+///
+/// Progress is appended to `log`.
+///
+/// # Returns
+///
+/// * `Ok(count)` - The number of files staged
+/// * `Err(message)` - A required input has no files, a binding names no
+///   dependency, or two inputs stage the same file
+pub fn stage_named_inputs(
+    workflow_path: &Path,
+    current_job: &JobFolder,
+    bindings: &BTreeMap<String, InputBinding>,
+    dependencies: &[String],
+    instances: &HashMap<String, MatrixInstance>,
+    log: &mut Vec<LogLine>,
+) -> Result<usize, String> {
+    let inputs_dir = workflow_path.join(&current_job.name).join("inputs");
+    fs::create_dir_all(&inputs_dir).map_err(|e| format!("Failed to create inputs dir: {e}"))?;
+
+    // Instances stand for their matrix job in bindings
+    let dependency_job = |dep: &str| {
+        instances
+            .get(dep)
+            .map_or(dep.to_string(), |instance| instance.job.clone())
+    };
+    let dependency_jobs: Vec<String> = dependencies.iter().map(|d| dependency_job(d)).collect();
+
+    let mut staged: HashSet<PathBuf> = HashSet::new();
+    let mut missing = Vec::new();
+    for (name, binding) in bindings {
+        let source = binding.source();
+        let (dep_job, output) = binding.split_source(&dependency_jobs).ok_or_else(|| {
+            format!("Input '{name}' binds to '{source}', which is not a dependency of this job")
+        })?;
+        let folder = binding.path().unwrap_or(dep_job);
+
+        let mut count = 0;
+        for dep in dependencies.iter().filter(|d| dependency_job(d) == dep_job) {
+            let mut outputs_dir = dependency_outputs_dir(workflow_path, dep);
+            if let Some(output) = output {
+                outputs_dir = outputs_dir.join(output);
+            }
+            let dest_dir = match instances.get(dep) {
+                Some(instance) => inputs_dir.join(folder).join(&instance.label),
+                None => inputs_dir.join(folder),
+            };
+            let files = crate::utils::list_files(&outputs_dir).unwrap_or_default();
+            for relative in files {
+                let dest = dest_dir.join(&relative);
+                let shown = dest.strip_prefix(&inputs_dir).unwrap_or(&dest).display();
+                if !staged.insert(dest.clone()) {
+                    return Err(format!(
+                        "Input '{name}' stages 'inputs/{shown}', which another input staged already"
+                    ));
+                }
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+                }
+                fs::copy(outputs_dir.join(&relative), &dest)
+                    .map_err(|e| format!("Failed to stage 'inputs/{shown}': {e}"))?;
+                count += 1;
+            }
+        }
+
+        if count > 0 {
+            log.push(LogLine::new(
+                LogSource::Stdout,
+                format!(
+                    "Staged input '{name}' from '{source}': {count} file(s) in inputs/{folder}/"
+                ),
+            ));
+        } else if binding.optional() {
+            log.push(LogLine::new(
+                LogSource::Stdout,
+                format!("Optional input '{name}' from '{source}' has no files"),
+            ));
+        } else {
+            missing.push(format!("'{name}' ('{source}')"));
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(format!(
+            "Required input(s) {} have no files in the dependencies' outputs",
+            missing.join(", ")
+        ));
+    }
+    Ok(staged.len())
+}
+
+/// Moves a completed job folder to `@complete/` to prevent cross-node path access.
+///
+/// After a job finishes, its folder is moved from the temp workflow root into
+/// `@complete/{job_name}/`. This ensures that subsequent jobs cannot use relative
+/// paths (e.g., `../01-data-prep/`) to access sibling node folders directly,
+/// forcing them to use the proper `inputs/` contract.
+pub fn move_job_to_complete(workflow_path: &Path, job_name: &str) -> Result<(), String> {
+    let source = workflow_path.join(job_name);
+    let complete_dir = workflow_path.join("@complete");
+    let dest = complete_dir.join(job_name);
+
+    fs::create_dir_all(&complete_dir)
+        .map_err(|e| format!("Failed to create @complete directory: {e}"))?;
+
+    fs::rename(&source, &dest)
+        .map_err(|e| format!("Failed to move '{job_name}' to @complete: {e}"))?;
+
+    Ok(())
+}
+
+/// Returns the jobs of an earlier run that a resumed run can reuse.
+///
+/// A job is reusable if it is in `@complete/`, completed with its current
+/// fingerprint, and all of its dependencies are reusable too. A matrix job is in
+/// `@complete/` when every instance its run state records is.
+fn reusable_jobs(
+    temp_workflow_path: &Path,
+    sorted_jobs: &[JobFolder],
+    metadata: &WorkflowMeta,
+    previous_run: &RunState,
+    fingerprints: &HashMap<String, String>,
+) -> HashSet<String> {
+    let mut reusable = HashSet::new();
+    for job in sorted_jobs {
+        let fingerprint = fingerprints.get(&job.name).map(String::as_str);
+        let mut folders: Vec<&str> = previous_run
+            .instances(&job.name)
+            .iter()
+            .map(String::as_str)
+            .collect();
+        if folders.is_empty() {
+            folders.push(&job.name);
+        }
+        let is_reusable = folders
+            .iter()
+            .all(|folder| temp_workflow_path.join("@complete").join(folder).is_dir())
+            && previous_run.is_completed(&job.name, fingerprint.unwrap_or_default())
+            && metadata
+                .get_job_dependencies(&job.name)
+                .iter()
+                .all(|dep| reusable.contains(dep));
+        if is_reusable {
+            reusable.insert(job.name.clone());
+        }
+    }
+    reusable
+}
+
+/// Replaces job `job_name` in the temp workflow with a fresh copy from `source_path`.
+///
+/// Removes the folders an earlier run left behind, in place or in `@complete/`,
+/// including those of the matrix `instances` it recorded for the job.
+fn reset_job_folder(
+    source_path: &Path,
+    temp_workflow_path: &Path,
+    job_name: &str,
+    instances: &[String],
+) -> Result<(), String> {
+    for name in std::iter::once(job_name).chain(instances.iter().map(String::as_str)) {
+        for stale in [
+            temp_workflow_path.join(name),
+            temp_workflow_path.join("@complete").join(name),
+        ] {
+            if stale.exists() {
+                fs::remove_dir_all(&stale)
+                    .map_err(|e| format!("Failed to remove {}: {e}", stale.display()))?;
+            }
+        }
+    }
+    copy_dir_recursive(
+        &source_path.join(job_name),
+        &temp_workflow_path.join(job_name),
+    )
+    .map_err(|e| format!("Failed to copy job '{job_name}': {e}"))?;
+    Ok(())
+}
+
+/// Writes the parameter overrides of each job in `jobs` into its params.json in
+/// the temp workflow folder, where the job and its matrix instances read them.
+fn apply_overrides(jobs: &[JobFolder], overrides: &ParamOverrides) -> Result<(), String> {
+    for job in jobs {
+        let Ok(config) = job.load_meta() else {
+            continue;
+        };
+        let mut params = job
+            .load_params()
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| config.generate_default_params());
+        overrides.apply_to_job(&job.name, &mut params);
+        job.save_params(&params)
+            .map_err(|e| format!("Failed to write parameters of '{}': {e}", job.name))?;
+    }
+    Ok(())
+}
+
+/// Writes the merged parameters of a job to `.silva/params.json` in its folder,
+/// where scripts read them with their JSON types.
+fn write_params_file(job_dir: &Path, merged_params: &JobParams) -> Result<(), String> {
+    let dir = job_dir.join(PARAMS_FILE_DIR);
+    let sorted: BTreeMap<_, _> = merged_params.iter().collect();
+    let json = serde_json::to_string_pretty(&sorted)
+        .map_err(|e| format!("Failed to serialize parameters: {e}"))?;
+    fs::create_dir_all(&dir)
+        .and_then(|_| fs::write(dir.join("params.json"), json))
+        .map_err(|e| format!("Failed to write {PARAMS_FILE_DIR}/params.json: {e}"))
+}
+
+/// Built-in `${run.*}` values of the run with ID `id`, starting today.
+pub fn run_info(id: &str) -> RunInfo {
+    RunInfo {
+        id: id.to_string(),
+        date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+    }
+}
+
+/// Resolves the `${...}` references in the workflow parameters and in the
+/// params.json of each job in `jobs`, in the temp workflow folder, so conditions,
+/// checks and `PARAM_*` variables see the resolved values.
+///
+/// `${run.id}` is the name of the temp workflow folder, which a resumed run keeps.
+///
+/// Returns `Err` listing every parameter that cannot be resolved.
+fn interpolate_params(workflow: &mut PreparedWorkflow, jobs: &[JobFolder]) -> Result<(), String> {
+    let run_id = workflow
+        .temp_workflow_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let interpolator = Interpolator::new(run_info(&run_id));
+    let mut violations = Vec::new();
+
+    match interpolator.resolve_workflow(&workflow.params) {
+        Ok(params) => workflow.params = params,
+        Err(e) => violations.extend(e.lines().map(|line| format!("[workflow] {line}"))),
+    }
+    for job in jobs {
+        let Ok(config) = job.load_meta() else {
+            continue;
+        };
+        let params = job
+            .load_params()
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| config.generate_default_params());
+        if !params.values().any(has_references) {
+            continue;
+        }
+        match interpolator.resolve_job(&workflow.params, &params) {
+            Ok(resolved) => job
+                .save_params(&resolved)
+                .map_err(|e| format!("Failed to write parameters of '{}': {e}", job.name))?,
+            Err(e) => violations.extend(e.lines().map(|line| format!("[{}] {line}", job.name))),
+        }
+    }
+
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    let mut msg = String::from("Invalid parameter references.\n");
+    for violation in &violations {
+        msg.push_str(&format!("\n  {violation}"));
+    }
+    msg.push_str(
+        "\n\nFix: Refer to existing parameters as ${global.<name>} or ${job.<name>}, set the referenced environment variables, and break reference cycles.",
+    );
+    Err(msg)
+}
+
+/// Converts CRLF line endings of the jobs' scripts to LF, in the temp workflow
+/// folder, so scripts written on Windows run without `sed` in the image. Scripts
+/// of jobs with `interpreter = "shebang"` are made executable.
+fn normalize_scripts(jobs: &[JobFolder], notes: &mut Vec<LogLine>) -> Result<(), String> {
+    let mut converted = Vec::new();
+    for job in jobs {
+        let Ok(config) = job.load_meta() else {
+            continue;
+        };
+        let scripts = [
+            &config.scripts.pre,
+            &config.scripts.run,
+            &config.scripts.post,
+        ];
+        for script in scripts {
+            let path = job.path.join(script);
+            if script.is_empty() || Path::new(script).is_absolute() || !path.is_file() {
+                continue;
+            }
+            let content =
+                fs::read(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+            if content.windows(2).any(|w| w == b"\r\n") {
+                let mut normalized = Vec::with_capacity(content.len());
+                for (i, byte) in content.iter().enumerate() {
+                    if !(*byte == b'\r' && content.get(i + 1) == Some(&b'\n')) {
+                        normalized.push(*byte);
+                    }
+                }
+                fs::write(&path, normalized)
+                    .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+                converted.push(format!("{}/{}", job.name, script.trim_start_matches("./")));
+            }
+            #[cfg(unix)]
+            if config.scripts.interpreter == Interpreter::Shebang {
+                use std::os::unix::fs::PermissionsExt;
+                let mut permissions = fs::metadata(&path)
+                    .map_err(|e| format!("Failed to read {}: {e}", path.display()))?
+                    .permissions();
+                permissions.set_mode(permissions.mode() | 0o111);
+                fs::set_permissions(&path, permissions)
+                    .map_err(|e| format!("Failed to make {} executable: {e}", path.display()))?;
+            }
+        }
+    }
+
+    if !converted.is_empty() {
+        notes.push(LogLine::new(
+            LogSource::Stdout,
+            format!(
+                "Converted CRLF line endings to LF in: {}",
+                converted.join(", ")
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Copies files from the workflow's `input_files/` folder to all jobs without dependencies.
+///
+/// If the `input_files/` folder exists, all its contents are copied to each dependency-free
+/// job's `inputs/` subfolder. If the folder doesn't exist, a hint is added to `log`.
+fn copy_input_files_to_dependency_free_jobs(
+    workflow_path: &Path,
+    temp_workflow_path: &Path,
+    sorted_jobs: &[JobFolder],
+    workflow_metadata: &WorkflowMeta,
+    log: &mut Vec<LogLine>,
+) {
+    let input_files_path = workflow_path.join("input_files");
+
+    if !input_files_path.is_dir() {
+        log.push(LogLine::new(
+            LogSource::Stdout,
+            "Hint: No 'input_files' folder found in workflow".to_string(),
+        ));
+        return;
+    }
+
+    // Find all jobs with no dependencies
+    let jobs_without_deps: Vec<&JobFolder> = sorted_jobs
+        .iter()
+        .filter(|job| workflow_metadata.get_job_dependencies(&job.name).is_empty())
+        .collect();
+
+    if jobs_without_deps.is_empty() {
+        log.push(LogLine::new(
+            LogSource::Stdout,
+            "Warning: No jobs to copy input files to".to_string(),
+        ));
+        return;
+    }
+
+    // Copy input_files to each job without dependencies
+    for job in &jobs_without_deps {
+        // Copy to inputs/ subfolder for clear separation
+        let inputs_dir = temp_workflow_path.join(&job.name).join("inputs");
+        if let Err(e) = fs::create_dir_all(&inputs_dir) {
+            log.push(LogLine::new(
+                LogSource::Stderr,
+                format!("Error creating inputs folder for '{}': {e}", job.name),
+            ));
+            continue;
+        }
+
+        // Read and copy all files from input_files/
+        let entries = match fs::read_dir(&input_files_path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log.push(LogLine::new(
+                    LogSource::Stderr,
+                    format!("Error reading input_files folder: {e}"),
+                ));
+                return;
+            }
+        };
+
+        let mut copied_count = 0;
+        for entry in entries.flatten() {
+            let source = entry.path();
+            let dest = inputs_dir.join(entry.file_name());
+
+            let result = if source.is_file() {
+                fs::copy(&source, &dest).map(|_| ())
+            } else if source.is_dir() {
+                copy_dir_recursive(&source, &dest).map(|_| ())
+            } else {
+                continue;
+            };
+
+            match result {
+                Ok(()) => copied_count += 1,
+                Err(e) => log.push(LogLine::new(
+                    LogSource::Stderr,
+                    format!(
+                        "Error copying '{}': {e}",
+                        entry.file_name().to_string_lossy()
+                    ),
+                )),
+            }
+        }
+
+        if copied_count > 0 {
+            log.push(LogLine::new(
+                LogSource::Stdout,
+                format!(
+                    "Copied {} item(s) from 'input_files/' to '{}/inputs/'",
+                    copied_count, job.name
+                ),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use job_config::job::{Container, Outputs, ParamDefinition, ParamType, Scripts};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::time::SystemTime;
+
+    /// In-memory executor: records every call and runs no containers.
+    ///
+    /// `run.sh` writes `result.txt` into the job folder; a script named in
+    /// `failing_script` exits with code 1, and one named in `blocking_script`
+    /// runs until it is cancelled, then fails with `stop_error` if that is set.
+    #[derive(Clone, Default)]
+    struct FakeExecutor {
+        calls: Arc<Mutex<Vec<String>>>,
+        /// Started and released environments, e.g. `start a (job)` and `release`
+        environments: Arc<Mutex<Vec<String>>>,
+        env_vars: Arc<Mutex<Vec<String>>>,
+        failing_script: Option<String>,
+        blocking_script: Option<String>,
+        stop_error: Option<String>,
+    }
+
+    impl FakeExecutor {
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    impl Executor for FakeExecutor {
+        type Error = String;
+        type Environment = PathBuf;
+
+        fn set_job_idx(&mut self, _job_idx: usize) {}
+
+        async fn prepare_image(&self, config: &JobMeta, _job_dir: &Path) -> Result<String, String> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("image {}", config.container.image));
+            Ok(config.container.image.clone())
+        }
+
+        async fn start_environment(&self, spec: EnvironmentSpec<'_>) -> Result<PathBuf, String> {
+            self.environments.lock().unwrap().push(format!(
+                "start {} ({})",
+                spec.job_name,
+                spec.isolation.as_str()
+            ));
+            Ok(spec.workflow_folder.to_path_buf())
+        }
+
+        async fn release_environment(&self, _environment: PathBuf) {
+            self.environments
+                .lock()
+                .unwrap()
+                .push("release".to_string());
+        }
+
+        async fn exec_script(
+            &self,
+            workflow_folder: &PathBuf,
+            job_name: &str,
+            script: &ScriptCommand,
+            env_vars: &[String],
+            cancel_rx: &mut mpsc::Receiver<()>,
+        ) -> Result<i64, String> {
+            let script = script.name.as_str();
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("{job_name}/{script}"));
+            self.env_vars.lock().unwrap().extend_from_slice(env_vars);
+            if self.failing_script.as_deref() == Some(script) {
+                return Ok(1);
+            }
+            if self.blocking_script.as_deref() == Some(script) {
+                // Killed by the cancel, like a real process
+                cancel_rx.recv().await;
+                return match &self.stop_error {
+                    Some(error) => Err(error.clone()),
+                    None => Ok(137),
+                };
+            }
+            if script == "run.sh" {
+                fs::write(workflow_folder.join(job_name).join("result.txt"), job_name)
+                    .map_err(|e| e.to_string())?;
+            }
+            Ok(0)
+        }
+
+        async fn cleanup(&self) {
+            self.calls.lock().unwrap().push("cleanup".to_string());
+        }
+    }
+
+    /// Creates a workflow whose jobs each have a `run.sh` and collect `result.txt`.
+    fn make_workflow(
+        root: &Path,
+        jobs: &[&str],
+        dependencies: &[(&str, &[&str])],
+    ) -> (WorkflowFolder, Vec<JobFolder>) {
+        let workflow_folder = WorkflowFolder::new(
+            "wf".to_string(),
+            root.to_path_buf(),
+            Some(SystemTime::now()),
+        );
+        let mut metadata = WorkflowMeta::new("wf".to_string(), String::new());
+        for (job, deps) in dependencies {
+            metadata.set_job_dependencies(
+                job.to_string(),
+                deps.iter().map(|d| d.to_string()).collect(),
+            );
+        }
+        workflow_folder.save_workflow_metadata(&metadata).unwrap();
+        fs::create_dir_all(root.join("input_files")).unwrap();
+        fs::write(root.join("input_files/seed.txt"), "seed").unwrap();
+
+        let job_folders = jobs
+            .iter()
+            .map(|name| {
+                let job = JobFolder::new(name.to_string(), root.join(name));
+                let mut meta = make_job_meta(vec![]);
+                meta.name = name.to_string();
+                meta.scripts.pre = "pre_run.sh".to_string();
+                meta.scripts.post = "post_run.sh".to_string();
+                meta.outputs = Outputs::Patterns(vec!["result.txt".to_string()]);
+                job.save_meta(&meta).unwrap();
+                fs::write(job.path.join("run.sh"), "echo run").unwrap();
+                job
+            })
+            .collect();
+        (workflow_folder, job_folders)
+    }
+
+    async fn run_prepared(
+        executor: FakeExecutor,
+        workflow: PreparedWorkflow,
+        options: &RunOptions,
+    ) -> (bool, Vec<Message>) {
+        let (tx, mut rx) = mpsc::channel::<Message>(1024);
+        let (_cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+        let succeeded = execute(executor, workflow, options, tx, &mut cancel_rx).await;
+        let mut messages = Vec::new();
+        while let Ok(message) = rx.try_recv() {
+            messages.push(message);
+        }
+        (succeeded, messages)
+    }
+
+    async fn run_fake(
+        executor: FakeExecutor,
+        workflow_folder: &WorkflowFolder,
+        jobs: &[JobFolder],
+        temp: &Path,
+    ) -> (bool, Vec<Message>) {
+        let workflow =
+            prepare_workflow(workflow_folder, temp, jobs, &ParamArgs::default()).unwrap();
+        run_prepared(executor, workflow, &RunOptions::default()).await
+    }
+
+    #[tokio::test]
+    async fn test_execute_runs_jobs_in_dependency_order() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a", "b"], &[("b", &["a"])]);
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+        fs::write(temp.path().join("a/pre_run.sh"), "echo pre").unwrap();
+
+        let executor = FakeExecutor::default();
+        let (succeeded, messages) =
+            run_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+
+        assert!(succeeded);
+        assert_eq!(
+            executor.calls(),
+            vec![
+                "image ubuntu:latest",
+                "a/pre_run.sh",
+                "a/run.sh",
+                "image ubuntu:latest",
+                "b/run.sh",
+                "cleanup",
+            ]
+        );
+        // Each job reports Completed once, followed by the end-of-run message
+        let completed: Vec<usize> = messages
+            .iter()
+            .filter(|(_, status, _)| *status == JobStatus::Completed)
+            .map(|(idx, _, _)| *idx)
+            .collect();
+        assert_eq!(completed, vec![0, 1, 2]);
+    }
+
+    async fn resume_fake(
+        executor: FakeExecutor,
+        workflow_folder: &WorkflowFolder,
+        jobs: &[JobFolder],
+        temp: &Path,
+    ) -> (bool, Vec<Message>) {
+        let workflow = prepare_resume(workflow_folder, temp, jobs, &ParamArgs::default()).unwrap();
+        run_prepared(executor, workflow, &RunOptions::default()).await
+    }
+
+    #[tokio::test]
+    async fn test_resume_continues_from_failed_job() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(
+            source.path(),
+            &["a", "b", "c"],
+            &[("b", &["a"]), ("c", &["b"])],
+        );
+        fs::write(source.path().join("b/post_run.sh"), "exit 1").unwrap();
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let failing = FakeExecutor {
+            failing_script: Some("post_run.sh".to_string()),
+            ..Default::default()
+        };
+        let (succeeded, _) = run_fake(failing, &workflow_folder, &jobs, temp.path()).await;
+        assert!(!succeeded);
+        let state = RunState::load(temp.path()).unwrap().unwrap();
+        assert_eq!(state.jobs.keys().collect::<Vec<_>>(), vec!["a"]);
+
+        // Fix b and resume: a is reused, b and c run
+        fs::remove_file(source.path().join("b/post_run.sh")).unwrap();
+        let executor = FakeExecutor::default();
+        let (succeeded, messages) =
+            resume_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+
+        assert!(succeeded);
+        assert_eq!(
+            executor.calls(),
+            vec![
+                "image ubuntu:latest",
+                "b/run.sh",
+                "image ubuntu:latest",
+                "c/run.sh",
+                "cleanup",
+            ]
+        );
+        assert!(messages.iter().any(|(idx, status, log_line)| {
+            *idx == 0 && *status == JobStatus::Completed && log_line.content.contains("reusing")
+        }));
+        // b is run from a fresh copy and receives the reused outputs of a
+        let complete = temp.path().join("@complete");
+        assert!(!complete.join("b/post_run.sh").exists());
+        assert_eq!(
+            fs::read_to_string(complete.join("b/inputs/result.txt")).unwrap(),
+            "a"
+        );
+        let state = RunState::load(temp.path()).unwrap().unwrap();
+        assert_eq!(state.jobs.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_resume_reuses_completed_matrix_instances() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a", "b"], &[("b", &["a"])]);
+        let mut meta = jobs[0].load_meta().unwrap();
+        meta.matrix = toml::from_str("seed = [1, 2]").unwrap();
+        jobs[0].save_meta(&meta).unwrap();
+        fs::write(source.path().join("b/post_run.sh"), "exit 1").unwrap();
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let failing = FakeExecutor {
+            failing_script: Some("post_run.sh".to_string()),
+            ..Default::default()
+        };
+        let (succeeded, _) = run_fake(failing, &workflow_folder, &jobs, temp.path()).await;
+        assert!(!succeeded);
+        let state = RunState::load(temp.path()).unwrap().unwrap();
+        assert_eq!(
+            state.jobs.keys().collect::<Vec<_>>(),
+            vec!["a", "a@1", "a@2"]
+        );
+        assert_eq!(state.instances("a"), ["a@1", "a@2"]);
+
+        // Fix b and resume: both instances of a are reused, only b runs
+        fs::remove_file(source.path().join("b/post_run.sh")).unwrap();
+        let workflow =
+            prepare_resume(&workflow_folder, temp.path(), &jobs, &ParamArgs::default()).unwrap();
+        let names: Vec<&str> = workflow.jobs.iter().map(|j| j.name.as_str()).collect();
+        assert_eq!(names, vec!["a@1", "a@2", "b"]);
+        assert_eq!(workflow.instances["a@2"].params["seed"], 2);
+        let executor = FakeExecutor::default();
+        let (succeeded, _) = run_prepared(executor.clone(), workflow, &RunOptions::default()).await;
+
+        assert!(succeeded);
+        assert_eq!(
+            executor.calls(),
+            vec!["image ubuntu:latest", "b/run.sh", "cleanup"]
+        );
+        let b_inputs = temp.path().join("@complete/b/inputs/a");
+        for label in ["1", "2"] {
+            assert_eq!(
+                fs::read_to_string(b_inputs.join(label).join("result.txt")).unwrap(),
+                format!("a@{label}")
+            );
+        }
+        let state = RunState::load(temp.path()).unwrap().unwrap();
+        assert_eq!(
+            state.jobs.keys().collect::<Vec<_>>(),
+            vec!["a", "a@1", "a@2", "b"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resume_reruns_changed_jobs_and_their_dependents() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) =
+            make_workflow(source.path(), &["a", "b", "c"], &[("b", &["a"])]);
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+        let (succeeded, _) = run_fake(
+            FakeExecutor::default(),
+            &workflow_folder,
+            &jobs,
+            temp.path(),
+        )
+        .await;
+        assert!(succeeded);
+
+        fs::write(source.path().join("a/run.sh"), "echo changed").unwrap();
+        let executor = FakeExecutor::default();
+        let (succeeded, _) =
+            resume_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+
+        assert!(succeeded);
+        let scripts: Vec<String> = executor
+            .calls()
+            .into_iter()
+            .filter(|call| call.ends_with("run.sh"))
+            .collect();
+        assert_eq!(scripts, vec!["a/run.sh", "b/run.sh"]);
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_cache_restores_outputs_without_running_scripts() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        // SAFETY: serialized with the other tests that touch the environment
+        unsafe { std::env::set_var(crate::SILVA_CACHE_DIR, cache_dir.path()) };
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a", "b"], &[("b", &["a"])]);
+        let mut meta = jobs[1].load_meta().unwrap();
+        meta.cache = false;
+        jobs[1].save_meta(&meta).unwrap();
+        let options = RunOptions {
+            cache: true,
+            ..Default::default()
+        };
+
+        let first = create_temp_workflow_folder(source.path()).unwrap();
+        let workflow =
+            prepare_workflow(&workflow_folder, first.path(), &jobs, &ParamArgs::default()).unwrap();
+        let (succeeded, _) = run_prepared(FakeExecutor::default(), workflow, &options).await;
+        assert!(succeeded);
+
+        let second = create_temp_workflow_folder(source.path()).unwrap();
+        let workflow = prepare_workflow(
+            &workflow_folder,
+            second.path(),
+            &jobs,
+            &ParamArgs::default(),
+        )
+        .unwrap();
+        let executor = FakeExecutor::default();
+        let (succeeded, messages) = run_prepared(executor.clone(), workflow, &options).await;
+        unsafe { std::env::remove_var(crate::SILVA_CACHE_DIR) };
+
+        assert!(succeeded);
+        // a is restored from the cache; b opted out and runs again
+        assert_eq!(
+            executor.calls(),
+            vec![
+                "image ubuntu:latest",
+                "image ubuntu:latest",
+                "b/run.sh",
+                "cleanup"
+            ]
+        );
+        assert!(messages.iter().any(|(idx, _, log_line)| {
+            *idx == 0
+                && log_line
+                    .content
+                    .starts_with("Restored 1 output file(s) from cache")
+        }));
+        let complete = second.path().join("@complete");
+        assert_eq!(
+            fs::read_to_string(complete.join("a/outputs/result.txt")).unwrap(),
+            "a"
+        );
+        assert_eq!(
+            fs::read_to_string(complete.join("b/inputs/result.txt")).unwrap(),
+            "a"
+        );
+        assert_eq!(
+            JobCache::new(cache_dir.path().to_path_buf())
+                .list()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_prepare_resume_requires_run_state() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a"], &[]);
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let err = prepare_resume(&workflow_folder, temp.path(), &jobs, &ParamArgs::default())
+            .unwrap_err();
+        assert!(err.contains("No run state found"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_stages_inputs_and_moves_jobs_to_complete() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a", "b"], &[("b", &["a"])]);
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let (succeeded, _) = run_fake(
+            FakeExecutor::default(),
+            &workflow_folder,
+            &jobs,
+            temp.path(),
+        )
+        .await;
+
+        assert!(succeeded);
+        let complete = temp.path().join("@complete");
+        assert!(complete.join("a/inputs/seed.txt").exists());
+        assert_eq!(
+            fs::read_to_string(complete.join("a/outputs/result.txt")).unwrap(),
+            "a"
+        );
+        // b received a's outputs through inputs/
+        assert_eq!(
+            fs::read_to_string(complete.join("b/inputs/result.txt")).unwrap(),
+            "a"
+        );
+        assert!(!temp.path().join("a").exists());
+    }
+
+    #[test]
+    fn test_prepare_rejects_mounts_outside_allowlist() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a"], &[]);
+        fs::create_dir_all(source.path().join("a/data")).unwrap();
+        let set_mounts = |host: &str| {
+            let mut meta = jobs[0].load_meta().unwrap();
+            meta.mounts = vec![Mount {
+                host: host.to_string(),
+                container: "/data".to_string(),
+                read_only: true,
+            }];
+            jobs[0].save_meta(&meta).unwrap();
+        };
+
+        // Folders inside the workflow are fine, relative to the job folder
+        set_mounts("data");
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+        let workflow =
+            prepare_workflow(&workflow_folder, temp.path(), &jobs, &ParamArgs::default()).unwrap();
+        let data = source.path().join("a/data").canonicalize().unwrap();
+        assert_eq!(workflow.mounts["a"][0].host, data.display().to_string());
+
+        set_mounts("/");
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+        let err = prepare_workflow(&workflow_folder, temp.path(), &jobs, &ParamArgs::default())
+            .unwrap_err();
+        assert!(err.contains("[a] Mount source '/' is outside the allowed folders"));
+    }
+
+    #[tokio::test]
+    async fn test_environment_is_released_when_job_fails() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a"], &[]);
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let executor = FakeExecutor {
+            failing_script: Some("run.sh".to_string()),
+            ..Default::default()
+        };
+        let (succeeded, _) = run_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+        assert!(!succeeded);
+        // New workflows isolate jobs
+        assert_eq!(
+            *executor.environments.lock().unwrap(),
+            vec!["start a (job)", "release"]
+        );
+
+        // Workflows without a workflow.toml keep sharing containers
+        fs::remove_file(workflow_folder.workflow_metadata_path()).unwrap();
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+        let executor = FakeExecutor::default();
+        let (succeeded, _) = run_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+        assert!(succeeded);
+        assert_eq!(
+            *executor.environments.lock().unwrap(),
+            vec!["start a (workflow)", "release"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_params_file_starts_no_environment() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a"], &[]);
+        // A file where the .silva folder belongs
+        fs::write(source.path().join("a").join(PARAMS_FILE_DIR), "").unwrap();
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let executor = FakeExecutor::default();
+        let (succeeded, messages) =
+            run_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+
+        assert!(!succeeded);
+        assert!(messages.iter().any(|(_, status, log_line)| {
+            *status == JobStatus::Failed
+                && log_line
+                    .content
+                    .contains("Failed to write .silva/params.json")
+        }));
+        assert!(executor.environments.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_stops_after_failing_script() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a", "b"], &[("b", &["a"])]);
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+        fs::write(temp.path().join("a/post_run.sh"), "echo post").unwrap();
+
+        let executor = FakeExecutor {
+            failing_script: Some("run.sh".to_string()),
+            ..Default::default()
+        };
+        let (succeeded, messages) =
+            run_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+
+        assert!(!succeeded);
+        // post_run.sh and job b never run, but cleanup still happens
+        assert_eq!(
+            executor.calls(),
+            vec!["image ubuntu:latest", "a/run.sh", "cleanup"]
+        );
+        assert!(temp.path().join("a").exists());
+        assert!(messages.iter().any(|(idx, status, log_line)| {
+            *idx == 0
+                && *status == JobStatus::Failed
+                && log_line.content.contains("failed with exit code 1")
+        }));
+        assert_eq!(
+            messages
+                .last()
+                .map(|(idx, status, _)| (*idx, status.clone())),
+            Some((2, JobStatus::Failed))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failing_script_is_retried() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a"], &[]);
+        let mut meta = jobs[0].load_meta().unwrap();
+        meta.retries = 2;
+        jobs[0].save_meta(&meta).unwrap();
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let executor = FakeExecutor {
+            failing_script: Some("run.sh".to_string()),
+            ..Default::default()
+        };
+        let (succeeded, messages) =
+            run_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+
+        assert!(!succeeded);
+        assert_eq!(
+            executor.calls(),
+            vec![
+                "image ubuntu:latest",
+                "a/run.sh",
+                "a/run.sh",
+                "a/run.sh",
+                "cleanup"
+            ]
+        );
+        let retries: Vec<&str> = messages
+            .iter()
+            .map(|(_, _, log_line)| log_line.content.as_str())
+            .filter(|content| content.contains("retrying"))
+            .collect();
+        assert_eq!(
+            retries,
+            vec![
+                "Script run.sh failed with exit code 1; retrying in 0s (attempt 2/3)",
+                "Script run.sh failed with exit code 1; retrying in 0s (attempt 3/3)",
+            ]
+        );
+        assert!(messages.iter().any(|(idx, status, log_line)| {
+            *idx == 0
+                && *status == JobStatus::Failed
+                && log_line.content
+                    == "Job 'a' failed: Script run.sh failed with exit code 1 (3 attempts)"
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_executor_error_after_timeout_is_a_timeout() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a"], &[]);
+        let mut meta = jobs[0].load_meta().unwrap();
+        meta.timeout = Some(HumanDuration(Duration::from_millis(50)));
+        jobs[0].save_meta(&meta).unwrap();
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let executor = FakeExecutor {
+            blocking_script: Some("run.sh".to_string()),
+            stop_error: Some("exec stream closed".to_string()),
+            ..Default::default()
+        };
+        let (succeeded, messages) =
+            run_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+
+        assert!(!succeeded);
+        assert!(messages.iter().any(|(idx, status, log_line)| {
+            *idx == 0
+                && *status == JobStatus::Failed
+                && log_line.content == "Job 'a' failed: Script run.sh timed out after 0s"
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_required_gpu_fails_before_any_job_starts() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a", "b"], &[("b", &["a"])]);
+        let mut meta = jobs[1].load_meta().unwrap();
+        meta.resources.gpu = Some(GpuPolicy::Required);
+        jobs[1].save_meta(&meta).unwrap();
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        // The fake executor has no GPU runtime
+        let executor = FakeExecutor::default();
+        let (succeeded, messages) =
+            run_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+
+        assert!(!succeeded);
+        assert!(executor.calls().is_empty());
+        assert!(messages.iter().any(|(idx, status, log_line)| {
+            *idx == 1
+                && *status == JobStatus::Failed
+                && log_line.content
+                    == "Job 'b' requires a GPU (gpu = \"required\"), but the host has no GPU runtime"
+        }));
+        assert_eq!(
+            messages
+                .last()
+                .map(|(idx, status, _)| (*idx, status.clone())),
+            Some((2, JobStatus::Failed))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_running_job_and_skips_the_rest() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a", "b"], &[("b", &["a"])]);
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+        let workflow =
+            prepare_workflow(&workflow_folder, temp.path(), &jobs, &ParamArgs::default()).unwrap();
+
+        let executor = FakeExecutor {
+            blocking_script: Some("run.sh".to_string()),
+            ..Default::default()
+        };
+        let (tx, mut rx) = mpsc::channel::<Message>(1024);
+        let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            cancel_tx.send(()).await.unwrap();
+        });
+        let succeeded = execute(
+            executor.clone(),
+            workflow,
+            &RunOptions::default(),
+            tx,
+            &mut cancel_rx,
+        )
+        .await;
+        let mut messages = Vec::new();
+        while let Ok(message) = rx.try_recv() {
+            messages.push(message);
+        }
+
+        assert!(!succeeded);
+        // post_run.sh and job b never run, but cleanup still happens
+        assert_eq!(
+            executor.calls(),
+            vec!["image ubuntu:latest", "a/run.sh", "cleanup"]
+        );
+        let finished: Vec<(usize, JobStatus, &str)> = messages
+            .iter()
+            .filter(|(_, status, _)| status.is_finished())
+            .map(|(idx, status, log_line)| (*idx, status.clone(), log_line.content.as_str()))
+            .collect();
+        assert_eq!(
+            finished,
+            vec![
+                (0, JobStatus::Cancelled, "Job 'a' cancelled"),
+                (
+                    1,
+                    JobStatus::Cancelled,
+                    "Job 'b' skipped: the run was cancelled"
+                ),
+                (2, JobStatus::Cancelled, ""),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_passes_parameters_as_env_vars() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a"], &[]);
+        fs::write(
+            source.path().join("global_params.json"),
+            r#"{"batch_size": 32}"#,
+        )
+        .unwrap();
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let executor = FakeExecutor::default();
+        let (succeeded, _) = run_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+
+        assert!(succeeded);
+        assert!(
+            executor
+                .env_vars
+                .lock()
+                .unwrap()
+                .contains(&"PARAM_BATCH_SIZE=32".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_param_overrides_reach_jobs_and_change_fingerprints() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a"], &[]);
+        let mut meta = jobs[0].load_meta().unwrap();
+        meta.params.insert(
+            "epochs".to_string(),
+            ParamDefinition::new(
+                ParamType::Integer,
+                toml::Value::Integer(10),
+                String::new(),
+                None,
+            ),
+        );
+        jobs[0].save_meta(&meta).unwrap();
+        let args = ParamArgs {
+            params: vec!["a.epochs=3".to_string()],
+            ..Default::default()
+        };
+
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+        let workflow = prepare_workflow(&workflow_folder, temp.path(), &jobs, &args).unwrap();
+        let executor = FakeExecutor::default();
+        let (succeeded, _) = run_prepared(executor.clone(), workflow, &RunOptions::default()).await;
+
+        assert!(succeeded);
+        assert!(
+            executor
+                .env_vars
+                .lock()
+                .unwrap()
+                .contains(&"PARAM_EPOCHS=3".to_string())
+        );
+        // The override lives in the run's copy, the source job keeps its defaults
+        assert!(!source.path().join("a/params.json").exists());
+
+        // A later resume without the override sees the job as changed
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+        let workflow = prepare_workflow(&workflow_folder, temp.path(), &jobs, &args).unwrap();
+        let fingerprint = workflow.fingerprints["a"].clone();
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+        let workflow =
+            prepare_workflow(&workflow_folder, temp.path(), &jobs, &ParamArgs::default()).unwrap();
+        assert_ne!(workflow.fingerprints["a"], fingerprint);
+    }
+
+    #[tokio::test]
+    async fn test_param_references_are_resolved_before_jobs_run() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a"], &[]);
+        fs::write(
+            source.path().join("global_params.json"),
+            r#"{"project": "dock", "epochs": 3}"#,
+        )
+        .unwrap();
+        fs::write(
+            source.path().join("a/params.json"),
+            r#"{"out": "${global.project}_${run.id}", "rounds": "${job.epochs}"}"#,
+        )
+        .unwrap();
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+        let run_id = temp.path().file_name().unwrap().to_string_lossy();
+
+        let executor = FakeExecutor::default();
+        let (succeeded, _) = run_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+
+        assert!(succeeded);
+        let env_vars = executor.env_vars.lock().unwrap();
+        assert!(env_vars.contains(&format!("PARAM_OUT=dock_{run_id}")));
+        assert!(env_vars.contains(&"PARAM_ROUNDS=3".to_string()));
+
+        // A cycle stops the run before any job starts
+        fs::write(
+            source.path().join("a/params.json"),
+            r#"{"out": "${job.name}", "name": "${job.out}"}"#,
+        )
+        .unwrap();
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+        let err = prepare_workflow(&workflow_folder, temp.path(), &jobs, &ParamArgs::default())
+            .unwrap_err();
+        assert!(
+            err.contains("[a] Parameter 'out': reference cycle job.name -> job.out -> job.name")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_params_are_written_to_a_file_and_env_vars_follow_the_workflow() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a"], &[]);
+        fs::write(
+            source.path().join("global_params.json"),
+            r#"{"batch-size": 32, "tags": ["x", "y"]}"#,
+        )
+        .unwrap();
+        let mut metadata = workflow_folder.load_workflow_metadata().unwrap().unwrap();
+        metadata.param_env_prefix = Some("P_".to_string());
+        workflow_folder.save_workflow_metadata(&metadata).unwrap();
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let executor = FakeExecutor::default();
+        let (succeeded, _) = run_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+
+        assert!(succeeded);
+        let env_vars = executor.env_vars.lock().unwrap().clone();
+        assert!(env_vars.contains(&"P_BATCH_SIZE=32".to_string()));
+        assert!(env_vars.contains(&r#"P_TAGS=["x","y"]"#.to_string()));
+        let params_file = temp.path().join("@complete/a/.silva/params.json");
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(params_file).unwrap()).unwrap();
+        assert_eq!(
+            written,
+            serde_json::json!({"batch-size": 32, "tags": ["x", "y"]})
+        );
+
+        // Opting out leaves the file as the only source of parameters
+        metadata.param_env = false;
+        workflow_folder.save_workflow_metadata(&metadata).unwrap();
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+        let executor = FakeExecutor::default();
+        let (succeeded, _) = run_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+
+        assert!(succeeded);
+        assert!(
+            !executor
+                .env_vars
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|var| var.starts_with("P_"))
+        );
+        assert!(temp.path().join("@complete/a/.silva/params.json").is_file());
+    }
+
+    #[tokio::test]
+    async fn test_matrix_job_runs_once_per_instance() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a", "b"], &[("b", &["a"])]);
+        let mut meta = jobs[0].load_meta().unwrap();
+        meta.matrix = toml::from_str("seed = [1, 2]").unwrap();
+        jobs[0].save_meta(&meta).unwrap();
+        let mut metadata = workflow_folder.load_workflow_metadata().unwrap().unwrap();
+        metadata.matrix.insert(
+            "a".to_string(),
+            toml::from_str("file = { inputs = \"*.txt\" }").unwrap(),
+        );
+        workflow_folder.save_workflow_metadata(&metadata).unwrap();
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let workflow =
+            prepare_workflow(&workflow_folder, temp.path(), &jobs, &ParamArgs::default()).unwrap();
+        let names: Vec<&str> = workflow.jobs.iter().map(|j| j.name.as_str()).collect();
+        assert_eq!(names, vec!["a@seed.txt_1", "a@seed.txt_2", "b"]);
+        // The job's folder is replaced by one folder per instance
+        assert!(!temp.path().join("a").exists());
+
+        let executor = FakeExecutor::default();
+        let (succeeded, _) = run_prepared(executor.clone(), workflow, &RunOptions::default()).await;
+
+        assert!(succeeded);
+        let ran: Vec<String> = executor
+            .calls()
+            .into_iter()
+            .filter(|call| call.ends_with("/run.sh"))
+            .collect();
+        assert_eq!(ran[2], "b/run.sh");
+        let env_vars = executor.env_vars.lock().unwrap().clone();
+        for expected in ["PARAM_SEED=1", "PARAM_SEED=2", "PARAM_FILE=seed.txt"] {
+            assert!(env_vars.contains(&expected.to_string()), "{expected}");
+        }
+        // Each instance has its own outputs/, and `b` gets all of them
+        let b_inputs = temp.path().join("@complete/b/inputs/a");
+        for label in ["seed.txt_1", "seed.txt_2"] {
+            assert_eq!(
+                fs::read_to_string(b_inputs.join(label).join("result.txt")).unwrap(),
+                format!("a@{label}")
+            );
+        }
+    }
+
+    fn set_io(job: &JobFolder, io: &str) {
+        let mut meta = job.load_meta().unwrap();
+        let parsed: JobMeta = toml::from_str(&format!(
+            "name = \"x\"\ndescription = \"\"\n[container]\nimage = \"ubuntu\"\n{io}"
+        ))
+        .unwrap();
+        meta.inputs = parsed.inputs;
+        meta.outputs = parsed.outputs;
+        job.save_meta(&meta).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_named_inputs_are_staged_from_named_outputs() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) =
+            make_workflow(source.path(), &["a", "b", "c"], &[("c", &["a", "b"])]);
+        set_io(&jobs[0], "[outputs]\nresult = \"result.txt\"");
+        set_io(
+            &jobs[1],
+            "[outputs]\nresult = \"result.txt\"\nlog = \"*.log\"",
+        );
+        set_io(
+            &jobs[2],
+            "[inputs]\nfirst = \"a.result\"\nsecond = { from = \"b.result\", path = \"second\" }\nlog = { from = \"b.log\", optional = true }",
+        );
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let workflow =
+            prepare_workflow(&workflow_folder, temp.path(), &jobs, &ParamArgs::default()).unwrap();
+        let (succeeded, _) =
+            run_prepared(FakeExecutor::default(), workflow, &RunOptions::default()).await;
+
+        assert!(succeeded);
+        // Both dependencies produce result.txt; each lands in its own folder
+        let inputs = temp.path().join("@complete/c/inputs");
+        assert_eq!(
+            fs::read_to_string(inputs.join("a/result.txt")).unwrap(),
+            "a"
+        );
+        assert_eq!(
+            fs::read_to_string(inputs.join("second/result.txt")).unwrap(),
+            "b"
+        );
+        assert!(
+            temp.path()
+                .join("@complete/a/outputs/result/result.txt")
+                .exists()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_required_input_fails_job_before_scripts() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a", "b"], &[("b", &["a"])]);
+        set_io(
+            &jobs[0],
+            "[outputs]\nresult = \"result.txt\"\nlog = \"*.log\"",
+        );
+        set_io(&jobs[1], "[inputs]\nlog = \"a.log\"");
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let workflow =
+            prepare_workflow(&workflow_folder, temp.path(), &jobs, &ParamArgs::default()).unwrap();
+        let executor = FakeExecutor::default();
+        let (succeeded, messages) =
+            run_prepared(executor.clone(), workflow, &RunOptions::default()).await;
+
+        assert!(!succeeded);
+        assert!(messages.iter().any(|(idx, status, log_line)| {
+            *idx == 1
+                && *status == JobStatus::Failed
+                && log_line
+                    .content
+                    .contains("Required input(s) 'log' ('a.log')")
+        }));
+        assert!(!executor.calls().iter().any(|call| call.starts_with("b/")));
+    }
+
+    fn set_when(job: &JobFolder, when: &str) {
+        let mut meta = job.load_meta().unwrap();
+        meta.when = Some(when.to_string());
+        job.save_meta(&meta).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_false_condition_skips_job_and_its_dependents() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(
+            source.path(),
+            &["a", "b", "c", "d", "e"],
+            &[("b", &["a"]), ("c", &["b"]), ("d", &["a"]), ("e", &["d"])],
+        );
+        fs::write(
+            source.path().join("global_params.json"),
+            r#"{"mode": "full"}"#,
+        )
+        .unwrap();
+        set_when(&jobs[1], "exists('a/*.txt') && params.mode == 'full'");
+        set_when(&jobs[3], "exists('a/missing.txt') || params.mode == 'fast'");
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let executor = FakeExecutor::default();
+        let (succeeded, messages) =
+            run_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+
+        // Skipped jobs do not fail the run
+        assert!(succeeded);
+        let ran: Vec<String> = executor
+            .calls()
+            .into_iter()
+            .filter(|call| call.ends_with("/run.sh"))
+            .collect();
+        assert_eq!(ran, vec!["a/run.sh", "b/run.sh", "c/run.sh"]);
+        let skipped: Vec<(usize, &str)> = messages
+            .iter()
+            .filter(|(_, status, _)| *status == JobStatus::Skipped)
+            .map(|(idx, _, log_line)| (*idx, log_line.content.as_str()))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![
+                (
+                    3,
+                    "Job 'd' skipped: 'when' condition is false: exists('a/missing.txt') || params.mode == 'fast'"
+                ),
+                (4, "Job 'e' skipped: dependency 'd' was skipped"),
+            ]
+        );
+        assert_eq!(
+            messages
+                .last()
+                .map(|(idx, status, _)| (*idx, status.clone())),
+            Some((5, JobStatus::Completed))
+        );
+        // Skipped jobs stay where they are instead of moving to @complete/
+        assert!(temp.path().join("d").exists());
+        assert!(!temp.path().join("@complete/d").exists());
+    }
+
+    #[tokio::test]
+    async fn test_condition_on_unknown_param_fails_job() {
+        let source = tempfile::tempdir().unwrap();
+        let (workflow_folder, jobs) = make_workflow(source.path(), &["a"], &[]);
+        set_when(&jobs[0], "params.missing");
+        let temp = create_temp_workflow_folder(source.path()).unwrap();
+
+        let executor = FakeExecutor::default();
+        let (succeeded, messages) =
+            run_fake(executor.clone(), &workflow_folder, &jobs, temp.path()).await;
+
+        assert!(!succeeded);
+        assert!(messages.iter().any(|(idx, status, log_line)| {
+            *idx == 0
+                && *status == JobStatus::Failed
+                && log_line.content.contains("unknown parameter 'missing'")
+        }));
+        assert!(!executor.calls().iter().any(|call| call.ends_with(".sh")));
+    }
+
+    fn make_job_meta(inputs: Vec<&str>) -> JobMeta {
+        JobMeta {
+            name: "test".to_string(),
+            description: String::new(),
+            container: Container::new("ubuntu:latest".to_string()),
+            scripts: Scripts {
+                pre: String::new(),
+                run: "run.sh".to_string(),
+                post: String::new(),
+                ..Default::default()
+            },
+            inputs: Inputs::Patterns(inputs.into_iter().map(String::from).collect()),
+            outputs: Outputs::default(),
+            params: HashMap::new(),
+            cache: true,
+            timeout: None,
+            retries: 0,
+            retry_backoff: None,
+            resources: Default::default(),
+            mounts: Vec::new(),
+            when: None,
+            matrix: Default::default(),
+        }
+    }
+
+    fn setup_dep_outputs(workflow: &Path, dep_name: &str, files: &[&str]) {
+        let outputs_dir = workflow.join(dep_name).join("outputs");
+        fs::create_dir_all(&outputs_dir).unwrap();
+        for f in files {
+            fs::write(outputs_dir.join(f), f).unwrap();
+        }
+    }
+
+    fn list_inputs(workflow: &Path, job_name: &str) -> Vec<String> {
+        let inputs_dir = workflow.join(job_name).join("inputs");
+        let mut names: Vec<String> = fs::read_dir(inputs_dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn brace_pattern_copies_matching_extensions() {
+        let tmp = tempfile::tempdir().unwrap();
+        let wf = tmp.path();
+        setup_dep_outputs(
+            wf,
+            "01-produce",
+            &["seq.fasta", "ref.fa", "proteins.faa", "notes.txt"],
+        );
+
+        let producer = JobFolder::new("01-produce".to_string(), wf.join("01-produce"));
+        let consumer = JobFolder::new("02-consume".to_string(), wf.join("02-consume"));
+        let config = make_job_meta(vec!["*.{fasta,fa,faa}"]);
+
+        let n = copy_input_files_from_dependencies(
+            wf,
+            &consumer,
+            &[producer],
+            &config,
+            &["01-produce".to_string()],
+            &HashMap::new(),
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        assert_eq!(n, 3);
+        assert_eq!(
+            list_inputs(wf, "02-consume"),
+            vec!["proteins.faa", "ref.fa", "seq.fasta"]
+        );
+    }
+
+    #[test]
+    fn brace_pattern_excludes_non_matching_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let wf = tmp.path();
+        setup_dep_outputs(wf, "01-produce", &["a.txt", "b.log", "c.fasta"]);
+
+        let producer = JobFolder::new("01-produce".to_string(), wf.join("01-produce"));
+        let consumer = JobFolder::new("02-consume".to_string(), wf.join("02-consume"));
+        let config = make_job_meta(vec!["*.{fasta,fa,faa}"]);
+
+        let n = copy_input_files_from_dependencies(
+            wf,
+            &consumer,
+            &[producer],
+            &config,
+            &["01-produce".to_string()],
+            &HashMap::new(),
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        assert_eq!(n, 1);
+        assert_eq!(list_inputs(wf, "02-consume"), vec!["c.fasta"]);
+    }
+
+    #[test]
+    fn plain_pattern_still_works() {
+        let tmp = tempfile::tempdir().unwrap();
+        let wf = tmp.path();
+        setup_dep_outputs(
+            wf,
+            "01-produce",
+            &["data.csv", "metadata.json", "other.txt"],
+        );
+
+        let producer = JobFolder::new("01-produce".to_string(), wf.join("01-produce"));
+        let consumer = JobFolder::new("02-consume".to_string(), wf.join("02-consume"));
+        let config = make_job_meta(vec!["*.csv", "*.json"]);
+
+        let n = copy_input_files_from_dependencies(
+            wf,
+            &consumer,
+            &[producer],
+            &config,
+            &["01-produce".to_string()],
+            &HashMap::new(),
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        assert_eq!(n, 2);
+        assert_eq!(
+            list_inputs(wf, "02-consume"),
+            vec!["data.csv", "metadata.json"]
+        );
+    }
+
+    #[test]
+    fn empty_inputs_copies_all_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let wf = tmp.path();
+        setup_dep_outputs(wf, "01-produce", &["a.txt", "b.csv", "c.fasta"]);
+
+        let producer = JobFolder::new("01-produce".to_string(), wf.join("01-produce"));
+        let consumer = JobFolder::new("02-consume".to_string(), wf.join("02-consume"));
+        let config = make_job_meta(vec![]);
+
+        let n = copy_input_files_from_dependencies(
+            wf,
+            &consumer,
+            &[producer],
+            &config,
+            &["01-produce".to_string()],
+            &HashMap::new(),
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        assert_eq!(n, 3);
+        assert_eq!(
+            list_inputs(wf, "02-consume"),
+            vec!["a.txt", "b.csv", "c.fasta"]
+        );
+    }
+
+    #[test]
+    fn nested_patterns_match_paths_below_outputs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let wf = tmp.path();
+        setup_dep_outputs(wf, "01-produce", &["top.csv"]);
+        let outputs_dir = wf.join("01-produce/outputs");
+        fs::create_dir_all(outputs_dir.join("runs/r1")).unwrap();
+        fs::write(outputs_dir.join("runs/r1/a.csv"), "a").unwrap();
+        fs::write(outputs_dir.join("runs/r1/a.tmp"), "t").unwrap();
+
+        let producer = JobFolder::new("01-produce".to_string(), wf.join("01-produce"));
+        let consumer = JobFolder::new("02-consume".to_string(), wf.join("02-consume"));
+        let config = make_job_meta(vec!["runs/**", "!*.tmp"]);
+
+        let n = copy_input_files_from_dependencies(
+            wf,
+            &consumer,
+            &[producer],
+            &config,
+            &["01-produce".to_string()],
+            &HashMap::new(),
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        assert_eq!(n, 1);
+        assert_eq!(
+            crate::utils::list_files(&wf.join("02-consume/inputs")).unwrap(),
+            vec![PathBuf::from("r1/a.csv")]
+        );
+    }
+}
@@ -0,0 +1,288 @@
+//! Dependency-aware job scheduling.
+//!
+//! Starts every job of a workflow as soon as all of its dependencies have
+//! completed, keeping at most `max_parallel` jobs in flight. Jobs without a
+//! dependency path between them (e.g. the branches of a fan-out) therefore run
+//! concurrently, while a linear chain still runs one job at a time.
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use job_config::workflow::WorkflowMeta;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+use crate::components::workflow::JobFolder;
+
+/// Number of jobs run at once when neither `--jobs` nor `max_parallel` is set.
+pub const DEFAULT_MAX_PARALLEL: usize = 1;
+
+/// Resolves how many jobs may run at once.
+///
+/// The `--jobs` command line value takes precedence over `max_parallel` in
+/// workflow.toml. The result is never lower than 1.
+pub fn resolve_max_parallel(cli_jobs: Option<usize>, workflow_metadata: &WorkflowMeta) -> usize {
+    cli_jobs
+        .or(workflow_metadata.max_parallel)
+        .unwrap_or(DEFAULT_MAX_PARALLEL)
+        .max(1)
+}
+
+/// Runs `sorted_jobs` in dependency order with at most `max_parallel` jobs at once.
+///
+/// `start_job` is called once per job when all of its dependencies have completed.
+/// It receives the job and a cancel receiver dedicated to that job, and returns a
+/// future resolving to `true` if the job succeeded. Futures are spawned onto the
+/// tokio runtime, so they must be `Send + 'static`.
+///
+/// A message on `cancel_rx` sets `cancelled` and is then forwarded to every running
+/// job, so a job can tell a cancel from a failure. After a failure or a cancel no new
+/// jobs are started; jobs already running are allowed to finish.
+///
+/// # Returns
+///
+/// `true` if every job was started and succeeded.
+pub async fn run_jobs<F, Fut>(
+    sorted_jobs: &[JobFolder],
+    workflow_metadata: &WorkflowMeta,
+    max_parallel: usize,
+    cancel_rx: &mut mpsc::Receiver<()>,
+    cancelled: &AtomicBool,
+    mut start_job: F,
+) -> bool
+where
+    F: FnMut(JobFolder, mpsc::Receiver<()>) -> Fut,
+    Fut: Future<Output = bool> + Send + 'static,
+{
+    let max_parallel = max_parallel.max(1);
+    let mut pending: Vec<&JobFolder> = sorted_jobs.iter().collect();
+    let mut completed: HashSet<String> = HashSet::new();
+    let mut running: JoinSet<(String, bool)> = JoinSet::new();
+    // Senders must outlive their jobs: a dropped sender reads as a closed channel
+    let mut job_cancel_txs: HashMap<String, mpsc::Sender<()>> = HashMap::new();
+    let mut stop_scheduling = false;
+    let mut all_succeeded = true;
+    let mut cancel_open = true;
+
+    loop {
+        while !stop_scheduling && running.len() < max_parallel {
+            // Pick the first job in topological order whose dependencies are all done
+            let Some(pos) = pending.iter().position(|job| {
+                workflow_metadata
+                    .get_job_dependencies(&job.name)
+                    .iter()
+                    .all(|dep| completed.contains(dep))
+            }) else {
+                break;
+            };
+
+            let job = pending.remove(pos).clone();
+            let job_name = job.name.clone();
+            let (job_cancel_tx, job_cancel_rx) = mpsc::channel::<()>(1);
+            job_cancel_txs.insert(job_name.clone(), job_cancel_tx);
+
+            let job_future = start_job(job, job_cancel_rx);
+            running.spawn(async move { (job_name, job_future.await) });
+        }
+
+        if running.is_empty() {
+            break;
+        }
+
+        tokio::select! {
+            Some(joined) = running.join_next() => match joined {
+                Ok((job_name, succeeded)) => {
+                    job_cancel_txs.remove(&job_name);
+                    if succeeded {
+                        completed.insert(job_name);
+                    } else {
+                        all_succeeded = false;
+                        stop_scheduling = true;
+                    }
+                }
+                Err(_) => {
+                    // The job task panicked or was aborted
+                    all_succeeded = false;
+                    stop_scheduling = true;
+                }
+            },
+            cancel = cancel_rx.recv(), if cancel_open => match cancel {
+                Some(()) => {
+                    all_succeeded = false;
+                    stop_scheduling = true;
+                    cancelled.store(true, Ordering::SeqCst);
+                    for job_cancel_tx in job_cancel_txs.values() {
+                        let _ = job_cancel_tx.try_send(());
+                    }
+                }
+                None => cancel_open = false,
+            },
+        }
+    }
+
+    all_succeeded && pending.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    fn make_jobs(names: &[&str]) -> Vec<JobFolder> {
+        names
+            .iter()
+            .map(|name| JobFolder::new(name.to_string(), PathBuf::from(name)))
+            .collect()
+    }
+
+    fn make_workflow(dependencies: &[(&str, &[&str])]) -> WorkflowMeta {
+        let mut metadata = WorkflowMeta::new("test".to_string(), String::new());
+        for (job, deps) in dependencies {
+            metadata.set_job_dependencies(
+                job.to_string(),
+                deps.iter().map(|d| d.to_string()).collect(),
+            );
+        }
+        metadata
+    }
+
+    #[test]
+    fn test_resolve_max_parallel_precedence() {
+        let mut metadata = make_workflow(&[]);
+        assert_eq!(resolve_max_parallel(None, &metadata), DEFAULT_MAX_PARALLEL);
+
+        metadata.max_parallel = Some(4);
+        assert_eq!(resolve_max_parallel(None, &metadata), 4);
+        assert_eq!(resolve_max_parallel(Some(2), &metadata), 2);
+
+        metadata.max_parallel = Some(0);
+        assert_eq!(resolve_max_parallel(None, &metadata), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_jobs_respects_dependencies() {
+        // a -> (b, c) -> d
+        let jobs = make_jobs(&["a", "b", "c", "d"]);
+        let metadata = make_workflow(&[("b", &["a"]), ("c", &["a"]), ("d", &["b", "c"])]);
+        let finished: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let (_cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+
+        let succeeded = run_jobs(
+            &jobs,
+            &metadata,
+            4,
+            &mut cancel_rx,
+            &AtomicBool::default(),
+            |job, _| {
+                let finished = finished.clone();
+                async move {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    finished.lock().unwrap().push(job.name);
+                    true
+                }
+            },
+        )
+        .await;
+
+        assert!(succeeded);
+        let finished = finished.lock().unwrap();
+        assert_eq!(finished.len(), 4);
+        assert_eq!(finished.first().map(String::as_str), Some("a"));
+        assert_eq!(finished.last().map(String::as_str), Some("d"));
+    }
+
+    #[tokio::test]
+    async fn test_run_jobs_limits_concurrency() {
+        let jobs = make_jobs(&["a", "b", "c", "d", "e"]);
+        let metadata = make_workflow(&[]);
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (_cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+
+        let succeeded = run_jobs(
+            &jobs,
+            &metadata,
+            2,
+            &mut cancel_rx,
+            &AtomicBool::default(),
+            |_, _| {
+                let in_flight = in_flight.clone();
+                let peak = peak.clone();
+                async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    true
+                }
+            },
+        )
+        .await;
+
+        assert!(succeeded);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_jobs_stops_scheduling_after_failure() {
+        let jobs = make_jobs(&["a", "b", "c"]);
+        let metadata = make_workflow(&[("b", &["a"]), ("c", &["b"])]);
+        let started: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let (_cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+
+        let succeeded = run_jobs(
+            &jobs,
+            &metadata,
+            2,
+            &mut cancel_rx,
+            &AtomicBool::default(),
+            |job, _| {
+                let started = started.clone();
+                async move {
+                    started.lock().unwrap().push(job.name.clone());
+                    job.name != "b"
+                }
+            },
+        )
+        .await;
+
+        assert!(!succeeded);
+        assert_eq!(*started.lock().unwrap(), vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_run_jobs_forwards_cancel_to_running_jobs() {
+        let jobs = make_jobs(&["a", "b", "c"]);
+        let metadata = make_workflow(&[("c", &["a"])]);
+        let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+        let started = Arc::new(AtomicUsize::new(0));
+        let cancelled = AtomicBool::default();
+
+        cancel_tx.send(()).await.unwrap();
+        let succeeded = run_jobs(
+            &jobs,
+            &metadata,
+            2,
+            &mut cancel_rx,
+            &cancelled,
+            |_, mut job_cancel_rx| {
+                let started = started.clone();
+                async move {
+                    started.fetch_add(1, Ordering::SeqCst);
+                    // Each job waits until it is told to stop
+                    job_cancel_rx.recv().await.is_none()
+                }
+            },
+        )
+        .await;
+
+        assert!(!succeeded);
+        assert!(cancelled.load(Ordering::SeqCst));
+        // "c" depends on "a" and must not start once the run is cancelled
+        assert_eq!(started.load(Ordering::SeqCst), 2);
+    }
+}
@@ -28,3 +28,189 @@ pub fn copy_dir_recursive(src: &Path, dst: &Path) -> std::io::Result<usize> {
 
     Ok(file_count)
 }
+
+/// Opens `path` in the system file explorer.
+/// On Linux without GUI, copies the path to clipboard.
+pub fn open_folder(path: &Path) {
+    // Try to open the folder with platform-specific command
+    let result = if cfg!(target_os = "windows") {
+        // Windows: use explorer
+        std::process::Command::new("explorer").arg(path).spawn()
+    } else if cfg!(target_os = "macos") {
+        // macOS: use open
+        std::process::Command::new("open").arg(path).spawn()
+    } else {
+        // Linux: check if GUI is available
+        let has_display =
+            std::env::var("DISPLAY").is_ok() || std::env::var("WAYLAND_DISPLAY").is_ok();
+
+        if has_display {
+            // Try xdg-open
+            std::process::Command::new("xdg-open").arg(path).spawn()
+        } else {
+            // Server mode: copy to clipboard silently
+            let _ = copy_to_clipboard(path);
+            return;
+        }
+    };
+
+    // On failure, try to copy to clipboard as fallback
+    if result.is_err() {
+        let _ = copy_to_clipboard(path);
+    }
+}
+
+/// Helper function to copy path to clipboard
+fn copy_to_clipboard(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    use arboard::Clipboard;
+    let mut clipboard = Clipboard::new()?;
+    clipboard.set_text(path.to_string_lossy().to_string())?;
+    Ok(())
+}
+
+/// Incremental SHA-256 over strings, files and directory trees.
+///
+/// Used to derive content-addressed names (e.g. image tags) that only change
+/// when the hashed inputs change. Every update is length-prefixed, so
+/// `("ab", "c")` and `("a", "bc")` hash differently.
+pub struct ContentHasher {
+    context: ring::digest::Context,
+}
+
+impl Default for ContentHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContentHasher {
+    pub fn new() -> Self {
+        Self {
+            context: ring::digest::Context::new(&ring::digest::SHA256),
+        }
+    }
+
+    /// Adds raw bytes to the hash.
+    pub fn update(&mut self, data: &[u8]) {
+        self.context.update(&(data.len() as u64).to_le_bytes());
+        self.context.update(data);
+    }
+
+    /// Adds a file's contents to the hash.
+    pub fn update_file(&mut self, path: &Path) -> std::io::Result<()> {
+        let content = std::fs::read(path)?;
+        self.update(&content);
+        Ok(())
+    }
+
+    /// Adds every file below `dir` to the hash, in a stable order.
+    ///
+    /// Each file contributes its path relative to `dir` and its contents.
+    /// Top-level entries whose names are listed in `skip`, and symbolic links,
+    /// are ignored.
+    pub fn update_dir(&mut self, dir: &Path, skip: &[&str]) -> std::io::Result<()> {
+        let mut files = Vec::new();
+        collect_files(dir, dir, skip, &mut files)?;
+        files.sort();
+        for relative in files {
+            self.update(relative.to_string_lossy().as_bytes());
+            self.update_file(&dir.join(&relative))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the lowercase hex digest.
+    pub fn finish(self) -> String {
+        self.context
+            .finish()
+            .as_ref()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+}
+
+/// Lists the files below `dir`, relative to `dir`, in sorted order.
+///
+/// Symbolic links are left out rather than followed: a job's script can create
+/// them, pointing anywhere on the host, or at a folder containing them.
+pub fn list_files(dir: &Path) -> std::io::Result<Vec<std::path::PathBuf>> {
+    list_files_skipping(dir, &[])
+}
+
+/// Lists the files below `dir` like `list_files`, leaving out the top-level
+/// entries named in `skip`.
+pub fn list_files_skipping(dir: &Path, skip: &[&str]) -> std::io::Result<Vec<std::path::PathBuf>> {
+    let mut files = Vec::new();
+    collect_files(dir, dir, skip, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// Collects file paths below `dir`, relative to `root`, skipping `skip` at the top level.
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    skip: &[&str],
+    files: &mut Vec<std::path::PathBuf>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if dir == root && skip.iter().any(|name| entry.file_name() == **name) {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            continue;
+        }
+        if file_type.is_dir() {
+            collect_files(root, &path, skip, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_content_hasher_is_length_prefixed() {
+        let mut a = ContentHasher::new();
+        a.update(b"ab");
+        a.update(b"c");
+        let mut b = ContentHasher::new();
+        b.update(b"a");
+        b.update(b"bc");
+        assert_ne!(a.finish(), b.finish());
+    }
+
+    #[test]
+    fn test_content_hasher_dir_skips_top_level_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::write(dir.join("Dockerfile"), "FROM ubuntu").unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/main.py"), "print(1)").unwrap();
+
+        let hash_dir = || {
+            let mut hasher = ContentHasher::new();
+            hasher.update_dir(dir, &["outputs"]).unwrap();
+            hasher.finish()
+        };
+        let before = hash_dir();
+
+        // Skipped folders do not affect the hash
+        fs::create_dir_all(dir.join("outputs")).unwrap();
+        fs::write(dir.join("outputs/result.txt"), "42").unwrap();
+        assert_eq!(hash_dir(), before);
+
+        // Nested files do
+        fs::write(dir.join("src/main.py"), "print(2)").unwrap();
+        assert_ne!(hash_dir(), before);
+    }
+}
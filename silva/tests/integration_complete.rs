@@ -38,6 +38,7 @@ fn run_silva(fixture_name: &str) -> (bool, String, Option<PathBuf>) {
 
     let output = Command::new(silva_bin())
         .arg(&fixture)
+        .args(["--output", "json"])
         .output()
         .expect("Failed to run silva binary");
 
@@ -50,16 +51,12 @@ fn run_silva(fixture_name: &str) -> (bool, String, Option<PathBuf>) {
         eprintln!("=== STDERR ===\n{stderr}");
     }
 
-    // Extract temp folder path from output
-    // Silva prints either "Output folder: /tmp/silva-..." or "Working folder: /tmp/silva-..."
-    let temp_path = stdout.lines().chain(stderr.lines()).find_map(|line| {
-        let line = line.trim();
-        if line.starts_with("Output folder:") || line.starts_with("Working folder:") {
-            line.split(':').nth(1).map(|p| PathBuf::from(p.trim()))
-        } else {
-            None
-        }
-    });
+    // Extract temp folder path from the final `run_finished` event
+    let temp_path = stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|event| event["event"] == "run_finished")
+        .and_then(|event| event["output_folder"].as_str().map(PathBuf::from));
 
     (output.status.success(), stdout, temp_path)
 }